@@ -3,13 +3,27 @@
 
 #![warn(clippy::mod_module_files, clippy::pedantic, clippy::unwrap_used)]
 
-use clap::{crate_version, ArgGroup, Parser, ValueEnum};
-use miette::{Context, IntoDiagnostic, Report};
-use qsc::compile::compile;
-use qsc_frontend::compile::{PackageStore, SourceContents, SourceMap, SourceName};
-use qsc_hir::hir::Package;
+use clap::{crate_version, ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use miette::{miette, Context, Diagnostic, IntoDiagnostic, Report};
+use qsc::{
+    compile::{self, compile},
+    estimate::{estimate, QecScheme, QubitModel},
+    interpret::{stateless, GenericReceiver, MeasurementPolicy},
+    qubit_count::{QubitAnalysis, QubitBound},
+};
+use qsc_codegen::{
+    qasm::generate_qasm,
+    qir::{generate_qir, Profile},
+};
+use qsc_frontend::compile::{
+    CompileUnit, PackageStore, Source, SourceContents, SourceMap, SourceName,
+};
+use qsc_hir::hir::{CallableKind, ItemKind, Package, PackageId};
 use std::{
-    concat, fs,
+    collections::HashMap,
+    concat,
+    fmt::Write,
+    fs,
     io::{self, Read},
     path::{Path, PathBuf},
     process::ExitCode,
@@ -18,16 +32,23 @@ use std::{
 
 #[derive(Debug, Parser)]
 #[command(version = concat!(crate_version!(), " (", env!("QSHARP_GIT_HASH"), ")"), arg_required_else_help(true))]
-#[clap(group(ArgGroup::new("input").args(["entry", "sources"]).required(true).multiple(true)))]
+#[clap(group(ArgGroup::new("input").required(true).multiple(true)))]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// Disable automatic inclusion of the standard library.
-    #[arg(long)]
-    nostdlib: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    input: Input,
 
     /// Emit the compilation unit in the specified format.
     #[arg(long, value_enum)]
     emit: Vec<Emit>,
 
+    /// Target the specified profile when emitting QIR.
+    #[arg(long, value_enum, default_value_t = QirProfile::Base)]
+    profile: QirProfile,
+
     /// Write output to compiler-chosen filename in <dir>.
     #[arg(long = "outdir", value_name = "DIR")]
     out_dir: Option<PathBuf>,
@@ -35,58 +56,268 @@ struct Cli {
     /// Enable verbose output.
     #[arg(short, long)]
     verbose: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the entry point without simulating it and print the logical resources it uses, like
+    /// qubits, gate counts and depth.
+    #[command(arg_required_else_help(true))]
+    Estimate(Estimate),
+}
+
+// The sources to compile, which are shared by compiling and estimating.
+#[derive(Args, Debug)]
+#[group(skip)]
+struct Input {
+    /// Disable automatic inclusion of the standard library.
+    #[arg(long)]
+    nostdlib: bool,
 
     /// Entry expression to execute as the main operation.
-    #[arg(short, long)]
+    #[arg(short, long, group = "input")]
     entry: Option<String>,
 
-    /// Q# source files to compile, or `-` to read from stdin.
-    #[arg()]
+    /// Q# source files to compile, or `-` to read from stdin. QASM files with the `.qasm`
+    /// extension are imported as an operation in the `Qasm` namespace, named after the file name,
+    /// which must be a Q# identifier.
+    #[arg(group = "input")]
     sources: Vec<PathBuf>,
 }
 
+#[derive(Args, Debug)]
+#[clap(group(ArgGroup::new("input").required(true).multiple(true)))]
+struct Estimate {
+    #[command(flatten)]
+    input: Input,
+
+    /// How measurements are resolved: `zero`, `random`, or the results in order as `0`s and `1`s
+    /// like `0110`, after which they are `Zero`.
+    #[arg(long, value_name = "POLICY", default_value = "zero")]
+    measurements: MeasurementPolicy,
+
+    /// Estimate the physical resources needed to run the entry point on this qubit technology with
+    /// surface code error correction, and print them as a JSON report. The model is one of
+    /// `gate_ns_e3`, `gate_ns_e4`, `gate_us_e3` or `gate_us_e4`, or a JSON object with the
+    /// `one_qubit_gate_time`, `two_qubit_gate_time` and `measurement_time` in nanoseconds, the
+    /// `one_qubit_gate_error`, `two_qubit_gate_error`, `measurement_error` and `t_gate_error`, and
+    /// an optional `name`.
+    #[arg(long, value_name = "MODEL")]
+    qubit_model: Option<QubitModel>,
+
+    /// The surface code error correction scheme of the physical resource estimate, as a JSON object
+    /// with its `threshold` and `crossing_prefactor`. A field that is left out has its default
+    /// value, which is 0.01 for the threshold and 0.03 for the crossing prefactor.
+    #[arg(long, value_name = "SCHEME", requires = "qubit_model")]
+    qec_scheme: Option<QecScheme>,
+
+    /// The largest probability of an error that the physical resource estimate allows.
+    #[arg(
+        long,
+        value_name = "P",
+        default_value_t = 1e-3,
+        requires = "qubit_model"
+    )]
+    error_budget: f64,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum Emit {
+    /// High-level intermediate representation.
     Hir,
+    /// Quantum intermediate representation.
+    Qir,
+    /// Quantum assembly language, version 3.
+    Qasm,
+    /// Upper bounds on the qubits that each operation allocates at the same time.
+    Qubits,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum QirProfile {
+    /// Straight-line programs that do not depend on measurement results.
+    Base,
+    /// Programs that branch on measurement results and compute on integers.
+    Adaptive,
 }
 
 fn main() -> miette::Result<ExitCode> {
     let cli = Cli::parse();
-    let mut store = PackageStore::new(qsc::compile::core());
-    let mut dependencies = Vec::new();
-    if !cli.nostdlib {
-        dependencies.push(store.insert(qsc::compile::std(&store)));
+    match &cli.command {
+        Some(Command::Estimate(estimate)) => run_estimate(estimate),
+        None => run_compile(&cli),
     }
+}
 
-    let sources = cli
-        .sources
-        .iter()
-        .map(read_source)
-        .collect::<miette::Result<Vec<_>>>()?;
-
-    let entry = cli.entry.unwrap_or_default();
-    let sources = SourceMap::new(sources, Some(entry.into()));
-    let (unit, errors) = compile(&store, &dependencies, sources);
+/// Compiles the sources and emits the compilation unit in the requested formats.
+fn run_compile(cli: &Cli) -> miette::Result<ExitCode> {
+    let Some((mut store, unit, errors)) = compile_input(&cli.input)? else {
+        return Ok(ExitCode::FAILURE);
+    };
 
     let out_dir = cli.out_dir.as_ref().map_or(".".as_ref(), PathBuf::as_path);
-    for emit in &cli.emit {
-        match emit {
-            Emit::Hir => emit_hir(&unit.package, out_dir)?,
+    if cli.emit.contains(&Emit::Hir) {
+        emit_hir(&unit.package, out_dir)?;
+    }
+
+    if !errors.is_empty() {
+        for error in errors {
+            print_error(&unit.sources, error);
+        }
+
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let package = store.insert(unit);
+    if cli.emit.contains(&Emit::Qubits) {
+        emit_qubits(&store, package, out_dir)?;
+    }
+
+    if cli.emit.contains(&Emit::Qir) {
+        let profile = match cli.profile {
+            QirProfile::Base => Profile::Base,
+            QirProfile::Adaptive => Profile::Adaptive,
+        };
+        match generate_qir(&store, package, profile) {
+            Ok(qir) => emit_qir(&qir, out_dir)?,
+            Err(errors) => {
+                let unit = store.get(package).expect("package should be in store");
+                for error in errors {
+                    print_error(&unit.sources, error);
+                }
+
+                return Ok(ExitCode::FAILURE);
+            }
         }
     }
 
-    if errors.is_empty() {
-        Ok(ExitCode::SUCCESS)
-    } else {
+    if cli.emit.contains(&Emit::Qasm) {
+        match generate_qasm(&store, package) {
+            Ok(qasm) => emit_qasm(&qasm, out_dir)?,
+            Err(errors) => {
+                let unit = store.get(package).expect("package should be in store");
+                for error in errors {
+                    print_error(&unit.sources, error);
+                }
+
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Compiles the sources and estimates the resources of the entry point, printing the logical
+/// resources or, with a qubit model, the physical resources as a JSON report.
+fn run_estimate(cli: &Estimate) -> miette::Result<ExitCode> {
+    let Some((mut store, unit, errors)) = compile_input(&cli.input)? else {
+        return Ok(ExitCode::FAILURE);
+    };
+
+    if !errors.is_empty() {
         for error in errors {
-            if let Some(source) = unit.sources.find_by_diagnostic(&error) {
-                eprintln!("{:?}", Report::new(error).with_source_code(source.clone()));
-            } else {
+            print_error(&unit.sources, error);
+        }
+
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let package = store.insert(unit);
+    let context = stateless::Context::from_store(store, package);
+    let mut stdout = io::stdout();
+    let resources = match context.estimate(
+        cli.measurements.clone(),
+        &mut GenericReceiver::new(&mut stdout),
+    ) {
+        Ok(resources) => resources,
+        Err(errors) => {
+            for error in errors {
+                if let Some(stack_trace) = error.stack_trace() {
+                    eprintln!("{stack_trace}");
+                }
+                eprintln!("{:?}", Report::new(error));
+            }
+
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    match &cli.qubit_model {
+        None => println!("{resources}"),
+        Some(model) => match estimate(
+            &resources,
+            model,
+            &cli.qec_scheme.unwrap_or_default(),
+            cli.error_budget,
+        ) {
+            Ok(physical) => println!("{}", physical.json()),
+            Err(error) => {
                 eprintln!("{:?}", Report::new(error));
+                return Ok(ExitCode::FAILURE);
+            }
+        },
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Reads and compiles the sources along with the standard library, returning the store of the
+/// dependencies and the compilation unit with its errors, or `None` if a QASM file could not be
+/// imported.
+fn compile_input(
+    input: &Input,
+) -> miette::Result<Option<(PackageStore, CompileUnit, Vec<compile::Error>)>> {
+    let mut store = PackageStore::new(qsc::compile::core());
+    let mut dependencies = Vec::new();
+    if !input.nostdlib {
+        dependencies.push(store.insert(qsc::compile::std(&store)));
+    }
+
+    let mut sources = Vec::new();
+    let mut imports = Vec::new();
+    let mut callables = HashMap::new();
+    for path in &input.sources {
+        let (name, contents) = read_source(path)?;
+        if path.extension() == Some("qasm".as_ref()) {
+            let callable = path.file_stem().unwrap_or_default().to_string_lossy();
+            if let Some(other) = callables.insert(callable.clone(), path) {
+                return Err(miette!(
+                    "QASM files `{}` and `{}` would both be imported as `Qasm.{callable}`",
+                    other.display(),
+                    path.display()
+                ));
+            }
+            let (namespace, errors) = qsc_qasm::namespace(&contents, "Qasm", &callable);
+            if !errors.is_empty() {
+                let source = Source {
+                    name,
+                    contents,
+                    offset: 0,
+                };
+                for error in errors {
+                    eprintln!("{:?}", Report::new(error).with_source_code(source.clone()));
+                }
+
+                return Ok(None);
             }
+            imports.push((name, contents, namespace));
+        } else {
+            sources.push((name, contents));
         }
+    }
 
-        Ok(ExitCode::FAILURE)
+    let entry = input.entry.clone().unwrap_or_default();
+    let sources = SourceMap::with_imports(sources, imports, Some(entry.into()));
+    let (unit, errors) = compile(&store, &dependencies, sources);
+    Ok(Some((store, unit, errors)))
+}
+
+fn print_error(sources: &SourceMap, error: impl Diagnostic + Send + Sync + 'static) {
+    if let Some(source) = sources.find_by_diagnostic(&error) {
+        eprintln!("{:?}", Report::new(error).with_source_code(source.clone()));
+    } else {
+        eprintln!("{:?}", Report::new(error));
     }
 }
 
@@ -115,3 +346,60 @@ fn emit_hir(package: &Package, dir: impl AsRef<Path>) -> miette::Result<()> {
         .into_diagnostic()
         .context("could not emit HIR")
 }
+
+fn emit_qubits(
+    store: &PackageStore,
+    package: PackageId,
+    dir: impl AsRef<Path>,
+) -> miette::Result<()> {
+    let unit = store.get(package).expect("package should be in store");
+    let mut analysis = QubitAnalysis::new(store, &unit.package);
+    let mut report = String::new();
+    for item in unit.package.items.values() {
+        let (ItemKind::Callable(decl), Some(parent)) = (&item.kind, item.parent) else {
+            continue;
+        };
+        let Some(ItemKind::Namespace(namespace, _)) =
+            unit.package.items.get(parent).map(|parent| &parent.kind)
+        else {
+            continue;
+        };
+        if decl.kind == CallableKind::Operation {
+            let bound = analysis
+                .callable_bound(item.id)
+                .expect("item should be a callable");
+            write!(report, "{}.{}: {bound}", namespace.name, decl.name.name)
+                .expect("writing to a string should succeed");
+            if let QubitBound::Unbounded(span) = bound {
+                if let Some(source) = unit.sources.find_by_offset(span.lo) {
+                    let before = &source.contents[..(span.lo - source.offset) as usize];
+                    let line = before.matches('\n').count() + 1;
+                    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+                    let column = before[line_start..].chars().count() + 1;
+                    write!(report, " at {}:{line}:{column}", source.name)
+                        .expect("writing to a string should succeed");
+                }
+            }
+            report.push('\n');
+        }
+    }
+
+    let path = dir.as_ref().join("qubits.txt");
+    fs::write(path, report)
+        .into_diagnostic()
+        .context("could not emit qubit counts")
+}
+
+fn emit_qir(qir: &str, dir: impl AsRef<Path>) -> miette::Result<()> {
+    let path = dir.as_ref().join("qir.ll");
+    fs::write(path, qir)
+        .into_diagnostic()
+        .context("could not emit QIR")
+}
+
+fn emit_qasm(qasm: &str, dir: impl AsRef<Path>) -> miette::Result<()> {
+    let path = dir.as_ref().join("program.qasm");
+    fs::write(path, qasm)
+        .into_diagnostic()
+        .context("could not emit OpenQASM")
+}
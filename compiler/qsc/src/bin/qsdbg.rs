@@ -0,0 +1,493 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#![warn(clippy::mod_module_files, clippy::pedantic, clippy::unwrap_used)]
+
+use clap::{crate_version, Parser};
+use num_bigint::BigUint;
+use num_complex::Complex64;
+use qsc::interpret::stateless::{self, Session, Step, StepResult};
+use qsc_eval::output::{self, Receiver, RegisterState};
+use qsc_frontend::compile::SourceMap;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    process::ExitCode,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+/// Runs a debug adapter that speaks the Debug Adapter Protocol over standard input and output.
+#[derive(Debug, Parser)]
+#[command(name = "qsdbg", version = concat!(crate_version!(), " (", env!("QSHARP_GIT_HASH"), ")"))]
+#[command(author, next_line_help = true)]
+struct Cli {}
+
+/// The Q# program runs on a single thread, which is the only thread the adapter reports.
+const THREAD_ID: i64 = 1;
+
+/// The variables reference of the quantum state scope. The locals of the stack frame at index `i`
+/// use the reference `i + 2`.
+const QUANTUM_STATE_REF: i64 = 1;
+
+/// The sequence number of the next message sent to the client.
+static SEQ: AtomicI64 = AtomicI64::new(1);
+
+fn main() -> ExitCode {
+    Cli::parse();
+    match serve(&mut io::stdin().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Handles requests until the program is launched and configured, then runs it under the
+/// debugger.
+fn serve(input: &mut impl BufRead) -> io::Result<()> {
+    let mut launched = None;
+    let mut configured = false;
+    let mut breakpoints = Breakpoints::default();
+    while launched.is_none() || !configured {
+        let Some(request) = read_message(input)? else {
+            return Ok(());
+        };
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                respond(
+                    &request,
+                    json!({ "supportsConfigurationDoneRequest": true }),
+                );
+                send_event("initialized", json!({}));
+            }
+            "launch" => match launch(&request["arguments"]) {
+                Ok(launch) => {
+                    respond(&request, json!({}));
+                    launched = Some(launch);
+                }
+                Err(message) => respond_error(&request, &message),
+            },
+            "setBreakpoints" => {
+                // The breakpoints are verified once the session starts.
+                let (_, lines) = breakpoints.set(&request["arguments"]);
+                let body: Vec<_> = lines
+                    .iter()
+                    .map(|&(id, line)| json!({ "id": id, "verified": false, "line": line }))
+                    .collect();
+                respond(&request, json!({ "breakpoints": body }));
+            }
+            "setExceptionBreakpoints" => respond(&request, json!({})),
+            "threads" => respond(&request, threads()),
+            "configurationDone" => {
+                respond(&request, json!({}));
+                configured = true;
+            }
+            "disconnect" => {
+                respond(&request, json!({}));
+                return Ok(());
+            }
+            command => respond_error(&request, &format!("unsupported request `{command}`")),
+        }
+    }
+
+    let (context, stop_on_entry) = launched.expect("program should be launched");
+    match context.debug(&mut DapReceiver, |session| {
+        debug(session, input, breakpoints, stop_on_entry)
+    }) {
+        Ok(result) => result,
+        Err(errors) => {
+            report_errors(errors);
+            exit(1);
+            Ok(())
+        }
+    }
+}
+
+/// Runs the program, pausing at breakpoints and steps to handle requests until the program
+/// finishes or the client disconnects.
+fn debug(
+    session: &mut Session,
+    input: &mut impl BufRead,
+    mut breakpoints: Breakpoints,
+    stop_on_entry: bool,
+) -> io::Result<()> {
+    for (source, lines) in &breakpoints.lines {
+        for (&(id, line), verified) in lines
+            .iter()
+            .zip(session.set_breakpoints(source, &breakpoint_lines(lines)))
+        {
+            send_event(
+                "breakpoint",
+                json!({
+                    "reason": "changed",
+                    "breakpoint": { "id": id, "verified": verified, "line": line },
+                }),
+            );
+        }
+    }
+
+    let (mut step, mut step_reason) = if stop_on_entry {
+        (Step::In, "entry")
+    } else {
+        (Step::Continue, "step")
+    };
+    loop {
+        let reason = match session.resume(step) {
+            Ok(StepResult::BreakpointHit(_)) => "breakpoint",
+            Ok(StepResult::StepComplete(_)) => step_reason,
+            Ok(StepResult::Return(value)) => {
+                send_output("stdout", &format!("{value}\n"));
+                exit(0);
+                return Ok(());
+            }
+            Err(errors) => {
+                report_errors(errors);
+                exit(1);
+                return Ok(());
+            }
+        };
+        send_event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+
+        step = loop {
+            let Some(request) = read_message(input)? else {
+                return Ok(());
+            };
+            match request["command"].as_str().unwrap_or_default() {
+                "continue" => {
+                    respond(&request, json!({ "allThreadsContinued": true }));
+                    break Step::Continue;
+                }
+                "next" => {
+                    respond(&request, json!({}));
+                    break Step::Over;
+                }
+                "stepIn" => {
+                    respond(&request, json!({}));
+                    break Step::In;
+                }
+                "stepOut" => {
+                    respond(&request, json!({}));
+                    break Step::Out;
+                }
+                "stackTrace" => respond(&request, stack_trace(session)),
+                "scopes" => respond(&request, scopes(&request["arguments"])),
+                "variables" => respond(&request, variables(session, &request["arguments"])),
+                "setBreakpoints" => {
+                    let (source, lines) = breakpoints.set(&request["arguments"]);
+                    let verified = session.set_breakpoints(&source, &breakpoint_lines(&lines));
+                    let body: Vec<_> = lines
+                        .iter()
+                        .zip(verified)
+                        .map(|(&(id, line), verified)| {
+                            json!({ "id": id, "verified": verified, "line": line })
+                        })
+                        .collect();
+                    respond(&request, json!({ "breakpoints": body }));
+                }
+                "setExceptionBreakpoints" => respond(&request, json!({})),
+                "threads" => respond(&request, threads()),
+                "disconnect" => {
+                    respond(&request, json!({}));
+                    return Ok(());
+                }
+                command => respond_error(&request, &format!("unsupported request `{command}`")),
+            }
+        };
+        step_reason = "step";
+    }
+}
+
+/// Reads the source files named in the launch arguments and compiles them with the entry
+/// expression. Returns the compiled program and whether to pause before the first statement.
+fn launch(arguments: &Value) -> Result<(stateless::Context, bool), String> {
+    let paths = arguments["sources"]
+        .as_array()
+        .ok_or("launch arguments should list the source files")?;
+    let mut sources = Vec::new();
+    for path in paths {
+        let path = path.as_str().ok_or("source paths should be strings")?;
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("could not read source file `{path}`: {error}"))?;
+        sources.push((source_name(path).into(), contents.into()));
+    }
+
+    let entry = arguments["entry"].as_str().map(Into::into);
+    let std = !arguments["noStdLib"].as_bool().unwrap_or_default();
+    let context =
+        stateless::Context::new(std, SourceMap::new(sources, entry)).map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+    Ok((
+        context,
+        arguments["stopOnEntry"].as_bool().unwrap_or_default(),
+    ))
+}
+
+/// The name of the source at the path, which is the canonical path so that the client can refer to
+/// the same file by a different path.
+fn source_name(path: &str) -> String {
+    fs::canonicalize(path).map_or_else(|_| path.to_string(), |path| path.display().to_string())
+}
+
+/// The breakpoints requested by the client, with the ids given to them, by source name.
+#[derive(Default)]
+struct Breakpoints {
+    next_id: i64,
+    lines: HashMap<String, Vec<(i64, u32)>>,
+}
+
+impl Breakpoints {
+    /// Replaces the breakpoints in the source from the arguments of a `setBreakpoints` request.
+    fn set(&mut self, arguments: &Value) -> (String, Vec<(i64, u32)>) {
+        let source = source_name(arguments["source"]["path"].as_str().unwrap_or_default());
+        let lines: Vec<_> = arguments["breakpoints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .map(|line| {
+                self.next_id += 1;
+                (
+                    self.next_id,
+                    line.try_into().expect("line should fit into u32"),
+                )
+            })
+            .collect();
+        self.lines.insert(source.clone(), lines.clone());
+        (source, lines)
+    }
+}
+
+fn breakpoint_lines(breakpoints: &[(i64, u32)]) -> Vec<u32> {
+    breakpoints.iter().map(|&(_, line)| line).collect()
+}
+
+fn threads() -> Value {
+    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })
+}
+
+fn stack_trace(session: &Session) -> Value {
+    let frames: Vec<_> = session
+        .stack_frames()
+        .into_iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            let mut value = json!({
+                "id": index,
+                "name": frame.name,
+                "line": frame.line,
+                "column": frame.column,
+            });
+            if let Some(source) = frame.source {
+                let name = Path::new(source.as_ref())
+                    .file_name()
+                    .map_or_else(|| source.to_string(), |name| name.to_string_lossy().into());
+                value["source"] = json!({ "name": name, "path": source.as_ref() });
+            }
+            value
+        })
+        .collect();
+    json!({ "totalFrames": frames.len(), "stackFrames": frames })
+}
+
+fn scopes(arguments: &Value) -> Value {
+    let frame = arguments["frameId"].as_i64().unwrap_or_default();
+    json!({
+        "scopes": [
+            { "name": "Locals", "variablesReference": frame + 2, "expensive": false },
+            { "name": "Quantum State", "variablesReference": QUANTUM_STATE_REF, "expensive": true },
+        ]
+    })
+}
+
+fn variables(session: &mut Session, arguments: &Value) -> Value {
+    let reference = arguments["variablesReference"].as_i64().unwrap_or_default();
+    let variables: Vec<_> = if reference == QUANTUM_STATE_REF {
+        let (state, qubit_count) = session.quantum_state().unwrap_or_default();
+        state
+            .into_iter()
+            .map(|(id, amplitude)| {
+                json!({
+                    "name": output::format_state_id(&id, qubit_count),
+                    "value": format!("{amplitude:.4}"),
+                    "variablesReference": 0,
+                })
+            })
+            .collect()
+    } else {
+        let frames = session.stack_frames();
+        usize::try_from(reference - 2)
+            .ok()
+            .and_then(|index| frames.into_iter().nth(index))
+            .map(|frame| frame.locals)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|local| {
+                json!({
+                    "name": local.name.as_ref(),
+                    "value": local.value.to_string(),
+                    "variablesReference": 0,
+                })
+            })
+            .collect()
+    };
+    json!({ "variables": variables })
+}
+
+fn report_errors(errors: Vec<stateless::Error>) {
+    for error in errors {
+        if let Some(stack_trace) = error.stack_trace() {
+            send_output("stderr", &format!("{stack_trace}\n"));
+        }
+        send_output("stderr", &format!("error: {error}\n"));
+    }
+}
+
+fn exit(exit_code: i32) {
+    send_event("exited", json!({ "exitCode": exit_code }));
+    send_event("terminated", json!({}));
+}
+
+/// Sends the program's output to the client as output events.
+struct DapReceiver;
+
+impl Receiver for DapReceiver {
+    fn state(
+        &mut self,
+        states: Vec<(BigUint, Complex64)>,
+        qubit_count: usize,
+    ) -> Result<(), output::Error> {
+        let mut dump = "DumpMachine:\n".to_string();
+        for (qubit, amplitude) in states {
+            let id = output::format_state_id(&qubit, qubit_count);
+            writeln!(dump, "{id}: [{}, {}]", amplitude.re, amplitude.im)
+                .map_err(|_| output::Error)?;
+        }
+        send_output("stdout", &dump);
+        Ok(())
+    }
+
+    fn register_state(
+        &mut self,
+        state: RegisterState,
+        qubit_count: usize,
+    ) -> Result<(), output::Error> {
+        let mut dump = String::new();
+        match state {
+            RegisterState::Separable(states) => {
+                dump.push_str("DumpRegister:\n");
+                for (qubit, amplitude) in states {
+                    let id = output::format_state_id(&qubit, qubit_count);
+                    writeln!(dump, "{id}: [{}, {}]", amplitude.re, amplitude.im)
+                        .map_err(|_| output::Error)?;
+                }
+            }
+            RegisterState::Entangled {
+                probabilities,
+                purity,
+            } => {
+                writeln!(
+                    dump,
+                    "DumpRegister: entangled with other qubits (purity {purity:.4})"
+                )
+                .map_err(|_| output::Error)?;
+                for (qubit, probability) in probabilities {
+                    let id = output::format_state_id(&qubit, qubit_count);
+                    writeln!(dump, "{id}: {:.4}%", probability * 100.0)
+                        .map_err(|_| output::Error)?;
+                }
+            }
+        }
+        send_output("stdout", &dump);
+        Ok(())
+    }
+
+    fn message(&mut self, msg: &str) -> Result<(), output::Error> {
+        send_output("stdout", &format!("{msg}\n"));
+        Ok(())
+    }
+}
+
+/// Reads the next message from the client, or `None` if the input has ended.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            let value = value
+                .trim()
+                .parse()
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            length = Some(value);
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message should have a content length",
+        )
+    })?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+fn send(mut message: Value) {
+    message["seq"] = SEQ.fetch_add(1, Ordering::Relaxed).into();
+    let content = message.to_string();
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "Content-Length: {}\r\n\r\n{content}", content.len())
+        .and_then(|()| stdout.flush())
+        .expect("standard out should be writable");
+}
+
+fn respond(request: &Value, body: Value) {
+    let mut response = json!({
+        "type": "response",
+        "request_seq": request["seq"],
+        "success": true,
+        "command": request["command"],
+    });
+    response["body"] = body;
+    send(response);
+}
+
+fn respond_error(request: &Value, message: &str) {
+    send(json!({
+        "type": "response",
+        "request_seq": request["seq"],
+        "success": false,
+        "command": request["command"],
+        "message": message,
+    }));
+}
+
+fn send_event(event: &str, body: Value) {
+    let mut message = json!({ "type": "event", "event": event });
+    message["body"] = body;
+    send(message);
+}
+
+fn send_output(category: &str, output: &str) {
+    send_event("output", json!({ "category": category, "output": output }));
+}
@@ -3,16 +3,18 @@
 
 #![warn(clippy::mod_module_files, clippy::pedantic, clippy::unwrap_used)]
 
-use clap::{crate_version, Parser};
+use clap::{crate_version, Parser, ValueEnum};
 use miette::{Context, IntoDiagnostic, Report, Result};
 use num_bigint::BigUint;
 use num_complex::Complex64;
 use qsc::interpret::{
+    args::Arg,
     stateful::{Interpreter, LineError},
-    stateless,
+    stateless, Circuit, Coverage, LogicalResources, MeasurementPolicy, NoiseModel, Profile,
+    Profiler, Recorders, ShotResults, Simulator, Tracer,
 };
 use qsc_eval::{
-    output::{self, Receiver},
+    output::{self, Receiver, RegisterState},
     val::Value,
 };
 use qsc_frontend::compile::{SourceContents, SourceMap, SourceName};
@@ -28,6 +30,7 @@ use std::{path::Path, sync::Arc};
 #[derive(Debug, Parser)]
 #[command(name = "qsi", version = concat!(crate_version!(), " (", env!("QSHARP_GIT_HASH"), ")"))]
 #[command(author, about, next_line_help = true)]
+#[allow(clippy::struct_excessive_bools)]
 struct Cli {
     /// Use the given file on startup as initial session input.
     #[arg(long = "use")]
@@ -37,6 +40,11 @@ struct Cli {
     #[arg(long)]
     entry: Option<String>,
 
+    /// Give an argument to a parameter of the entry point callable, written like a Q# literal, such
+    /// as `--arg n=3` or `--arg angles=[0.5, 1.0]`.
+    #[arg(long = "arg", value_name = "NAME=VALUE", value_parser = parse_arg, requires = "exec")]
+    args: Vec<(String, String)>,
+
     /// Disable automatic inclusion of the standard library.
     #[arg(long)]
     nostdlib: bool,
@@ -44,6 +52,143 @@ struct Cli {
     /// Exit after loading the files or running the given file(s)/entry on the command line.
     #[arg(long)]
     exec: bool,
+
+    /// The simulator to run quantum operations on.
+    #[arg(long, value_enum, default_value_t = SimulatorArg::Sparse)]
+    simulator: SimulatorArg,
+
+    /// The probability of a random Pauli error on each qubit a gate acts on.
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = parse_probability)]
+    depolarizing: f64,
+
+    /// The probability of an X error on each qubit a gate acts on.
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = parse_probability)]
+    bit_flip: f64,
+
+    /// The probability of a Z error on each qubit a gate acts on.
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = parse_probability)]
+    phase_flip: f64,
+
+    /// The probability of a random Pauli error on each active qubit a gate does not act on.
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = parse_probability)]
+    idle_noise: f64,
+
+    /// The probability that a measurement reports the wrong result.
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = parse_probability)]
+    readout_error: f64,
+
+    /// Seed the random number generator so that measurements and random numbers are reproducible.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Stop each evaluation with an error after this many steps, so that code that never finishes
+    /// cannot run forever.
+    #[arg(long, value_name = "STEPS")]
+    step_limit: Option<u64>,
+
+    /// Stop each evaluation with an error when it would allocate more than this many qubits at the
+    /// same time.
+    #[arg(long, value_name = "QUBITS")]
+    qubit_limit: Option<usize>,
+
+    /// Stop each evaluation with an error when it would build an array longer than this.
+    #[arg(long, value_name = "LEN")]
+    array_limit: Option<usize>,
+
+    /// Run the entry point this many times and print a histogram of the results.
+    #[arg(long, requires = "exec")]
+    shots: Option<usize>,
+
+    /// Profile the calls made by the program and print a table of the time spent in each callable
+    /// when it finishes.
+    #[arg(long, conflicts_with = "shots")]
+    profile: bool,
+
+    /// Profile the calls made by the program and write them to the given file in the Chrome trace
+    /// event format, which trace viewers can show as a flame graph.
+    #[arg(long, value_name = "FILE", conflicts_with = "shots")]
+    profile_trace: Option<PathBuf>,
+
+    /// Record which lines of the source files the entry point executes and write them to the given
+    /// file as an lcov tracefile.
+    #[arg(long, value_name = "FILE", requires = "exec", conflicts_with = "shots")]
+    coverage: Option<PathBuf>,
+
+    /// Trace the quantum operations that the entry point applies and print them as a circuit
+    /// diagram when it finishes.
+    #[arg(long, requires = "exec", conflicts_with = "shots")]
+    circuit: bool,
+
+    /// Run the entry point without simulating it and print the logical resources it uses, like
+    /// qubits, gate counts and depth.
+    #[arg(
+        long,
+        requires = "exec",
+        conflicts_with_all = ["shots", "circuit", "coverage", "profile", "profile_trace"]
+    )]
+    estimate: bool,
+
+    /// How measurements are resolved when estimating: `zero`, `random`, or the results in order as
+    /// `0`s and `1`s like `0110`, after which they are `Zero`.
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "zero",
+        requires = "estimate"
+    )]
+    measurements: MeasurementPolicy,
+}
+
+impl Cli {
+    fn noise(&self) -> NoiseModel {
+        NoiseModel {
+            depolarizing: self.depolarizing,
+            bit_flip: self.bit_flip,
+            phase_flip: self.phase_flip,
+            idle: self.idle_noise,
+            readout: self.readout_error,
+        }
+    }
+
+    fn profiler(&self) -> Option<Profiler> {
+        match (self.profile, &self.profile_trace) {
+            (false, None) => None,
+            (_, None) => Some(Profiler::new()),
+            (_, Some(_)) => Some(Profiler::new().with_trace()),
+        }
+    }
+}
+
+fn parse_arg(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) => Ok((name.trim().to_string(), value.to_string())),
+        None => Err("expected NAME=VALUE".to_string()),
+    }
+}
+
+fn parse_probability(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        Ok(_) => Err("probability must be between 0 and 1".to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SimulatorArg {
+    /// Sparse state-vector simulator that supports every operation.
+    Sparse,
+    /// Stabilizer simulator for large circuits made of Clifford operations only.
+    Stabilizer,
+}
+
+impl From<SimulatorArg> for Simulator {
+    fn from(arg: SimulatorArg) -> Self {
+        match arg {
+            SimulatorArg::Sparse => Simulator::Sparse,
+            SimulatorArg::Stabilizer => Simulator::Stabilizer,
+        }
+    }
 }
 
 struct TerminalReceiver;
@@ -63,6 +208,34 @@ impl Receiver for TerminalReceiver {
         Ok(())
     }
 
+    fn register_state(
+        &mut self,
+        state: RegisterState,
+        qubit_count: usize,
+    ) -> Result<(), output::Error> {
+        match state {
+            RegisterState::Separable(states) => {
+                println!("DumpRegister:");
+                for (qubit, amplitude) in states {
+                    let id = output::format_state_id(&qubit, qubit_count);
+                    println!("{id}: [{}, {}]", amplitude.re, amplitude.im);
+                }
+            }
+            RegisterState::Entangled {
+                probabilities,
+                purity,
+            } => {
+                println!("DumpRegister: entangled with other qubits (purity {purity:.4})");
+                for (qubit, probability) in probabilities {
+                    let id = output::format_state_id(&qubit, qubit_count);
+                    println!("{id}: {:.4}%", probability * 100.0);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn message(&mut self, msg: &str) -> Result<(), output::Error> {
         println!("{msg}");
         Ok(())
@@ -71,6 +244,7 @@ impl Receiver for TerminalReceiver {
 
 fn main() -> miette::Result<ExitCode> {
     let cli = Cli::parse();
+    let noise = cli.noise();
     let sources = cli
         .sources
         .iter()
@@ -78,10 +252,7 @@ fn main() -> miette::Result<ExitCode> {
         .collect::<miette::Result<Vec<_>>>()?;
 
     if cli.exec {
-        let context = match stateless::Context::new(
-            !cli.nostdlib,
-            SourceMap::new(sources, cli.entry.map(std::convert::Into::into)),
-        ) {
+        let context = match exec_context(&cli, sources) {
             Ok(context) => context,
             Err(errors) => {
                 for error in errors {
@@ -90,11 +261,65 @@ fn main() -> miette::Result<ExitCode> {
                 return Ok(ExitCode::FAILURE);
             }
         };
-        return Ok(print_exec_result(context.eval(&mut TerminalReceiver)));
+        if let Some(shots) = cli.shots {
+            return Ok(print_shot_results(
+                context.run_shots(shots, &mut TerminalReceiver),
+            ));
+        }
+        if cli.estimate {
+            return Ok(print_estimate(
+                context.estimate(cli.measurements.clone(), &mut TerminalReceiver),
+            ));
+        }
+
+        let mut profiler = cli.profiler();
+        let mut coverage = cli.coverage.as_ref().map(|_| Coverage::new());
+        let mut tracer = cli.circuit.then(Tracer::new);
+        let result = context.eval_with_recorders(
+            &mut TerminalReceiver,
+            Recorders {
+                profiler: profiler.as_mut(),
+                coverage: coverage.as_mut(),
+                tracer: tracer.as_mut(),
+            },
+        );
+        let exit_code = print_exec_result(result);
+        if let Some(tracer) = tracer {
+            print!("{}", Circuit::new(tracer.into_operations()).diagram());
+        }
+        if let Some(profiler) = &profiler {
+            report_profile(&cli, &context.profile(profiler))?;
+        }
+        if let (Some(path), Some(coverage)) = (&cli.coverage, &coverage) {
+            fs::write(path, context.lcov(coverage))
+                .into_diagnostic()
+                .with_context(|| format!("could not write coverage `{}`", path.display()))?;
+        }
+        return Ok(exit_code);
     }
 
     let mut interpreter = match Interpreter::new(!cli.nostdlib, SourceMap::new(sources, None)) {
-        Ok(interpreter) => interpreter,
+        Ok(interpreter) => {
+            let mut interpreter = interpreter
+                .with_simulator(cli.simulator.into())
+                .with_noise(noise);
+            if let Some(seed) = cli.seed {
+                interpreter = interpreter.with_seed(seed);
+            }
+            if let Some(steps) = cli.step_limit {
+                interpreter = interpreter.with_step_limit(steps);
+            }
+            if let Some(qubits) = cli.qubit_limit {
+                interpreter = interpreter.with_qubit_limit(qubits);
+            }
+            if let Some(len) = cli.array_limit {
+                interpreter = interpreter.with_array_limit(len);
+            }
+            if let Some(profiler) = cli.profiler() {
+                interpreter = interpreter.with_profiler(profiler);
+            }
+            interpreter
+        }
         Err(errors) => {
             for error in errors {
                 eprintln!("error: {:?}", Report::new(error));
@@ -103,18 +328,64 @@ fn main() -> miette::Result<ExitCode> {
         }
     };
 
-    if let Some(entry) = cli.entry {
+    if let Some(entry) = &cli.entry {
         print_interpret_result(
-            &entry,
-            interpreter.interpret_line(&mut TerminalReceiver, &entry),
+            entry,
+            interpreter.interpret_line(&mut TerminalReceiver, entry),
         );
     }
 
     repl(&mut interpreter, &mut TerminalReceiver).into_diagnostic()?;
+    if let Some(profile) = interpreter.profile() {
+        report_profile(&cli, &profile)?;
+    }
 
     Ok(ExitCode::SUCCESS)
 }
 
+/// Creates the context that runs the entry point, as configured on the command line.
+fn exec_context(
+    cli: &Cli,
+    sources: Vec<(SourceName, SourceContents)>,
+) -> Result<stateless::Context, Vec<stateless::Error>> {
+    let mut context = stateless::Context::new(
+        !cli.nostdlib,
+        SourceMap::new(sources, cli.entry.as_deref().map(Into::into)),
+    )?
+    .with_simulator(cli.simulator.into())
+    .with_noise(cli.noise());
+    if let Some(seed) = cli.seed {
+        context = context.with_seed(seed);
+    }
+    if let Some(steps) = cli.step_limit {
+        context = context.with_step_limit(steps);
+    }
+    if let Some(qubits) = cli.qubit_limit {
+        context = context.with_qubit_limit(qubits);
+    }
+    if let Some(len) = cli.array_limit {
+        context = context.with_array_limit(len);
+    }
+    context.with_args(
+        cli.args
+            .iter()
+            .map(|(name, value)| (name.clone(), Arg::Text(value.clone()))),
+    )
+}
+
+/// Prints the profile table and writes the trace, as requested on the command line.
+fn report_profile(cli: &Cli, profile: &Profile) -> miette::Result<()> {
+    if cli.profile {
+        eprint!("{}", profile.table());
+    }
+    if let Some(path) = &cli.profile_trace {
+        fs::write(path, profile.chrome_trace())
+            .into_diagnostic()
+            .with_context(|| format!("could not write profile trace `{}`", path.display()))?;
+    }
+    Ok(())
+}
+
 fn repl(interpreter: &mut Interpreter, receiver: &mut dyn Receiver) -> io::Result<()> {
     print_prompt(false);
 
@@ -187,14 +458,57 @@ fn print_exec_result(result: Result<Value, Vec<stateless::Error>>) -> ExitCode {
             ExitCode::SUCCESS
         }
         Err(errors) => {
-            for error in errors {
-                if let Some(stack_trace) = error.stack_trace() {
-                    eprintln!("{stack_trace}");
-                }
-                let report = Report::new(error);
-                eprintln!("error: {report:?}");
+            print_exec_errors(errors);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_shot_results(
+    results: Result<ShotResults<stateless::Error>, Vec<stateless::Error>>,
+) -> ExitCode {
+    match results {
+        Ok(results) => {
+            for (value, count) in &results.histogram {
+                println!("{value}: {count}");
             }
+            let succeeded = results.errors.is_empty();
+            for (shot, errors) in results.errors {
+                eprintln!("shot {shot} failed");
+                print_exec_errors(errors);
+            }
+            if succeeded {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(errors) => {
+            print_exec_errors(errors);
             ExitCode::FAILURE
         }
     }
 }
+
+fn print_estimate(result: Result<LogicalResources, Vec<stateless::Error>>) -> ExitCode {
+    match result {
+        Ok(resources) => {
+            println!("{resources}");
+            ExitCode::SUCCESS
+        }
+        Err(errors) => {
+            print_exec_errors(errors);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_exec_errors(errors: Vec<stateless::Error>) {
+    for error in errors {
+        if let Some(stack_trace) = error.stack_trace() {
+            eprintln!("{stack_trace}");
+        }
+        let report = Report::new(error);
+        eprintln!("error: {report:?}");
+    }
+}
@@ -8,6 +8,9 @@ use qsc_hir::hir::PackageId;
 use qsc_passes::{run_core_passes, run_default_passes};
 use thiserror::Error;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Clone, Debug, Diagnostic, Error)]
 #[diagnostic(transparent)]
 #[error(transparent)]
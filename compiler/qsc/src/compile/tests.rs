@@ -0,0 +1,82 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{compile, core, std};
+use indoc::indoc;
+use miette::{Diagnostic, Report};
+use qsc_codegen::{
+    qasm::generate_qasm,
+    qir::{generate_qir, Profile},
+};
+use qsc_frontend::compile::{PackageStore, SourceMap};
+use qsc_hir::hir::PackageId;
+use std::fmt::Write;
+
+fn compile_with_std(source: &str) -> (PackageStore, PackageId) {
+    let mut store = PackageStore::new(core());
+    let std = store.insert(std(&store));
+    let sources = SourceMap::new([("test".into(), source.into())], None);
+    let (unit, errors) = compile(&store, &[std], sources);
+    assert!(errors.is_empty(), "{errors:?}");
+    let package = store.insert(unit);
+    (store, package)
+}
+
+/// Renders each error with the sources of the compiled package, like the command line compiler.
+fn render(
+    store: &PackageStore,
+    package: PackageId,
+    errors: Vec<impl Diagnostic + Send + Sync + 'static>,
+) -> String {
+    let sources = &store
+        .get(package)
+        .expect("package should be in store")
+        .sources;
+    let mut rendered = String::new();
+    for error in errors {
+        let source = sources
+            .find_by_diagnostic(&error)
+            .expect("error should be in the compiled package");
+        write!(
+            rendered,
+            "{:?}",
+            Report::new(error).with_source_code(source.clone())
+        )
+        .expect("report should be written");
+    }
+    rendered
+}
+
+#[test]
+fn qir_error_in_std_is_rendered_at_call_site() {
+    let (store, package) = compile_with_std(indoc! {"
+        namespace Test {
+            open Microsoft.Quantum.Convert;
+            @EntryPoint()
+            operation Main() : Int {
+                use qs = Qubit[2];
+                ResultArrayAsInt([M(qs[0]), M(qs[1])])
+            }
+        }
+    "});
+    let errors = generate_qir(&store, package, Profile::Base).expect_err("codegen should fail");
+    let rendered = render(&store, package, errors);
+    assert!(rendered.contains("ResultArrayAsInt"), "{rendered}");
+}
+
+#[test]
+fn qasm_error_in_std_is_rendered_at_call_site() {
+    let (store, package) = compile_with_std(indoc! {r#"
+        namespace Test {
+            open Microsoft.Quantum.Diagnostics;
+            @EntryPoint()
+            operation Main() : Unit {
+                use q = Qubit();
+                Fact(M(q) == Zero, "qubit should be in the zero state");
+            }
+        }
+    "#});
+    let errors = generate_qasm(&store, package).expect_err("codegen should fail");
+    let rendered = render(&store, package, errors);
+    assert!(rendered.contains("Fact"), "{rendered}");
+}
@@ -0,0 +1,479 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Physical resource estimation turns the logical resources of a program into the physical qubits
+//! and runtime needed to run it fault-tolerantly on a qubit technology protected by a quantum
+//! error correction scheme.
+//!
+//! The logical qubits are laid out on a grid of surface code patches with routing space between
+//! them, and every operation takes one logical cycle except Toffoli gates, which take three, and
+//! Clifford gates, which are absorbed into lattice surgery. Rotations are synthesized from T
+//! gates and are assumed to run one after another. T states come from 15-to-1 distillation
+//! factories, with enough factories running in parallel to supply the T states as fast as the
+//! program consumes them. The error budget is split evenly between logical errors, T state errors
+//! and rotation synthesis errors.
+
+#[cfg(test)]
+mod tests;
+
+use miette::Diagnostic;
+use qsc_eval::backend::LogicalResources;
+use serde::Deserialize;
+use serde_json::json;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// The largest code distance that is considered.
+const MAX_CODE_DISTANCE: usize = 101;
+
+/// The largest number of distillation rounds that a T factory can have.
+const MAX_DISTILLATION_ROUNDS: u32 = 3;
+
+/// The logical cycles taken by one round of 15-to-1 distillation.
+const DISTILLATION_CYCLES: u64 = 6;
+
+/// The logical qubits used by one 15-to-1 distillation unit.
+const DISTILLATION_UNIT_QUBITS: u64 = 16;
+
+#[derive(Clone, Debug, Diagnostic, Error, PartialEq)]
+pub enum Error {
+    #[error("error budget {0} is not between 0 and 1")]
+    #[diagnostic(code("Qsc.Estimate.ErrorBudget"))]
+    ErrorBudget(f64),
+
+    #[error("{0} {1} of the qubit model is not a positive number of nanoseconds")]
+    #[diagnostic(code("Qsc.Estimate.QubitModelTime"))]
+    QubitModelTime(&'static str, f64),
+
+    #[error("{0} {1} of the qubit model is not between 0 and 1")]
+    #[diagnostic(code("Qsc.Estimate.QubitModelError"))]
+    QubitModelError(&'static str, f64),
+
+    #[error("threshold {0} of the error correction scheme is not between 0 and 1")]
+    #[diagnostic(code("Qsc.Estimate.Threshold"))]
+    Threshold(f64),
+
+    #[error("crossing prefactor {0} of the error correction scheme is not positive")]
+    #[diagnostic(code("Qsc.Estimate.CrossingPrefactor"))]
+    CrossingPrefactor(f64),
+
+    #[error(
+        "physical error rate {0} is not below the threshold {1} of the error correction scheme"
+    )]
+    #[diagnostic(code("Qsc.Estimate.AboveThreshold"))]
+    AboveThreshold(f64, f64),
+
+    #[error("no code distance up to {MAX_CODE_DISTANCE} reaches the logical error rate {0}")]
+    #[diagnostic(code("Qsc.Estimate.CodeDistance"))]
+    CodeDistance(f64),
+
+    #[error(
+        "no T factory with up to {MAX_DISTILLATION_ROUNDS} rounds of distillation reaches the T \
+         state error rate {0}"
+    )]
+    #[diagnostic(code("Qsc.Estimate.Distillation"))]
+    Distillation(f64),
+}
+
+/// The gate times, in nanoseconds, and error rates of a qubit technology.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct QubitModel {
+    #[serde(default = "QubitModel::custom_name")]
+    pub name: String,
+    pub one_qubit_gate_time: f64,
+    pub two_qubit_gate_time: f64,
+    pub measurement_time: f64,
+    pub one_qubit_gate_error: f64,
+    pub two_qubit_gate_error: f64,
+    pub measurement_error: f64,
+    /// The error rate of a T state prepared directly on a physical qubit, before distillation.
+    pub t_gate_error: f64,
+}
+
+impl QubitModel {
+    /// The names of the preset models, which can be parsed into a model.
+    pub const PRESETS: [&'static str; 4] = ["gate_ns_e3", "gate_ns_e4", "gate_us_e3", "gate_us_e4"];
+
+    /// A gate-based technology with nanosecond operations and error rates around 10⁻³, like
+    /// superconducting qubits.
+    #[must_use]
+    pub fn gate_ns_e3() -> Self {
+        Self::gate("gate_ns_e3", 50.0, 100.0, 1e-3)
+    }
+
+    /// A gate-based technology with nanosecond operations and error rates around 10⁻⁴.
+    #[must_use]
+    pub fn gate_ns_e4() -> Self {
+        Self::gate("gate_ns_e4", 50.0, 100.0, 1e-4)
+    }
+
+    /// A gate-based technology with microsecond operations and error rates around 10⁻³, like
+    /// trapped ions.
+    #[must_use]
+    pub fn gate_us_e3() -> Self {
+        Self::gate("gate_us_e3", 100_000.0, 100_000.0, 1e-3)
+    }
+
+    /// A gate-based technology with microsecond operations and error rates around 10⁻⁴.
+    #[must_use]
+    pub fn gate_us_e4() -> Self {
+        Self::gate("gate_us_e4", 100_000.0, 100_000.0, 1e-4)
+    }
+
+    /// The name of a model that is not a preset.
+    fn custom_name() -> String {
+        "custom".to_string()
+    }
+
+    fn gate(name: &str, gate_time: f64, measurement_time: f64, error: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            one_qubit_gate_time: gate_time,
+            two_qubit_gate_time: gate_time,
+            measurement_time,
+            one_qubit_gate_error: error,
+            two_qubit_gate_error: error,
+            measurement_error: error,
+            t_gate_error: error,
+        }
+    }
+
+    /// Checks that the gate times are positive and finite and the error rates are probabilities.
+    fn validate(&self) -> Result<(), Error> {
+        let times = [
+            ("one-qubit gate time", self.one_qubit_gate_time),
+            ("two-qubit gate time", self.two_qubit_gate_time),
+            ("measurement time", self.measurement_time),
+        ];
+        if let Some(&(name, time)) = times
+            .iter()
+            .find(|(_, time)| !(*time > 0.0 && time.is_finite()))
+        {
+            return Err(Error::QubitModelTime(name, time));
+        }
+        let errors = [
+            ("one-qubit gate error", self.one_qubit_gate_error),
+            ("two-qubit gate error", self.two_qubit_gate_error),
+            ("measurement error", self.measurement_error),
+            ("T gate error", self.t_gate_error),
+        ];
+        if let Some(&(name, error)) = errors
+            .iter()
+            .find(|(_, error)| !(*error > 0.0 && *error < 1.0))
+        {
+            return Err(Error::QubitModelError(name, error));
+        }
+        Ok(())
+    }
+
+    /// The error rate that the error correction scheme has to suppress, which is the largest
+    /// error rate of the Clifford operations.
+    fn clifford_error(&self) -> f64 {
+        self.one_qubit_gate_error
+            .max(self.two_qubit_gate_error)
+            .max(self.measurement_error)
+    }
+}
+
+/// Parses the name of a preset model, or a custom model written as a JSON object with a field for
+/// each gate time and error rate, like `{"one_qubit_gate_time": 50, ...}`, and an optional name.
+impl FromStr for QubitModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gate_ns_e3" => Ok(Self::gate_ns_e3()),
+            "gate_ns_e4" => Ok(Self::gate_ns_e4()),
+            "gate_us_e3" => Ok(Self::gate_us_e3()),
+            "gate_us_e4" => Ok(Self::gate_us_e4()),
+            _ if s.trim_start().starts_with('{') => {
+                serde_json::from_str(s).map_err(|error| format!("invalid qubit model: {error}"))
+            }
+            _ => Err(format!(
+                "expected one of {} or a JSON object",
+                Self::PRESETS.join(", ")
+            )),
+        }
+    }
+}
+
+/// A surface code error correction scheme, where a patch of code distance `d` uses `2d²` physical
+/// qubits and a logical cycle is `d` rounds of syndrome extraction. The logical error rate per
+/// qubit and cycle is `crossing_prefactor * (p / threshold)^((d + 1) / 2)` for a physical error
+/// rate `p`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct QecScheme {
+    pub threshold: f64,
+    pub crossing_prefactor: f64,
+}
+
+impl Default for QecScheme {
+    fn default() -> Self {
+        Self {
+            threshold: 0.01,
+            crossing_prefactor: 0.03,
+        }
+    }
+}
+
+/// Parses a scheme written as a JSON object, like `{"threshold": 0.01, "crossing_prefactor": 0.03}`,
+/// where a field that is left out has its default value.
+impl FromStr for QecScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|error| format!("invalid error correction scheme: {error}"))
+    }
+}
+
+impl QecScheme {
+    /// Checks that the threshold is a probability and the crossing prefactor is positive and finite.
+    fn validate(&self) -> Result<(), Error> {
+        if !(self.threshold > 0.0 && self.threshold < 1.0) {
+            return Err(Error::Threshold(self.threshold));
+        }
+        if !(self.crossing_prefactor > 0.0 && self.crossing_prefactor.is_finite()) {
+            return Err(Error::CrossingPrefactor(self.crossing_prefactor));
+        }
+        Ok(())
+    }
+
+    /// The logical error rate at the code distance, which is odd.
+    fn logical_error_rate(&self, physical_error: f64, distance: usize) -> f64 {
+        let exponent = i32::try_from(distance / 2 + 1).expect("distance should fit in i32");
+        self.crossing_prefactor * (physical_error / self.threshold).powi(exponent)
+    }
+
+    fn physical_qubits(distance: usize) -> u64 {
+        2 * (distance * distance) as u64
+    }
+
+    /// The time of a logical cycle in nanoseconds, where each round of syndrome extraction takes
+    /// four two-qubit gates and two measurements.
+    #[allow(clippy::cast_precision_loss)]
+    fn cycle_time(model: &QubitModel, distance: usize) -> f64 {
+        (4.0 * model.two_qubit_gate_time + 2.0 * model.measurement_time) * distance as f64
+    }
+}
+
+/// The physical resources needed to run a program, with the intermediate quantities of the
+/// estimate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicalResources {
+    pub logical: LogicalResources,
+    pub qubit_model: QubitModel,
+    pub qec_scheme: QecScheme,
+    pub error_budget: f64,
+    /// The logical qubits of the layout, including routing space.
+    pub logical_qubits: u64,
+    /// The logical cycles that the program takes.
+    pub logical_cycles: u64,
+    /// The T gates that each rotation is synthesized from.
+    pub t_gates_per_rotation: u64,
+    /// The T states consumed by T gates, Toffoli gates and rotations.
+    pub t_states: u64,
+    pub code_distance: usize,
+    /// The logical error rate per qubit and cycle at the code distance.
+    pub logical_error_rate: f64,
+    /// The time of a logical cycle in nanoseconds.
+    pub logical_cycle_time: f64,
+    /// The runtime of the program in nanoseconds.
+    pub runtime: f64,
+    pub t_factories: u64,
+    pub t_factory_rounds: u32,
+    pub physical_qubits_per_t_factory: u64,
+    /// The physical qubits of the logical qubits of the layout.
+    pub physical_qubits_for_algorithm: u64,
+    /// The physical qubits of all the T factories.
+    pub physical_qubits_for_t_factories: u64,
+    pub physical_qubits: u64,
+}
+
+impl PhysicalResources {
+    /// The estimate as JSON, with the logical resources in a `logical` object, the qubit model in a
+    /// `qubit_model` object and the error correction scheme in a `qec_scheme` object. Times are in
+    /// nanoseconds.
+    #[must_use]
+    pub fn json(&self) -> String {
+        let logical = &self.logical;
+        let model = &self.qubit_model;
+        let qec = &self.qec_scheme;
+        json!({
+            "logical": {
+                "peak_qubits": logical.peak_qubits,
+                "total_qubits": logical.total_qubits,
+                "t_gates": logical.t_gates,
+                "rotations": logical.rotations,
+                "cnot_gates": logical.cnot_gates,
+                "ccx_gates": logical.ccx_gates,
+                "measurements": logical.measurements,
+                "other_gates": logical.other_gates,
+                "depth": logical.depth,
+                "t_depth": logical.t_depth,
+            },
+            "qubit_model": {
+                "name": model.name,
+                "one_qubit_gate_time": model.one_qubit_gate_time,
+                "two_qubit_gate_time": model.two_qubit_gate_time,
+                "measurement_time": model.measurement_time,
+                "one_qubit_gate_error": model.one_qubit_gate_error,
+                "two_qubit_gate_error": model.two_qubit_gate_error,
+                "measurement_error": model.measurement_error,
+                "t_gate_error": model.t_gate_error,
+            },
+            "qec_scheme": {
+                "threshold": qec.threshold,
+                "crossing_prefactor": qec.crossing_prefactor,
+            },
+            "error_budget": self.error_budget,
+            "logical_qubits": self.logical_qubits,
+            "logical_cycles": self.logical_cycles,
+            "t_gates_per_rotation": self.t_gates_per_rotation,
+            "t_states": self.t_states,
+            "code_distance": self.code_distance,
+            "logical_error_rate": self.logical_error_rate,
+            "logical_cycle_time": self.logical_cycle_time,
+            "runtime": self.runtime,
+            "t_factories": self.t_factories,
+            "t_factory_rounds": self.t_factory_rounds,
+            "physical_qubits_per_t_factory": self.physical_qubits_per_t_factory,
+            "physical_qubits_for_algorithm": self.physical_qubits_for_algorithm,
+            "physical_qubits_for_t_factories": self.physical_qubits_for_t_factories,
+            "physical_qubits": self.physical_qubits,
+        })
+        .to_string()
+    }
+}
+
+impl fmt::Display for PhysicalResources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "physical qubits: {}", self.physical_qubits)?;
+        writeln!(f, "runtime:         {} ns", self.runtime)?;
+        writeln!(f, "code distance:   {}", self.code_distance)?;
+        writeln!(f, "logical qubits:  {}", self.logical_qubits)?;
+        writeln!(f, "logical cycles:  {}", self.logical_cycles)?;
+        writeln!(f, "T states:        {}", self.t_states)?;
+        write!(f, "T factories:     {}", self.t_factories)
+    }
+}
+
+/// Estimates the physical resources needed to run a program with the logical resources on the
+/// qubit technology, protected by the error correction scheme, such that the probability of any
+/// error is at most the error budget.
+///
+/// # Errors
+///
+/// Returns an error if the error budget is not between 0 and 1, if the qubit model or the error
+/// correction scheme has a time, rate or threshold that is out of range, if the qubit technology is
+/// not good enough for the error correction scheme, or if no code distance or T factory reaches the
+/// error rates needed for the budget.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub fn estimate(
+    logical: &LogicalResources,
+    qubit_model: &QubitModel,
+    qec: &QecScheme,
+    error_budget: f64,
+) -> Result<PhysicalResources, Error> {
+    if !(error_budget > 0.0 && error_budget < 1.0) {
+        return Err(Error::ErrorBudget(error_budget));
+    }
+    qubit_model.validate()?;
+    qec.validate()?;
+    let physical_error = qubit_model.clifford_error();
+    if physical_error >= qec.threshold {
+        return Err(Error::AboveThreshold(physical_error, qec.threshold));
+    }
+
+    let qubits = logical.peak_qubits as u64;
+    let logical_qubits = if qubits == 0 {
+        0
+    } else {
+        2 * qubits + (8.0 * qubits as f64).sqrt().ceil() as u64 + 1
+    };
+
+    let rotations = logical.rotations as u64;
+    let t_states_needed = logical.t_gates + logical.ccx_gates + logical.rotations > 0;
+    let parts = 1.0 + f64::from(u8::from(t_states_needed)) + f64::from(u8::from(rotations > 0));
+    let budget = error_budget / parts;
+    let t_gates_per_rotation = if rotations == 0 {
+        0
+    } else {
+        (0.53 * (rotations as f64 / budget).log2() + 5.3).ceil() as u64
+    };
+    let t_states =
+        logical.t_gates as u64 + 4 * logical.ccx_gates as u64 + rotations * t_gates_per_rotation;
+    let logical_cycles = (logical.measurements + logical.t_gates) as u64
+        + 3 * logical.ccx_gates as u64
+        + rotations * (1 + t_gates_per_rotation);
+
+    let required_error = budget / (logical_qubits.max(1) * logical_cycles.max(1)) as f64;
+    let code_distance = (1..=MAX_CODE_DISTANCE)
+        .step_by(2)
+        .find(|&d| qec.logical_error_rate(physical_error, d) <= required_error)
+        .ok_or(Error::CodeDistance(required_error))?;
+    let logical_cycle_time = QecScheme::cycle_time(qubit_model, code_distance);
+    let runtime = logical_cycles as f64 * logical_cycle_time;
+
+    let (t_factory_rounds, t_factories, physical_qubits_per_t_factory) = if t_states == 0 {
+        (0, 0, 0)
+    } else {
+        let rounds = distillation_rounds(qubit_model.t_gate_error, budget / t_states as f64)?;
+        let units = 15u64.pow(rounds.saturating_sub(1));
+        let factory_qubits = if rounds == 0 {
+            0
+        } else {
+            units * DISTILLATION_UNIT_QUBITS * QecScheme::physical_qubits(code_distance)
+        };
+        let factory_cycles = u64::from(rounds) * DISTILLATION_CYCLES;
+        let factories = if rounds == 0 {
+            0
+        } else {
+            (t_states * factory_cycles - 1) / logical_cycles.max(1) + 1
+        };
+        (rounds, factories, factory_qubits)
+    };
+
+    let physical_qubits_for_algorithm = logical_qubits * QecScheme::physical_qubits(code_distance);
+    let physical_qubits_for_t_factories = t_factories * physical_qubits_per_t_factory;
+    Ok(PhysicalResources {
+        logical: *logical,
+        qubit_model: qubit_model.clone(),
+        qec_scheme: *qec,
+        error_budget,
+        logical_qubits,
+        logical_cycles,
+        t_gates_per_rotation,
+        t_states,
+        code_distance,
+        logical_error_rate: qec.logical_error_rate(physical_error, code_distance),
+        logical_cycle_time,
+        runtime,
+        t_factories,
+        t_factory_rounds,
+        physical_qubits_per_t_factory,
+        physical_qubits_for_algorithm,
+        physical_qubits_for_t_factories,
+        physical_qubits: physical_qubits_for_algorithm + physical_qubits_for_t_factories,
+    })
+}
+
+/// The number of rounds of 15-to-1 distillation, each of which turns T states with error rate `p`
+/// into one with error rate `35p³`, needed to reach the target error rate.
+fn distillation_rounds(t_gate_error: f64, target: f64) -> Result<u32, Error> {
+    let mut error = t_gate_error;
+    let mut rounds = 0;
+    while error > target {
+        if rounds == MAX_DISTILLATION_ROUNDS {
+            return Err(Error::Distillation(target));
+        }
+        error = 35.0 * error.powi(3);
+        rounds += 1;
+    }
+    Ok(rounds)
+}
@@ -0,0 +1,221 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{estimate, Error, QecScheme, QubitModel};
+use expect_test::expect;
+use qsc_eval::backend::LogicalResources;
+
+fn resources() -> LogicalResources {
+    LogicalResources {
+        peak_qubits: 10,
+        total_qubits: 10,
+        t_gates: 100,
+        rotations: 0,
+        cnot_gates: 50,
+        ccx_gates: 20,
+        measurements: 10,
+        other_gates: 40,
+        depth: 120,
+        t_depth: 60,
+    }
+}
+
+#[test]
+fn t_states_are_supplied_by_factories() {
+    let physical = estimate(
+        &resources(),
+        &QubitModel::gate_ns_e3(),
+        &QecScheme::default(),
+        1e-3,
+    )
+    .expect("estimate should succeed");
+    expect![[r"
+        physical qubits: 34364
+        runtime:         748000 ns
+        code distance:   11
+        logical qubits:  30
+        logical cycles:  170
+        T states:        180
+        T factories:     7"]]
+    .assert_eq(&physical.to_string());
+    assert_eq!(physical.t_factory_rounds, 1);
+    assert_eq!(
+        physical.physical_qubits,
+        physical.physical_qubits_for_algorithm + 7 * physical.physical_qubits_per_t_factory
+    );
+}
+
+#[test]
+fn clifford_program_needs_no_factories() {
+    let logical = LogicalResources {
+        t_gates: 0,
+        ccx_gates: 0,
+        ..resources()
+    };
+    let physical = estimate(
+        &logical,
+        &QubitModel::gate_ns_e4(),
+        &QecScheme::default(),
+        1e-3,
+    )
+    .expect("estimate should succeed");
+    assert_eq!(physical.t_states, 0);
+    assert_eq!(physical.t_factories, 0);
+    assert_eq!(physical.physical_qubits_for_t_factories, 0);
+    assert_eq!(physical.logical_cycles, 10);
+    assert_eq!(physical.code_distance, 3);
+}
+
+#[test]
+fn rotations_are_synthesized_from_t_gates() {
+    let logical = LogicalResources {
+        rotations: 100,
+        ..resources()
+    };
+    let physical = estimate(
+        &logical,
+        &QubitModel::gate_us_e3(),
+        &QecScheme::default(),
+        1e-3,
+    )
+    .expect("estimate should succeed");
+    assert_eq!(physical.t_gates_per_rotation, 15);
+    assert_eq!(physical.t_states, 180 + 100 * 15);
+    assert_eq!(physical.logical_cycles, 170 + 100 * 16);
+}
+
+#[test]
+fn lower_error_budget_needs_larger_code_distance() {
+    let model = QubitModel::gate_ns_e3();
+    let loose = estimate(&resources(), &model, &QecScheme::default(), 1e-2)
+        .expect("estimate should succeed");
+    let strict = estimate(&resources(), &model, &QecScheme::default(), 1e-6)
+        .expect("estimate should succeed");
+    assert!(strict.code_distance > loose.code_distance);
+    assert!(strict.runtime > loose.runtime);
+}
+
+#[test]
+fn error_rate_above_threshold_fails() {
+    let model = QubitModel {
+        two_qubit_gate_error: 0.02,
+        ..QubitModel::gate_ns_e3()
+    };
+    let error = estimate(&resources(), &model, &QecScheme::default(), 1e-3)
+        .expect_err("estimate should fail");
+    assert_eq!(error, Error::AboveThreshold(0.02, 0.01));
+}
+
+#[test]
+fn invalid_error_budget_fails() {
+    let error = estimate(
+        &resources(),
+        &QubitModel::gate_ns_e3(),
+        &QecScheme::default(),
+        0.0,
+    )
+    .expect_err("estimate should fail");
+    assert_eq!(error, Error::ErrorBudget(0.0));
+}
+
+#[test]
+fn json_report_has_logical_and_physical_resources() {
+    let physical = estimate(
+        &resources(),
+        &QubitModel::gate_ns_e3(),
+        &QecScheme::default(),
+        1e-3,
+    )
+    .expect("estimate should succeed");
+    let json: serde_json::Value =
+        serde_json::from_str(&physical.json()).expect("report should be JSON");
+    assert_eq!(json["logical"]["t_gates"], 100);
+    assert_eq!(json["qubit_model"]["name"], "gate_ns_e3");
+    assert_eq!(json["code_distance"], 11);
+    assert_eq!(json["physical_qubits"], physical.physical_qubits);
+}
+
+#[test]
+fn presets_are_parsed() {
+    for name in QubitModel::PRESETS {
+        let model: QubitModel = name.parse().expect("preset should parse");
+        assert_eq!(model.name, name);
+    }
+    assert!("gate_ps_e9".parse::<QubitModel>().is_err());
+}
+
+#[test]
+fn custom_model_is_parsed_from_json() {
+    let model: QubitModel = r#"{
+        "name": "slow",
+        "one_qubit_gate_time": 50,
+        "two_qubit_gate_time": 50,
+        "measurement_time": 100,
+        "one_qubit_gate_error": 1e-3,
+        "two_qubit_gate_error": 1e-3,
+        "measurement_error": 1e-3,
+        "t_gate_error": 1e-3
+    }"#
+    .parse()
+    .expect("model should parse");
+    assert_eq!(
+        model,
+        QubitModel {
+            name: "slow".to_string(),
+            ..QubitModel::gate_ns_e3()
+        }
+    );
+    assert!(r#"{"one_qubit_gate_time": 50}"#.parse::<QubitModel>().is_err());
+}
+
+#[test]
+fn invalid_custom_model_fails() {
+    let model = QubitModel {
+        measurement_time: 0.0,
+        ..QubitModel::gate_ns_e3()
+    };
+    let error = estimate(&resources(), &model, &QecScheme::default(), 1e-3)
+        .expect_err("estimate should fail");
+    assert_eq!(error, Error::QubitModelTime("measurement time", 0.0));
+    let model = QubitModel {
+        t_gate_error: 1.5,
+        ..QubitModel::gate_ns_e3()
+    };
+    let error = estimate(&resources(), &model, &QecScheme::default(), 1e-3)
+        .expect_err("estimate should fail");
+    assert_eq!(error, Error::QubitModelError("T gate error", 1.5));
+}
+
+#[test]
+fn custom_scheme_is_used_and_reported() {
+    let qec: QecScheme = r#"{"threshold": 0.005}"#.parse().expect("scheme should parse");
+    assert_eq!(
+        qec,
+        QecScheme {
+            threshold: 0.005,
+            ..QecScheme::default()
+        }
+    );
+    let model = QubitModel::gate_ns_e3();
+    let default = estimate(&resources(), &model, &QecScheme::default(), 1e-3)
+        .expect("estimate should succeed");
+    let physical = estimate(&resources(), &model, &qec, 1e-3).expect("estimate should succeed");
+    assert!(physical.code_distance > default.code_distance);
+    let json: serde_json::Value =
+        serde_json::from_str(&physical.json()).expect("report should be JSON");
+    assert_eq!(json["qec_scheme"]["threshold"], 0.005);
+    assert_eq!(json["qec_scheme"]["crossing_prefactor"], 0.03);
+
+    let error = estimate(
+        &resources(),
+        &model,
+        &QecScheme {
+            threshold: 1.5,
+            ..QecScheme::default()
+        },
+        1e-3,
+    )
+    .expect_err("estimate should fail");
+    assert_eq!(error, Error::Threshold(1.5));
+    assert!(r#"{"distance": 3}"#.parse::<QecScheme>().is_err());
+}
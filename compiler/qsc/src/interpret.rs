@@ -1,11 +1,25 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+pub mod args;
+mod circuit;
+mod coverage;
 mod debug;
+mod profile;
+mod shots;
 pub mod stateful;
 pub mod stateless;
 
+pub use circuit::{Cell, Circuit};
+pub use profile::Profile;
+pub use shots::ShotResults;
+
 pub use qsc_eval::{
+    backend::{LogicalResources, MeasurementPolicy, NoiseModel, Simulator},
+    circuit::{Gate, Operation, OperationKind, Tracer},
+    coverage::Coverage,
     output::{self, GenericReceiver},
+    profile::Profiler,
     val::Value,
+    CancellationToken, Recorders,
 };
@@ -0,0 +1,365 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#[cfg(test)]
+mod tests;
+
+use miette::Diagnostic;
+use qsc_data_structures::span::Span;
+use qsc_eval::val::Value;
+use qsc_hir::{
+    hir::{self, Expr, ExprKind, Lit, NodeId, StringComponent},
+    ty::{Prim, Ty},
+};
+use qsc_passes::entry_point::Param;
+use thiserror::Error;
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+pub enum Error {
+    #[error("entry point has no parameter `{0}`")]
+    #[diagnostic(code("Qsc.Args.Unknown"))]
+    Unknown(String),
+
+    #[error("duplicate argument for parameter `{0}`")]
+    #[diagnostic(code("Qsc.Args.Duplicate"))]
+    Duplicate(String, #[label("parameter")] Span),
+
+    #[error("missing argument for parameter `{0}`")]
+    #[diagnostic(code("Qsc.Args.Missing"))]
+    Missing(String, #[label("parameter")] Span),
+
+    #[error("invalid argument for parameter `{0}` of type `{1}`: {2}")]
+    #[diagnostic(code("Qsc.Args.Ty"))]
+    Ty(String, String, String, #[label("parameter")] Span),
+}
+
+/// An argument for a parameter of the entry point, which is checked against the type of the
+/// parameter before evaluation.
+#[derive(Clone, Debug)]
+pub enum Arg {
+    /// An argument written like a Q# literal, such as `3`, `[1.0, 2.5]` or `(PauliX, "label")`. A
+    /// big integer may have the `L` suffix, and a string only needs quotes inside an array or a
+    /// tuple.
+    Text(String),
+    /// A JSON argument. Pauli operators and results are strings like `"PauliX"` and `"One"`, a big
+    /// integer may also be a string of digits, and a tuple is an array. An object is read as the
+    /// typed JSON form of a [`Value`], like `{"type": "Int", "value": 3}`.
+    Json(serde_json::Value),
+    /// An argument that is already a value. An `Int` is also accepted for a `Double` or `BigInt`
+    /// parameter.
+    Value(Value),
+}
+
+/// Checks the named arguments against the parameters, returning an expression for each
+/// parameter in order.
+pub(super) fn bind(
+    params: &[Param],
+    args: impl IntoIterator<Item = (String, Arg)>,
+) -> Result<Vec<Expr>, Vec<Error>> {
+    let mut values = vec![None; params.len()];
+    let mut given = vec![false; params.len()];
+    let mut errors = Vec::new();
+    for (name, arg) in args {
+        let Some(index) = params.iter().position(|param| *param.name == name) else {
+            errors.push(Error::Unknown(name));
+            continue;
+        };
+        let param = &params[index];
+        if given[index] {
+            errors.push(Error::Duplicate(name, param.span));
+            continue;
+        }
+        given[index] = true;
+        match value(&param.ty, arg) {
+            Ok(value) => values[index] = Some(value),
+            Err(reason) => errors.push(Error::Ty(name, param.ty.to_string(), reason, param.span)),
+        }
+    }
+
+    let mut exprs = Vec::new();
+    for ((param, value), given) in params.iter().zip(values).zip(given) {
+        match value {
+            Some(value) => exprs.push(expr(&param.ty, value)),
+            None if !given => errors.push(Error::Missing(param.name.to_string(), param.span)),
+            None => {}
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(exprs)
+    } else {
+        Err(errors)
+    }
+}
+
+fn value(ty: &Ty, arg: Arg) -> Result<Value, String> {
+    match arg {
+        Arg::Text(text) => {
+            if *ty == Ty::Prim(Prim::String) && !text.trim_start().starts_with('"') {
+                return Ok(Value::String(text.into()));
+            }
+            let mut parser = TextParser { rest: &text };
+            let value = parser.value(ty)?;
+            parser.skip_whitespace();
+            if parser.rest.is_empty() {
+                Ok(value)
+            } else {
+                Err(format!("unexpected `{}` after the argument", parser.rest))
+            }
+        }
+        Arg::Json(json) => from_json(ty, &json),
+        Arg::Value(value) => check(ty, value),
+    }
+}
+
+/// Parses text in Q# literal syntax, guided by the type that the text should have.
+struct TextParser<'a> {
+    rest: &'a str,
+}
+
+impl TextParser<'_> {
+    fn value(&mut self, ty: &Ty) -> Result<Value, String> {
+        self.skip_whitespace();
+        match ty {
+            Ty::Array(item) => {
+                self.expect('[')?;
+                let mut items = Vec::new();
+                if !self.eat(']') {
+                    loop {
+                        items.push(self.value(item)?);
+                        if self.eat(']') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                Ok(Value::Array(items.into()))
+            }
+            Ty::Tuple(tys) => {
+                self.expect('(')?;
+                let mut items = Vec::new();
+                for (index, ty) in tys.iter().enumerate() {
+                    if index > 0 {
+                        self.expect(',')?;
+                    }
+                    items.push(self.value(ty)?);
+                }
+                if tys.len() == 1 {
+                    self.eat(',');
+                }
+                self.expect(')')?;
+                Ok(Value::Tuple(items.into()))
+            }
+            Ty::Prim(Prim::String) if self.rest.starts_with('"') => self.string(),
+            Ty::Prim(prim) => {
+                let end = self.rest.find([',', ']', ')']).unwrap_or(self.rest.len());
+                let token = self.rest[..end].trim_end();
+                self.rest = &self.rest[end..];
+                prim_value(*prim, token)
+                    .ok_or_else(|| format!("expected `{ty}`, found {}", found(token)))
+            }
+            _ => Err(format!("`{ty}` cannot be given as an argument")),
+        }
+    }
+
+    /// Parses a string in quotes, where a quote or a backslash is escaped with a backslash.
+    fn string(&mut self) -> Result<Value, String> {
+        let mut string = String::new();
+        let mut chars = self.rest.char_indices().skip(1);
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[index + 1..];
+                    return Ok(Value::String(string.into()));
+                }
+                '\\' => match chars.next() {
+                    Some((_, c @ ('"' | '\\'))) => string.push(c),
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, 't')) => string.push('\t'),
+                    Some((_, c)) => return Err(format!("unknown escape sequence `\\{c}`")),
+                    None => break,
+                },
+                c => string.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("expected `{c}`, found {}", found(self.rest)))
+        }
+    }
+}
+
+fn found(text: &str) -> String {
+    if text.is_empty() {
+        "end of argument".to_string()
+    } else {
+        format!("`{text}`")
+    }
+}
+
+fn prim_value(prim: Prim, token: &str) -> Option<Value> {
+    match prim {
+        Prim::BigInt => token
+            .strip_suffix('L')
+            .unwrap_or(token)
+            .parse()
+            .ok()
+            .map(Value::BigInt),
+        Prim::Bool => match token {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Prim::Double => token.parse().ok().map(Value::Double),
+        Prim::Int => token.parse().ok().map(Value::Int),
+        Prim::Pauli => pauli(token),
+        Prim::Result => result(token),
+        Prim::String => Some(Value::String(token.into())),
+        _ => None,
+    }
+}
+
+fn pauli(name: &str) -> Option<Value> {
+    match name {
+        "PauliI" => Some(Value::Pauli(hir::Pauli::I)),
+        "PauliX" => Some(Value::Pauli(hir::Pauli::X)),
+        "PauliY" => Some(Value::Pauli(hir::Pauli::Y)),
+        "PauliZ" => Some(Value::Pauli(hir::Pauli::Z)),
+        _ => None,
+    }
+}
+
+fn result(name: &str) -> Option<Value> {
+    match name {
+        "Zero" => Some(Value::Result(false)),
+        "One" => Some(Value::Result(true)),
+        _ => None,
+    }
+}
+
+fn from_json(ty: &Ty, json: &serde_json::Value) -> Result<Value, String> {
+    use serde_json::Value as Json;
+
+    let value = match (ty, json) {
+        (_, Json::Object(_)) => {
+            let value = serde_json::from_value(json.clone()).map_err(|error| error.to_string())?;
+            return check(ty, value);
+        }
+        (Ty::Array(item), Json::Array(items)) => Some(Value::Array(
+            items
+                .iter()
+                .map(|json| from_json(item, json))
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+        )),
+        (Ty::Tuple(tys), Json::Array(items)) if tys.len() == items.len() => Some(Value::Tuple(
+            tys.iter()
+                .zip(items)
+                .map(|(ty, json)| from_json(ty, json))
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+        )),
+        (Ty::Prim(Prim::BigInt), Json::Number(n)) => n.as_i64().map(|n| Value::BigInt(n.into())),
+        (Ty::Prim(Prim::BigInt), Json::String(s)) => s.parse().ok().map(Value::BigInt),
+        (Ty::Prim(Prim::Bool), Json::Bool(b)) => Some(Value::Bool(*b)),
+        (Ty::Prim(Prim::Double), Json::Number(n)) => n.as_f64().map(Value::Double),
+        (Ty::Prim(Prim::Int), Json::Number(n)) => n.as_i64().map(Value::Int),
+        (Ty::Prim(Prim::Pauli), Json::String(s)) => pauli(s),
+        (Ty::Prim(Prim::Result), Json::String(s)) => result(s),
+        (Ty::Prim(Prim::String), Json::String(s)) => Some(Value::String(s.as_str().into())),
+        _ => None,
+    };
+    value.ok_or_else(|| format!("expected `{ty}`, found `{json}`"))
+}
+
+fn check(ty: &Ty, value: Value) -> Result<Value, String> {
+    match (ty, value) {
+        (Ty::Array(item), Value::Array(items)) => Ok(Value::Array(
+            items
+                .iter()
+                .map(|value| check(item, value.clone()))
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+        )),
+        (Ty::Tuple(tys), Value::Tuple(items)) if tys.len() == items.len() => Ok(Value::Tuple(
+            tys.iter()
+                .zip(items.iter())
+                .map(|(ty, value)| check(ty, value.clone()))
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+        )),
+        (Ty::Prim(Prim::BigInt), Value::Int(n)) => Ok(Value::BigInt(n.into())),
+        #[allow(clippy::cast_precision_loss)]
+        (Ty::Prim(Prim::Double), Value::Int(n)) => Ok(Value::Double(n as f64)),
+        (Ty::Prim(Prim::BigInt), value @ Value::BigInt(_))
+        | (Ty::Prim(Prim::Bool), value @ Value::Bool(_))
+        | (Ty::Prim(Prim::Double), value @ Value::Double(_))
+        | (Ty::Prim(Prim::Int), value @ Value::Int(_))
+        | (Ty::Prim(Prim::Pauli), value @ Value::Pauli(_))
+        | (Ty::Prim(Prim::Result), value @ Value::Result(_))
+        | (Ty::Prim(Prim::String), value @ Value::String(_)) => Ok(value),
+        (ty, value) => Err(format!(
+            "expected `{ty}`, found {} `{value}`",
+            value.type_name()
+        )),
+    }
+}
+
+/// The literal expression for a value that was checked against the type.
+fn expr(ty: &Ty, value: Value) -> Expr {
+    let kind = match (ty, value) {
+        (Ty::Array(item), Value::Array(items)) => ExprKind::Array(
+            items
+                .iter()
+                .map(|value| expr(item, value.clone()))
+                .collect(),
+        ),
+        (Ty::Tuple(tys), Value::Tuple(items)) => ExprKind::Tuple(
+            tys.iter()
+                .zip(items.iter())
+                .map(|(ty, value)| expr(ty, value.clone()))
+                .collect(),
+        ),
+        (_, Value::BigInt(n)) => ExprKind::Lit(Lit::BigInt(n)),
+        (_, Value::Bool(b)) => ExprKind::Lit(Lit::Bool(b)),
+        (_, Value::Double(d)) => ExprKind::Lit(Lit::Double(d)),
+        (_, Value::Int(n)) => ExprKind::Lit(Lit::Int(n)),
+        (_, Value::Pauli(p)) => ExprKind::Lit(Lit::Pauli(p)),
+        (_, Value::Result(r)) => ExprKind::Lit(Lit::Result(if r {
+            hir::Result::One
+        } else {
+            hir::Result::Zero
+        })),
+        (_, Value::String(s)) => ExprKind::String(vec![StringComponent::Lit(s)]),
+        (_, value) => panic!(
+            "value should have been checked, found {}",
+            value.type_name()
+        ),
+    };
+    Expr {
+        id: NodeId::default(),
+        span: Span::default(),
+        ty: ty.clone(),
+        kind,
+    }
+}
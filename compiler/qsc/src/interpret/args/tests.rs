@@ -0,0 +1,232 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::Arg;
+use crate::interpret::{stateful::Interpreter, stateless::Context};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_eval::{output::CursorReceiver, val::Value};
+use qsc_frontend::compile::SourceMap;
+use serde_json::json;
+use std::{fmt::Write, io::Cursor};
+
+fn context(main: &str) -> Context {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            format!("namespace Test {{ @EntryPoint() {main} }}").into(),
+        )],
+        None,
+    );
+    Context::new(true, sources).expect("code should compile")
+}
+
+fn check(main: &str, args: Vec<(&str, Arg)>, expect: &Expect) {
+    let args = args.into_iter().map(|(name, arg)| (name.to_string(), arg));
+    let actual = match context(main).with_args(args) {
+        Ok(context) => {
+            let mut cursor = Cursor::new(Vec::new());
+            match context.eval(&mut CursorReceiver::new(&mut cursor)) {
+                Ok(value) => value.to_string(),
+                Err(errors) => format!("{errors:?}"),
+            }
+        }
+        Err(errors) => errors.iter().fold(String::new(), |mut actual, error| {
+            writeln!(actual, "{error}").expect("string should be writable");
+            actual
+        }),
+    };
+    expect.assert_eq(&actual);
+}
+
+fn text(text: &str) -> Arg {
+    Arg::Text(text.to_string())
+}
+
+#[test]
+fn text_args_of_each_type() {
+    check(
+        indoc! {"
+            function Main(
+                n : Int,
+                x : Double,
+                b : Bool,
+                s : String,
+                p : Pauli,
+                r : Result,
+                big : BigInt,
+                xs : Int[],
+                t : (String, Double[]),
+            ) : (Int, Double, Bool, String, Pauli, Result, BigInt, Int[], (String, Double[])) {
+                (n, x, b, s, p, r, big, xs, t)
+            }
+        "},
+        vec![
+            ("n", text("-3")),
+            ("x", text("2.5")),
+            ("b", text("true")),
+            ("s", text("hello, world")),
+            ("p", text("PauliY")),
+            ("r", text("One")),
+            ("big", text("123456789012345678901234567890L")),
+            ("xs", text("[1, 2 ,3]")),
+            ("t", text(r#"("a \"quoted\" string", [1, 0.5])"#)),
+        ],
+        &expect![[
+            r#"(-3, 2.5, true, hello, world, PauliY, One, 123456789012345678901234567890, [1, 2, 3], (a "quoted" string, [1.0, 0.5]))"#
+        ]],
+    );
+}
+
+#[test]
+fn json_args_of_each_type() {
+    check(
+        indoc! {"
+            function Main(
+                n : Int,
+                x : Double,
+                b : Bool,
+                s : String,
+                p : Pauli,
+                r : Result,
+                big : BigInt,
+                t : (Int, String)[],
+            ) : (Int, Double, Bool, String, Pauli, Result, BigInt, (Int, String)[]) {
+                (n, x, b, s, p, r, big, t)
+            }
+        "},
+        vec![
+            ("n", Arg::Json(json!(7))),
+            ("x", Arg::Json(json!(1))),
+            ("b", Arg::Json(json!(false))),
+            ("s", Arg::Json(json!("text"))),
+            ("p", Arg::Json(json!("PauliZ"))),
+            ("r", Arg::Json(json!("Zero"))),
+            ("big", Arg::Json(json!("98765432109876543210"))),
+            ("t", Arg::Json(json!([[1, "one"], [2, "two"]]))),
+        ],
+        &expect!["(7, 1.0, false, text, PauliZ, Zero, 98765432109876543210, [(1, one), (2, two)])"],
+    );
+}
+
+#[test]
+fn typed_json_args() {
+    check(
+        "function Main(t : (Int, Double[]), r : Result, big : BigInt) : ((Int, Double[]), Result, BigInt) { (t, r, big) }",
+        vec![
+            (
+                "t",
+                Arg::Json(json!({"type": "Tuple", "value": [
+                    {"type": "Int", "value": 1},
+                    {"type": "Array", "value": [{"type": "Double", "value": "Infinity"}]},
+                ]})),
+            ),
+            ("r", Arg::Json(json!({"type": "Result", "value": "One"}))),
+            ("big", Arg::Json(json!({"type": "Int", "value": 2}))),
+        ],
+        &expect!["((1, [inf]), One, 2)"],
+    );
+    check(
+        "function Main(n : Int, xs : Int[]) : Unit {}",
+        vec![
+            ("n", Arg::Json(json!({"type": "Double", "value": 1.0}))),
+            ("xs", Arg::Json(json!([1, {"type": "Pauli", "value": "Y"}]))),
+        ],
+        &expect![[r"
+            invalid argument for parameter `n` of type `Int`: expected `Int`, found Double `1.0`
+            invalid argument for parameter `xs` of type `(Int)[]`: invalid Pauli `Y`
+        "]],
+    );
+}
+
+#[test]
+fn int_value_is_accepted_for_double_and_big_int() {
+    check(
+        "function Main(x : Double, big : BigInt) : (Double, BigInt) { (x, big) }",
+        vec![
+            ("x", Arg::Value(Value::Int(2))),
+            ("big", Arg::Value(Value::Int(3))),
+        ],
+        &expect!["(2.0, 3)"],
+    );
+}
+
+#[test]
+fn args_are_checked_against_param_types() {
+    check(
+        "function Main(n : Int, xs : Int[], t : (Bool, Result)) : Unit {}",
+        vec![
+            ("n", text("1.5")),
+            ("xs", text("[1, 2")),
+            ("t", Arg::Json(json!([true, 1]))),
+        ],
+        &expect![[r"
+            invalid argument for parameter `n` of type `Int`: expected `Int`, found `1.5`
+            invalid argument for parameter `xs` of type `(Int)[]`: expected `,`, found end of argument
+            invalid argument for parameter `t` of type `(Bool, Result)`: expected `Result`, found `1`
+        "]],
+    );
+}
+
+#[test]
+fn unknown_duplicate_and_missing_args_are_errors() {
+    check(
+        "function Main(a : Int, b : Int, c : Int) : Unit {}",
+        vec![("a", text("1")), ("d", text("2")), ("a", text("3"))],
+        &expect![[r"
+            entry point has no parameter `d`
+            duplicate argument for parameter `a`
+            missing argument for parameter `b`
+            missing argument for parameter `c`
+        "]],
+    );
+}
+
+#[test]
+fn entry_point_without_args_is_an_error() {
+    check(
+        "function Main(n : Int) : Int { n }",
+        vec![],
+        &expect![[r"
+            missing argument for parameter `n`
+        "]],
+    );
+}
+
+#[test]
+fn interpreter_runs_latest_entry_point_with_args() {
+    let mut interpreter =
+        Interpreter::new(true, SourceMap::default()).expect("interpreter should be created");
+    let mut cursor = Cursor::new(Vec::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    for line in [
+        "@EntryPoint() function First(n : Int) : Int { n }",
+        "@EntryPoint() function Second(n : Int, s : String) : String { $\"{s}{n}\" }",
+    ] {
+        interpreter
+            .interpret_line(&mut receiver, line)
+            .expect("line should succeed");
+    }
+    let value = interpreter
+        .run_entry(
+            &mut receiver,
+            [
+                ("n".to_string(), Arg::Value(Value::Int(4))),
+                ("s".to_string(), Arg::Value(Value::String("x".into()))),
+            ],
+        )
+        .expect("entry point should succeed");
+    assert_eq!(value, Value::String("x4".into()));
+}
+
+#[test]
+fn interpreter_without_entry_point_cannot_run_it() {
+    let mut interpreter =
+        Interpreter::new(true, SourceMap::default()).expect("interpreter should be created");
+    let mut cursor = Cursor::new(Vec::new());
+    let errors = interpreter
+        .run_entry(&mut CursorReceiver::new(&mut cursor), [])
+        .expect_err("entry point should not be found");
+    let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(messages, ["entry point not found"]);
+}
@@ -0,0 +1,283 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#[cfg(test)]
+mod tests;
+
+use qsc_eval::circuit::{Gate, Operation, OperationKind};
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+/// How an operation is drawn on one of the qubits it acts on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+    /// The qubit controls the operation.
+    Control,
+    /// One of the two qubits exchanged by a swap.
+    Swap,
+    /// A box with a label, like `H` or `Rx(0.5000)`.
+    Box(String),
+}
+
+/// The circuit of quantum operations traced from an evaluation.
+#[derive(Clone, Debug, Default)]
+pub struct Circuit {
+    operations: Vec<Operation>,
+}
+
+impl Circuit {
+    #[must_use]
+    pub fn new(operations: Vec<Operation>) -> Self {
+        Self { operations }
+    }
+
+    /// Every traced operation, including allocations and releases, in the order they were applied.
+    #[must_use]
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// The number of wires in the circuit, which is one more than the largest qubit id. Qubits
+    /// that are released and allocated again reuse their wire.
+    #[must_use]
+    pub fn qubit_count(&self) -> usize {
+        self.operations
+            .iter()
+            .flat_map(|operation| operation.qubits.iter().map(|q| q + 1))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The operations that are drawn, arranged into columns. Each operation is placed in the
+    /// earliest column after every operation before it on the wires it covers, which includes the
+    /// wires that its vertical line crosses. Allocations and releases are not drawn, since every
+    /// wire starts in |0⟩ and a qubit must be returned to |0⟩ before it is released.
+    #[must_use]
+    pub fn columns(&self) -> Vec<Vec<&Operation>> {
+        let mut columns: Vec<Vec<&Operation>> = Vec::new();
+        let mut next = vec![0; self.qubit_count()];
+        for operation in &self.operations {
+            if matches!(
+                operation.kind,
+                OperationKind::Allocate | OperationKind::Release
+            ) {
+                continue;
+            }
+            let (lo, hi) = extent(operation);
+            let column = next[lo..=hi].iter().copied().max().unwrap_or_default();
+            if column == columns.len() {
+                columns.push(Vec::new());
+            }
+            columns[column].push(operation);
+            next[lo..=hi].fill(column + 1);
+        }
+        columns
+    }
+
+    /// Draws the circuit as text, with a row for each wire and a column for each set of operations
+    /// that can be drawn side by side.
+    #[must_use]
+    pub fn diagram(&self) -> String {
+        let qubit_count = self.qubit_count();
+        let names: Vec<_> = (0..qubit_count).map(|q| format!("q{q}: ")).collect();
+        let name_width = names.iter().map(String::len).max().unwrap_or_default();
+
+        // Each wire is drawn on a row, with a row between each pair of wires for vertical lines.
+        let mut rows: Vec<String> = (0..2 * qubit_count)
+            .map(|row| {
+                if row % 2 == 0 {
+                    format!("{:name_width$}", names[row / 2])
+                } else {
+                    " ".repeat(name_width)
+                }
+            })
+            .collect();
+        for column in self.columns() {
+            let mut marks: Vec<Option<String>> = vec![None; qubit_count];
+            let mut lines = vec![false; qubit_count];
+            for operation in &column {
+                for (q, cell) in Self::cells(operation) {
+                    marks[q] = Some(match cell {
+                        Cell::Control => "@".to_string(),
+                        Cell::Swap => "x".to_string(),
+                        Cell::Box(label) => label,
+                    });
+                }
+                let (lo, hi) = extent(operation);
+                lines[lo..hi].fill(true);
+            }
+
+            let width = marks
+                .iter()
+                .flatten()
+                .map(|mark| mark.chars().count())
+                .max()
+                .unwrap_or(1);
+            let center = (width - 1) / 2;
+            for (q, mark) in marks.iter().enumerate() {
+                let crossed = q > 0 && lines[q - 1] && lines[q];
+                let wire = match mark {
+                    Some(mark) => format!("{mark:-^width$}"),
+                    None if crossed => format!("{:-<width$}", format!("{:-<center$}+", "")),
+                    None => "-".repeat(width),
+                };
+                write!(rows[2 * q], "-{wire}-").expect("writing to string should succeed");
+                let gap = if lines[q] {
+                    format!("{:width$}", format!("{:center$}|", ""))
+                } else {
+                    " ".repeat(width)
+                };
+                write!(rows[2 * q + 1], " {gap} ").expect("writing to string should succeed");
+            }
+        }
+
+        rows.iter()
+            .step_by(2)
+            .zip(rows.iter().skip(1).step_by(2))
+            .enumerate()
+            .fold(String::new(), |mut diagram, (q, (wire, gap))| {
+                writeln!(diagram, "{}", wire.trim_end()).expect("writing to string should succeed");
+                if q + 1 < qubit_count {
+                    writeln!(diagram, "{}", gap.trim_end())
+                        .expect("writing to string should succeed");
+                }
+                diagram
+            })
+    }
+
+    /// How the operation is drawn on each of its qubits.
+    #[must_use]
+    pub fn cells(operation: &Operation) -> Vec<(usize, Cell)> {
+        let label = match operation.kind {
+            OperationKind::Allocate => "|0⟩".to_string(),
+            OperationKind::Release => "Release".to_string(),
+            OperationKind::Gate(Gate::Swap) => {
+                return operation.qubits.iter().map(|&q| (q, Cell::Swap)).collect();
+            }
+            OperationKind::Gate(gate) => gate_label(gate),
+            OperationKind::Measure(_) => "M".to_string(),
+            OperationKind::MResetZ(_) => "MResetZ".to_string(),
+            OperationKind::Reset => "Reset".to_string(),
+        };
+        let controls = match operation.kind {
+            OperationKind::Gate(gate) => gate.controls(),
+            _ => 0,
+        };
+        operation
+            .qubits
+            .iter()
+            .enumerate()
+            .map(|(i, &q)| {
+                if i < controls {
+                    (q, Cell::Control)
+                } else {
+                    (q, Cell::Box(label.clone()))
+                }
+            })
+            .collect()
+    }
+
+    /// The circuit as JSON, with the number of wires and every traced operation, like
+    /// `{"qubits": 1, "operations": [{"kind": "Gate", "gate": "H", "controls": [], "targets": [0],
+    /// "span": {"lo": 80, "hi": 81}}]}`. Spans are offsets into the sources of the program.
+    #[must_use]
+    pub fn json(&self) -> String {
+        let operations: Vec<_> = self.operations.iter().map(operation_json).collect();
+        json!({ "qubits": self.qubit_count(), "operations": operations }).to_string()
+    }
+}
+
+/// The lowest and highest qubits that the operation acts on.
+fn extent(operation: &Operation) -> (usize, usize) {
+    let lo = operation.qubits.iter().copied().min().unwrap_or_default();
+    let hi = operation.qubits.iter().copied().max().unwrap_or_default();
+    (lo, hi)
+}
+
+fn gate_label(gate: Gate) -> String {
+    match gate {
+        Gate::Ccx | Gate::Cx | Gate::X => "X".to_string(),
+        Gate::Cy | Gate::Y => "Y".to_string(),
+        Gate::Cz | Gate::Z => "Z".to_string(),
+        Gate::H => "H".to_string(),
+        Gate::Rx(theta) => format!("Rx({theta:.4})"),
+        Gate::Rxx(theta) => format!("Rxx({theta:.4})"),
+        Gate::Ry(theta) => format!("Ry({theta:.4})"),
+        Gate::Ryy(theta) => format!("Ryy({theta:.4})"),
+        Gate::Rz(theta) => format!("Rz({theta:.4})"),
+        Gate::Rzz(theta) => format!("Rzz({theta:.4})"),
+        Gate::S => "S".to_string(),
+        Gate::SAdj => "S†".to_string(),
+        Gate::Swap => "Swap".to_string(),
+        Gate::T => "T".to_string(),
+        Gate::TAdj => "T†".to_string(),
+    }
+}
+
+fn gate_name(gate: Gate) -> &'static str {
+    match gate {
+        Gate::Ccx => "Ccx",
+        Gate::Cx => "Cx",
+        Gate::Cy => "Cy",
+        Gate::Cz => "Cz",
+        Gate::H => "H",
+        Gate::Rx(_) => "Rx",
+        Gate::Rxx(_) => "Rxx",
+        Gate::Ry(_) => "Ry",
+        Gate::Ryy(_) => "Ryy",
+        Gate::Rz(_) => "Rz",
+        Gate::Rzz(_) => "Rzz",
+        Gate::S => "S",
+        Gate::SAdj => "SAdj",
+        Gate::Swap => "Swap",
+        Gate::T => "T",
+        Gate::TAdj => "TAdj",
+        Gate::X => "X",
+        Gate::Y => "Y",
+        Gate::Z => "Z",
+    }
+}
+
+fn operation_json(operation: &Operation) -> Value {
+    let span = operation
+        .span
+        .map(|span| json!({ "lo": span.lo, "hi": span.hi }));
+    let result = |result: bool| if result { "One" } else { "Zero" };
+    match operation.kind {
+        OperationKind::Allocate => {
+            json!({ "kind": "Allocate", "qubits": operation.qubits, "span": span })
+        }
+        OperationKind::Release => {
+            json!({ "kind": "Release", "qubits": operation.qubits, "span": span })
+        }
+        OperationKind::Gate(gate) => {
+            let (controls, targets) = operation.qubits.split_at(gate.controls());
+            let mut value = json!({
+                "kind": "Gate",
+                "gate": gate_name(gate),
+                "controls": controls,
+                "targets": targets,
+                "span": span,
+            });
+            if let Some(angle) = gate.angle() {
+                value["angle"] = json!(angle);
+            }
+            value
+        }
+        OperationKind::Measure(r) => json!({
+            "kind": "Measure",
+            "qubits": operation.qubits,
+            "result": result(r),
+            "span": span,
+        }),
+        OperationKind::MResetZ(r) => json!({
+            "kind": "MResetZ",
+            "qubits": operation.qubits,
+            "result": result(r),
+            "span": span,
+        }),
+        OperationKind::Reset => {
+            json!({ "kind": "Reset", "qubits": operation.qubits, "span": span })
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::Circuit;
+use crate::interpret::{stateless::Context, Recorders, Tracer};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_eval::output::CursorReceiver;
+use qsc_frontend::compile::SourceMap;
+use std::io::Cursor;
+
+fn trace(source: &str) -> Circuit {
+    let sources = SourceMap::new([("test.qs".into(), source.into())], None);
+    let context = Context::new(true, sources)
+        .expect("code should compile")
+        .with_seed(0);
+    let mut tracer = Tracer::new();
+    let mut cursor = Cursor::new(Vec::new());
+    context
+        .eval_with_recorders(
+            &mut CursorReceiver::new(&mut cursor),
+            Recorders {
+                tracer: Some(&mut tracer),
+                ..Recorders::default()
+            },
+        )
+        .expect("evaluation should succeed");
+    Circuit::new(tracer.into_operations())
+}
+
+fn check_diagram(source: &str, expect: &Expect) {
+    expect.assert_eq(&trace(source).diagram());
+}
+
+fn check_json(source: &str, expect: &Expect) {
+    expect.assert_eq(&trace(source).json());
+}
+
+#[test]
+fn bell_pair() {
+    check_diagram(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Result[] {
+                    use qs = Qubit[2];
+                    H(qs[0]);
+                    CNOT(qs[0], qs[1]);
+                    Microsoft.Quantum.Measurement.MResetEachZ(qs)
+                }
+            }
+        "},
+        &expect![[r"
+            q0: -H--@--MResetZ-
+                    |
+            q1: ----X--MResetZ-
+        "]],
+    );
+}
+
+#[test]
+fn gates_on_different_qubits_share_a_column() {
+    check_diagram(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Unit {
+                    use qs = Qubit[3];
+                    H(qs[0]);
+                    Rx(0.5, qs[1]);
+                    Adjoint S(qs[2]);
+                    Adjoint S(qs[2]);
+                    ResetAll(qs);
+                }
+            }
+        "},
+        &expect![[r"
+            q0: -----H-------Reset--------
+
+            q1: -Rx(0.5000)--Reset--------
+
+            q2: -----S†-------S†----Reset-
+        "]],
+    );
+}
+
+#[test]
+fn vertical_lines_cross_wires_between_qubits() {
+    check_diagram(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Unit {
+                    use qs = Qubit[3];
+                    X(qs[1]);
+                    CNOT(qs[0], qs[2]);
+                    SWAP(qs[1], qs[2]);
+                    CCNOT(qs[2], qs[1], qs[0]);
+                    ResetAll(qs);
+                }
+            }
+        "},
+        &expect![[r"
+            q0: ----@-----X--Reset-
+                    |     |
+            q1: -X--+--x--@--Reset-
+                    |  |  |
+            q2: ----X--x--@--Reset-
+        "]],
+    );
+}
+
+#[test]
+fn json_has_every_operation() {
+    check_json(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Result {
+                    use q = Qubit();
+                    Ry(1.5, q);
+                    X(q);
+                    let r = M(q);
+                    Reset(q);
+                    r
+                }
+            }
+        "},
+        &expect![[r#"{"operations":[{"kind":"Allocate","qubits":[0],"span":{"hi":80,"lo":79}},{"angle":1.5,"controls":[],"gate":"Ry","kind":"Gate","span":{"hi":102,"lo":100},"targets":[0]},{"controls":[],"gate":"X","kind":"Gate","span":{"hi":121,"lo":120},"targets":[0]},{"kind":"Measure","qubits":[0],"result":"Zero","span":{"hi":143,"lo":142}},{"kind":"Reset","qubits":[0],"span":{"hi":161,"lo":156}},{"kind":"Release","qubits":[0],"span":{"hi":80,"lo":79}}],"qubits":1}"#]],
+    );
+}
@@ -0,0 +1,110 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#[cfg(test)]
+mod tests;
+
+use qsc_data_structures::span::Span;
+use qsc_eval::coverage::Coverage;
+use qsc_frontend::compile::{PackageStore, Source};
+use qsc_hir::{
+    hir::{Expr, PackageId, Stmt},
+    visit::{self, Visitor},
+};
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Renders the coverage of the unit's source files as an lcov tracefile. Each statement and
+/// expression counts toward the line it starts on, and a line is hit as many times as the
+/// statement or expression on it that was evaluated the most.
+pub(super) fn lcov(store: &PackageStore, package: PackageId, coverage: &Coverage) -> String {
+    let unit = store.get(package).expect("package should be in store");
+    let mut spans = SpanCollector(Vec::new());
+    spans.visit_package(&unit.package);
+
+    let mut files: Vec<SourceLines> = Vec::new();
+    for span in spans.0 {
+        let Some(source) = unit.sources.find_by_offset(span.lo) else {
+            continue;
+        };
+        // The entry expression has a source, but it is not one of the files.
+        if unit.sources.find_by_name(&source.name).is_none() {
+            continue;
+        }
+
+        let index = files
+            .iter()
+            .position(|file| file.source.offset == source.offset)
+            .unwrap_or_else(|| {
+                files.push(SourceLines::new(source));
+                files.len() - 1
+            });
+        let file = &mut files[index];
+        let line = file.line(span.lo - source.offset);
+        let line_hits = file.hits.entry(line).or_default();
+        *line_hits = (*line_hits).max(coverage.hits(package, span));
+    }
+    files.sort_by_key(|file| file.source.offset);
+
+    let mut lcov = String::new();
+    for file in files {
+        writeln!(lcov, "TN:").expect("writing to string should succeed");
+        writeln!(lcov, "SF:{}", file.source.name).expect("writing to string should succeed");
+        for (line, hits) in &file.hits {
+            writeln!(lcov, "DA:{line},{hits}").expect("writing to string should succeed");
+        }
+        let lines_hit = file.hits.values().filter(|&&hits| hits > 0).count();
+        writeln!(lcov, "LF:{}", file.hits.len()).expect("writing to string should succeed");
+        writeln!(lcov, "LH:{lines_hit}").expect("writing to string should succeed");
+        writeln!(lcov, "end_of_record").expect("writing to string should succeed");
+    }
+    lcov
+}
+
+/// The spans of every statement and expression, which are the parts of the program that coverage
+/// is recorded for.
+struct SpanCollector(Vec<Span>);
+
+impl Visitor<'_> for SpanCollector {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if stmt.span.lo < stmt.span.hi {
+            self.0.push(stmt.span);
+        }
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if expr.span.lo < expr.span.hi {
+            self.0.push(expr.span);
+        }
+        visit::walk_expr(self, expr);
+    }
+}
+
+/// The executable lines of a source file, numbered from one, with the number of times each was
+/// hit.
+struct SourceLines<'a> {
+    source: &'a Source,
+    line_starts: Vec<u32>,
+    hits: BTreeMap<u32, u64>,
+}
+
+impl<'a> SourceLines<'a> {
+    fn new(source: &'a Source) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.contents.match_indices('\n').map(|(index, _)| {
+                u32::try_from(index + 1).expect("source offset should fit into u32")
+            }))
+            .collect();
+        Self {
+            source,
+            line_starts,
+            hits: BTreeMap::new(),
+        }
+    }
+
+    /// The line that the offset into the source is on.
+    fn line(&self, offset: u32) -> u32 {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        line.try_into().expect("line should fit into u32")
+    }
+}
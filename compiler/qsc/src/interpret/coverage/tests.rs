@@ -0,0 +1,112 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::interpret::{stateless::Context, Coverage, Recorders};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_eval::output::CursorReceiver;
+use qsc_frontend::compile::SourceMap;
+use std::io::Cursor;
+
+fn check_lcov(sources: SourceMap, expect: &Expect) {
+    let context = Context::new(true, sources).expect("code should compile");
+    let mut coverage = Coverage::new();
+    let mut cursor = Cursor::new(Vec::new());
+    context
+        .eval_with_recorders(
+            &mut CursorReceiver::new(&mut cursor),
+            Recorders {
+                coverage: Some(&mut coverage),
+                ..Recorders::default()
+            },
+        )
+        .expect("evaluation should succeed");
+    expect.assert_eq(&context.lcov(&coverage));
+}
+
+#[test]
+fn lines_not_executed_have_no_hits() {
+    check_lcov(
+        SourceMap::new(
+            [(
+                "test.qs".into(),
+                indoc! {"
+                    namespace Test {
+                        function Sign(x : Int) : Int {
+                            if x < 0 {
+                                return -1;
+                            }
+                            1
+                        }
+
+                        @EntryPoint()
+                        function Main() : Int {
+                            Sign(1) + Sign(2)
+                        }
+                    }
+                "}
+                .into(),
+            )],
+            None,
+        ),
+        &expect![[r"
+            TN:
+            SF:test.qs
+            DA:3,2
+            DA:4,0
+            DA:6,2
+            DA:11,1
+            LF:4
+            LH:3
+            end_of_record
+        "]],
+    );
+}
+
+#[test]
+fn each_source_file_has_a_record() {
+    check_lcov(
+        SourceMap::new(
+            [
+                (
+                    "a.qs".into(),
+                    indoc! {"
+                        namespace A {
+                            function Double(x : Int) : Int {
+                                2 * x
+                            }
+                        }
+                    "}
+                    .into(),
+                ),
+                (
+                    "b.qs".into(),
+                    indoc! {"
+                        namespace B {
+                            open A;
+                            function Unused() : Int {
+                                Double(1)
+                            }
+                        }
+                    "}
+                    .into(),
+                ),
+            ],
+            Some("A.Double(3)".into()),
+        ),
+        &expect![[r"
+            TN:
+            SF:a.qs
+            DA:3,1
+            LF:1
+            LH:1
+            end_of_record
+            TN:
+            SF:b.qs
+            DA:4,0
+            LF:1
+            LH:0
+            end_of_record
+        "]],
+    );
+}
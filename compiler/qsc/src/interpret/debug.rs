@@ -6,8 +6,13 @@ mod tests;
 
 use qsc_frontend::compile::PackageStore;
 
-use qsc_eval::{debug::CallStack, val::GlobalId, Global, GlobalLookup};
+use qsc_eval::{
+    debug::CallStack,
+    val::{FunctorApp, GlobalId},
+    Global, GlobalLookup,
+};
 use qsc_hir::hir::{Item, ItemKind};
+use std::fmt::Write;
 
 #[must_use]
 pub(crate) fn format_call_stack<'a>(
@@ -17,40 +22,58 @@ pub(crate) fn format_call_stack<'a>(
     error: &dyn std::error::Error,
 ) -> String {
     let mut trace = String::new();
-    trace.push_str(&format!("Error: {error}\n"));
+    writeln!(trace, "Error: {error}").expect("writing to string should succeed");
     trace.push_str("Call stack:\n");
 
     let mut frames = call_stack.clone().into_frames();
     frames.reverse();
 
     for frame in frames {
-        let Some(Global::Callable(call)) = globals.get(frame.id) else { panic!("missing global"); };
-
         trace.push_str("    at ");
-        if frame.functor.adjoint {
-            trace.push_str("Adjoint ");
-        }
-        if frame.functor.controlled > 0 {
-            trace.push_str(&format!("Controlled({}) ", frame.functor.controlled));
-        }
-        if let Some(item) = get_item_parent(store, frame.id) {
-            if let Some(ns) = get_ns_name(&item) {
-                trace.push_str(&format!("{ns}."));
-            }
-        }
-        trace.push_str(&format!("{}", call.name.name));
+        trace.push_str(&format_callable_name(
+            store,
+            globals,
+            frame.id,
+            frame.functor,
+        ));
 
         let name = get_item_file_name(store, frame.id);
-        trace.push_str(&format!(
-            " in {}",
-            name.unwrap_or("<expression>".to_string())
-        ));
+        write!(trace, " in {}", name.unwrap_or("<expression>".to_string()))
+            .expect("writing to string should succeed");
 
         trace.push('\n');
     }
     trace
 }
 
+/// The name of a callable with its namespace and the functors applied to it, like
+/// `Adjoint Microsoft.Quantum.Intrinsic.S`.
+#[must_use]
+pub(crate) fn format_callable_name<'a>(
+    store: &PackageStore,
+    globals: &'a impl GlobalLookup<'a>,
+    id: GlobalId,
+    functor: FunctorApp,
+) -> String {
+    let Some(Global::Callable(call)) = globals.get(id) else { panic!("missing global"); };
+
+    let mut name = String::new();
+    if functor.adjoint {
+        name.push_str("Adjoint ");
+    }
+    if functor.controlled > 0 {
+        write!(name, "Controlled({}) ", functor.controlled)
+            .expect("writing to string should succeed");
+    }
+    if let Some(item) = get_item_parent(store, id) {
+        if let Some(ns) = get_ns_name(&item) {
+            write!(name, "{ns}.").expect("writing to string should succeed");
+        }
+    }
+    name.push_str(&call.name.name);
+    name
+}
+
 #[must_use]
 fn get_item_parent(store: &PackageStore, id: GlobalId) -> Option<Item> {
     store.get(id.package).and_then(|unit| {
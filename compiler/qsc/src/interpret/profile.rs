@@ -0,0 +1,109 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#[cfg(test)]
+mod tests;
+
+use super::debug::format_callable_name;
+use qsc_eval::{profile::Profiler, GlobalLookup};
+use qsc_frontend::compile::PackageStore;
+use serde_json::json;
+use std::{cmp::Reverse, collections::HashMap, fmt::Write, time::Duration};
+
+/// The statistics recorded by a profiler, with each callable named the way it appears in call
+/// stacks, like `Adjoint Microsoft.Quantum.Intrinsic.S`.
+pub struct Profile<'a> {
+    profiler: &'a Profiler,
+    names: Vec<String>,
+}
+
+impl<'a> Profile<'a> {
+    pub(super) fn new<'b>(
+        store: &PackageStore,
+        globals: &'b impl GlobalLookup<'b>,
+        profiler: &'a Profiler,
+    ) -> Self {
+        let names = profiler
+            .callables()
+            .iter()
+            .map(|callable| format_callable_name(store, globals, callable.id, callable.functor))
+            .collect();
+        Self { profiler, names }
+    }
+
+    /// A table with the statistics of each callable, starting with the callable that took the
+    /// most time including its callees.
+    #[must_use]
+    pub fn table(&self) -> String {
+        let mut rows: Vec<_> = self.names.iter().zip(self.profiler.callables()).collect();
+        rows.sort_by_key(|(_, callable)| Reverse(callable.inclusive));
+
+        let header = "Callable";
+        let width = self
+            .names
+            .iter()
+            .map(|name| name.chars().count())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default();
+
+        let mut table = String::new();
+        writeln!(
+            table,
+            "{header:width$}  {:>10}  {:>10}  {:>14}  {:>14}",
+            "Calls", "Gates", "Inclusive (ms)", "Exclusive (ms)"
+        )
+        .expect("writing to string should succeed");
+        for (name, callable) in rows {
+            writeln!(
+                table,
+                "{name:width$}  {:>10}  {:>10}  {:>14.3}  {:>14.3}",
+                callable.calls,
+                callable.gates,
+                millis(callable.inclusive),
+                millis(callable.exclusive)
+            )
+            .expect("writing to string should succeed");
+        }
+        table
+    }
+
+    /// The recorded calls in the Chrome trace event format, which can be loaded into trace viewers
+    /// to show them as a flame graph. There are no events unless the profiler records a trace.
+    #[must_use]
+    pub fn chrome_trace(&self) -> String {
+        let names: HashMap<_, _> = self
+            .profiler
+            .callables()
+            .iter()
+            .zip(&self.names)
+            .map(|(callable, name)| ((callable.id, callable.functor), name))
+            .collect();
+        let events: Vec<_> = self
+            .profiler
+            .trace()
+            .unwrap_or_default()
+            .iter()
+            .map(|event| {
+                json!({
+                    "name": names[&(event.id, event.functor)],
+                    "cat": "callable",
+                    "ph": "X",
+                    "ts": micros(event.start),
+                    "dur": micros(event.duration),
+                    "pid": 1,
+                    "tid": 1,
+                })
+            })
+            .collect();
+        json!({ "traceEvents": events, "displayTimeUnit": "ms" }).to_string()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e3
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
@@ -0,0 +1,162 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::interpret::{stateful::Interpreter, stateless::Context, Profile, Profiler, Recorders};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_eval::output::CursorReceiver;
+use qsc_frontend::compile::SourceMap;
+use std::io::Cursor;
+
+/// Checks the rows of the table without their times, which differ from run to run, sorted by name
+/// since the order of the rows depends on the times.
+fn check_table(profile: &Profile, expect: &Expect) {
+    let table = profile.table();
+    let mut lines = table.lines();
+    let header = lines.next().expect("table should have a header");
+    assert!(header.starts_with("Callable"));
+    let mut rows: Vec<_> = lines
+        .map(|line| {
+            let columns: Vec<_> = line.split_whitespace().collect();
+            columns[..columns.len() - 2].join(" ")
+        })
+        .collect();
+    rows.sort();
+    expect.assert_eq(&rows.join("\n"));
+}
+
+fn context() -> Context {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Test {
+                    operation Prepare(q : Qubit) : Unit is Adj {
+                        H(q);
+                        S(q);
+                    }
+
+                    @EntryPoint()
+                    operation Main() : Unit {
+                        use q = Qubit();
+                        Prepare(q);
+                        Adjoint Prepare(q);
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    Context::new(true, sources).expect("code should compile")
+}
+
+#[test]
+fn table_names_callables_with_namespace_and_functors() {
+    let context = context();
+    let mut profiler = Profiler::new();
+    let mut cursor = Cursor::new(Vec::new());
+    context
+        .eval_with_recorders(
+            &mut CursorReceiver::new(&mut cursor),
+            Recorders {
+                profiler: Some(&mut profiler),
+                ..Recorders::default()
+            },
+        )
+        .expect("evaluation should succeed");
+    check_table(
+        &context.profile(&profiler),
+        &expect![[r"
+            Adjoint Microsoft.Quantum.Intrinsic.H 1 1
+            Adjoint Microsoft.Quantum.Intrinsic.S 1 1
+            Adjoint Test.Prepare 1 2
+            Microsoft.Quantum.Intrinsic.H 1 1
+            Microsoft.Quantum.Intrinsic.S 1 1
+            QIR.Intrinsic.__quantum__qis__h__body 2 2
+            QIR.Intrinsic.__quantum__qis__s__adj 1 1
+            QIR.Intrinsic.__quantum__qis__s__body 1 1
+            QIR.Runtime.__quantum__rt__qubit_allocate 1 0
+            QIR.Runtime.__quantum__rt__qubit_release 1 0
+            Test.Prepare 1 2"]],
+    );
+}
+
+#[test]
+fn chrome_trace_has_an_event_per_call() {
+    let context = context();
+    let mut profiler = Profiler::new().with_trace();
+    let mut cursor = Cursor::new(Vec::new());
+    context
+        .eval_with_recorders(
+            &mut CursorReceiver::new(&mut cursor),
+            Recorders {
+                profiler: Some(&mut profiler),
+                ..Recorders::default()
+            },
+        )
+        .expect("evaluation should succeed");
+    let trace: serde_json::Value = serde_json::from_str(&context.profile(&profiler).chrome_trace())
+        .expect("trace should be valid JSON");
+    let events = trace["traceEvents"]
+        .as_array()
+        .expect("trace should have events");
+    assert!(events.iter().all(|event| event["ph"] == "X"));
+    let names: Vec<_> = events
+        .iter()
+        .map(|event| event["name"].as_str().expect("event should have a name"))
+        .collect();
+    expect![[r#"
+        [
+            "QIR.Runtime.__quantum__rt__qubit_allocate",
+            "QIR.Intrinsic.__quantum__qis__h__body",
+            "Microsoft.Quantum.Intrinsic.H",
+            "QIR.Intrinsic.__quantum__qis__s__body",
+            "Microsoft.Quantum.Intrinsic.S",
+            "Test.Prepare",
+            "QIR.Intrinsic.__quantum__qis__s__adj",
+            "Adjoint Microsoft.Quantum.Intrinsic.S",
+            "QIR.Intrinsic.__quantum__qis__h__body",
+            "Adjoint Microsoft.Quantum.Intrinsic.H",
+            "Adjoint Test.Prepare",
+            "QIR.Runtime.__quantum__rt__qubit_release",
+        ]
+    "#]]
+    .assert_debug_eq(&names);
+}
+
+#[test]
+fn interpreter_profile_accumulates_across_lines() {
+    let mut interpreter = Interpreter::new(true, SourceMap::default())
+        .expect("interpreter should be created")
+        .with_profiler(Profiler::new());
+    let mut cursor = Cursor::new(Vec::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    for line in [
+        "operation Flip(q : Qubit) : Unit { X(q); }",
+        "use q = Qubit();",
+        "Flip(q);",
+        "Flip(q);",
+    ] {
+        interpreter
+            .interpret_line(&mut receiver, line)
+            .expect("line should succeed");
+    }
+    check_table(
+        &interpreter
+            .profile()
+            .expect("interpreter should have a profile"),
+        &expect![[r"
+            Flip 2 2
+            Microsoft.Quantum.Intrinsic.X 2 2
+            QIR.Intrinsic.__quantum__qis__x__body 2 2
+            QIR.Runtime.__quantum__rt__qubit_allocate 1 0"]],
+    );
+}
+
+#[test]
+fn interpreter_without_profiler_has_no_profile() {
+    let interpreter =
+        Interpreter::new(false, SourceMap::default()).expect("interpreter should be created");
+    assert!(interpreter.profile().is_none());
+}
@@ -0,0 +1,57 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use qsc_eval::val::Value;
+use std::collections::{hash_map::Entry, HashMap};
+
+/// The results of running a program for a number of shots.
+#[derive(Debug)]
+pub struct ShotResults<E> {
+    /// Each distinct value returned by a successful shot and the number of shots that returned it,
+    /// in the order that the values first appeared.
+    pub histogram: Vec<(Value, usize)>,
+    /// The errors from each failed shot, along with the index of the shot.
+    pub errors: Vec<(usize, Vec<E>)>,
+    /// The position in the histogram of each value, keyed by its typed JSON form, which is
+    /// different for each distinct value so that a shot is counted without scanning the histogram.
+    index: HashMap<String, usize>,
+}
+
+impl<E> Default for ShotResults<E> {
+    fn default() -> Self {
+        Self {
+            histogram: Vec::new(),
+            errors: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<E> ShotResults<E> {
+    /// Adds the result of a shot, counting its value in the histogram or keeping its errors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value cannot be serialized to JSON.
+    pub fn record(&mut self, shot: usize, result: Result<Value, Vec<E>>) {
+        match result {
+            Ok(value) => {
+                let key = serde_json::to_string(&value).expect("value should serialize to JSON");
+                match self.index.entry(key) {
+                    Entry::Occupied(entry) => self.histogram[*entry.get()].1 += 1,
+                    Entry::Vacant(entry) => {
+                        entry.insert(self.histogram.len());
+                        self.histogram.push((value, 1));
+                    }
+                }
+            }
+            Err(errors) => self.errors.push((shot, errors)),
+        }
+    }
+
+    /// The number of shots that ran, including the ones that failed.
+    #[must_use]
+    pub fn shots(&self) -> usize {
+        self.histogram.iter().map(|(_, count)| count).sum::<usize>() + self.errors.len()
+    }
+}
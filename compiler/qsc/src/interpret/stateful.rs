@@ -9,23 +9,33 @@ use crate::{
     error::WithSource,
 };
 use miette::Diagnostic;
-use qsc_data_structures::index_map::IndexMap;
+use qsc_data_structures::{index_map::IndexMap, span::Span};
 use qsc_eval::{
+    backend::{
+        Backend, LogicalCounter, LogicalResources, MeasurementPolicy, NoiseModel, Simulator,
+    },
+    circuit::Tracer,
     debug::CallStack,
     output::Receiver,
+    profile::Profiler,
     val::{GlobalId, Value},
-    Env, Global,
+    CancellationToken, Env, EvalContext, Global, Limits, Recorders,
 };
 use qsc_frontend::{
     compile::{CompileUnit, PackageStore, Source, SourceMap},
     incremental::{self, Compiler, Fragment},
 };
-use qsc_hir::hir::{CallableDecl, ItemKind, LocalItemId, PackageId, Stmt};
-use qsc_passes::run_default_passes_for_fragment;
+use qsc_hir::hir::{Attr, CallableDecl, ItemKind, LocalItemId, NodeId, PackageId, Stmt, StmtKind};
+use qsc_passes::{entry_point, run_default_passes_for_fragment};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{collections::HashSet, sync::Arc};
 use thiserror::Error;
 
-use super::{debug::format_call_stack, stateless};
+use super::{
+    args::{self, Arg},
+    debug::format_call_stack,
+    stateless, Circuit, Profile, ShotResults,
+};
 
 #[derive(Clone, Debug, Diagnostic, Error)]
 #[diagnostic(transparent)]
@@ -56,6 +66,8 @@ pub enum LineErrorKind {
     Compile(#[from] incremental::Error),
     #[error(transparent)]
     Pass(#[from] qsc_passes::Error),
+    #[error(transparent)]
+    Args(#[from] args::Error),
     #[error("runtime error")]
     Eval(#[from] qsc_eval::Error),
 }
@@ -67,6 +79,14 @@ pub struct Interpreter {
     udts: HashSet<LocalItemId>,
     callables: IndexMap<LocalItemId, CallableDecl>,
     env: Env,
+    simulator: Simulator,
+    noise: NoiseModel,
+    sim: Box<dyn Backend>,
+    rng: StdRng,
+    limits: Limits,
+    profiler: Option<Profiler>,
+    /// The entry point callable most recently declared, with the line that declared it.
+    entry: Option<(LocalItemId, Arc<str>)>,
 }
 
 impl Interpreter {
@@ -74,8 +94,6 @@ impl Interpreter {
     /// If the compilation of the standard library fails, an error is returned.
     /// If the compilation of the sources fails, an error is returned.
     pub fn new(std: bool, sources: SourceMap) -> Result<Self, Vec<CompileError>> {
-        qsc_eval::init();
-
         let mut store = PackageStore::new(compile::core());
         let mut dependencies = Vec::new();
         if std {
@@ -100,9 +118,106 @@ impl Interpreter {
             udts: HashSet::new(),
             callables: IndexMap::new(),
             env: Env::with_empty_scope(),
+            simulator: Simulator::default(),
+            noise: NoiseModel::default(),
+            sim: Simulator::default().backend(),
+            rng: StdRng::from_entropy(),
+            limits: Limits::default(),
+            profiler: None,
+            entry: None,
+        }
+        .with_new_sim())
+    }
+
+    /// Runs lines on the given simulator instead of the default one.
+    #[must_use]
+    pub fn with_simulator(self, simulator: Simulator) -> Self {
+        Self { simulator, ..self }.with_new_sim()
+    }
+
+    /// Subjects the operations in each line to errors from the noise model.
+    #[must_use]
+    pub fn with_noise(self, noise: NoiseModel) -> Self {
+        Self { noise, ..self }.with_new_sim()
+    }
+
+    /// Seeds the random number generator used for measurements, noise and classical randomness, so
+    /// that interpreting the same lines always gives the same results.
+    #[must_use]
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            ..self
+        }
+        .with_new_sim()
+    }
+
+    /// Stops each statement with an error once it has taken the given number of steps, so that a
+    /// line that never finishes cannot run forever.
+    #[must_use]
+    pub fn with_step_limit(mut self, steps: u64) -> Self {
+        self.limits.steps = Some(steps);
+        self
+    }
+
+    /// Stops a statement with an error when it would allocate a qubit while the given number of
+    /// qubits are already allocated, including those that earlier lines have not released.
+    #[must_use]
+    pub fn with_qubit_limit(mut self, qubits: usize) -> Self {
+        self.limits.qubits = Some(qubits);
+        self
+    }
+
+    /// Stops a statement with an error when it would build an array longer than the given length
+    /// by repetition or concatenation.
+    #[must_use]
+    pub fn with_array_limit(mut self, len: usize) -> Self {
+        self.limits.array_len = Some(len);
+        self
+    }
+
+    /// Stops each statement with an error once the token is cancelled.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.limits.cancellation = Some(token);
+        self
+    }
+
+    /// Records the calls made by each line with the profiler.
+    #[must_use]
+    pub fn with_profiler(self, profiler: Profiler) -> Self {
+        Self {
+            profiler: Some(profiler),
+            ..self
+        }
+    }
+
+    /// The statistics recorded by the profiler in the lines interpreted so far, or `None` if the
+    /// interpreter has no profiler.
+    #[must_use]
+    pub fn profile(&self) -> Option<Profile<'_>> {
+        self.profiler.as_ref().map(|profiler| {
+            Profile::new(
+                &self.store,
+                &|id| get_global(&self.store, &self.udts, &self.callables, self.package, id),
+                profiler,
+            )
         })
     }
 
+    fn with_new_sim(mut self) -> Self {
+        self.reset_sim();
+        self
+    }
+
+    /// Replaces the simulator with a new one that reflects the current configuration. Qubits
+    /// allocated on the old simulator are forgotten, along with the variables that hold them.
+    fn reset_sim(&mut self) {
+        self.sim = self.noise.wrap(self.simulator.backend());
+        self.sim.set_seed(self.rng.gen());
+        self.env.release_qubits();
+    }
+
     /// # Errors
     /// If the parsing of the line fails, an error is returned.
     /// If the compilation of the line fails, an error is returned.
@@ -113,65 +228,251 @@ impl Interpreter {
         line: &str,
     ) -> Result<Value, Vec<LineError>> {
         let mut result = Value::unit();
-        for mut fragment in self.compiler.compile_fragments(line) {
-            let pass_errors = run_default_passes_for_fragment(
-                self.store.core(),
-                self.compiler.assigner_mut(),
-                &mut fragment,
-            );
-            if !pass_errors.is_empty() {
-                let source = line.into();
-                return Err(pass_errors
-                    .into_iter()
-                    .map(|error| {
-                        LineError(WithSource::new(Arc::clone(&source), error.into(), None))
-                    })
-                    .collect());
+        for fragment in self.compiler.compile_fragments(line) {
+            if let Some(stmt) = self.add_fragment(line, fragment)? {
+                match self.eval_stmt(receiver, &stmt) {
+                    Ok(value) => result = value,
+                    Err((error, call_stack)) => {
+                        return Err(vec![self.runtime_error(line, error, &call_stack)]);
+                    }
+                }
             }
+        }
 
-            match fragment {
-                Fragment::Item(item) => match item.kind {
-                    ItemKind::Callable(callable) => self.callables.insert(item.id, callable),
+        Ok(result)
+    }
+
+    /// Interprets the line once per shot, each time on a new simulator, and collects the values
+    /// of the line into a histogram. A shot that fails is recorded without stopping the others.
+    /// Quantum state from previous lines is discarded, along with the variables that hold their
+    /// qubits, but their other variables and callables remain available.
+    ///
+    /// # Errors
+    /// If the parsing of the line fails, an error is returned.
+    /// If the compilation of the line fails, an error is returned.
+    pub fn run_shots(
+        &mut self,
+        receiver: &mut dyn Receiver,
+        line: &str,
+        shots: usize,
+    ) -> Result<ShotResults<LineError>, Vec<LineError>> {
+        let mut stmts = Vec::new();
+        for fragment in self.compiler.compile_fragments(line) {
+            if let Some(stmt) = self.add_fragment(line, fragment)? {
+                stmts.push(stmt);
+            }
+        }
+
+        let mut results = ShotResults::default();
+        for shot in 0..shots {
+            self.reset_sim();
+            let mut result = Ok(Value::unit());
+            for stmt in &stmts {
+                result = self
+                    .eval_stmt(receiver, stmt)
+                    .map_err(|(error, call_stack)| {
+                        vec![self.runtime_error(line, error, &call_stack)]
+                    });
+                if result.is_err() {
+                    break;
+                }
+            }
+            results.record(shot, result);
+        }
+        Ok(results)
+    }
+
+    /// Interprets the line on a new simulator while tracing the quantum operations it applies, and
+    /// returns the circuit of those operations. Quantum state from previous lines is discarded,
+    /// along with the variables that hold their qubits, but their other variables and callables
+    /// remain available.
+    ///
+    /// # Errors
+    /// If the parsing of the line fails, an error is returned.
+    /// If the compilation of the line fails, an error is returned.
+    /// If there is a runtime error when interpreting the line, an error is returned.
+    pub fn circuit(
+        &mut self,
+        receiver: &mut dyn Receiver,
+        line: &str,
+    ) -> Result<Circuit, Vec<LineError>> {
+        self.reset_sim();
+        let mut tracer = Tracer::new();
+        for fragment in self.compiler.compile_fragments(line) {
+            if let Some(stmt) = self.add_fragment(line, fragment)? {
+                self.eval_stmt_with(receiver, &stmt, None, Some(&mut tracer))
+                    .map_err(|(error, call_stack)| {
+                        vec![self.runtime_error(line, error, &call_stack)]
+                    })?;
+            }
+        }
+
+        Ok(Circuit::new(tracer.into_operations()))
+    }
+
+    /// Interprets the line without simulating it and counts the logical resources that its quantum
+    /// operations use. Measurement results are chosen by the policy. The simulator is not used, so
+    /// its quantum state is unchanged, and qubits allocated by previous lines are counted as if
+    /// they had only been allocated when first used.
+    ///
+    /// # Errors
+    /// If the parsing of the line fails, an error is returned.
+    /// If the compilation of the line fails, an error is returned.
+    /// If there is a runtime error when interpreting the line, an error is returned, which includes
+    /// lines that need the quantum state, like those that call `DumpMachine`.
+    pub fn estimate(
+        &mut self,
+        receiver: &mut dyn Receiver,
+        line: &str,
+        policy: MeasurementPolicy,
+    ) -> Result<LogicalResources, Vec<LineError>> {
+        let mut counter = LogicalCounter::new(policy);
+        counter.set_seed(self.rng.gen());
+        for fragment in self.compiler.compile_fragments(line) {
+            if let Some(stmt) = self.add_fragment(line, fragment)? {
+                self.eval_stmt_with(receiver, &stmt, Some(&mut counter), None)
+                    .map_err(|(error, call_stack)| {
+                        vec![self.runtime_error(line, error, &call_stack)]
+                    })?;
+            }
+        }
+
+        Ok(counter.resources())
+    }
+
+    /// Evaluates the entry point callable most recently declared in the interpreted lines, giving
+    /// arguments to its parameters by name.
+    ///
+    /// # Errors
+    /// If no entry point callable has been declared, an error is returned.
+    /// If an argument is unknown, missing or does not have the type of its parameter, an error is
+    /// returned.
+    /// If there is a runtime error when evaluating the entry point, an error is returned.
+    pub fn run_entry(
+        &mut self,
+        receiver: &mut dyn Receiver,
+        args: impl IntoIterator<Item = (String, Arg)>,
+    ) -> Result<Value, Vec<LineError>> {
+        let Some((callable, line)) = self
+            .entry
+            .as_ref()
+            .and_then(|(item, line)| Some((self.callables.get(*item)?, Arc::clone(line))))
+        else {
+            let error = qsc_passes::Error::EntryPoint(entry_point::Error::NotFound);
+            return Err(vec![LineError(WithSource::new(
+                "".into(),
+                error.into(),
+                None,
+            ))]);
+        };
+        let to_errors = |errors: Vec<LineErrorKind>| -> Vec<LineError> {
+            errors
+                .into_iter()
+                .map(|error| LineError(WithSource::new(Arc::clone(&line), error, None)))
+                .collect()
+        };
+
+        let entry = entry_point::params(callable)
+            .map_err(|errors| errors.into_iter().map(Into::into).collect())
+            .and_then(|params| {
+                args::bind(&params, args)
+                    .map_err(|errors| errors.into_iter().map(Into::into).collect())
+            })
+            .and_then(|args| {
+                entry_point::bind_entry(callable, args)
+                    .map_err(|errors| errors.into_iter().map(Into::into).collect())
+            })
+            .map_err(to_errors)?;
+
+        let stmt = Stmt {
+            id: NodeId::default(),
+            span: Span::default(),
+            kind: StmtKind::Expr(entry),
+        };
+        self.eval_stmt(receiver, &stmt)
+            .map_err(|(error, call_stack)| vec![self.runtime_error(&line, error, &call_stack)])
+    }
+
+    /// Runs passes on the fragment and adds any item it declares to the interpreter, returning the
+    /// fragment's statement if it has one.
+    fn add_fragment(
+        &mut self,
+        line: &str,
+        mut fragment: Fragment,
+    ) -> Result<Option<Stmt>, Vec<LineError>> {
+        let pass_errors = run_default_passes_for_fragment(
+            self.store.core(),
+            self.compiler.assigner_mut(),
+            &mut fragment,
+        );
+        if !pass_errors.is_empty() {
+            let source = line.into();
+            return Err(pass_errors
+                .into_iter()
+                .map(|error| LineError(WithSource::new(Arc::clone(&source), error.into(), None)))
+                .collect());
+        }
+
+        match fragment {
+            Fragment::Item(item) => {
+                if item.attrs.contains(&Attr::EntryPoint) {
+                    self.entry = Some((item.id, line.into()));
+                }
+                match item.kind {
+                    ItemKind::Callable(callable) => {
+                        self.callables.insert(item.id, callable);
+                    }
                     ItemKind::Namespace(..) => {}
                     ItemKind::Ty(..) => {
                         self.udts.insert(item.id);
                     }
-                },
-                Fragment::Stmt(stmt) => match self.eval_stmt(receiver, &stmt) {
-                    Ok(value) => result = value,
-                    Err((error, call_stack)) => {
-                        let stack_trace = if call_stack.is_empty() {
-                            None
-                        } else {
-                            Some(self.render_call_stack(&call_stack, &error))
-                        };
-
-                        return Err(vec![LineError(WithSource::new(
-                            line.into(),
-                            error.into(),
-                            stack_trace,
-                        ))]);
-                    }
-                },
-                Fragment::Error(errors) => {
-                    let source = line.into();
-                    return Err(errors
-                        .into_iter()
-                        .map(|error| {
-                            LineError(WithSource::new(Arc::clone(&source), error.into(), None))
-                        })
-                        .collect());
                 }
+                Ok(None)
+            }
+            Fragment::Stmt(stmt) => Ok(Some(stmt)),
+            Fragment::Error(errors) => {
+                let source = line.into();
+                Err(errors
+                    .into_iter()
+                    .map(|error| {
+                        LineError(WithSource::new(Arc::clone(&source), error.into(), None))
+                    })
+                    .collect())
             }
         }
+    }
 
-        Ok(result)
+    fn runtime_error(
+        &self,
+        line: &str,
+        error: qsc_eval::Error,
+        call_stack: &CallStack,
+    ) -> LineError {
+        let stack_trace = if call_stack.is_empty() {
+            None
+        } else {
+            Some(self.render_call_stack(call_stack, &error))
+        };
+
+        LineError(WithSource::new(line.into(), error.into(), stack_trace))
     }
 
     fn eval_stmt(
         &mut self,
         receiver: &mut dyn Receiver,
         stmt: &Stmt,
+    ) -> Result<Value, (qsc_eval::Error, CallStack)> {
+        self.eval_stmt_with(receiver, stmt, None, None)
+    }
+
+    /// Evaluates the statement on the given backend, or on the interpreter's simulator if there is
+    /// none, while the tracer records its operations.
+    fn eval_stmt_with(
+        &mut self,
+        receiver: &mut dyn Receiver,
+        stmt: &Stmt,
+        sim: Option<&mut dyn Backend>,
+        tracer: Option<&mut Tracer>,
     ) -> Result<Value, (qsc_eval::Error, CallStack)> {
         qsc_eval::eval_stmt(
             stmt,
@@ -179,6 +480,13 @@ impl Interpreter {
             self.package,
             &mut self.env,
             receiver,
+            EvalContext::new(sim.unwrap_or(self.sim.as_mut()), &mut self.rng)
+                .with_limits(self.limits.clone())
+                .with_recorders(Recorders {
+                    profiler: self.profiler.as_mut(),
+                    tracer,
+                    ..Recorders::default()
+                }),
         )
     }
 
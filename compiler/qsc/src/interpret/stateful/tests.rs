@@ -2,7 +2,11 @@
 // Licensed under the MIT License.
 
 mod given_interpreter {
-    use crate::interpret::stateful::{Interpreter, LineError};
+    use crate::interpret::{
+        stateful::{Interpreter, LineError},
+        MeasurementPolicy, NoiseModel, Simulator,
+    };
+    use expect_test::expect;
     use qsc_eval::{output::CursorReceiver, val::Value};
     use qsc_frontend::compile::SourceMap;
     use std::{error::Error, fmt::Write, io::Cursor, iter};
@@ -231,6 +235,228 @@ mod given_interpreter {
             let (result, output) = line(&mut interpreter, "DumpMachine()");
             is_unit_with_output(&result, &output, "STATE:\n|0101⟩: 1+0i");
         }
+
+        #[test]
+        fn stabilizer_simulator() {
+            let mut interpreter = get_interpreter().with_simulator(Simulator::Stabilizer);
+            let (result, output) = line(&mut interpreter, "use qs = Qubit[200];");
+            is_only_value(&result, &output, &Value::unit());
+            let (result, output) = line(
+                &mut interpreter,
+                "H(qs[0]); for i in 1..199 { CNOT(qs[0], qs[i]); }",
+            );
+            is_only_value(&result, &output, &Value::unit());
+            let (result, output) = line(
+                &mut interpreter,
+                "let r = M(qs[0]); ResetAll(qs); Microsoft.Quantum.Diagnostics.CheckZero(qs[199])",
+            );
+            is_only_value(&result, &output, &Value::Bool(true));
+            let (result, output) = line(&mut interpreter, "T(qs[0]);");
+            is_only_error(
+                &result,
+                &output,
+                "runtime error: `T` is not supported by the simulator",
+            );
+        }
+
+        #[test]
+        fn readout_noise() {
+            let mut interpreter = get_interpreter().with_noise(NoiseModel {
+                readout: 1.0,
+                ..NoiseModel::default()
+            });
+            let (result, output) = line(&mut interpreter, "use q = Qubit();");
+            is_only_value(&result, &output, &Value::unit());
+            let (result, output) = line(&mut interpreter, "M(q)");
+            is_only_value(&result, &output, &Value::Result(true));
+            let (result, output) = line(
+                &mut interpreter,
+                "Microsoft.Quantum.Diagnostics.CheckZero(q)",
+            );
+            is_only_value(&result, &output, &Value::Bool(true));
+        }
+
+        #[test]
+        fn seeded_interpreters_agree() {
+            let input = "{
+                use qs = Qubit[8];
+                for q in qs { H(q); }
+                let results = Microsoft.Quantum.Measurement.MResetEachZ(qs);
+                (results, Microsoft.Quantum.Random.DrawRandomInt(0, 100))
+            }";
+            let run = || {
+                let mut interpreter = get_interpreter().with_seed(42);
+                let (result, _) = line(&mut interpreter, input);
+                result.expect("line should succeed").to_string()
+            };
+            assert_eq!(run(), run());
+        }
+
+        #[test]
+        fn step_limit_stops_runaway_line() {
+            let mut interpreter = get_interpreter().with_step_limit(1000);
+            let (result, output) = line(
+                &mut interpreter,
+                "function Loop(n : Int) : Int { Loop(n + 1) }",
+            );
+            is_only_value(&result, &output, &Value::unit());
+            let (result, output) = line(&mut interpreter, "Loop(0)");
+            is_only_error(
+                &result,
+                &output,
+                "runtime error: evaluation exceeded the step limit",
+            );
+            let stack_trace = result.expect_err("line should fail")[0]
+                .stack_trace()
+                .clone()
+                .expect("error should have a stack trace");
+            assert!(stack_trace.ends_with("    at Loop in <expression>\n"));
+            let (result, output) = line(&mut interpreter, "while true {}");
+            is_only_error(
+                &result,
+                &output,
+                "runtime error: evaluation exceeded the step limit",
+            );
+            let (result, output) = line(&mut interpreter, "1 + 1");
+            is_only_value(&result, &output, &Value::Int(2));
+        }
+
+        #[test]
+        fn qubit_limit_counts_qubits_from_earlier_lines() {
+            let mut interpreter = get_interpreter().with_qubit_limit(2);
+            let (result, output) = line(&mut interpreter, "use q = Qubit();");
+            is_only_value(&result, &output, &Value::unit());
+            let (result, output) = line(&mut interpreter, "{ use qs = Qubit[2]; }");
+            is_only_error(
+                &result,
+                &output,
+                "runtime error: qubits allocated at the same time exceed the limit of 2",
+            );
+            let (result, output) = line(&mut interpreter, "{ use r = Qubit(); }");
+            is_only_value(&result, &output, &Value::unit());
+        }
+
+        #[test]
+        fn array_limit_stops_large_array() {
+            let mut interpreter = get_interpreter().with_array_limit(10);
+            let (result, output) = line(&mut interpreter, "[0, size = 1000000000]");
+            is_only_error(
+                &result,
+                &output,
+                "runtime error: array length exceeds the limit of 10",
+            );
+            let (result, output) = line(
+                &mut interpreter,
+                "{ let xs = [0, size = 5] + [1, size = 5]; xs[9] }",
+            );
+            is_only_value(&result, &output, &Value::Int(1));
+        }
+
+        #[test]
+        fn run_shots_collects_histogram() {
+            let mut interpreter = get_interpreter().with_seed(42);
+            let mut cursor = Cursor::new(Vec::<u8>::new());
+            let mut receiver = CursorReceiver::new(&mut cursor);
+            let results = interpreter
+                .run_shots(
+                    &mut receiver,
+                    "{
+                        use q = Qubit();
+                        H(q);
+                        let r = Microsoft.Quantum.Measurement.MResetZ(q);
+                        if Microsoft.Quantum.Random.DrawRandomInt(0, 9) == 0 { fail \"unlucky\"; }
+                        r
+                    }",
+                    100,
+                )
+                .expect("line should compile");
+            assert_eq!(results.shots(), 100);
+            assert_eq!(results.histogram.len(), 2);
+            assert!(!results.errors.is_empty());
+            assert!(results.errors.iter().all(|(_, errors)| errors.len() == 1));
+        }
+
+        #[test]
+        fn run_shots_forgets_qubits_of_earlier_lines() {
+            let mut interpreter = get_interpreter()
+                .with_simulator(Simulator::Stabilizer)
+                .with_seed(42);
+            let (result, output) = line(&mut interpreter, "use q = Qubit(); X(q);");
+            is_only_value(&result, &output, &Value::unit());
+            let mut cursor = Cursor::new(Vec::<u8>::new());
+            let mut receiver = CursorReceiver::new(&mut cursor);
+            let results = interpreter
+                .run_shots(
+                    &mut receiver,
+                    "{ use qs = Qubit[2]; CNOT(qs[0], qs[1]); Microsoft.Quantum.Measurement.MResetEachZ(qs) }",
+                    10,
+                )
+                .expect("line should compile");
+            assert!(results.errors.is_empty(), "{:?}", results.errors);
+            assert_eq!(
+                results.histogram,
+                vec![(
+                    Value::Array(vec![Value::Result(false), Value::Result(false)].into()),
+                    10
+                )]
+            );
+            let (result, output) = line(&mut interpreter, "M(q)");
+            is_only_error(&result, &output, "runtime error: name is not bound");
+        }
+
+        #[test]
+        fn run_shots_compile_error() {
+            let mut interpreter = get_interpreter();
+            let mut cursor = Cursor::new(Vec::<u8>::new());
+            let mut receiver = CursorReceiver::new(&mut cursor);
+            assert!(interpreter.run_shots(&mut receiver, "Foo()", 10).is_err());
+        }
+
+        #[test]
+        fn circuit_traces_line_on_new_simulator() {
+            let mut interpreter = get_interpreter();
+            let (result, _) = line(
+                &mut interpreter,
+                "operation Bell(qs : Qubit[]) : Unit { H(qs[0]); CNOT(qs[0], qs[1]); }",
+            );
+            assert!(result.is_ok());
+            let mut cursor = Cursor::new(Vec::<u8>::new());
+            let mut receiver = CursorReceiver::new(&mut cursor);
+            let circuit = interpreter
+                .circuit(
+                    &mut receiver,
+                    "use qs = Qubit[2]; Bell(qs); Microsoft.Quantum.Measurement.MResetEachZ(qs);",
+                )
+                .expect("line should succeed");
+            expect![[r"
+                q0: -H--@--MResetZ-
+                        |
+                q1: ----X--MResetZ-
+            "]]
+            .assert_eq(&circuit.diagram());
+        }
+
+        #[test]
+        fn estimate_leaves_simulator_state_unchanged() {
+            let mut interpreter = get_interpreter();
+            let (result, _) = line(&mut interpreter, "use q = Qubit(); X(q);");
+            assert!(result.is_ok());
+            let mut cursor = Cursor::new(Vec::<u8>::new());
+            let mut receiver = CursorReceiver::new(&mut cursor);
+            let resources = interpreter
+                .estimate(
+                    &mut receiver,
+                    "use qs = Qubit[2]; H(qs[0]); CNOT(qs[0], qs[1]); T(qs[1]); ResetAll(qs);",
+                    MeasurementPolicy::Zero,
+                )
+                .expect("line should succeed");
+            assert_eq!(resources.peak_qubits, 2);
+            assert_eq!(resources.cnot_gates, 1);
+            assert_eq!(resources.t_gates, 1);
+            assert_eq!(resources.depth, 4);
+            let (result, _) = line(&mut interpreter, "M(q)");
+            assert_eq!(result.expect("line should succeed").to_string(), "One");
+        }
     }
 
     #[cfg(test)]
@@ -1,24 +1,45 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+mod session;
+#[cfg(test)]
+mod tests;
+
 use crate::{
     compile::{self, compile},
     error::WithSource,
 };
 use miette::Diagnostic;
+use num_bigint::BigUint;
+use num_complex::Complex64;
 use qsc_eval::{
-    debug::CallStack,
+    backend::{
+        Backend, LogicalCounter, LogicalResources, MeasurementPolicy, NoiseModel, Simulator,
+    },
+    debug::{CallStack, Debugger},
     eval_expr,
-    output::Receiver,
+    output::{self, Receiver, RegisterState},
+    profile::Profiler,
     val::{GlobalId, Value},
-    Env, Global,
+    CancellationToken, Env, EvalContext, Global, Limits, Recorders,
 };
 use qsc_frontend::compile::{PackageStore, Source, SourceMap};
 use qsc_hir::hir::{Expr, ItemKind, PackageId};
-use qsc_passes::entry_point::extract_entry;
+use qsc_passes::entry_point::{self, bind_entry, find_entry};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{num::NonZeroUsize, sync::Mutex, thread};
 use thiserror::Error;
 
-use super::debug::format_call_stack;
+use super::{
+    args::{self, Arg},
+    coverage,
+    debug::format_call_stack,
+    Coverage, Profile, ShotResults,
+};
+use session::StoreGlobals;
+
+pub use qsc_eval::debug::{Local, Step, StepResult};
+pub use session::{Session, StackFrame};
 
 #[derive(Clone, Debug, Diagnostic, Error)]
 #[diagnostic(transparent)]
@@ -39,6 +60,8 @@ enum ErrorKind {
     Compile(#[from] compile::Error),
     #[error(transparent)]
     Pass(#[from] qsc_passes::Error),
+    #[error(transparent)]
+    Args(#[from] args::Error),
     #[error("runtime error")]
     Eval(#[from] qsc_eval::Error),
 }
@@ -46,6 +69,11 @@ enum ErrorKind {
 pub struct Context {
     store: PackageStore,
     package: PackageId,
+    simulator: Simulator,
+    noise: NoiseModel,
+    rng: Mutex<StdRng>,
+    limits: Limits,
+    args: Vec<Expr>,
 }
 
 impl Context {
@@ -62,7 +90,7 @@ impl Context {
         let (unit, errors) = compile(&store, &dependencies, sources);
         if errors.is_empty() {
             let package = store.insert(unit);
-            Ok(Self { store, package })
+            Ok(Self::from_store(store, package))
         } else {
             Err(errors
                 .into_iter()
@@ -71,37 +99,366 @@ impl Context {
         }
     }
 
+    /// Creates a context for a package that was already compiled and inserted into the store.
+    #[must_use]
+    pub fn from_store(store: PackageStore, package: PackageId) -> Self {
+        Self {
+            store,
+            package,
+            simulator: Simulator::default(),
+            noise: NoiseModel::default(),
+            rng: Mutex::new(StdRng::from_entropy()),
+            limits: Limits::default(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Runs the program on the given simulator instead of the default one.
+    #[must_use]
+    pub fn with_simulator(self, simulator: Simulator) -> Self {
+        Self { simulator, ..self }
+    }
+
+    /// Subjects the operations in the program to errors from the noise model.
+    #[must_use]
+    pub fn with_noise(self, noise: NoiseModel) -> Self {
+        Self { noise, ..self }
+    }
+
+    /// Seeds the random number generator used for measurements, noise and classical randomness.
+    /// Each evaluation continues the random sequence of the previous one, so repeated evaluations
+    /// differ from each other but the sequence as a whole is reproducible.
+    #[must_use]
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            ..self
+        }
+    }
+
+    /// Stops each evaluation of the entry point with an error once it has taken the given number of
+    /// steps, so that a program that never finishes cannot run forever. Programs run under the
+    /// debugger are not limited.
+    #[must_use]
+    pub fn with_step_limit(mut self, steps: u64) -> Self {
+        self.limits.steps = Some(steps);
+        self
+    }
+
+    /// Stops each evaluation of the entry point with an error when it would allocate a qubit while
+    /// the given number of qubits are already allocated.
+    #[must_use]
+    pub fn with_qubit_limit(mut self, qubits: usize) -> Self {
+        self.limits.qubits = Some(qubits);
+        self
+    }
+
+    /// Stops each evaluation of the entry point with an error when it would build an array longer
+    /// than the given length by repetition or concatenation, so that a program cannot exhaust the
+    /// memory of its host.
+    #[must_use]
+    pub fn with_array_limit(mut self, len: usize) -> Self {
+        self.limits.array_len = Some(len);
+        self
+    }
+
+    /// Gives arguments to the parameters of the entry point callable by name. Each argument is
+    /// checked against the type of its parameter before the program is evaluated.
+    ///
+    /// # Errors
+    ///
+    /// Returns a vector of errors if the entry point cannot be found, or if an argument is unknown,
+    /// missing or does not have the type of its parameter.
+    pub fn with_args(
+        self,
+        args: impl IntoIterator<Item = (String, Arg)>,
+    ) -> Result<Self, Vec<Error>> {
+        let args = bind_args(&self.store, self.package, args)?;
+        Ok(Self { args, ..self })
+    }
+
+    /// Stops each evaluation of the entry point with an error once the token is cancelled.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.limits.cancellation = Some(token);
+        self
+    }
+
     /// # Errors
     ///
     /// Returns a vector of errors if evaluating the entry point fails.
     pub fn eval(&self, receiver: &mut dyn Receiver) -> Result<Value, Vec<Error>> {
-        qsc_eval::init();
+        let entry = get_entry_expr(&self.store, self.package, &self.args)?;
+        self.eval_entry(&entry, self.next_seed(), receiver, Recorders::default())
+    }
+
+    /// Evaluates the entry point like [`Self::eval`], while the recorders observe it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a vector of errors if evaluating the entry point fails.
+    pub fn eval_with_recorders(
+        &self,
+        receiver: &mut dyn Receiver,
+        recorders: Recorders,
+    ) -> Result<Value, Vec<Error>> {
+        let entry = get_entry_expr(&self.store, self.package, &self.args)?;
+        self.eval_entry(&entry, self.next_seed(), receiver, recorders)
+    }
+
+    /// The statistics recorded by the profiler in evaluations of the entry point.
+    #[must_use]
+    pub fn profile<'a>(&self, profiler: &'a Profiler) -> Profile<'a> {
+        Profile::new(&self.store, &StoreGlobals(&self.store), profiler)
+    }
+
+    /// The lines of the source files covered by evaluations of the entry point, as an lcov
+    /// tracefile.
+    #[must_use]
+    pub fn lcov(&self, coverage: &Coverage) -> String {
+        coverage::lcov(&self.store, self.package, coverage)
+    }
+
+    /// Evaluates the entry point without simulating it and counts the logical resources that its
+    /// quantum operations use. Measurement results are chosen by the policy, and the simulator and
+    /// noise model of the context are not used.
+    ///
+    /// # Errors
+    ///
+    /// Returns a vector of errors if evaluating the entry point fails, which includes programs
+    /// that need the quantum state, like those that call `DumpMachine`.
+    pub fn estimate(
+        &self,
+        policy: MeasurementPolicy,
+        receiver: &mut dyn Receiver,
+    ) -> Result<LogicalResources, Vec<Error>> {
+        let entry = get_entry_expr(&self.store, self.package, &self.args)?;
+        let mut rng = StdRng::seed_from_u64(self.next_seed());
+        let mut counter = LogicalCounter::new(policy);
+        counter.set_seed(rng.gen());
+        self.eval_entry_on(
+            &entry,
+            &mut counter,
+            &mut rng,
+            receiver,
+            Recorders::default(),
+        )?;
+        Ok(counter.resources())
+    }
+
+    /// Evaluates the entry point once per shot, each time on a new simulator, and collects the
+    /// returned values into a histogram. A shot that fails is recorded without stopping the others.
+    ///
+    /// Shots are split across the available threads. The output of each shot is buffered and
+    /// passed to the receiver in shot order once all shots finish, so the output and results are
+    /// the same as evaluating the entry point once per shot in sequence.
+    ///
+    /// # Errors
+    ///
+    /// Returns a vector of errors if the entry point cannot be found.
+    ///
+    /// # Panics
+    ///
+    /// Panics if evaluating a shot on another thread panics.
+    pub fn run_shots(
+        &self,
+        shots: usize,
+        receiver: &mut dyn Receiver,
+    ) -> Result<ShotResults<Error>, Vec<Error>> {
+        let entry = get_entry_expr(&self.store, self.package, &self.args)?;
+        let seeds: Vec<_> = (0..shots).map(|_| self.next_seed()).collect();
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let mut results = ShotResults::default();
+        if threads <= 1 || shots <= 1 {
+            for (shot, &seed) in seeds.iter().enumerate() {
+                results.record(
+                    shot,
+                    self.eval_entry(&entry, seed, receiver, Recorders::default()),
+                );
+            }
+            return Ok(results);
+        }
+
+        let shots_per_thread = (shots - 1) / threads + 1;
+        let outcomes: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = seeds
+                .chunks(shots_per_thread)
+                .map(|chunk| {
+                    scope.spawn(|| {
+                        chunk
+                            .iter()
+                            .map(|&seed| {
+                                let mut output = BufferedReceiver::default();
+                                let result = self.eval_entry(
+                                    &entry,
+                                    seed,
+                                    &mut output,
+                                    Recorders::default(),
+                                );
+                                (output, result)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("shot thread should not panic"))
+                .collect()
+        });
+
+        for (shot, (output, result)) in outcomes.into_iter().enumerate() {
+            output.replay(receiver);
+            results.record(shot, result);
+        }
+        Ok(results)
+    }
+
+    /// Starts evaluating the entry point under a debugger, paused before anything runs, and calls
+    /// `f` with the session to drive it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a vector of errors if the entry point cannot be found.
+    pub fn debug<T>(
+        &self,
+        receiver: &mut dyn Receiver,
+        f: impl FnOnce(&mut Session) -> T,
+    ) -> Result<T, Vec<Error>> {
+        let entry = get_entry_expr(&self.store, self.package, &self.args)?;
+        let mut rng = StdRng::seed_from_u64(self.next_seed());
+        let mut sim = self.noise.wrap(self.simulator.backend());
+        sim.set_seed(rng.gen());
+        let globals = StoreGlobals(&self.store);
+        let mut env = Env::with_empty_scope();
+        let debugger = Debugger::new(
+            &entry,
+            &globals,
+            self.package,
+            &mut env,
+            sim.as_mut(),
+            &mut rng,
+            receiver,
+        );
+        Ok(f(&mut Session::new(self, debugger)))
+    }
+
+    /// Draws the seed for the next evaluation from the context's random number generator.
+    fn next_seed(&self) -> u64 {
+        self.rng
+            .lock()
+            .expect("rng lock should not be poisoned")
+            .gen()
+    }
+
+    fn eval_entry(
+        &self,
+        entry: &Expr,
+        seed: u64,
+        receiver: &mut dyn Receiver,
+        recorders: Recorders,
+    ) -> Result<Value, Vec<Error>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sim = self.noise.wrap(self.simulator.backend());
+        sim.set_seed(rng.gen());
+        self.eval_entry_on(entry, sim.as_mut(), &mut rng, receiver, recorders)
+    }
 
+    fn eval_entry_on(
+        &self,
+        entry: &Expr,
+        sim: &mut dyn Backend,
+        rng: &mut StdRng,
+        receiver: &mut dyn Receiver,
+        recorders: Recorders,
+    ) -> Result<Value, Vec<Error>> {
         eval_expr(
-            &get_entry_expr(&self.store, self.package)?,
+            entry,
             &|id| get_global(&self.store, id),
             self.package,
             &mut Env::with_empty_scope(),
             receiver,
+            EvalContext::new(sim, rng)
+                .with_limits(self.limits.clone())
+                .with_recorders(recorders),
         )
-        .map_err(|(error, call_stack)| {
-            let package = self
-                .store
-                .get(self.package)
-                .expect("package should be in store");
-
-            let stack_trace = if call_stack.is_empty() {
-                None
-            } else {
-                Some(render_call_stack(&self.store, &call_stack, &error))
+        .map_err(|(error, call_stack)| vec![self.runtime_error(error, &call_stack)])
+    }
+
+    fn runtime_error(&self, error: qsc_eval::Error, call_stack: &CallStack) -> Error {
+        let package = self
+            .store
+            .get(self.package)
+            .expect("package should be in store");
+
+        let stack_trace = if call_stack.is_empty() {
+            None
+        } else {
+            Some(render_call_stack(&self.store, call_stack, &error))
+        };
+
+        Error(WithSource::from_map(
+            &package.sources,
+            error.into(),
+            stack_trace,
+        ))
+    }
+}
+
+/// Output from a single shot, held until the shots before it have passed their output to the real
+/// receiver.
+#[derive(Default)]
+struct BufferedReceiver {
+    events: Vec<OutputEvent>,
+}
+
+enum OutputEvent {
+    State(Vec<(BigUint, Complex64)>, usize),
+    Register(RegisterState, usize),
+    Message(String),
+}
+
+impl BufferedReceiver {
+    /// Passes the buffered output to the receiver. Output that the receiver fails to handle is
+    /// dropped, since the shot that produced it has already finished.
+    fn replay(self, receiver: &mut dyn Receiver) {
+        for event in self.events {
+            let result = match event {
+                OutputEvent::State(state, qubit_count) => receiver.state(state, qubit_count),
+                OutputEvent::Register(state, qubit_count) => {
+                    receiver.register_state(state, qubit_count)
+                }
+                OutputEvent::Message(msg) => receiver.message(&msg),
             };
+            if result.is_err() {
+                break;
+            }
+        }
+    }
+}
 
-            vec![Error(WithSource::from_map(
-                &package.sources,
-                error.into(),
-                stack_trace,
-            ))]
-        })
+impl Receiver for BufferedReceiver {
+    fn state(
+        &mut self,
+        state: Vec<(BigUint, Complex64)>,
+        qubit_count: usize,
+    ) -> Result<(), output::Error> {
+        self.events.push(OutputEvent::State(state, qubit_count));
+        Ok(())
+    }
+
+    fn register_state(
+        &mut self,
+        state: RegisterState,
+        qubit_count: usize,
+    ) -> Result<(), output::Error> {
+        self.events.push(OutputEvent::Register(state, qubit_count));
+        Ok(())
+    }
+
+    fn message(&mut self, msg: &str) -> Result<(), output::Error> {
+        self.events.push(OutputEvent::Message(msg.to_string()));
+        Ok(())
     }
 }
 
@@ -113,16 +470,46 @@ fn render_call_stack(
     format_call_stack(store, &|id| get_global(store, id), call_stack, error)
 }
 
-fn get_entry_expr(store: &PackageStore, package: PackageId) -> Result<Expr, Vec<Error>> {
+fn bind_args(
+    store: &PackageStore,
+    package: PackageId,
+    args: impl IntoIterator<Item = (String, Arg)>,
+) -> Result<Vec<Expr>, Vec<Error>> {
+    let unit = store.get(package).expect("store should have package");
+    let to_errors = |errors: Vec<ErrorKind>| -> Vec<Error> {
+        errors
+            .into_iter()
+            .map(|error| Error(WithSource::from_map(&unit.sources, error, None)))
+            .collect()
+    };
+
+    // An entry expression has no parameters, so any argument is unknown.
+    let params = match &unit.package.entry {
+        Some(_) => Vec::new(),
+        None => find_entry(&unit.package)
+            .and_then(entry_point::params)
+            .map_err(|errors| to_errors(errors.into_iter().map(Into::into).collect()))?,
+    };
+    args::bind(&params, args)
+        .map_err(|errors| to_errors(errors.into_iter().map(Into::into).collect()))
+}
+
+fn get_entry_expr(
+    store: &PackageStore,
+    package: PackageId,
+    args: &[Expr],
+) -> Result<Expr, Vec<Error>> {
     let unit = store.get(package).expect("store should have package");
     match &unit.package.entry {
         Some(entry) => Ok(entry.clone()),
-        None => extract_entry(&unit.package).map_err(|errors| {
-            errors
-                .into_iter()
-                .map(|error| Error(WithSource::from_map(&unit.sources, error.into(), None)))
-                .collect()
-        }),
+        None => find_entry(&unit.package)
+            .and_then(|entry| bind_entry(entry, args.to_vec()))
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|error| Error(WithSource::from_map(&unit.sources, error.into(), None)))
+                    .collect()
+            }),
     }
 }
 
@@ -0,0 +1,235 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{get_global, Context, Error};
+use crate::interpret::debug::format_callable_name;
+use num_bigint::BigUint;
+use num_complex::Complex64;
+use qsc_data_structures::span::Span;
+use qsc_eval::{
+    backend::Backend,
+    debug::{Debugger, Local, Location, Step, StepResult},
+    val::{FunctorApp, GlobalId},
+    Global, GlobalLookup,
+};
+use qsc_frontend::compile::{CompileUnit, PackageStore, SourceName};
+use qsc_hir::hir::Attr;
+use std::collections::HashMap;
+
+pub(super) struct StoreGlobals<'a>(pub(super) &'a PackageStore);
+
+impl<'a> GlobalLookup<'a> for StoreGlobals<'a> {
+    fn get(&self, id: GlobalId) -> Option<Global<'a>> {
+        get_global(self.0, id)
+    }
+}
+
+/// A frame on the stack of a paused program, described in terms of its source code.
+#[derive(Debug)]
+pub struct StackFrame {
+    /// The name of the callable, or `<entry>` for an entry expression given with the sources. When
+    /// the entry expression is the body of the `@EntryPoint()` callable, the frame is named after it.
+    pub name: String,
+    /// The source file of the program that the frame is paused in, or `None` if the frame is
+    /// paused in the entry expression or in another package such as the standard library.
+    pub source: Option<SourceName>,
+    /// The line that the frame is paused at, numbered from one.
+    pub line: u32,
+    /// The column that the frame is paused at, numbered from one.
+    pub column: u32,
+    /// The variables in scope, leaving out variables that are shadowed by an inner scope.
+    pub locals: Vec<Local>,
+}
+
+/// A program being evaluated under a debugger. Breakpoints are set on lines of the program's
+/// source files, and steps only pause in the program's own code, stepping through the standard
+/// library and other packages.
+pub struct Session<'a> {
+    context: &'a Context,
+    debugger: Debugger<'a, StoreGlobals<'a>, dyn Backend>,
+    breakpoints: HashMap<SourceName, Vec<Location>>,
+}
+
+impl<'a> Session<'a> {
+    pub(super) fn new(
+        context: &'a Context,
+        debugger: Debugger<'a, StoreGlobals<'a>, dyn Backend>,
+    ) -> Self {
+        Self {
+            context,
+            debugger,
+            breakpoints: HashMap::new(),
+        }
+    }
+
+    /// Replaces the breakpoints in the source file with breakpoints on the given lines, numbered
+    /// from one. Returns whether each line was found in the file.
+    pub fn set_breakpoints(&mut self, source: &str, lines: &[u32]) -> Vec<bool> {
+        let unit = self.unit();
+        let Some(source) = unit.sources.find_by_name(source) else {
+            return vec![false; lines.len()];
+        };
+
+        let line_spans = line_spans(&source.contents);
+        let mut verified = Vec::with_capacity(lines.len());
+        let mut locations = Vec::new();
+        for &line in lines {
+            let span = line
+                .checked_sub(1)
+                .and_then(|index| line_spans.get(index as usize));
+            if let Some(span) = span {
+                locations.push(Location {
+                    package: self.context.package,
+                    span: Span {
+                        lo: source.offset + span.lo,
+                        hi: source.offset + span.hi,
+                    },
+                });
+            }
+            verified.push(span.is_some());
+        }
+
+        self.breakpoints.insert(source.name.clone(), locations);
+        self.debugger
+            .set_breakpoints(self.breakpoints.values().flatten().copied().collect());
+        verified
+    }
+
+    /// Runs until the step finishes, a breakpoint is hit or the program returns. A step that
+    /// pauses outside the program's own code keeps stepping in until it reaches the program again.
+    /// The session must not be resumed after the program returns or fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns a vector of errors if evaluation fails.
+    pub fn resume(&mut self, step: Step) -> Result<StepResult, Vec<Error>> {
+        let mut step = step;
+        loop {
+            let result = self.debugger.resume(step).map_err(|(error, call_stack)| {
+                vec![self.context.runtime_error(error, &call_stack)]
+            })?;
+            match &result {
+                StepResult::StepComplete(location) if location.package != self.context.package => {
+                    step = Step::In;
+                }
+                _ => return Ok(result),
+            }
+        }
+    }
+
+    /// The frames on the stack, starting from the innermost frame. Returns no frames unless the
+    /// program is paused.
+    #[must_use]
+    pub fn stack_frames(&self) -> Vec<StackFrame> {
+        let store = &self.context.store;
+        let globals = StoreGlobals(store);
+        let name = |id, functor| format_callable_name(store, &globals, id, functor);
+        self.debugger
+            .stack_frames()
+            .into_iter()
+            .map(|frame| {
+                let name = match frame.callable {
+                    Some((id, functor)) => name(id, functor),
+                    None => self.entry_point().map_or_else(
+                        || "<entry>".to_string(),
+                        |id| name(id, FunctorApp::default()),
+                    ),
+                };
+
+                let mut locals: Vec<Local> = Vec::new();
+                for local in frame.scopes.into_iter().flatten() {
+                    match locals.iter_mut().find(|outer| outer.name == local.name) {
+                        Some(outer) => *outer = local,
+                        None => locals.push(local),
+                    }
+                }
+
+                let (source, line, column) = self.position(frame.location);
+                StackFrame {
+                    name,
+                    source,
+                    line,
+                    column,
+                    locals,
+                }
+            })
+            .collect()
+    }
+
+    /// The amplitudes of the quantum state at the pause point, with the number of qubits, or
+    /// `None` if the simulator cannot capture its state.
+    pub fn quantum_state(&mut self) -> Option<(Vec<(BigUint, Complex64)>, usize)> {
+        self.debugger.capture_quantum_state().ok()
+    }
+
+    fn unit(&self) -> &'a CompileUnit {
+        self.context
+            .store
+            .get(self.context.package)
+            .expect("package should be in store")
+    }
+
+    /// The `@EntryPoint()` callable, if the program has no entry expression of its own and so runs
+    /// the body of the callable instead.
+    fn entry_point(&self) -> Option<GlobalId> {
+        let package = &self.unit().package;
+        if package.entry.is_some() {
+            return None;
+        }
+        package
+            .items
+            .iter()
+            .find(|(_, item)| item.attrs.contains(&Attr::EntryPoint))
+            .map(|(item, _)| GlobalId {
+                package: self.context.package,
+                item,
+            })
+    }
+
+    fn position(&self, location: Location) -> (Option<SourceName>, u32, u32) {
+        let Some(source) = self
+            .context
+            .store
+            .get(location.package)
+            .and_then(|unit| unit.sources.find_by_offset(location.span.lo))
+        else {
+            return (None, 1, 1);
+        };
+
+        let offset = (location.span.lo - source.offset) as usize;
+        let before = source.contents.get(..offset).unwrap_or_default();
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+
+        // The entry expression has a source, but it cannot be found by name like the files of the
+        // program.
+        let is_program_file = location.package == self.context.package
+            && self.unit().sources.find_by_name(&source.name).is_some();
+        (
+            is_program_file.then(|| source.name.clone()),
+            line.try_into().expect("line should fit into u32"),
+            column.try_into().expect("column should fit into u32"),
+        )
+    }
+}
+
+/// The span of each line in the contents, not including the line break.
+fn line_spans(contents: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut lo = 0;
+    for line in contents.split('\n') {
+        let len: u32 = line
+            .len()
+            .try_into()
+            .expect("line length should fit into u32");
+        spans.push(Span { lo, hi: lo + len });
+        lo += len + 1;
+    }
+    spans
+}
@@ -0,0 +1,353 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{Context, Step, StepResult};
+use expect_test::expect;
+use indoc::indoc;
+use qsc_eval::{
+    backend::{MeasurementPolicy, NoiseModel},
+    output::{format_state_id, CursorReceiver},
+    CancellationToken,
+};
+use qsc_frontend::compile::SourceMap;
+use std::{error::Error, fmt::Write, io::Cursor, thread, time::Duration};
+
+fn context(seed: u64) -> Context {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {r#"
+                namespace Test {
+                    open Microsoft.Quantum.Measurement;
+                    open Microsoft.Quantum.Random;
+
+                    @EntryPoint()
+                    operation Main() : (Result[], Int) {
+                        use qs = Qubit[3];
+                        for q in qs { H(q); }
+                        let results = MResetEachZ(qs);
+                        let n = DrawRandomInt(0, 9);
+                        Message($"drew {n}");
+                        if n == 0 { fail "unlucky"; }
+                        (results, n)
+                    }
+                }
+            "#}
+            .into(),
+        )],
+        None,
+    );
+    Context::new(true, sources)
+        .expect("code should compile")
+        .with_seed(seed)
+}
+
+#[test]
+fn run_shots_matches_sequential_evaluation() {
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    let results = context(42)
+        .run_shots(200, &mut receiver)
+        .expect("entry point should be found");
+    let parallel_output = receiver.dump();
+
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    let sequential = context(42);
+    let mut histogram: Vec<(String, usize)> = Vec::new();
+    let mut failures = Vec::new();
+    for shot in 0..200 {
+        match sequential.eval(&mut receiver) {
+            Ok(value) => {
+                let value = value.to_string();
+                match histogram.iter_mut().find(|(v, _)| *v == value) {
+                    Some((_, count)) => *count += 1,
+                    None => histogram.push((value, 1)),
+                }
+            }
+            Err(_) => failures.push(shot),
+        }
+    }
+
+    assert_eq!(receiver.dump(), parallel_output);
+    assert_eq!(
+        results
+            .histogram
+            .iter()
+            .map(|(value, count)| (value.to_string(), *count))
+            .collect::<Vec<_>>(),
+        histogram
+    );
+    assert_eq!(
+        results
+            .errors
+            .iter()
+            .map(|(shot, _)| *shot)
+            .collect::<Vec<_>>(),
+        failures
+    );
+    assert!(!failures.is_empty());
+}
+
+#[test]
+fn noisy_ancillas_are_reset_on_release() {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Test {
+                    @EntryPoint()
+                    operation Main() : Result {
+                        use q = Qubit();
+                        X(q);
+                        use ancilla = Qubit();
+                        CNOT(q, ancilla);
+                        CNOT(q, ancilla);
+                        let r = M(q);
+                        if r == One { X(q); }
+                        r
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    let context = Context::new(true, sources)
+        .expect("code should compile")
+        .with_noise(NoiseModel {
+            depolarizing: 0.1,
+            idle: 0.1,
+            readout: 0.1,
+            ..NoiseModel::default()
+        })
+        .with_seed(42);
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    let results = context
+        .run_shots(100, &mut receiver)
+        .expect("entry point should be found");
+    assert!(results.errors.is_empty(), "{:?}", results.errors);
+}
+
+#[test]
+fn context_is_shareable_across_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Context>();
+    assert_send_sync::<qsc_eval::val::Value>();
+    assert_send_sync::<super::Error>();
+}
+
+#[test]
+fn debug_session_pauses_in_program_code() {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Test {
+                    open Microsoft.Quantum.Measurement;
+
+                    operation Prepare(q : Qubit) : Unit {
+                        let angle = 0.5;
+                        H(q);
+                    }
+
+                    @EntryPoint()
+                    operation Main() : Result {
+                        use q = Qubit();
+                        let x = 1;
+                        Prepare(q);
+                        let x = x + 1;
+                        MResetZ(q)
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    let context = Context::new(true, sources).expect("code should compile");
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    let trace = context
+        .debug(&mut receiver, |session| {
+            assert_eq!(session.set_breakpoints("test", &[5, 99]), [true, false]);
+            assert_eq!(session.set_breakpoints("other", &[1]), [false]);
+            let mut trace = String::new();
+            for step in [Step::Continue, Step::Over, Step::In, Step::Over] {
+                let result = session.resume(step).expect("program should not fail");
+                assert!(
+                    !matches!(result, StepResult::Return(_)),
+                    "program should not return"
+                );
+                writeln!(trace, "{step:?}:").expect("writing to string should succeed");
+                for frame in session.stack_frames() {
+                    let locals: Vec<_> = frame
+                        .locals
+                        .iter()
+                        .map(|local| format!("{} = {}", local.name, local.value))
+                        .collect();
+                    writeln!(
+                        trace,
+                        "    {} {:?}:{}:{} [{}]",
+                        frame.name,
+                        frame.source.as_deref(),
+                        frame.line,
+                        frame.column,
+                        locals.join(", ")
+                    )
+                    .expect("writing to string should succeed");
+                }
+                let (state, qubit_count) =
+                    session.quantum_state().expect("state should be captured");
+                for (id, amplitude) in state {
+                    writeln!(
+                        trace,
+                        "    {}: {:.4}",
+                        format_state_id(&id, qubit_count),
+                        amplitude
+                    )
+                    .expect("writing to string should succeed");
+                }
+            }
+            trace
+        })
+        .expect("entry point should be found");
+    expect![[r#"
+        Continue:
+            Test.Prepare Some("test"):5:9 [q = Qubit0]
+            Test.Main Some("test"):13:9 [q = Qubit0, x = 1]
+            |0⟩: 1.0000+0.0000i
+        Over:
+            Test.Prepare Some("test"):6:9 [q = Qubit0, angle = 0.5]
+            Test.Main Some("test"):13:9 [q = Qubit0, x = 1]
+            |0⟩: 1.0000+0.0000i
+        In:
+            Test.Main Some("test"):14:9 [q = Qubit0, x = 1]
+            |0⟩: 0.7071+0.0000i
+            |1⟩: 0.7071+0.0000i
+        Over:
+            Test.Main Some("test"):15:9 [q = Qubit0, x = 2]
+            |0⟩: 0.7071+0.0000i
+            |1⟩: 0.7071+0.0000i
+    "#]]
+    .assert_eq(&trace);
+}
+
+#[test]
+fn cancellation_interrupts_running_evaluation() {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Test {
+                    @EntryPoint()
+                    operation Main() : Unit {
+                        while true {}
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    let token = CancellationToken::new();
+    let context = Context::new(true, sources)
+        .expect("code should compile")
+        .with_cancellation(token.clone());
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        token.cancel();
+    });
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    let errors = context
+        .eval(&mut receiver)
+        .expect_err("evaluation should be interrupted");
+    canceller.join().expect("canceller should succeed");
+    let source = errors[0].source().expect("error should have a source");
+    assert_eq!(source.to_string(), "evaluation was interrupted");
+}
+
+#[test]
+fn estimate_counts_resources_with_given_measurements() {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Test {
+                    @EntryPoint()
+                    operation Main() : Unit {
+                        use (a, b) = (Qubit(), Qubit());
+                        T(a);
+                        if M(a) == One {
+                            CNOT(a, b);
+                            T(b);
+                        }
+                        ResetAll([a, b]);
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    let context = Context::new(true, sources).expect("code should compile");
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    let zero = context
+        .estimate(MeasurementPolicy::Zero, &mut receiver)
+        .expect("estimate should succeed");
+    let one = context
+        .estimate(MeasurementPolicy::Given(vec![true]), &mut receiver)
+        .expect("estimate should succeed");
+    expect![[r"
+        peak qubits:  2
+        total qubits: 2
+        T gates:      1
+        rotations:    0
+        CNOT gates:   0
+        CCX gates:    0
+        measurements: 1
+        other gates:  2
+        depth:        3
+        T-depth:      1"]]
+    .assert_eq(&zero.to_string());
+    expect![[r"
+        peak qubits:  2
+        total qubits: 2
+        T gates:      2
+        rotations:    0
+        CNOT gates:   1
+        CCX gates:    0
+        measurements: 1
+        other gates:  2
+        depth:        5
+        T-depth:      2"]]
+    .assert_eq(&one.to_string());
+}
+
+#[test]
+fn estimate_cannot_dump_state() {
+    let sources = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Test {
+                    @EntryPoint()
+                    operation Main() : Unit {
+                        Microsoft.Quantum.Diagnostics.DumpMachine();
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    let context = Context::new(true, sources).expect("code should compile");
+    let mut cursor = Cursor::new(Vec::<u8>::new());
+    let mut receiver = CursorReceiver::new(&mut cursor);
+    context
+        .estimate(MeasurementPolicy::Zero, &mut receiver)
+        .expect_err("estimate should fail");
+}
@@ -5,9 +5,11 @@
 
 pub mod compile;
 mod error;
+pub mod estimate;
 pub mod interpret;
 
 pub use qsc_frontend::compile::{PackageStore, SourceContents, SourceMap, SourceName};
+pub use qsc_passes::qubit_count;
 
 pub mod hir {
     pub use qsc_hir::{hir::*, *};
@@ -3,18 +3,146 @@
 
 #![warn(clippy::mod_module_files, clippy::pedantic, clippy::unwrap_used)]
 
-#[must_use]
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+mod partial_eval;
+pub mod qasm;
+pub mod qir;
+mod val;
+
+use miette::Diagnostic;
+use qsc_data_structures::span::Span;
+use thiserror::Error;
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+pub enum Error {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    EntryPoint(qsc_passes::Error),
+
+    #[error("array too large")]
+    #[diagnostic(code("Qsc.Codegen.ArrayTooLarge"))]
+    ArrayTooLarge(#[label("this array has too many items")] Span),
+
+    #[error("invalid array length: {0}")]
+    #[diagnostic(code("Qsc.Codegen.InvalidArrayLength"))]
+    InvalidArrayLength(i64, #[label("cannot be used as a length")] Span),
+
+    #[error("division by zero")]
+    #[diagnostic(code("Qsc.Codegen.DivZero"))]
+    DivZero(#[label("cannot divide by zero")] Span),
+
+    #[error("array size or index depends on a measurement result")]
+    #[diagnostic(help(
+        "generated code cannot use arrays whose size or indices are only known at runtime"
+    ))]
+    #[diagnostic(code("Qsc.Codegen.DynamicArray"))]
+    DynamicArray(#[label("requires a measurement result at runtime")] Span),
+
+    #[error("cannot exit from a branch that depends on a measurement result")]
+    #[diagnostic(help(
+        "generated code cannot return or fail conditionally on measurement results"
+    ))]
+    #[diagnostic(code("Qsc.Codegen.DynamicExit"))]
+    DynamicExit(#[label("exits conditionally")] Span),
+
+    #[error("loop condition depends on a measurement result")]
+    #[diagnostic(help(
+        "generated code cannot use loops whose number of iterations is only known at runtime"
+    ))]
+    #[diagnostic(code("Qsc.Codegen.DynamicLoop"))]
+    DynamicLoop(#[label("requires a measurement result at runtime")] Span),
+
+    #[error("value depends on a measurement result")]
+    #[diagnostic(help(
+        "generated code only supports Bool and Int computations on measurement results"
+    ))]
+    #[diagnostic(code("Qsc.Codegen.DynamicValue"))]
+    DynamicValue(#[label("requires a measurement result at runtime")] Span),
+
+    #[error("value cannot be used as an index: {0}")]
+    #[diagnostic(code("Qsc.Codegen.InvalidIndex"))]
+    InvalidIndex(i64, #[label("invalid index")] Span),
+
+    #[error("integer too large for operation")]
+    #[diagnostic(code("Qsc.Codegen.IntTooLarge"))]
+    IntTooLarge(i64, #[label("this value is too large")] Span),
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[error("missing specialization: {0}")]
+    #[diagnostic(code("Qsc.Codegen.MissingSpec"))]
+    MissingSpec(String, #[label("callable has no {0} specialization")] Span),
+
+    #[error("index out of range: {0}")]
+    #[diagnostic(code("Qsc.Codegen.IndexOutOfRange"))]
+    IndexOutOfRange(i64, #[label("out of range")] Span),
+
+    #[error("negative integers cannot be used here: {0}")]
+    #[diagnostic(code("Qsc.Codegen.InvalidNegativeInt"))]
+    InvalidNegativeInt(i64, #[label("invalid negative integer")] Span),
+
+    #[error("qubits in gate invocation are not unique")]
+    #[diagnostic(code("Qsc.Codegen.QubitUniqueness"))]
+    QubitUniqueness(#[label] Span),
+
+    #[error("range with step size of zero")]
+    #[diagnostic(code("Qsc.Codegen.RangeStepZero"))]
+    RangeStepZero(#[label("invalid range")] Span),
+
+    #[error("cannot compare measurement results")]
+    #[diagnostic(help(
+        "the base profile does not support branching on or comparing measurement results, use the adaptive profile instead"
+    ))]
+    #[diagnostic(code("Qsc.Codegen.ResultComparison"))]
+    ResultComparison(#[label("requires a measurement result at runtime")] Span),
+
+    #[error("name is not bound")]
+    #[diagnostic(code("Qsc.Codegen.UnboundName"))]
+    UnboundName(#[label] Span),
+
+    #[error("unknown intrinsic `{0}`")]
+    #[diagnostic(code("Qsc.Codegen.UnknownIntrinsic"))]
+    UnknownIntrinsic(String, #[label("callable has no implementation")] Span),
+
+    #[error("intrinsic `{0}` cannot be used in generated code")]
+    #[diagnostic(code("Qsc.Codegen.UnsupportedIntrinsic"))]
+    UnsupportedIntrinsic(String, #[label("not supported by the target profile")] Span),
+
+    #[error("entry point output of type {0} cannot be recorded")]
+    #[diagnostic(help(
+        "the entry point may only return results, booleans, integers, doubles, and tuples or arrays of them"
+    ))]
+    #[diagnostic(code("Qsc.Codegen.UnsupportedOutput"))]
+    UnsupportedOutput(&'static str, #[label("unsupported output")] Span),
+
+    #[error("program failed: {0}")]
+    #[diagnostic(code("Qsc.Codegen.UserFail"))]
+    UserFail(String, #[label("explicit fail")] Span),
+}
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+impl Error {
+    /// Moves the error to a different span, keeping its message.
+    #[must_use]
+    fn with_span(self, span: Span) -> Self {
+        match self {
+            Self::EntryPoint(error) => Self::EntryPoint(error),
+            Self::ArrayTooLarge(_) => Self::ArrayTooLarge(span),
+            Self::InvalidArrayLength(len, _) => Self::InvalidArrayLength(len, span),
+            Self::DivZero(_) => Self::DivZero(span),
+            Self::DynamicArray(_) => Self::DynamicArray(span),
+            Self::DynamicExit(_) => Self::DynamicExit(span),
+            Self::DynamicLoop(_) => Self::DynamicLoop(span),
+            Self::DynamicValue(_) => Self::DynamicValue(span),
+            Self::InvalidIndex(index, _) => Self::InvalidIndex(index, span),
+            Self::IntTooLarge(val, _) => Self::IntTooLarge(val, span),
+            Self::MissingSpec(spec, _) => Self::MissingSpec(spec, span),
+            Self::IndexOutOfRange(index, _) => Self::IndexOutOfRange(index, span),
+            Self::InvalidNegativeInt(val, _) => Self::InvalidNegativeInt(val, span),
+            Self::QubitUniqueness(_) => Self::QubitUniqueness(span),
+            Self::RangeStepZero(_) => Self::RangeStepZero(span),
+            Self::ResultComparison(_) => Self::ResultComparison(span),
+            Self::UnboundName(_) => Self::UnboundName(span),
+            Self::UnknownIntrinsic(name, _) => Self::UnknownIntrinsic(name, span),
+            Self::UnsupportedIntrinsic(name, _) => Self::UnsupportedIntrinsic(name, span),
+            Self::UnsupportedOutput(ty, _) => Self::UnsupportedOutput(ty, span),
+            Self::UserFail(msg, _) => Self::UserFail(msg, span),
+        }
     }
 }
@@ -0,0 +1,1455 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Evaluates the classical parts of a program ahead of time, recording the quantum operations it
+//! performs as a list of instructions over statically allocated qubits and results.
+//!
+//! When measurement-dependent code is allowed, values computed from measurement results are held in
+//! runtime variables, and conditionals on them are recorded as blocks of instructions that execute
+//! depending on a variable's value.
+
+use crate::{
+    val::{self, FunctorApp, GlobalId, Value, Var, VarTy},
+    Error,
+};
+use num_bigint::BigInt;
+use qsc_data_structures::span::Span;
+use qsc_frontend::compile::PackageStore;
+use qsc_hir::hir::{
+    self, BinOp, Block, Expr, ExprKind, Field, Functor, ItemKind, Lit, LocalItemId, NodeId,
+    PackageId, Pat, PatKind, PrimField, Res, SpecBody, SpecGen, Stmt, StmtKind, StringComponent,
+    UnOp,
+};
+use qsc_passes::entry_point::extract_entry;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::{self, Display, Formatter, Write},
+    iter, mem,
+    ops::Neg,
+    sync::Arc,
+};
+
+/// A program produced by evaluating an entry expression.
+pub(super) struct Program {
+    /// The quantum instructions in the order they are executed.
+    pub(super) instrs: Vec<Instr>,
+    /// The number of distinct qubits used by the program.
+    pub(super) num_qubits: usize,
+    /// The number of distinct results written by the program.
+    pub(super) num_results: usize,
+    /// The value returned by the entry expression.
+    pub(super) output: Value,
+}
+
+/// An instruction in the generated program.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Instr {
+    /// A call to a quantum intrinsic, such as `__quantum__qis__h__body`.
+    Call(Arc<str>, Vec<Operand>),
+    /// A measurement of a qubit into a result, which also resets the qubit if `reset` is true.
+    Measure {
+        qubit: usize,
+        result: usize,
+        reset: bool,
+    },
+    /// Reads a measurement result into a Bool variable that is true if the result is `One`.
+    ReadResult { var: Var, result: usize },
+    /// A classical operation on runtime values.
+    Op {
+        var: Var,
+        op: Op,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    /// Executes `then` if `cond` is true and `otherwise` if it is false, then assigns each phi
+    /// variable according to the branch that was taken.
+    If {
+        cond: Var,
+        then: Vec<Instr>,
+        otherwise: Vec<Instr>,
+        phis: Vec<Phi>,
+    },
+}
+
+/// An operand to an instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Operand {
+    Bool(bool),
+    Double(f64),
+    Int(i64),
+    Qubit(usize),
+    Var(Var),
+}
+
+/// A classical operation, with the semantics of the LLVM instruction of the same name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Op {
+    Add,
+    Sub,
+    Mul,
+    SDiv,
+    SRem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    AShr,
+    Eq,
+    Sgt,
+    Sge,
+    Slt,
+    Sle,
+}
+
+impl Op {
+    /// True if the operation compares its operands, producing a Bool.
+    pub(super) fn is_comparison(self) -> bool {
+        matches!(self, Op::Eq | Op::Sgt | Op::Sge | Op::Slt | Op::Sle)
+    }
+}
+
+/// A variable assigned at the end of an `If` instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Phi {
+    pub(super) var: Var,
+    /// The value of the variable if the `then` branch was taken.
+    pub(super) then: Operand,
+    /// The value of the variable if the `otherwise` branch was taken.
+    pub(super) otherwise: Operand,
+}
+
+/// Finds the entry expression of a package, which is either given explicitly or is a call to its
+/// entry point callable.
+pub(super) fn entry_expr(store: &PackageStore, package: PackageId) -> Result<Expr, Vec<Error>> {
+    let unit = store.get(package).expect("package should be in store");
+    match &unit.package.entry {
+        Some(entry) => Ok(entry.clone()),
+        None => extract_entry(&unit.package)
+            .map_err(|errors| errors.into_iter().map(Error::EntryPoint).collect()),
+    }
+}
+
+/// Evaluates the entry expression of a package. If `dynamic` is true, measurement results may be
+/// compared and branched on, which is recorded with runtime variables and conditional blocks.
+pub(super) fn evaluate(
+    store: &PackageStore,
+    package: PackageId,
+    entry: &Expr,
+    dynamic: bool,
+) -> Result<Program, Error> {
+    let mut evaluator = Evaluator {
+        store,
+        entry_package: package,
+        package,
+        dynamic,
+        env: Env::with_empty_scope(),
+        qubits: Vec::new(),
+        num_qubits: 0,
+        num_results: 0,
+        next_var: 0,
+        instrs: Vec::new(),
+    };
+
+    let output = match evaluator.eval_expr(entry) {
+        Ok(value) | Err(Break::Return(value, _)) => value,
+        Err(Break::Error(error) | Break::Located(error)) => return Err(error),
+    };
+
+    Ok(Program {
+        instrs: evaluator.instrs,
+        num_qubits: evaluator.num_qubits,
+        num_results: evaluator.num_results,
+        output,
+    })
+}
+
+/// A specialization that may be implemented for an operation.
+enum Spec {
+    Body,
+    Adj,
+    Ctl,
+    CtlAdj,
+}
+
+impl Display for Spec {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Spec::Body => f.write_str("body"),
+            Spec::Adj => f.write_str("adjoint"),
+            Spec::Ctl => f.write_str("controlled"),
+            Spec::CtlAdj => f.write_str("controlled adjoint"),
+        }
+    }
+}
+
+/// Interrupts evaluation of the current callable.
+enum Break {
+    /// A `return` expression was evaluated.
+    Return(Value, Span),
+    /// Evaluation failed.
+    Error(Error),
+    /// Evaluation failed with an error that is already reported in the package where evaluation
+    /// started.
+    Located(Error),
+}
+
+impl From<Error> for Break {
+    fn from(error: Error) -> Self {
+        Break::Error(error)
+    }
+}
+
+type EvalResult = Result<Value, Break>;
+
+#[derive(Clone, Default)]
+struct Env(Vec<HashMap<NodeId, Value>>);
+
+impl Env {
+    fn with_empty_scope() -> Self {
+        Self(vec![HashMap::new()])
+    }
+
+    fn get(&self, id: NodeId) -> Option<&Value> {
+        self.0.iter().rev().find_map(|scope| scope.get(&id))
+    }
+
+    fn get_mut(&mut self, id: NodeId) -> Option<&mut Value> {
+        self.0.iter_mut().rev().find_map(|scope| scope.get_mut(&id))
+    }
+
+    fn push_scope(&mut self) {
+        self.0.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.0
+            .pop()
+            .expect("scope should be entered first before leaving");
+    }
+
+    fn bind(&mut self, pat: &Pat, val: Value) {
+        match &pat.kind {
+            PatKind::Bind(variable) => {
+                let scope = self.0.last_mut().expect("binding should have a scope");
+                match scope.entry(variable.id) {
+                    Entry::Vacant(entry) => entry.insert(val),
+                    Entry::Occupied(_) => panic!("duplicate binding"),
+                };
+            }
+            PatKind::Discard => {}
+            PatKind::Tuple(tup) => {
+                let val_tup = val.unwrap_tuple();
+                for (pat, val) in tup.iter().zip(val_tup.iter()) {
+                    self.bind(pat, val.clone());
+                }
+            }
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    store: &'a PackageStore,
+    entry_package: PackageId,
+    package: PackageId,
+    dynamic: bool,
+    env: Env,
+    qubits: Vec<bool>,
+    num_qubits: usize,
+    num_results: usize,
+    next_var: usize,
+    instrs: Vec<Instr>,
+}
+
+impl Evaluator<'_> {
+    fn eval_expr(&mut self, expr: &Expr) -> EvalResult {
+        match &expr.kind {
+            ExprKind::Array(arr) => {
+                let mut vals = Vec::with_capacity(arr.len());
+                for item in arr {
+                    vals.push(self.eval_expr(item)?);
+                }
+                Ok(Value::Array(vals.into()))
+            }
+            ExprKind::ArrayRepeat(item, size) => {
+                let item_val = self.eval_expr(item)?;
+                let size_val =
+                    static_int(self.eval_expr(size)?, || Error::DynamicArray(size.span))?;
+                match size_val.try_into() {
+                    Ok(size) => Ok(Value::Array(vec![item_val; size].into())),
+                    Err(_) => Err(Error::InvalidArrayLength(size_val, size.span).into()),
+                }
+            }
+            ExprKind::Assign(lhs, rhs) => {
+                let rhs_val = self.eval_expr(rhs)?;
+                self.update_binding(lhs, rhs_val)?;
+                Ok(Value::unit())
+            }
+            ExprKind::AssignOp(op, lhs, rhs) => {
+                let val = self.eval_binop(*op, lhs, rhs, expr.span)?;
+                self.update_binding(lhs, val)?;
+                Ok(Value::unit())
+            }
+            ExprKind::AssignField(record, field, replace) => {
+                let val = self.eval_update_field(record, field, replace)?;
+                self.update_binding(record, val)?;
+                Ok(Value::unit())
+            }
+            ExprKind::AssignIndex(lhs, mid, rhs) => {
+                let val = self.eval_update_index(lhs, mid, rhs)?;
+                self.update_binding(lhs, val)?;
+                Ok(Value::unit())
+            }
+            ExprKind::BinOp(op, lhs, rhs) => self.eval_binop(*op, lhs, rhs, expr.span),
+            ExprKind::Block(block) => self.eval_block(block),
+            ExprKind::Call(callee, arg) => {
+                let callee_val = self.eval_expr(callee)?;
+                let arg_val = self.eval_expr(arg)?;
+                self.eval_call(callee_val, arg_val, callee.span, arg.span)
+            }
+            ExprKind::Closure(args, callable) => {
+                Ok(self.resolve_closure(expr.span, args, *callable)?)
+            }
+            ExprKind::Conjugate(..) => panic!("conjugate should be eliminated by passes"),
+            ExprKind::Err => panic!("error expr should not be present"),
+            ExprKind::Fail(msg) => {
+                let msg = self.eval_expr(msg)?.unwrap_string();
+                Err(Error::UserFail(msg.to_string(), expr.span).into())
+            }
+            ExprKind::Field(record, field) => {
+                let record = self.eval_expr(record)?;
+                Ok(eval_field(record, field))
+            }
+            ExprKind::For(..) => panic!("for-loop should be eliminated by passes"),
+            ExprKind::Hole => panic!("hole expr should be disallowed by passes"),
+            ExprKind::If(cond, then_expr, else_expr) => {
+                self.eval_if(cond, then_expr, else_expr.as_deref(), expr.span)
+            }
+            ExprKind::Index(arr, index) => self.eval_index(arr, index),
+            ExprKind::Lit(lit) => Ok(lit_to_val(lit)),
+            ExprKind::Range(start, step, end) => {
+                self.eval_range(start.as_deref(), step.as_deref(), end.as_deref())
+            }
+            ExprKind::Repeat(..) => panic!("repeat-loop should be eliminated by passes"),
+            ExprKind::Return(value) => Err(Break::Return(self.eval_expr(value)?, expr.span)),
+            ExprKind::String(components) => self.eval_string(components),
+            ExprKind::Tuple(tup) => {
+                let mut vals = Vec::with_capacity(tup.len());
+                for item in tup {
+                    vals.push(self.eval_expr(item)?);
+                }
+                Ok(Value::Tuple(vals.into()))
+            }
+            ExprKind::UnOp(op, operand) => match self.eval_expr(operand)? {
+                Value::Var(var) => Ok(self.eval_dynamic_unop(*op, var)),
+                val => Ok(eval_unop(*op, val)),
+            },
+            ExprKind::UpdateField(record, field, replace) => {
+                self.eval_update_field(record, field, replace)
+            }
+            ExprKind::UpdateIndex(lhs, mid, rhs) => self.eval_update_index(lhs, mid, rhs),
+            ExprKind::Var(res, _) => Ok(self.resolve_binding(*res, expr.span)?),
+            ExprKind::While(cond, block) => self.eval_while(cond, block),
+        }
+    }
+
+    fn eval_block(&mut self, block: &Block) -> EvalResult {
+        self.env.push_scope();
+        let mut val = Value::unit();
+        for stmt in &block.stmts {
+            val = self.eval_stmt(stmt)?;
+        }
+        self.env.pop_scope();
+        Ok(val)
+    }
+
+    fn eval_stmt(&mut self, stmt: &Stmt) -> EvalResult {
+        match &stmt.kind {
+            StmtKind::Expr(expr) => self.eval_expr(expr),
+            StmtKind::Item(..) => Ok(Value::unit()),
+            StmtKind::Local(_, pat, expr) => {
+                let val = self.eval_expr(expr)?;
+                self.env.bind(pat, val);
+                Ok(Value::unit())
+            }
+            StmtKind::Qubit(..) => panic!("qubit use-stmt should be eliminated by passes"),
+            StmtKind::Semi(expr) => {
+                self.eval_expr(expr)?;
+                Ok(Value::unit())
+            }
+        }
+    }
+
+    fn eval_if(
+        &mut self,
+        cond: &Expr,
+        then_expr: &Expr,
+        else_expr: Option<&Expr>,
+        span: Span,
+    ) -> EvalResult {
+        match self.eval_expr(cond)? {
+            Value::Var(cond) => self.eval_dynamic_if(
+                cond,
+                |this| this.eval_expr(then_expr),
+                |this| match else_expr {
+                    Some(else_expr) => this.eval_expr(else_expr),
+                    None => Ok(Value::unit()),
+                },
+                span,
+            ),
+            cond => {
+                if cond.unwrap_bool() {
+                    self.eval_expr(then_expr)
+                } else if let Some(else_expr) = else_expr {
+                    self.eval_expr(else_expr)
+                } else {
+                    Ok(Value::unit())
+                }
+            }
+        }
+    }
+
+    fn eval_while(&mut self, cond: &Expr, block: &Block) -> EvalResult {
+        loop {
+            match self.eval_expr(cond)? {
+                Value::Var(_) => return Err(Error::DynamicLoop(cond.span).into()),
+                cond_val => {
+                    if !cond_val.unwrap_bool() {
+                        break;
+                    }
+                }
+            }
+            self.eval_block(block)?;
+        }
+        Ok(Value::unit())
+    }
+
+    fn eval_index(&mut self, arr: &Expr, index: &Expr) -> EvalResult {
+        let arr = self.eval_expr(arr)?.unwrap_array();
+        match self.eval_expr(index)? {
+            Value::Int(i) => Ok(index_array(&arr, i, index.span)?),
+            Value::Range(start, step, end) => Ok(slice_array(&arr, start, step, end, index.span)?),
+            Value::Var(_) => Err(Error::DynamicArray(index.span).into()),
+            _ => panic!("array should only be indexed by Int or Range"),
+        }
+    }
+
+    fn eval_range(
+        &mut self,
+        start: Option<&Expr>,
+        step: Option<&Expr>,
+        end: Option<&Expr>,
+    ) -> EvalResult {
+        let mut eval_bound = |bound: &Expr| {
+            static_int(self.eval_expr(bound)?, || Error::DynamicValue(bound.span))
+                .map_err(Break::from)
+        };
+        let start = match start {
+            Some(start) => Some(eval_bound(start)?),
+            None => None,
+        };
+        let step = match step {
+            Some(step) => eval_bound(step)?,
+            None => val::DEFAULT_RANGE_STEP,
+        };
+        let end = match end {
+            Some(end) => Some(eval_bound(end)?),
+            None => None,
+        };
+        Ok(Value::Range(start, step, end))
+    }
+
+    fn eval_string(&mut self, components: &[StringComponent]) -> EvalResult {
+        let mut string = String::new();
+        for component in components {
+            match component {
+                StringComponent::Expr(expr) => {
+                    let val = self.eval_expr(expr)?;
+                    write!(string, "{val}").expect("string should be writable");
+                }
+                StringComponent::Lit(lit) => string.push_str(lit),
+            }
+        }
+        Ok(Value::String(string.into()))
+    }
+
+    fn eval_binop(&mut self, op: BinOp, lhs: &Expr, rhs: &Expr, span: Span) -> EvalResult {
+        let lhs_val = self.eval_expr(lhs)?;
+        match op {
+            BinOp::AndL => match lhs_val {
+                Value::Var(cond) => self.eval_dynamic_if(
+                    cond,
+                    |this| this.eval_expr(rhs),
+                    |_| Ok(Value::Bool(false)),
+                    span,
+                ),
+                lhs_val => {
+                    if lhs_val.unwrap_bool() {
+                        self.eval_expr(rhs)
+                    } else {
+                        Ok(Value::Bool(false))
+                    }
+                }
+            },
+            BinOp::OrL => match lhs_val {
+                Value::Var(cond) => self.eval_dynamic_if(
+                    cond,
+                    |_| Ok(Value::Bool(true)),
+                    |this| this.eval_expr(rhs),
+                    span,
+                ),
+                lhs_val => {
+                    if lhs_val.unwrap_bool() {
+                        Ok(Value::Bool(true))
+                    } else {
+                        self.eval_expr(rhs)
+                    }
+                }
+            },
+            BinOp::Eq => {
+                let rhs_val = self.eval_expr(rhs)?;
+                Ok(self.eval_eq(&lhs_val, &rhs_val, span)?)
+            }
+            BinOp::Neq => {
+                let rhs_val = self.eval_expr(rhs)?;
+                match self.eval_eq(&lhs_val, &rhs_val, span)? {
+                    Value::Var(var) => Ok(self.eval_dynamic_unop(UnOp::NotL, var)),
+                    eq => Ok(Value::Bool(!eq.unwrap_bool())),
+                }
+            }
+            _ => {
+                let rhs_val = self.eval_expr(rhs)?;
+                if matches!(lhs_val, Value::Var(_)) || matches!(rhs_val, Value::Var(_)) {
+                    Ok(self.eval_dynamic_binop(op, &lhs_val, &rhs_val, span, rhs.span)?)
+                } else {
+                    Ok(eval_static_binop(op, lhs_val, rhs_val, rhs.span)?)
+                }
+            }
+        }
+    }
+
+    /// Compares two values for equality. Comparisons that depend on measurement results are
+    /// recorded as instructions if measurement-dependent code is allowed, and are an error
+    /// otherwise.
+    fn eval_eq(&mut self, lhs: &Value, rhs: &Value, span: Span) -> Result<Value, Error> {
+        match (lhs, rhs) {
+            (&Value::Result(lhs), &Value::Result(rhs)) => self.eval_result_eq(lhs, rhs, span),
+            (Value::Array(lhs), Value::Array(rhs)) | (Value::Tuple(lhs), Value::Tuple(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Ok(Value::Bool(false));
+                }
+                let mut vars = Vec::new();
+                for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+                    match self.eval_eq(lhs, rhs, span)? {
+                        Value::Bool(true) => {}
+                        Value::Bool(false) => return Ok(Value::Bool(false)),
+                        var => vars.push(var),
+                    }
+                }
+                Ok(vars
+                    .into_iter()
+                    .reduce(|lhs, rhs| self.emit_op(Op::And, &lhs, &rhs))
+                    .unwrap_or(Value::Bool(true)))
+            }
+            (Value::Var(_), _) | (_, Value::Var(_)) => Ok(self.emit_op(Op::Eq, lhs, rhs)),
+            _ => Ok(Value::Bool(lhs == rhs)),
+        }
+    }
+
+    fn eval_result_eq(
+        &mut self,
+        lhs: val::Result,
+        rhs: val::Result,
+        span: Span,
+    ) -> Result<Value, Error> {
+        match (lhs, rhs) {
+            (val::Result::Lit(lhs), val::Result::Lit(rhs)) => Ok(Value::Bool(lhs == rhs)),
+            _ if !self.dynamic => Err(Error::ResultComparison(span)),
+            (val::Result::Measured(id), val::Result::Lit(one))
+            | (val::Result::Lit(one), val::Result::Measured(id)) => {
+                let is_one = self.read_result(id);
+                if one {
+                    Ok(is_one)
+                } else {
+                    Ok(self.emit_op(Op::Xor, &is_one, &Value::Bool(true)))
+                }
+            }
+            (val::Result::Measured(lhs), val::Result::Measured(rhs)) => {
+                let lhs = self.read_result(lhs);
+                let rhs = self.read_result(rhs);
+                Ok(self.emit_op(Op::Eq, &lhs, &rhs))
+            }
+        }
+    }
+
+    /// Records a binary operator where at least one operand is a runtime variable.
+    fn eval_dynamic_binop(
+        &mut self,
+        op: BinOp,
+        lhs_val: &Value,
+        rhs_val: &Value,
+        span: Span,
+        rhs_span: Span,
+    ) -> Result<Value, Error> {
+        let op = match op {
+            BinOp::Add => Op::Add,
+            BinOp::AndB => Op::And,
+            BinOp::Div if *rhs_val == Value::Int(0) => return Err(Error::DivZero(rhs_span)),
+            BinOp::Div => Op::SDiv,
+            BinOp::Gt => Op::Sgt,
+            BinOp::Gte => Op::Sge,
+            BinOp::Lt => Op::Slt,
+            BinOp::Lte => Op::Sle,
+            BinOp::Mod => Op::SRem,
+            BinOp::Mul => Op::Mul,
+            BinOp::OrB => Op::Or,
+            BinOp::Shl | BinOp::Shr => {
+                let &Value::Int(amount) = rhs_val else {
+                    return Err(Error::DynamicValue(rhs_span));
+                };
+                let shift = if (op == BinOp::Shl) == (amount > 0) {
+                    Op::Shl
+                } else {
+                    Op::AShr
+                };
+                return Ok(self.emit_op(shift, lhs_val, &Value::Int(amount.abs())));
+            }
+            BinOp::Sub => Op::Sub,
+            BinOp::XorB => Op::Xor,
+            BinOp::Exp => return Err(Error::DynamicValue(span)),
+            BinOp::AndL | BinOp::Eq | BinOp::Neq | BinOp::OrL => {
+                panic!("operator should not be evaluated as a dynamic binop")
+            }
+        };
+        Ok(self.emit_op(op, lhs_val, rhs_val))
+    }
+
+    /// Records a unary operator on a runtime variable.
+    fn eval_dynamic_unop(&mut self, op: UnOp, var: Var) -> Value {
+        match op {
+            UnOp::Neg => self.emit_op(Op::Sub, &Value::Int(0), &Value::Var(var)),
+            UnOp::NotB => self.emit_op(Op::Xor, &Value::Var(var), &Value::Int(-1)),
+            UnOp::NotL => self.emit_op(Op::Xor, &Value::Var(var), &Value::Bool(true)),
+            UnOp::Pos | UnOp::Unwrap => Value::Var(var),
+            UnOp::Functor(_) => panic!("value should be callable"),
+        }
+    }
+
+    /// Evaluates both branches of a conditional whose condition is only known at runtime. The
+    /// instructions of each branch are recorded in an `If` instruction, and values that differ
+    /// between the branches, including the result and any reassigned variables, are merged into
+    /// new runtime variables.
+    fn eval_dynamic_if(
+        &mut self,
+        cond: Var,
+        then_branch: impl FnOnce(&mut Self) -> EvalResult,
+        else_branch: impl FnOnce(&mut Self) -> EvalResult,
+        span: Span,
+    ) -> EvalResult {
+        let env = self.env.clone();
+        let instrs = mem::take(&mut self.instrs);
+
+        let then_val = forbid_exit(then_branch(self))?;
+        let then_instrs = mem::take(&mut self.instrs);
+        let mut then_env = mem::replace(&mut self.env, env);
+
+        let else_val = forbid_exit(else_branch(self))?;
+        let else_instrs = mem::replace(&mut self.instrs, instrs);
+        let else_env = mem::take(&mut self.env);
+
+        let mut phis = Vec::new();
+        let val = self.merge(then_val, &else_val, &mut phis, span)?;
+        for (scope, mut else_scope) in then_env.0.iter_mut().zip(else_env.0) {
+            let mut ids: Vec<_> = scope.keys().copied().collect();
+            ids.sort_unstable();
+            for id in ids {
+                let else_val = else_scope
+                    .remove(&id)
+                    .expect("variable should be bound in both branches");
+                let then_val = scope
+                    .get_mut(&id)
+                    .expect("variable should be bound in both branches");
+                *then_val = self.merge(then_val.clone(), &else_val, &mut phis, span)?;
+            }
+        }
+
+        self.env = then_env;
+        if !then_instrs.is_empty() || !else_instrs.is_empty() || !phis.is_empty() {
+            self.instrs.push(Instr::If {
+                cond,
+                then: then_instrs,
+                otherwise: else_instrs,
+                phis,
+            });
+        }
+        Ok(val)
+    }
+
+    /// Merges the values produced by the two branches of an `If` instruction, adding a phi for
+    /// each Bool or Int that differs between them.
+    fn merge(
+        &mut self,
+        then_val: Value,
+        else_val: &Value,
+        phis: &mut Vec<Phi>,
+        span: Span,
+    ) -> Result<Value, Error> {
+        if then_val == *else_val {
+            return Ok(then_val);
+        }
+
+        match (&then_val, else_val) {
+            (Value::Array(then_items), Value::Array(else_items))
+            | (Value::Tuple(then_items), Value::Tuple(else_items))
+                if then_items.len() == else_items.len() =>
+            {
+                let mut items = Vec::with_capacity(then_items.len());
+                for (then_item, else_item) in then_items.iter().zip(else_items.iter()) {
+                    items.push(self.merge(then_item.clone(), else_item, phis, span)?);
+                }
+                Ok(if matches!(then_val, Value::Array(_)) {
+                    Value::Array(items.into())
+                } else {
+                    Value::Tuple(items.into())
+                })
+            }
+            _ => match (var_ty(&then_val), var_ty(else_val)) {
+                (Some(then_ty), Some(else_ty)) if then_ty == else_ty => {
+                    let var = self.new_var(then_ty);
+                    phis.push(Phi {
+                        var,
+                        then: to_operand(&then_val),
+                        otherwise: to_operand(else_val),
+                    });
+                    Ok(Value::Var(var))
+                }
+                _ => Err(Error::DynamicValue(span)),
+            },
+        }
+    }
+
+    fn new_var(&mut self, ty: VarTy) -> Var {
+        let var = Var {
+            id: self.next_var,
+            ty,
+        };
+        self.next_var += 1;
+        var
+    }
+
+    fn read_result(&mut self, result: usize) -> Value {
+        let var = self.new_var(VarTy::Bool);
+        self.instrs.push(Instr::ReadResult { var, result });
+        Value::Var(var)
+    }
+
+    fn emit_op(&mut self, op: Op, lhs: &Value, rhs: &Value) -> Value {
+        let ty = if op.is_comparison() {
+            VarTy::Bool
+        } else {
+            var_ty(lhs).expect("operand should be Bool or Int")
+        };
+        let var = self.new_var(ty);
+        self.instrs.push(Instr::Op {
+            var,
+            op,
+            lhs: to_operand(lhs),
+            rhs: to_operand(rhs),
+        });
+        Value::Var(var)
+    }
+
+    fn eval_update_field(&mut self, record: &Expr, field: &Field, replace: &Expr) -> EvalResult {
+        let record = self.eval_expr(record)?;
+        let value = self.eval_expr(replace)?;
+        let int = || static_int(value.clone(), || Error::DynamicValue(replace.span));
+        Ok(match (record, field) {
+            (Value::Range(_, step, end), Field::Prim(PrimField::Start)) => {
+                Value::Range(Some(int()?), step, end)
+            }
+            (Value::Range(start, _, end), Field::Prim(PrimField::Step)) => {
+                Value::Range(start, int()?, end)
+            }
+            (Value::Range(start, step, _), Field::Prim(PrimField::End)) => {
+                Value::Range(start, step, Some(int()?))
+            }
+            (record, Field::Path(path)) => update_field_path(&record, &path.indices, &value)
+                .expect("field path should be valid"),
+            _ => panic!("invalid field access"),
+        })
+    }
+
+    fn eval_update_index(&mut self, lhs: &Expr, mid: &Expr, rhs: &Expr) -> EvalResult {
+        let index = static_int(self.eval_expr(mid)?, || Error::DynamicArray(mid.span))?;
+        let update = self.eval_expr(rhs)?;
+        let values = self.eval_expr(lhs)?.unwrap_array();
+        if index < 0 {
+            return Err(Error::InvalidNegativeInt(index, mid.span).into());
+        }
+        let i = as_index(index, mid.span)?;
+        let mut values = values.iter().cloned().collect::<Vec<_>>();
+        match values.get_mut(i) {
+            Some(value) => *value = update,
+            None => return Err(Error::IndexOutOfRange(index, mid.span).into()),
+        }
+        Ok(Value::Array(values.into()))
+    }
+
+    fn eval_call(
+        &mut self,
+        callee: Value,
+        arg: Value,
+        callee_span: Span,
+        arg_span: Span,
+    ) -> EvalResult {
+        let (callee_id, functor, fixed_args) = match callee {
+            Value::Closure(fixed_args, id, functor) => (id, functor, Some(fixed_args)),
+            Value::Global(id, functor) => (id, functor, None),
+            _ => panic!("value is not callable"),
+        };
+
+        let arg = if let Some(fixed_args) = fixed_args {
+            Value::Tuple(fixed_args.iter().cloned().chain(iter::once(arg)).collect())
+        } else {
+            arg
+        };
+
+        let store = self.store;
+        let callee = match store
+            .get(callee_id.package)
+            .and_then(|unit| unit.package.items.get(callee_id.item))
+            .map(|item| &item.kind)
+        {
+            Some(ItemKind::Callable(callable)) => callable,
+            Some(ItemKind::Ty(..)) => return Ok(arg),
+            _ => return Err(Error::UnboundName(callee_span).into()),
+        };
+
+        let spec = spec_from_functor_app(functor);
+        let spec_decl = match spec {
+            Spec::Body => Some(&callee.body),
+            Spec::Adj => callee.adj.as_ref(),
+            Spec::Ctl => callee.ctl.as_ref(),
+            Spec::CtlAdj => callee.ctl_adj.as_ref(),
+        }
+        .ok_or_else(|| Error::MissingSpec(spec.to_string(), callee_span))?;
+
+        match &spec_decl.body {
+            SpecBody::Impl(input, block) => {
+                let caller_env = mem::replace(&mut self.env, Env::with_empty_scope());
+                let caller_package = mem::replace(&mut self.package, callee_id.package);
+                bind_args_for_spec(
+                    &mut self.env,
+                    &callee.input,
+                    input.as_ref(),
+                    arg,
+                    functor.controlled,
+                );
+                let res = self.eval_block(block);
+                self.env = caller_env;
+                self.package = caller_package;
+                match res {
+                    Ok(val) | Err(Break::Return(val, _)) => Ok(val),
+                    Err(Break::Error(error)) => Err(self.locate(error, callee_id, callee_span)),
+                    Err(Break::Located(error)) => Err(Break::Located(error)),
+                }
+            }
+            SpecBody::Gen(SpecGen::Intrinsic) => {
+                Ok(self.call_intrinsic(&callee.name.name, callee_span, arg, arg_span)?)
+            }
+            SpecBody::Gen(_) => Err(Error::MissingSpec(spec.to_string(), callee_span).into()),
+        }
+    }
+
+    /// Reports an error that left a callable. Errors from inside other packages, usually the
+    /// standard library, are reported at the innermost call made from the package where evaluation
+    /// started, since their spans cannot be shown with that package's sources.
+    fn locate(&self, error: Error, callee_id: GlobalId, callee_span: Span) -> Break {
+        if self.package != self.entry_package {
+            if callee_id.package == self.entry_package {
+                Break::Located(error)
+            } else {
+                Break::Error(error)
+            }
+        } else if callee_id.package == self.entry_package {
+            Break::Error(error)
+        } else {
+            Break::Error(error.with_span(callee_span))
+        }
+    }
+
+    fn call_intrinsic(
+        &mut self,
+        name: &Arc<str>,
+        name_span: Span,
+        arg: Value,
+        arg_span: Span,
+    ) -> Result<Value, Error> {
+        if name.as_ref() != "Length" && contains_var(&arg) {
+            return Err(Error::DynamicValue(arg_span));
+        }
+
+        match name.as_ref() {
+            "Length" => match arg.unwrap_array().len().try_into() {
+                Ok(len) => Ok(Value::Int(len)),
+                Err(_) => Err(Error::ArrayTooLarge(arg_span)),
+            },
+            #[allow(clippy::cast_precision_loss)]
+            "IntAsDouble" => Ok(Value::Double(arg.unwrap_int() as f64)),
+            "IntAsBigInt" => Ok(Value::BigInt(BigInt::from(arg.unwrap_int()))),
+            "DumpMachine"
+            | "DumpRegister"
+            | "Message"
+            | "AssertMeasurementProbabilityImpl"
+            | "AssertQubitIsInStateImpl"
+            | "AssertAllZeroImpl" => Ok(Value::unit()),
+            "ArcCos" => Ok(Value::Double(arg.unwrap_double().acos())),
+            "ArcSin" => Ok(Value::Double(arg.unwrap_double().asin())),
+            "ArcTan" => Ok(Value::Double(arg.unwrap_double().atan())),
+            "ArcTan2" => {
+                let [x, y] = unwrap_tuple(arg);
+                Ok(Value::Double(x.unwrap_double().atan2(y.unwrap_double())))
+            }
+            "Cos" => Ok(Value::Double(arg.unwrap_double().cos())),
+            "Cosh" => Ok(Value::Double(arg.unwrap_double().cosh())),
+            "Sin" => Ok(Value::Double(arg.unwrap_double().sin())),
+            "Sinh" => Ok(Value::Double(arg.unwrap_double().sinh())),
+            "Tan" => Ok(Value::Double(arg.unwrap_double().tan())),
+            "Tanh" => Ok(Value::Double(arg.unwrap_double().tanh())),
+            "Sqrt" => Ok(Value::Double(arg.unwrap_double().sqrt())),
+            "Log" => Ok(Value::Double(arg.unwrap_double().ln())),
+            #[allow(clippy::cast_possible_truncation)]
+            "Truncate" => Ok(Value::Int(arg.unwrap_double() as i64)),
+            "CheckZero" | "DrawRandomInt" => {
+                Err(Error::UnsupportedIntrinsic(name.to_string(), name_span))
+            }
+            "__quantum__rt__qubit_allocate" => Ok(Value::Qubit(self.allocate_qubit())),
+            "__quantum__rt__qubit_release" => {
+                self.qubits[arg.unwrap_qubit()] = false;
+                Ok(Value::unit())
+            }
+            "__quantum__qis__m__body" | "__quantum__qis__mresetz__body" => {
+                let result = self.num_results;
+                self.num_results += 1;
+                self.instrs.push(Instr::Measure {
+                    qubit: arg.unwrap_qubit(),
+                    result,
+                    reset: name.as_ref() == "__quantum__qis__mresetz__body",
+                });
+                Ok(Value::Result(val::Result::Measured(result)))
+            }
+            "__quantum__qis__ccx__body"
+            | "__quantum__qis__cx__body"
+            | "__quantum__qis__cy__body"
+            | "__quantum__qis__cz__body"
+            | "__quantum__qis__rx__body"
+            | "__quantum__qis__rxx__body"
+            | "__quantum__qis__ry__body"
+            | "__quantum__qis__ryy__body"
+            | "__quantum__qis__rz__body"
+            | "__quantum__qis__rzz__body"
+            | "__quantum__qis__h__body"
+            | "__quantum__qis__s__body"
+            | "__quantum__qis__s__adj"
+            | "__quantum__qis__t__body"
+            | "__quantum__qis__t__adj"
+            | "__quantum__qis__x__body"
+            | "__quantum__qis__y__body"
+            | "__quantum__qis__z__body"
+            | "__quantum__qis__swap__body"
+            | "__quantum__qis__reset__body" => {
+                let mut operands = Vec::new();
+                flatten_operands(arg, &mut operands);
+                let qubits: Vec<_> = operands
+                    .iter()
+                    .filter_map(|operand| match operand {
+                        Operand::Qubit(q) => Some(q),
+                        _ => None,
+                    })
+                    .collect();
+                if (1..qubits.len()).any(|i| qubits[..i].contains(&qubits[i])) {
+                    return Err(Error::QubitUniqueness(arg_span));
+                }
+                self.instrs.push(Instr::Call(Arc::clone(name), operands));
+                Ok(Value::unit())
+            }
+            _ => Err(Error::UnknownIntrinsic(name.to_string(), name_span)),
+        }
+    }
+
+    fn allocate_qubit(&mut self) -> usize {
+        let id = self
+            .qubits
+            .iter()
+            .position(|in_use| !in_use)
+            .unwrap_or_else(|| {
+                self.qubits.push(false);
+                self.qubits.len() - 1
+            });
+        self.qubits[id] = true;
+        self.num_qubits = self.num_qubits.max(self.qubits.len());
+        id
+    }
+
+    fn resolve_binding(&self, res: Res, span: Span) -> Result<Value, Error> {
+        Ok(match res {
+            Res::Err => panic!("resolution error"),
+            Res::Item(item) => Value::Global(
+                GlobalId {
+                    package: item.package.unwrap_or(self.package),
+                    item: item.item,
+                },
+                FunctorApp::default(),
+            ),
+            Res::Local(node) => self.env.get(node).ok_or(Error::UnboundName(span))?.clone(),
+        })
+    }
+
+    fn resolve_closure(
+        &self,
+        span: Span,
+        args: &[NodeId],
+        callable: LocalItemId,
+    ) -> Result<Value, Error> {
+        let args: Option<_> = args.iter().map(|&arg| self.env.get(arg).cloned()).collect();
+        let args: Vec<_> = args.ok_or(Error::UnboundName(span))?;
+        let callable = GlobalId {
+            package: self.package,
+            item: callable,
+        };
+        Ok(Value::Closure(args.into(), callable, FunctorApp::default()))
+    }
+
+    #[allow(clippy::similar_names)]
+    fn update_binding(&mut self, lhs: &Expr, rhs: Value) -> Result<(), Error> {
+        match (&lhs.kind, rhs) {
+            (ExprKind::Hole, _) => {}
+            (&ExprKind::Var(Res::Local(node), _), rhs) => match self.env.get_mut(node) {
+                Some(var) => *var = rhs,
+                None => return Err(Error::UnboundName(lhs.span)),
+            },
+            (ExprKind::Tuple(var_tup), Value::Tuple(tup)) => {
+                for (expr, val) in var_tup.iter().zip(tup.iter()) {
+                    self.update_binding(expr, val.clone())?;
+                }
+            }
+            _ => panic!("unassignable pattern should be disallowed by compiler"),
+        }
+        Ok(())
+    }
+}
+
+fn bind_args_for_spec(
+    env: &mut Env,
+    decl_pat: &Pat,
+    spec_pat: Option<&Pat>,
+    args_val: Value,
+    ctl_count: u8,
+) {
+    match spec_pat {
+        Some(spec_pat) => {
+            assert!(
+                ctl_count > 0,
+                "spec pattern tuple used without controlled functor"
+            );
+
+            let mut tup = args_val;
+            let mut ctls = vec![];
+            for _ in 0..ctl_count {
+                let [c, rest] = &*tup.unwrap_tuple() else {
+                    panic!("tuple should be arity 2");
+                };
+                ctls.extend_from_slice(&c.clone().unwrap_array());
+                tup = rest.clone();
+            }
+
+            env.bind(spec_pat, Value::Array(ctls.into()));
+            env.bind(decl_pat, tup);
+        }
+        None => env.bind(decl_pat, args_val),
+    }
+}
+
+fn spec_from_functor_app(functor: FunctorApp) -> Spec {
+    match (functor.adjoint, functor.controlled) {
+        (false, 0) => Spec::Body,
+        (true, 0) => Spec::Adj,
+        (false, _) => Spec::Ctl,
+        (true, _) => Spec::CtlAdj,
+    }
+}
+
+fn flatten_operands(arg: Value, operands: &mut Vec<Operand>) {
+    match arg {
+        Value::Double(v) => operands.push(Operand::Double(v)),
+        Value::Qubit(q) => operands.push(Operand::Qubit(q)),
+        Value::Tuple(tup) => {
+            for item in tup.iter() {
+                flatten_operands(item.clone(), operands);
+            }
+        }
+        _ => panic!("intrinsic operand should be Double or Qubit"),
+    }
+}
+
+fn unwrap_tuple<const N: usize>(value: Value) -> [Value; N] {
+    let values = value.unwrap_tuple();
+    std::array::from_fn(|i| values[i].clone())
+}
+
+fn lit_to_val(lit: &Lit) -> Value {
+    match lit {
+        Lit::BigInt(v) => Value::BigInt(v.clone()),
+        Lit::Bool(v) => Value::Bool(*v),
+        Lit::Double(v) => Value::Double(*v),
+        Lit::Int(v) => Value::Int(*v),
+        Lit::Pauli(v) => Value::Pauli(*v),
+        Lit::Result(hir::Result::Zero) => Value::Result(val::Result::Lit(false)),
+        Lit::Result(hir::Result::One) => Value::Result(val::Result::Lit(true)),
+    }
+}
+
+/// Checks the result of a branch of a dynamic conditional. Leaving the callable from inside the
+/// branch would make the rest of the program conditional, so it is an error.
+fn forbid_exit(result: EvalResult) -> EvalResult {
+    match result {
+        Err(Break::Return(_, span) | Break::Error(Error::UserFail(_, span))) => {
+            Err(Error::DynamicExit(span).into())
+        }
+        Err(Break::Located(Error::UserFail(_, span))) => {
+            Err(Break::Located(Error::DynamicExit(span)))
+        }
+        res => res,
+    }
+}
+
+fn eval_static_binop(
+    op: BinOp,
+    lhs_val: Value,
+    rhs_val: Value,
+    rhs_span: Span,
+) -> Result<Value, Error> {
+    match op {
+        BinOp::Add => Ok(eval_binop_add(lhs_val, rhs_val)),
+        BinOp::AndB => Ok(eval_binop_andb(lhs_val, rhs_val)),
+        BinOp::Div => eval_binop_div(lhs_val, rhs_val, rhs_span),
+        BinOp::Exp => eval_binop_exp(lhs_val, rhs_val, rhs_span),
+        BinOp::Gt | BinOp::Gte | BinOp::Lt | BinOp::Lte => Ok(eval_binop_cmp(lhs_val, rhs_val, op)),
+        BinOp::Mod => Ok(eval_binop_mod(lhs_val, rhs_val)),
+        BinOp::Mul => Ok(eval_binop_mul(lhs_val, rhs_val)),
+        BinOp::OrB => Ok(eval_binop_orb(lhs_val, rhs_val)),
+        BinOp::Shl => Ok(eval_binop_shl(lhs_val, rhs_val)),
+        BinOp::Shr => Ok(eval_binop_shr(lhs_val, rhs_val)),
+        BinOp::Sub => Ok(eval_binop_sub(lhs_val, rhs_val)),
+        BinOp::XorB => Ok(eval_binop_xorb(lhs_val, rhs_val)),
+        BinOp::AndL | BinOp::Eq | BinOp::Neq | BinOp::OrL => {
+            panic!("operator should not be evaluated as a static binop")
+        }
+    }
+}
+
+/// Unwraps an Int value, returning an error if the value is only known at runtime.
+fn static_int(value: Value, error: impl FnOnce() -> Error) -> Result<i64, Error> {
+    match value {
+        Value::Var(_) => Err(error()),
+        value => Ok(value.unwrap_int()),
+    }
+}
+
+fn contains_var(value: &Value) -> bool {
+    match value {
+        Value::Var(_) => true,
+        Value::Array(items) | Value::Closure(items, ..) | Value::Tuple(items) => {
+            items.iter().any(contains_var)
+        }
+        _ => false,
+    }
+}
+
+fn var_ty(value: &Value) -> Option<VarTy> {
+    match value {
+        Value::Bool(_) => Some(VarTy::Bool),
+        Value::Int(_) => Some(VarTy::Int),
+        Value::Var(var) => Some(var.ty),
+        _ => None,
+    }
+}
+
+fn to_operand(value: &Value) -> Operand {
+    match *value {
+        Value::Bool(v) => Operand::Bool(v),
+        Value::Int(v) => Operand::Int(v),
+        Value::Var(var) => Operand::Var(var),
+        _ => panic!("value should be Bool or Int, got {}", value.type_name()),
+    }
+}
+
+fn as_index(index: i64, span: Span) -> Result<usize, Error> {
+    index
+        .try_into()
+        .map_err(|_| Error::InvalidIndex(index, span))
+}
+
+fn index_array(arr: &[Value], index: i64, span: Span) -> Result<Value, Error> {
+    let i = as_index(index, span)?;
+    match arr.get(i) {
+        Some(v) => Ok(v.clone()),
+        None => Err(Error::IndexOutOfRange(index, span)),
+    }
+}
+
+fn slice_array(
+    arr: &[Value],
+    start: Option<i64>,
+    step: i64,
+    end: Option<i64>,
+    span: Span,
+) -> Result<Value, Error> {
+    if step == 0 {
+        return Err(Error::RangeStepZero(span));
+    }
+
+    let len: i64 = arr
+        .len()
+        .try_into()
+        .map_err(|_| Error::ArrayTooLarge(span))?;
+    let (start, end) = if step > 0 {
+        (start.unwrap_or(0), end.unwrap_or(len - 1))
+    } else {
+        (start.unwrap_or(len - 1), end.unwrap_or(0))
+    };
+
+    let mut slice = vec![];
+    let mut i = start;
+    while (step > 0 && i <= end) || (step < 0 && i >= end) {
+        slice.push(index_array(arr, i, span)?);
+        i += step;
+    }
+
+    Ok(Value::Array(slice.into()))
+}
+
+fn eval_field(record: Value, field: &Field) -> Value {
+    match (record, field) {
+        (Value::Range(Some(start), _, _), Field::Prim(PrimField::Start)) => Value::Int(start),
+        (Value::Range(_, step, _), Field::Prim(PrimField::Step)) => Value::Int(step),
+        (Value::Range(_, _, Some(end)), Field::Prim(PrimField::End)) => Value::Int(end),
+        (mut record, Field::Path(path)) => {
+            for &index in &path.indices {
+                let Value::Tuple(items) = record else {
+                    panic!("field path should be valid");
+                };
+                record = items[index].clone();
+            }
+            record
+        }
+        _ => panic!("invalid field access"),
+    }
+}
+
+fn update_field_path(record: &Value, path: &[usize], replace: &Value) -> Option<Value> {
+    match (record, path) {
+        (_, []) => Some(replace.clone()),
+        (Value::Tuple(items), &[next_index, ..]) if next_index < items.len() => {
+            let update = |(index, item)| {
+                if index == next_index {
+                    update_field_path(item, &path[1..], replace)
+                } else {
+                    Some(item.clone())
+                }
+            };
+
+            let items: Option<_> = items.iter().enumerate().map(update).collect();
+            Some(Value::Tuple(items?))
+        }
+        _ => None,
+    }
+}
+
+fn eval_unop(op: UnOp, val: Value) -> Value {
+    match op {
+        UnOp::Functor(functor) => match val {
+            Value::Closure(args, id, app) => {
+                Value::Closure(args, id, update_functor_app(functor, app))
+            }
+            Value::Global(id, app) => Value::Global(id, update_functor_app(functor, app)),
+            _ => panic!("value should be callable"),
+        },
+        UnOp::Neg => match val {
+            Value::BigInt(v) => Value::BigInt(v.neg()),
+            Value::Double(v) => Value::Double(v.neg()),
+            Value::Int(v) => Value::Int(v.wrapping_neg()),
+            _ => panic!("value should be number"),
+        },
+        UnOp::NotB => match val {
+            Value::Int(v) => Value::Int(!v),
+            Value::BigInt(v) => Value::BigInt(!v),
+            _ => panic!("value should be Int or BigInt"),
+        },
+        UnOp::NotL => Value::Bool(!val.unwrap_bool()),
+        UnOp::Pos | UnOp::Unwrap => val,
+    }
+}
+
+fn update_functor_app(functor: Functor, app: FunctorApp) -> FunctorApp {
+    match functor {
+        Functor::Adj => FunctorApp {
+            adjoint: !app.adjoint,
+            controlled: app.controlled,
+        },
+        Functor::Ctl => FunctorApp {
+            adjoint: app.adjoint,
+            controlled: app.controlled + 1,
+        },
+    }
+}
+
+fn eval_binop_add(lhs_val: Value, rhs_val: Value) -> Value {
+    match lhs_val {
+        Value::Array(arr) => {
+            let rhs_arr = rhs_val.unwrap_array();
+            Value::Array(arr.iter().chain(rhs_arr.iter()).cloned().collect())
+        }
+        Value::BigInt(val) => Value::BigInt(val + rhs_val.unwrap_big_int()),
+        Value::Double(val) => Value::Double(val + rhs_val.unwrap_double()),
+        Value::Int(val) => Value::Int(val + rhs_val.unwrap_int()),
+        Value::String(val) => Value::String((val.to_string() + &rhs_val.unwrap_string()).into()),
+        _ => panic!("value is not addable"),
+    }
+}
+
+fn eval_binop_andb(lhs_val: Value, rhs_val: Value) -> Value {
+    match lhs_val {
+        Value::BigInt(val) => Value::BigInt(val & rhs_val.unwrap_big_int()),
+        Value::Int(val) => Value::Int(val & rhs_val.unwrap_int()),
+        _ => panic!("value type does not support andb"),
+    }
+}
+
+fn eval_binop_cmp(lhs_val: Value, rhs_val: Value, op: BinOp) -> Value {
+    let ordering = match lhs_val {
+        Value::BigInt(val) => val.partial_cmp(&rhs_val.unwrap_big_int()),
+        Value::Double(val) => val.partial_cmp(&rhs_val.unwrap_double()),
+        Value::Int(val) => val.partial_cmp(&rhs_val.unwrap_int()),
+        _ => panic!("value doesn't support comparison"),
+    };
+    Value::Bool(ordering.is_some_and(|ordering| match op {
+        BinOp::Gt => ordering.is_gt(),
+        BinOp::Gte => ordering.is_ge(),
+        BinOp::Lt => ordering.is_lt(),
+        BinOp::Lte => ordering.is_le(),
+        _ => panic!("operator should be a comparison"),
+    }))
+}
+
+fn eval_binop_div(lhs_val: Value, rhs_val: Value, rhs_span: Span) -> Result<Value, Error> {
+    match lhs_val {
+        Value::BigInt(val) => {
+            let rhs = rhs_val.unwrap_big_int();
+            if rhs == BigInt::from(0) {
+                Err(Error::DivZero(rhs_span))
+            } else {
+                Ok(Value::BigInt(val / rhs))
+            }
+        }
+        Value::Int(val) => {
+            let rhs = rhs_val.unwrap_int();
+            if rhs == 0 {
+                Err(Error::DivZero(rhs_span))
+            } else {
+                Ok(Value::Int(val / rhs))
+            }
+        }
+        Value::Double(val) => {
+            let rhs = rhs_val.unwrap_double();
+            if rhs == 0.0 {
+                Err(Error::DivZero(rhs_span))
+            } else {
+                Ok(Value::Double(val / rhs))
+            }
+        }
+        _ => panic!("value should support div"),
+    }
+}
+
+fn eval_binop_exp(lhs_val: Value, rhs_val: Value, rhs_span: Span) -> Result<Value, Error> {
+    let exp = |rhs_val: Value| {
+        let rhs_val = rhs_val.unwrap_int();
+        if rhs_val < 0 {
+            Err(Error::InvalidNegativeInt(rhs_val, rhs_span))
+        } else {
+            u32::try_from(rhs_val).map_err(|_| Error::IntTooLarge(rhs_val, rhs_span))
+        }
+    };
+
+    match lhs_val {
+        Value::BigInt(val) => Ok(Value::BigInt(val.pow(exp(rhs_val)?))),
+        Value::Double(val) => Ok(Value::Double(val.powf(rhs_val.unwrap_double()))),
+        Value::Int(val) => Ok(Value::Int(val.pow(exp(rhs_val)?))),
+        _ => panic!("value should support exp"),
+    }
+}
+
+fn eval_binop_mod(lhs_val: Value, rhs_val: Value) -> Value {
+    match lhs_val {
+        Value::BigInt(val) => Value::BigInt(val % rhs_val.unwrap_big_int()),
+        Value::Int(val) => Value::Int(val % rhs_val.unwrap_int()),
+        Value::Double(val) => Value::Double(val % rhs_val.unwrap_double()),
+        _ => panic!("value should support mod"),
+    }
+}
+
+fn eval_binop_mul(lhs_val: Value, rhs_val: Value) -> Value {
+    match lhs_val {
+        Value::BigInt(val) => Value::BigInt(val * rhs_val.unwrap_big_int()),
+        Value::Int(val) => Value::Int(val * rhs_val.unwrap_int()),
+        Value::Double(val) => Value::Double(val * rhs_val.unwrap_double()),
+        _ => panic!("value should support mul"),
+    }
+}
+
+fn eval_binop_orb(lhs_val: Value, rhs_val: Value) -> Value {
+    match lhs_val {
+        Value::BigInt(val) => Value::BigInt(val | rhs_val.unwrap_big_int()),
+        Value::Int(val) => Value::Int(val | rhs_val.unwrap_int()),
+        _ => panic!("value type does not support orb"),
+    }
+}
+
+fn eval_binop_shl(lhs_val: Value, rhs_val: Value) -> Value {
+    let rhs = rhs_val.unwrap_int();
+    match lhs_val {
+        Value::BigInt(val) if rhs > 0 => Value::BigInt(val << rhs),
+        Value::BigInt(val) => Value::BigInt(val >> rhs.abs()),
+        Value::Int(val) if rhs > 0 => Value::Int(val << rhs),
+        Value::Int(val) => Value::Int(val >> rhs.abs()),
+        _ => panic!("value should support shl"),
+    }
+}
+
+fn eval_binop_shr(lhs_val: Value, rhs_val: Value) -> Value {
+    let rhs = rhs_val.unwrap_int();
+    match lhs_val {
+        Value::BigInt(val) if rhs > 0 => Value::BigInt(val >> rhs),
+        Value::BigInt(val) => Value::BigInt(val << rhs.abs()),
+        Value::Int(val) if rhs > 0 => Value::Int(val >> rhs),
+        Value::Int(val) => Value::Int(val << rhs.abs()),
+        _ => panic!("value should support shr"),
+    }
+}
+
+fn eval_binop_sub(lhs_val: Value, rhs_val: Value) -> Value {
+    match lhs_val {
+        Value::BigInt(val) => Value::BigInt(val - rhs_val.unwrap_big_int()),
+        Value::Double(val) => Value::Double(val - rhs_val.unwrap_double()),
+        Value::Int(val) => Value::Int(val - rhs_val.unwrap_int()),
+        _ => panic!("value is not subtractable"),
+    }
+}
+
+fn eval_binop_xorb(lhs_val: Value, rhs_val: Value) -> Value {
+    match lhs_val {
+        Value::BigInt(val) => Value::BigInt(val ^ rhs_val.unwrap_big_int()),
+        Value::Int(val) => Value::Int(val ^ rhs_val.unwrap_int()),
+        _ => panic!("value type does not support xorb"),
+    }
+}
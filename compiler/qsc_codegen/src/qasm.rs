@@ -0,0 +1,351 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    partial_eval::{self, Instr, Op, Operand, Program},
+    val::{self, Value, Var, VarTy},
+    Error,
+};
+use qsc_data_structures::span::Span;
+use qsc_frontend::compile::PackageStore;
+use qsc_hir::hir::PackageId;
+use std::{collections::HashMap, fmt::Write, iter};
+
+/// Definitions of the two-qubit rotations, which are not in the QASM standard gate library.
+const RZZ_DEF: &str = "gate rzz(theta) a, b { cx a, b; rz(theta) b; cx a, b; }\n";
+const RXX_DEF: &str = "gate rxx(theta) a, b { h a; h b; rzz(theta) a, b; h a; h b; }\n";
+const RYY_DEF: &str =
+    "gate ryy(theta) a, b { rx(pi/2) a; rx(pi/2) b; rzz(theta) a, b; rx(-pi/2) a; rx(-pi/2) b; }\n";
+
+/// Generates a QASM 3 program from the entry expression of a package, or from its entry
+/// point callable if it has no entry expression.
+///
+/// The classical parts of the program are evaluated ahead of time, so loops are unrolled and the
+/// program operates on a single qubit register `q` and bit register `c`. Conditionals on
+/// measurement results become `if` statements, and values of the entry expression that are not
+/// measurement results are written to output variables.
+///
+/// # Errors
+/// Returns an error if the package does not have a valid entry point, if evaluating the program
+/// fails, or if the program uses a feature that cannot be expressed in QASM.
+///
+/// # Panics
+/// Panics if the package is not in the store.
+pub fn generate_qasm(store: &PackageStore, package: PackageId) -> Result<String, Vec<Error>> {
+    let entry = partial_eval::entry_expr(store, package)?;
+    let program =
+        partial_eval::evaluate(store, package, &entry, true).map_err(|error| vec![error])?;
+    let mut outputs = Vec::new();
+    collect_outputs(&program.output, entry.span, &mut outputs).map_err(|error| vec![error])?;
+    Ok(render(&program, &outputs))
+}
+
+/// A value of the entry expression that is written to an output variable.
+enum Output {
+    Bool(Operand),
+    Double(f64),
+    Int(Operand),
+}
+
+fn collect_outputs(value: &Value, span: Span, outputs: &mut Vec<Output>) -> Result<(), Error> {
+    match value {
+        Value::Array(items) | Value::Tuple(items) => {
+            for item in items.iter() {
+                collect_outputs(item, span, outputs)?;
+            }
+            Ok(())
+        }
+        &Value::Bool(v) => {
+            outputs.push(Output::Bool(Operand::Bool(v)));
+            Ok(())
+        }
+        &Value::Double(v) => {
+            outputs.push(Output::Double(v));
+            Ok(())
+        }
+        &Value::Int(v) => {
+            outputs.push(Output::Int(Operand::Int(v)));
+            Ok(())
+        }
+        &Value::Var(var) => {
+            outputs.push(match var.ty {
+                VarTy::Bool => Output::Bool(Operand::Var(var)),
+                VarTy::Int => Output::Int(Operand::Var(var)),
+            });
+            Ok(())
+        }
+        // Measurement results are already in the bit register.
+        Value::Result(val::Result::Measured(_)) => Ok(()),
+        Value::Result(val::Result::Lit(_))
+        | Value::BigInt(_)
+        | Value::Closure(..)
+        | Value::Global(..)
+        | Value::Pauli(_)
+        | Value::Qubit(_)
+        | Value::Range(..)
+        | Value::String(_) => Err(Error::UnsupportedOutput(value.type_name(), span)),
+    }
+}
+
+/// The statements of a QASM program.
+#[derive(Default)]
+struct Statements {
+    body: String,
+    indent: usize,
+    /// Variables that are true if a bit has the given value, which are written inline as a
+    /// comparison of the bit instead of being declared.
+    bits: HashMap<usize, (usize, bool)>,
+    uses_rxx: bool,
+    uses_ryy: bool,
+    uses_rzz: bool,
+}
+
+impl Statements {
+    fn instr(&mut self, instr: &Instr) {
+        match instr {
+            Instr::Call(name, operands) => self.gate(name, operands),
+            &Instr::Measure {
+                qubit,
+                result,
+                reset,
+            } => {
+                self.line(&format!("c[{result}] = measure q[{qubit}];"));
+                if reset {
+                    self.line(&format!("reset q[{qubit}];"));
+                }
+            }
+            &Instr::ReadResult { var, result } => {
+                self.bits.insert(var.id, (result, true));
+            }
+            &Instr::Op {
+                var,
+                op: Op::Xor,
+                lhs: Operand::Var(lhs),
+                rhs: Operand::Bool(true),
+            } if self.bits.contains_key(&lhs.id) => {
+                let (result, value) = self.bits[&lhs.id];
+                self.bits.insert(var.id, (result, !value));
+            }
+            &Instr::Op { var, op, lhs, rhs } => {
+                let is_bool = matches!(ty(lhs), VarTy::Bool);
+                let op = match op {
+                    Op::Add => "+",
+                    Op::Sub => "-",
+                    Op::Mul => "*",
+                    Op::SDiv => "/",
+                    Op::SRem => "%",
+                    Op::And if is_bool => "&&",
+                    Op::And => "&",
+                    Op::Or if is_bool => "||",
+                    Op::Or => "|",
+                    Op::Xor if is_bool => "!=",
+                    Op::Xor => "^",
+                    Op::Shl => "<<",
+                    Op::AShr => ">>",
+                    Op::Eq => "==",
+                    Op::Sgt => ">",
+                    Op::Sge => ">=",
+                    Op::Slt => "<",
+                    Op::Sle => "<=",
+                };
+                let (lhs, rhs) = (self.operand(lhs), self.operand(rhs));
+                self.line(&format!(
+                    "{} var_{} = {lhs} {op} {rhs};",
+                    type_name(var.ty),
+                    var.id
+                ));
+            }
+            Instr::If {
+                cond,
+                then,
+                otherwise,
+                phis,
+            } => {
+                if then.is_empty() && phis.is_empty() {
+                    let cond = self.cond(*cond, false);
+                    self.line(&format!("if ({cond}) {{"));
+                    self.block(otherwise, iter::empty());
+                    self.line("}");
+                    return;
+                }
+
+                for phi in phis {
+                    self.line(&format!("{} var_{};", type_name(phi.var.ty), phi.var.id));
+                }
+                let cond = self.cond(*cond, true);
+                self.line(&format!("if ({cond}) {{"));
+                self.block(then, phis.iter().map(|phi| (phi.var, phi.then)));
+                if !otherwise.is_empty() || !phis.is_empty() {
+                    self.line("} else {");
+                    self.block(otherwise, phis.iter().map(|phi| (phi.var, phi.otherwise)));
+                }
+                self.line("}");
+            }
+        }
+    }
+
+    fn gate(&mut self, name: &str, operands: &[Operand]) {
+        let gate = match name {
+            "__quantum__qis__ccx__body" => "ccx",
+            "__quantum__qis__cx__body" => "cx",
+            "__quantum__qis__cy__body" => "cy",
+            "__quantum__qis__cz__body" => "cz",
+            "__quantum__qis__rx__body" => "rx",
+            "__quantum__qis__rxx__body" => {
+                self.uses_rxx = true;
+                "rxx"
+            }
+            "__quantum__qis__ry__body" => "ry",
+            "__quantum__qis__ryy__body" => {
+                self.uses_ryy = true;
+                "ryy"
+            }
+            "__quantum__qis__rz__body" => "rz",
+            "__quantum__qis__rzz__body" => {
+                self.uses_rzz = true;
+                "rzz"
+            }
+            "__quantum__qis__h__body" => "h",
+            "__quantum__qis__s__body" => "s",
+            "__quantum__qis__s__adj" => "sdg",
+            "__quantum__qis__t__body" => "t",
+            "__quantum__qis__t__adj" => "tdg",
+            "__quantum__qis__x__body" => "x",
+            "__quantum__qis__y__body" => "y",
+            "__quantum__qis__z__body" => "z",
+            "__quantum__qis__swap__body" => "swap",
+            "__quantum__qis__reset__body" => "reset",
+            _ => panic!("intrinsic `{name}` should be a known gate"),
+        };
+
+        let mut params = Vec::new();
+        let mut qubits = Vec::new();
+        for &operand in operands {
+            match operand {
+                Operand::Double(v) => params.push(float(v)),
+                Operand::Qubit(q) => qubits.push(format!("q[{q}]")),
+                _ => panic!("gate operand should be Double or Qubit"),
+            }
+        }
+
+        let qubits = qubits.join(", ");
+        if params.is_empty() {
+            self.line(&format!("{gate} {qubits};"));
+        } else {
+            self.line(&format!("{gate}({}) {qubits};", params.join(", ")));
+        }
+    }
+
+    /// Writes the body of an `if` or `else` block, followed by assignments to phi variables.
+    fn block(&mut self, instrs: &[Instr], assignments: impl Iterator<Item = (Var, Operand)>) {
+        self.indent += 1;
+        for instr in instrs {
+            self.instr(instr);
+        }
+        for (var, value) in assignments {
+            let value = self.operand(value);
+            self.line(&format!("var_{} = {value};", var.id));
+        }
+        self.indent -= 1;
+    }
+
+    fn line(&mut self, line: &str) {
+        writeln!(self.body, "{:indent$}{line}", "", indent = self.indent * 4)
+            .expect("writing to string should succeed");
+    }
+
+    /// Writes a condition that is true if the variable has the given value.
+    fn cond(&self, var: Var, value: bool) -> String {
+        match self.bits.get(&var.id) {
+            Some(&(result, bit)) => format!("c[{result}] == {}", u8::from(bit == value)),
+            None if value => format!("var_{}", var.id),
+            None => format!("!var_{}", var.id),
+        }
+    }
+
+    fn operand(&self, operand: Operand) -> String {
+        match operand {
+            Operand::Bool(v) => v.to_string(),
+            Operand::Double(v) => float(v),
+            Operand::Int(v) => v.to_string(),
+            Operand::Qubit(q) => format!("q[{q}]"),
+            Operand::Var(var) if self.bits.contains_key(&var.id) => {
+                format!("({})", self.cond(var, true))
+            }
+            Operand::Var(var) => format!("var_{}", var.id),
+        }
+    }
+}
+
+fn render(program: &Program, outputs: &[Output]) -> String {
+    let mut statements = Statements::default();
+    for instr in &program.instrs {
+        statements.instr(instr);
+    }
+    let mut declarations = String::new();
+    for (i, output) in outputs.iter().enumerate() {
+        let (ty, value) = match *output {
+            Output::Bool(v) => ("bool", statements.operand(v)),
+            Output::Double(v) => ("float[64]", float(v)),
+            Output::Int(v) => ("int[64]", statements.operand(v)),
+        };
+        writeln!(declarations, "output {ty} out_{i};").expect("writing to string should succeed");
+        statements.line(&format!("out_{i} = {value};"));
+    }
+
+    let mut qasm = String::new();
+    qasm.push_str("OPENQASM 3.0;\ninclude \"stdgates.inc\";\n\n");
+    if statements.uses_rzz || statements.uses_rxx || statements.uses_ryy {
+        qasm.push_str(RZZ_DEF);
+    }
+    if statements.uses_rxx {
+        qasm.push_str(RXX_DEF);
+    }
+    if statements.uses_ryy {
+        qasm.push_str(RYY_DEF);
+    }
+    if statements.uses_rzz || statements.uses_rxx || statements.uses_ryy {
+        qasm.push('\n');
+    }
+    if program.num_qubits > 0 {
+        writeln!(qasm, "qubit[{}] q;", program.num_qubits)
+            .expect("writing to string should succeed");
+    }
+    if program.num_results > 0 {
+        writeln!(qasm, "bit[{}] c;", program.num_results)
+            .expect("writing to string should succeed");
+    }
+    qasm.push_str(&declarations);
+    if !statements.body.is_empty() {
+        qasm.push('\n');
+        qasm.push_str(&statements.body);
+    }
+    qasm
+}
+
+fn ty(operand: Operand) -> VarTy {
+    match operand {
+        Operand::Bool(_) => VarTy::Bool,
+        Operand::Int(_) => VarTy::Int,
+        Operand::Var(var) => var.ty,
+        Operand::Double(_) | Operand::Qubit(_) => panic!("operand should be Bool or Int"),
+    }
+}
+
+fn type_name(ty: VarTy) -> &'static str {
+    match ty {
+        VarTy::Bool => "bool",
+        VarTy::Int => "int[64]",
+    }
+}
+
+fn float(v: f64) -> String {
+    if (v.floor() - v.ceil()).abs() < f64::EPSILON {
+        format!("{v:.1}")
+    } else {
+        format!("{v}")
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::generate_qasm;
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_passes::{run_core_passes, run_default_passes};
+
+fn check(file: &str, expect: &Expect) {
+    let mut core = compile::core();
+    run_core_passes(&mut core);
+    let mut store = PackageStore::new(core);
+
+    let mut std = compile::std(&store);
+    assert!(std.errors.is_empty());
+    assert!(run_default_passes(store.core(), &mut std).is_empty());
+    let std_id = store.insert(std);
+
+    let sources = SourceMap::new([("test".into(), file.into())], None);
+    let mut unit = compile(&store, &[std_id], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    let pass_errors = run_default_passes(store.core(), &mut unit);
+    assert!(pass_errors.is_empty(), "{pass_errors:?}");
+    let id = store.insert(unit);
+
+    match generate_qasm(&store, id) {
+        Ok(qasm) => expect.assert_eq(&qasm),
+        Err(errors) => expect.assert_debug_eq(&errors),
+    }
+}
+
+#[test]
+fn bell_pair() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : (Result, Result) {
+                    use (q0, q1) = (Qubit(), Qubit());
+                    H(q0);
+                    CNOT(q0, q1);
+                    (MResetZ(q0), MResetZ(q1))
+                }
+            }
+        "},
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[2] q;
+            bit[2] c;
+
+            h q[0];
+            cx q[0], q[1];
+            c[0] = measure q[0];
+            reset q[0];
+            c[1] = measure q[1];
+            reset q[1];
+        "#]],
+    );
+}
+
+#[test]
+fn gates() {
+    check(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Result {
+                    use (q0, q1, q2) = (Qubit(), Qubit(), Qubit());
+                    X(q0);
+                    Y(q1);
+                    Z(q2);
+                    S(q0);
+                    Adjoint S(q1);
+                    T(q2);
+                    Adjoint T(q0);
+                    Rx(0.5, q0);
+                    Ry(1.0, q1);
+                    Rz(-0.25, q2);
+                    Rxx(0.5, q0, q1);
+                    Ryy(0.5, q1, q2);
+                    Rzz(0.5, q2, q0);
+                    CY(q0, q1);
+                    CZ(q1, q2);
+                    CCNOT(q0, q1, q2);
+                    SWAP(q0, q2);
+                    ResetAll([q0, q1]);
+                    M(q2)
+                }
+            }
+        "},
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            gate rzz(theta) a, b { cx a, b; rz(theta) b; cx a, b; }
+            gate rxx(theta) a, b { h a; h b; rzz(theta) a, b; h a; h b; }
+            gate ryy(theta) a, b { rx(pi/2) a; rx(pi/2) b; rzz(theta) a, b; rx(-pi/2) a; rx(-pi/2) b; }
+
+            qubit[3] q;
+            bit[1] c;
+
+            x q[0];
+            y q[1];
+            z q[2];
+            s q[0];
+            sdg q[1];
+            t q[2];
+            tdg q[0];
+            rx(0.5) q[0];
+            ry(1.0) q[1];
+            rz(-0.25) q[2];
+            rxx(0.5) q[0], q[1];
+            ryy(0.5) q[1], q[2];
+            rzz(0.5) q[2], q[0];
+            cy q[0], q[1];
+            cz q[1], q[2];
+            ccx q[0], q[1], q[2];
+            swap q[0], q[2];
+            reset q[0];
+            reset q[1];
+            c[0] = measure q[2];
+        "#]],
+    );
+}
+
+#[test]
+fn loops_are_unrolled() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : Result[] {
+                    use qs = Qubit[3];
+                    for i in 1..Length(qs) - 1 {
+                        CNOT(qs[i - 1], qs[i]);
+                    }
+                    mutable results = [];
+                    for q in qs {
+                        set results += [MResetZ(q)];
+                    }
+                    results
+                }
+            }
+        "},
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[3] q;
+            bit[3] c;
+
+            cx q[0], q[1];
+            cx q[1], q[2];
+            c[0] = measure q[0];
+            reset q[0];
+            c[1] = measure q[1];
+            reset q[1];
+            c[2] = measure q[2];
+            reset q[2];
+        "#]],
+    );
+}
+
+#[test]
+fn teleport() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : Result {
+                    use (msg, alice, bob) = (Qubit(), Qubit(), Qubit());
+                    X(msg);
+                    H(alice);
+                    CNOT(alice, bob);
+                    CNOT(msg, alice);
+                    H(msg);
+                    if MResetZ(msg) == One { Z(bob); }
+                    if MResetZ(alice) == Zero { } else { X(bob); }
+                    MResetZ(bob)
+                }
+            }
+        "},
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[3] q;
+            bit[3] c;
+
+            x q[0];
+            h q[1];
+            cx q[1], q[2];
+            cx q[0], q[1];
+            h q[0];
+            c[0] = measure q[0];
+            reset q[0];
+            if (c[0] == 1) {
+                z q[2];
+            }
+            c[1] = measure q[1];
+            reset q[1];
+            if (c[1] == 1) {
+                x q[2];
+            }
+            c[2] = measure q[2];
+            reset q[2];
+        "#]],
+    );
+}
+
+#[test]
+fn classical_values_are_output() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : (Int, Bool, Double) {
+                    use qs = Qubit[2];
+                    ApplyToEach(H, qs);
+                    mutable count = 0;
+                    for q in qs {
+                        if MResetZ(q) == One {
+                            set count += 1;
+                        }
+                    }
+                    (count, count > 1, 1.5)
+                }
+            }
+        "},
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[2] q;
+            bit[2] c;
+            output int[64] out_0;
+            output bool out_1;
+            output float[64] out_2;
+
+            h q[0];
+            h q[1];
+            c[0] = measure q[0];
+            reset q[0];
+            int[64] var_1;
+            if (c[0] == 1) {
+                var_1 = 1;
+            } else {
+                var_1 = 0;
+            }
+            c[1] = measure q[1];
+            reset q[1];
+            int[64] var_4;
+            if (c[1] == 1) {
+                int[64] var_3 = var_1 + 1;
+                var_4 = var_3;
+            } else {
+                var_4 = var_1;
+            }
+            bool var_5 = var_4 > 1;
+            out_0 = var_4;
+            out_1 = var_5;
+            out_2 = 1.5;
+        "#]],
+    );
+}
+
+#[test]
+fn std_error_is_reported_at_call_site() {
+    check(
+        indoc! {r#"
+            namespace Test {
+                open Microsoft.Quantum.Diagnostics;
+                @EntryPoint()
+                operation Main() : Unit {
+                    use q = Qubit();
+                    Fact(M(q) == Zero, "qubit should be in the zero state");
+                }
+            }
+        "#},
+        &expect![[r#"
+            [
+                DynamicExit(
+                    Span {
+                        lo: 138,
+                        hi: 142,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn pauli_output_is_unsupported() {
+    check(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Pauli {
+                    PauliX
+                }
+            }
+        "},
+        &expect![[r#"
+            [
+                UnsupportedOutput(
+                    "Pauli",
+                    Span {
+                        lo: 0,
+                        hi: 0,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
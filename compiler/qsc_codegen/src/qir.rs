@@ -0,0 +1,364 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    partial_eval::{self, Instr, Op, Operand, Program},
+    val::{self, Value, Var, VarTy},
+    Error,
+};
+use qsc_data_structures::span::Span;
+use qsc_frontend::compile::PackageStore;
+use qsc_hir::hir::PackageId;
+use std::{collections::BTreeMap, fmt::Write};
+
+/// A QIR profile, which determines the features that generated code may use.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Profile {
+    /// The base profile, where the entry point is a straight-line sequence of quantum
+    /// instructions.
+    Base,
+    /// The adaptive profile, which also supports branching on measurement results and integer
+    /// computations at runtime.
+    Adaptive,
+}
+
+/// Generates QIR for the given profile from the entry expression of a package, or from its entry
+/// point callable if it has no entry expression.
+///
+/// The classical parts of the program are evaluated ahead of time, so the generated entry point
+/// function is a sequence of quantum instructions over statically allocated qubits and results,
+/// followed by calls that record the output. For the adaptive profile, conditionals on
+/// measurement results become branches between basic blocks, and computations on values derived
+/// from measurement results become integer and boolean instructions.
+///
+/// # Errors
+/// Returns an error if the package does not have a valid entry point, if evaluating the program
+/// fails, or if the program uses a feature that is not supported by the profile.
+///
+/// # Panics
+/// Panics if the package is not in the store.
+pub fn generate_qir(
+    store: &PackageStore,
+    package: PackageId,
+    profile: Profile,
+) -> Result<String, Vec<Error>> {
+    let entry = partial_eval::entry_expr(store, package)?;
+    let program = partial_eval::evaluate(store, package, &entry, profile == Profile::Adaptive)
+        .map_err(|error| vec![error])?;
+    let mut output = Vec::new();
+    if program.output != Value::unit() {
+        record_output(&program.output, entry.span, &mut output).map_err(|error| vec![error])?;
+    }
+    Ok(render(&program, &output, profile))
+}
+
+/// A call that records part of the program output.
+enum Record {
+    Array(usize),
+    Bool(Operand),
+    Double(f64),
+    Int(Operand),
+    Result(usize),
+    Tuple(usize),
+}
+
+fn record_output(value: &Value, span: Span, output: &mut Vec<Record>) -> Result<(), Error> {
+    match value {
+        Value::Array(items) | Value::Tuple(items) => {
+            output.push(if matches!(value, Value::Array(_)) {
+                Record::Array(items.len())
+            } else {
+                Record::Tuple(items.len())
+            });
+            for item in items.iter() {
+                record_output(item, span, output)?;
+            }
+            Ok(())
+        }
+        &Value::Bool(v) => {
+            output.push(Record::Bool(Operand::Bool(v)));
+            Ok(())
+        }
+        &Value::Double(v) => {
+            output.push(Record::Double(v));
+            Ok(())
+        }
+        &Value::Int(v) => {
+            output.push(Record::Int(Operand::Int(v)));
+            Ok(())
+        }
+        &Value::Result(val::Result::Measured(id)) => {
+            output.push(Record::Result(id));
+            Ok(())
+        }
+        &Value::Var(var) => {
+            output.push(match var.ty {
+                VarTy::Bool => Record::Bool(Operand::Var(var)),
+                VarTy::Int => Record::Int(Operand::Var(var)),
+            });
+            Ok(())
+        }
+        Value::Result(val::Result::Lit(_))
+        | Value::BigInt(_)
+        | Value::Closure(..)
+        | Value::Global(..)
+        | Value::Pauli(_)
+        | Value::Qubit(_)
+        | Value::Range(..)
+        | Value::String(_) => Err(Error::UnsupportedOutput(value.type_name(), span)),
+    }
+}
+
+/// The body of the entry point function, along with the declarations of the functions it calls.
+#[derive(Default)]
+struct Function {
+    body: String,
+    decls: BTreeMap<String, String>,
+    /// The label of the current basic block.
+    block: String,
+    num_branches: usize,
+}
+
+impl Function {
+    fn instr(&mut self, instr: &Instr) {
+        match instr {
+            Instr::Call(name, operands) => {
+                let params = operands.iter().map(|&operand| ty(operand));
+                let args = operands.iter().map(|&operand| typed(operand));
+                self.call(None, name, &join(args), &format!("({})", join(params)));
+            }
+            &Instr::Measure {
+                qubit,
+                result: r,
+                reset,
+            } => {
+                let name = if reset {
+                    "__quantum__qis__mresetz__body"
+                } else {
+                    "__quantum__qis__mz__body"
+                };
+                let args = format!("{}, {}", typed(Operand::Qubit(qubit)), result(r));
+                self.call(None, name, &args, "(%Qubit*, %Result* writeonly) #1");
+            }
+            &Instr::ReadResult { var, result: r } => {
+                self.call(
+                    Some(var),
+                    "__quantum__qis__read_result__body",
+                    &result(r),
+                    "(%Result*)",
+                );
+            }
+            &Instr::Op { var, op, lhs, rhs } => {
+                let name = match op {
+                    Op::Add => "add",
+                    Op::Sub => "sub",
+                    Op::Mul => "mul",
+                    Op::SDiv => "sdiv",
+                    Op::SRem => "srem",
+                    Op::And => "and",
+                    Op::Or => "or",
+                    Op::Xor => "xor",
+                    Op::Shl => "shl",
+                    Op::AShr => "ashr",
+                    Op::Eq => "icmp eq",
+                    Op::Sgt => "icmp sgt",
+                    Op::Sge => "icmp sge",
+                    Op::Slt => "icmp slt",
+                    Op::Sle => "icmp sle",
+                };
+                writeln!(
+                    self.body,
+                    "  %var_{} = {name} {} {}, {}",
+                    var.id,
+                    ty(lhs),
+                    value(lhs),
+                    value(rhs)
+                )
+                .expect("writing to string should succeed");
+            }
+            Instr::If {
+                cond,
+                then,
+                otherwise,
+                phis,
+            } => {
+                let id = self.num_branches;
+                self.num_branches += 1;
+                writeln!(
+                    self.body,
+                    "  br i1 %var_{}, label %then_{id}, label %else_{id}",
+                    cond.id
+                )
+                .expect("writing to string should succeed");
+                let then_block = self.branch(&format!("then_{id}"), then, id);
+                let else_block = self.branch(&format!("else_{id}"), otherwise, id);
+                self.label(&format!("continue_{id}"));
+                for phi in phis {
+                    writeln!(
+                        self.body,
+                        "  %var_{} = phi {} [{}, %{then_block}], [{}, %{else_block}]",
+                        phi.var.id,
+                        ty(phi.then),
+                        value(phi.then),
+                        value(phi.otherwise)
+                    )
+                    .expect("writing to string should succeed");
+                }
+            }
+        }
+    }
+
+    /// Writes a branch of an `If` instruction, returning the label of the block that it ends in.
+    fn branch(&mut self, label: &str, instrs: &[Instr], id: usize) -> String {
+        self.label(label);
+        for instr in instrs {
+            self.instr(instr);
+        }
+        writeln!(self.body, "  br label %continue_{id}").expect("writing to string should succeed");
+        self.block.clone()
+    }
+
+    fn label(&mut self, label: &str) {
+        writeln!(self.body, "{label}:").expect("writing to string should succeed");
+        self.block = label.to_string();
+    }
+
+    fn record(&mut self, record: &Record) {
+        let (kind, arg, param) = match *record {
+            Record::Array(len) => ("array", format!("i64 {len}"), "i64"),
+            Record::Bool(v) => ("bool", typed(v), ty(v)),
+            Record::Double(v) => ("double", typed(Operand::Double(v)), "double"),
+            Record::Int(v) => ("int", typed(v), ty(v)),
+            Record::Result(r) => ("result", result(r), "%Result*"),
+            Record::Tuple(len) => ("tuple", format!("i64 {len}"), "i64"),
+        };
+        let name = format!("__quantum__rt__{kind}_record_output");
+        self.call(
+            None,
+            &name,
+            &format!("{arg}, i8* null"),
+            &format!("({param}, i8*)"),
+        );
+    }
+
+    /// Writes a call to a function that returns `var`, or void if there is no variable,
+    /// declaring the function with the given signature if this is the first call.
+    fn call(&mut self, var: Option<Var>, name: &str, args: &str, signature: &str) {
+        let ret = if let Some(var) = var {
+            write!(self.body, "  %var_{} = ", var.id).expect("writing to string should succeed");
+            ty(Operand::Var(var))
+        } else {
+            self.body.push_str("  ");
+            "void"
+        };
+        writeln!(self.body, "call {ret} @{name}({args})")
+            .expect("writing to string should succeed");
+        self.decls
+            .entry(name.to_string())
+            .or_insert_with(|| format!("declare {ret} @{name}{signature}"));
+    }
+}
+
+fn render(program: &Program, output: &[Record], profile: Profile) -> String {
+    let mut function = Function::default();
+    function.label("entry");
+    for instr in &program.instrs {
+        function.instr(instr);
+    }
+    for record in output {
+        function.record(record);
+    }
+
+    let mut qir = String::new();
+    qir.push_str("%Result = type opaque\n%Qubit = type opaque\n\n");
+    writeln!(
+        qir,
+        "define void @ENTRYPOINT__main() #0 {{\n{}  ret void\n}}\n",
+        function.body
+    )
+    .expect("writing to string should succeed");
+    for decl in function.decls.values() {
+        writeln!(qir, "{decl}\n").expect("writing to string should succeed");
+    }
+    let profile_name = match profile {
+        Profile::Base => "base_profile",
+        Profile::Adaptive => "adaptive_profile",
+    };
+    writeln!(
+        qir,
+        "attributes #0 = {{ \"entry_point\" \"output_labeling_schema\" \"qir_profiles\"=\"{profile_name}\" \"required_num_qubits\"=\"{}\" \"required_num_results\"=\"{}\" }}",
+        program.num_qubits, program.num_results
+    )
+    .expect("writing to string should succeed");
+    qir.push_str("attributes #1 = { \"irreversible\" }\n\n");
+    match profile {
+        Profile::Base => qir.push_str("!llvm.module.flags = !{!0, !1, !2, !3}\n\n"),
+        Profile::Adaptive => qir.push_str("!llvm.module.flags = !{!0, !1, !2, !3, !4}\n\n"),
+    }
+    qir.push_str(concat!(
+        "!0 = !{i32 1, !\"qir_major_version\", i32 1}\n",
+        "!1 = !{i32 7, !\"qir_minor_version\", i32 0}\n",
+        "!2 = !{i32 1, !\"dynamic_qubit_management\", i1 false}\n",
+        "!3 = !{i32 1, !\"dynamic_result_management\", i1 false}\n",
+    ));
+    if profile == Profile::Adaptive {
+        qir.push_str("!4 = !{i32 5, !\"int_computations\", !{!\"i64\"}}\n");
+    }
+    qir
+}
+
+fn join(items: impl Iterator<Item = impl AsRef<str>>) -> String {
+    items
+        .map(|item| item.as_ref().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn ty(operand: Operand) -> &'static str {
+    match operand {
+        Operand::Bool(_)
+        | Operand::Var(Var {
+            ty: VarTy::Bool, ..
+        }) => "i1",
+        Operand::Double(_) => "double",
+        Operand::Int(_) | Operand::Var(Var { ty: VarTy::Int, .. }) => "i64",
+        Operand::Qubit(_) => "%Qubit*",
+    }
+}
+
+fn value(operand: Operand) -> String {
+    match operand {
+        Operand::Bool(v) => v.to_string(),
+        Operand::Double(v) => double(v),
+        Operand::Int(v) => v.to_string(),
+        Operand::Qubit(0) => "null".to_string(),
+        Operand::Qubit(id) => format!("inttoptr (i64 {id} to %Qubit*)"),
+        Operand::Var(var) => format!("%var_{}", var.id),
+    }
+}
+
+fn typed(operand: Operand) -> String {
+    format!("{} {}", ty(operand), value(operand))
+}
+
+fn result(id: usize) -> String {
+    if id == 0 {
+        "%Result* null".to_string()
+    } else {
+        format!("%Result* inttoptr (i64 {id} to %Result*)")
+    }
+}
+
+fn double(v: f64) -> String {
+    if !v.is_finite() {
+        // LLVM only accepts infinities and NaN in hexadecimal notation.
+        format!("0x{:016X}", v.to_bits())
+    } else if (v.floor() - v.ceil()).abs() < f64::EPSILON {
+        format!("{v:.1}")
+    } else {
+        format!("{v}")
+    }
+}
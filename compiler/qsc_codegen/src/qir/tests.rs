@@ -0,0 +1,870 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{generate_qir, Profile};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_passes::{run_core_passes, run_default_passes};
+
+fn check(file: &str, expr: Option<&str>, expect: &Expect) {
+    check_profile(file, expr, Profile::Base, expect);
+}
+
+fn check_adaptive(file: &str, expect: &Expect) {
+    check_profile(file, None, Profile::Adaptive, expect);
+}
+
+fn check_profile(file: &str, expr: Option<&str>, profile: Profile, expect: &Expect) {
+    let mut core = compile::core();
+    run_core_passes(&mut core);
+    let mut store = PackageStore::new(core);
+
+    let mut std = compile::std(&store);
+    assert!(std.errors.is_empty());
+    assert!(run_default_passes(store.core(), &mut std).is_empty());
+    let std_id = store.insert(std);
+
+    let sources = SourceMap::new([("test".into(), file.into())], expr.map(Into::into));
+    let mut unit = compile(&store, &[std_id], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    let pass_errors = run_default_passes(store.core(), &mut unit);
+    assert!(pass_errors.is_empty(), "{pass_errors:?}");
+    let id = store.insert(unit);
+
+    match generate_qir(&store, id, profile) {
+        Ok(qir) => expect.assert_eq(&qir),
+        Err(errors) => expect.assert_debug_eq(&errors),
+    }
+}
+
+#[test]
+fn bell_pair() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : (Result, Result) {
+                    use (q0, q1) = (Qubit(), Qubit());
+                    H(q0);
+                    CNOT(q0, q1);
+                    (MResetZ(q0), MResetZ(q1))
+                }
+            }
+        "},
+        None,
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__h__body(%Qubit* null)
+              call void @__quantum__qis__cx__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__mresetz__body(%Qubit* null, %Result* null)
+              call void @__quantum__qis__mresetz__body(%Qubit* inttoptr (i64 1 to %Qubit*), %Result* inttoptr (i64 1 to %Result*))
+              call void @__quantum__rt__tuple_record_output(i64 2, i8* null)
+              call void @__quantum__rt__result_record_output(%Result* null, i8* null)
+              call void @__quantum__rt__result_record_output(%Result* inttoptr (i64 1 to %Result*), i8* null)
+              ret void
+            }
+
+            declare void @__quantum__qis__cx__body(%Qubit*, %Qubit*)
+
+            declare void @__quantum__qis__h__body(%Qubit*)
+
+            declare void @__quantum__qis__mresetz__body(%Qubit*, %Result* writeonly) #1
+
+            declare void @__quantum__rt__result_record_output(%Result*, i8*)
+
+            declare void @__quantum__rt__tuple_record_output(i64, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="base_profile" "required_num_qubits"="2" "required_num_results"="2" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+        "#]],
+    );
+}
+
+#[test]
+fn entry_expr() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Flip() : Result {
+                    use q = Qubit();
+                    X(q);
+                    M(q)
+                }
+            }
+        "},
+        Some("Test.Flip()"),
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__x__body(%Qubit* null)
+              call void @__quantum__qis__mz__body(%Qubit* null, %Result* null)
+              call void @__quantum__rt__result_record_output(%Result* null, i8* null)
+              ret void
+            }
+
+            declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+
+            declare void @__quantum__qis__x__body(%Qubit*)
+
+            declare void @__quantum__rt__result_record_output(%Result*, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="base_profile" "required_num_qubits"="1" "required_num_results"="1" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+        "#]],
+    );
+}
+
+#[test]
+fn classical_loops_are_unrolled() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Convert;
+                open Microsoft.Quantum.Math;
+                @EntryPoint()
+                operation Main() : Result[] {
+                    use qs = Qubit[3];
+                    for i in 0..Length(qs) - 1 {
+                        Rx(PI() / IntAsDouble(i + 1), qs[i]);
+                    }
+                    mutable results = [];
+                    for q in qs {
+                        set results += [M(q)];
+                    }
+                    results
+                }
+            }
+        "},
+        None,
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__rx__body(double 3.141592653589793, %Qubit* null)
+              call void @__quantum__qis__rx__body(double 1.5707963267948966, %Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__rx__body(double 1.0471975511965976, %Qubit* inttoptr (i64 2 to %Qubit*))
+              call void @__quantum__qis__mz__body(%Qubit* null, %Result* null)
+              call void @__quantum__qis__mz__body(%Qubit* inttoptr (i64 1 to %Qubit*), %Result* inttoptr (i64 1 to %Result*))
+              call void @__quantum__qis__mz__body(%Qubit* inttoptr (i64 2 to %Qubit*), %Result* inttoptr (i64 2 to %Result*))
+              call void @__quantum__rt__array_record_output(i64 3, i8* null)
+              call void @__quantum__rt__result_record_output(%Result* null, i8* null)
+              call void @__quantum__rt__result_record_output(%Result* inttoptr (i64 1 to %Result*), i8* null)
+              call void @__quantum__rt__result_record_output(%Result* inttoptr (i64 2 to %Result*), i8* null)
+              ret void
+            }
+
+            declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+
+            declare void @__quantum__qis__rx__body(double, %Qubit*)
+
+            declare void @__quantum__rt__array_record_output(i64, i8*)
+
+            declare void @__quantum__rt__result_record_output(%Result*, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="base_profile" "required_num_qubits"="3" "required_num_results"="3" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+        "#]],
+    );
+}
+
+#[test]
+fn functors_use_decompositions() {
+    check(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Unit {
+                    use (ctl, q) = (Qubit(), Qubit());
+                    Controlled Y([ctl], q);
+                    Adjoint T(q);
+                    Adjoint S(q);
+                }
+            }
+        "},
+        None,
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__cy__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__t__adj(%Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__s__adj(%Qubit* inttoptr (i64 1 to %Qubit*))
+              ret void
+            }
+
+            declare void @__quantum__qis__cy__body(%Qubit*, %Qubit*)
+
+            declare void @__quantum__qis__s__adj(%Qubit*)
+
+            declare void @__quantum__qis__t__adj(%Qubit*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="base_profile" "required_num_qubits"="2" "required_num_results"="0" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+        "#]],
+    );
+}
+
+#[test]
+fn released_qubits_are_reused() {
+    check(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : (Result, Result) {
+                    let r1 = {
+                        use q = Qubit();
+                        H(q);
+                        M(q)
+                    };
+                    let r2 = {
+                        use q = Qubit();
+                        X(q);
+                        M(q)
+                    };
+                    (r1, r2)
+                }
+            }
+        "},
+        None,
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__h__body(%Qubit* null)
+              call void @__quantum__qis__mz__body(%Qubit* null, %Result* null)
+              call void @__quantum__qis__x__body(%Qubit* null)
+              call void @__quantum__qis__mz__body(%Qubit* null, %Result* inttoptr (i64 1 to %Result*))
+              call void @__quantum__rt__tuple_record_output(i64 2, i8* null)
+              call void @__quantum__rt__result_record_output(%Result* null, i8* null)
+              call void @__quantum__rt__result_record_output(%Result* inttoptr (i64 1 to %Result*), i8* null)
+              ret void
+            }
+
+            declare void @__quantum__qis__h__body(%Qubit*)
+
+            declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+
+            declare void @__quantum__qis__x__body(%Qubit*)
+
+            declare void @__quantum__rt__result_record_output(%Result*, i8*)
+
+            declare void @__quantum__rt__tuple_record_output(i64, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="base_profile" "required_num_qubits"="1" "required_num_results"="2" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+        "#]],
+    );
+}
+
+#[test]
+fn classical_output() {
+    check(
+        "",
+        Some("(1 + 2, 3.0 / 2.0, true, [1.0, 2.0])"),
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__rt__tuple_record_output(i64 4, i8* null)
+              call void @__quantum__rt__int_record_output(i64 3, i8* null)
+              call void @__quantum__rt__double_record_output(double 1.5, i8* null)
+              call void @__quantum__rt__bool_record_output(i1 true, i8* null)
+              call void @__quantum__rt__array_record_output(i64 2, i8* null)
+              call void @__quantum__rt__double_record_output(double 1.0, i8* null)
+              call void @__quantum__rt__double_record_output(double 2.0, i8* null)
+              ret void
+            }
+
+            declare void @__quantum__rt__array_record_output(i64, i8*)
+
+            declare void @__quantum__rt__bool_record_output(i1, i8*)
+
+            declare void @__quantum__rt__double_record_output(double, i8*)
+
+            declare void @__quantum__rt__int_record_output(i64, i8*)
+
+            declare void @__quantum__rt__tuple_record_output(i64, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="base_profile" "required_num_qubits"="0" "required_num_results"="0" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+        "#]],
+    );
+}
+
+#[test]
+fn result_comparison_error() {
+    check(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Unit {
+                    use q = Qubit();
+                    if M(q) == One {
+                        X(q);
+                    }
+                }
+            }
+        "},
+        None,
+        &expect![[r#"
+            [
+                ResultComparison(
+                    Span {
+                        lo: 101,
+                        hi: 112,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn std_error_is_reported_at_call_site() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Convert;
+                @EntryPoint()
+                operation Main() : Int {
+                    use qs = Qubit[2];
+                    ResultArrayAsInt([M(qs[0]), M(qs[1])])
+                }
+            }
+        "},
+        None,
+        &expect![[r#"
+            [
+                ResultComparison(
+                    Span {
+                        lo: 135,
+                        hi: 151,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn random_is_unsupported() {
+    check(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Random;
+                @EntryPoint()
+                operation Main() : Int {
+                    DrawRandomInt(0, 10)
+                }
+            }
+        "},
+        None,
+        &expect![[r#"
+            [
+                UnsupportedIntrinsic(
+                    "DrawRandomInt",
+                    Span {
+                        lo: 107,
+                        hi: 120,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn string_output_is_unsupported() {
+    check(
+        "",
+        Some(r#""hello""#),
+        &expect![[r#"
+        [
+            UnsupportedOutput(
+                "String",
+                Span {
+                    lo: 0,
+                    hi: 7,
+                },
+            ),
+        ]
+    "#]],
+    );
+}
+
+#[test]
+fn fail_is_reported() {
+    check(
+        indoc! {r#"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Unit {
+                    let n = 3;
+                    if n > 2 {
+                        fail $"n is too large: {n}";
+                    }
+                }
+            }
+        "#},
+        None,
+        &expect![[r#"
+            [
+                UserFail(
+                    "n is too large: 3",
+                    Span {
+                        lo: 115,
+                        hi: 142,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn missing_entry_point() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Main() : Unit {}
+            }
+        "},
+        None,
+        &expect![[r#"
+            [
+                EntryPoint(
+                    EntryPoint(
+                        NotFound,
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn adaptive_teleport() {
+    check_adaptive(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : Result {
+                    use (msg, alice, bob) = (Qubit(), Qubit(), Qubit());
+                    X(msg);
+                    H(alice);
+                    CNOT(alice, bob);
+                    CNOT(msg, alice);
+                    H(msg);
+                    if MResetZ(msg) == One { Z(bob); }
+                    if MResetZ(alice) == One { X(bob); }
+                    MResetZ(bob)
+                }
+            }
+        "},
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__x__body(%Qubit* null)
+              call void @__quantum__qis__h__body(%Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__cx__body(%Qubit* inttoptr (i64 1 to %Qubit*), %Qubit* inttoptr (i64 2 to %Qubit*))
+              call void @__quantum__qis__cx__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__h__body(%Qubit* null)
+              call void @__quantum__qis__mresetz__body(%Qubit* null, %Result* null)
+              %var_0 = call i1 @__quantum__qis__read_result__body(%Result* null)
+              br i1 %var_0, label %then_0, label %else_0
+            then_0:
+              call void @__quantum__qis__z__body(%Qubit* inttoptr (i64 2 to %Qubit*))
+              br label %continue_0
+            else_0:
+              br label %continue_0
+            continue_0:
+              call void @__quantum__qis__mresetz__body(%Qubit* inttoptr (i64 1 to %Qubit*), %Result* inttoptr (i64 1 to %Result*))
+              %var_1 = call i1 @__quantum__qis__read_result__body(%Result* inttoptr (i64 1 to %Result*))
+              br i1 %var_1, label %then_1, label %else_1
+            then_1:
+              call void @__quantum__qis__x__body(%Qubit* inttoptr (i64 2 to %Qubit*))
+              br label %continue_1
+            else_1:
+              br label %continue_1
+            continue_1:
+              call void @__quantum__qis__mresetz__body(%Qubit* inttoptr (i64 2 to %Qubit*), %Result* inttoptr (i64 2 to %Result*))
+              call void @__quantum__rt__result_record_output(%Result* inttoptr (i64 2 to %Result*), i8* null)
+              ret void
+            }
+
+            declare void @__quantum__qis__cx__body(%Qubit*, %Qubit*)
+
+            declare void @__quantum__qis__h__body(%Qubit*)
+
+            declare void @__quantum__qis__mresetz__body(%Qubit*, %Result* writeonly) #1
+
+            declare i1 @__quantum__qis__read_result__body(%Result*)
+
+            declare void @__quantum__qis__x__body(%Qubit*)
+
+            declare void @__quantum__qis__z__body(%Qubit*)
+
+            declare void @__quantum__rt__result_record_output(%Result*, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="adaptive_profile" "required_num_qubits"="3" "required_num_results"="3" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3, !4}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+            !4 = !{i32 5, !"int_computations", !{!"i64"}}
+        "#]],
+    );
+}
+
+#[test]
+fn adaptive_branch_values_are_merged() {
+    check_adaptive(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : (Int, Bool) {
+                    use qs = Qubit[3];
+                    ApplyToEach(H, qs);
+                    mutable count = 0;
+                    for q in qs {
+                        if MResetZ(q) == One {
+                            set count += 1;
+                        }
+                    }
+                    let parity = if count % 2 == 0 { false } else { true };
+                    (count * 2, parity)
+                }
+            }
+        "},
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__h__body(%Qubit* null)
+              call void @__quantum__qis__h__body(%Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__h__body(%Qubit* inttoptr (i64 2 to %Qubit*))
+              call void @__quantum__qis__mresetz__body(%Qubit* null, %Result* null)
+              %var_0 = call i1 @__quantum__qis__read_result__body(%Result* null)
+              br i1 %var_0, label %then_0, label %else_0
+            then_0:
+              br label %continue_0
+            else_0:
+              br label %continue_0
+            continue_0:
+              %var_1 = phi i64 [1, %then_0], [0, %else_0]
+              call void @__quantum__qis__mresetz__body(%Qubit* inttoptr (i64 1 to %Qubit*), %Result* inttoptr (i64 1 to %Result*))
+              %var_2 = call i1 @__quantum__qis__read_result__body(%Result* inttoptr (i64 1 to %Result*))
+              br i1 %var_2, label %then_1, label %else_1
+            then_1:
+              %var_3 = add i64 %var_1, 1
+              br label %continue_1
+            else_1:
+              br label %continue_1
+            continue_1:
+              %var_4 = phi i64 [%var_3, %then_1], [%var_1, %else_1]
+              call void @__quantum__qis__mresetz__body(%Qubit* inttoptr (i64 2 to %Qubit*), %Result* inttoptr (i64 2 to %Result*))
+              %var_5 = call i1 @__quantum__qis__read_result__body(%Result* inttoptr (i64 2 to %Result*))
+              br i1 %var_5, label %then_2, label %else_2
+            then_2:
+              %var_6 = add i64 %var_4, 1
+              br label %continue_2
+            else_2:
+              br label %continue_2
+            continue_2:
+              %var_7 = phi i64 [%var_6, %then_2], [%var_4, %else_2]
+              %var_8 = srem i64 %var_7, 2
+              %var_9 = icmp eq i64 %var_8, 0
+              br i1 %var_9, label %then_3, label %else_3
+            then_3:
+              br label %continue_3
+            else_3:
+              br label %continue_3
+            continue_3:
+              %var_10 = phi i1 [false, %then_3], [true, %else_3]
+              %var_11 = mul i64 %var_7, 2
+              call void @__quantum__rt__tuple_record_output(i64 2, i8* null)
+              call void @__quantum__rt__int_record_output(i64 %var_11, i8* null)
+              call void @__quantum__rt__bool_record_output(i1 %var_10, i8* null)
+              ret void
+            }
+
+            declare void @__quantum__qis__h__body(%Qubit*)
+
+            declare void @__quantum__qis__mresetz__body(%Qubit*, %Result* writeonly) #1
+
+            declare i1 @__quantum__qis__read_result__body(%Result*)
+
+            declare void @__quantum__rt__bool_record_output(i1, i8*)
+
+            declare void @__quantum__rt__int_record_output(i64, i8*)
+
+            declare void @__quantum__rt__tuple_record_output(i64, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="adaptive_profile" "required_num_qubits"="3" "required_num_results"="3" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3, !4}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+            !4 = !{i32 5, !"int_computations", !{!"i64"}}
+        "#]],
+    );
+}
+
+#[test]
+fn adaptive_result_equality() {
+    check_adaptive(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main() : Bool {
+                    use (q0, q1) = (Qubit(), Qubit());
+                    H(q0);
+                    CNOT(q0, q1);
+                    let (r0, r1) = (M(q0), M(q1));
+                    Reset(q0);
+                    Reset(q1);
+                    r0 != r1 or [r0] == [Zero]
+                }
+            }
+        "},
+        &expect![[r#"
+            %Result = type opaque
+            %Qubit = type opaque
+
+            define void @ENTRYPOINT__main() #0 {
+            entry:
+              call void @__quantum__qis__h__body(%Qubit* null)
+              call void @__quantum__qis__cx__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+              call void @__quantum__qis__mz__body(%Qubit* null, %Result* null)
+              call void @__quantum__qis__mz__body(%Qubit* inttoptr (i64 1 to %Qubit*), %Result* inttoptr (i64 1 to %Result*))
+              call void @__quantum__qis__reset__body(%Qubit* null)
+              call void @__quantum__qis__reset__body(%Qubit* inttoptr (i64 1 to %Qubit*))
+              %var_0 = call i1 @__quantum__qis__read_result__body(%Result* null)
+              %var_1 = call i1 @__quantum__qis__read_result__body(%Result* inttoptr (i64 1 to %Result*))
+              %var_2 = icmp eq i1 %var_0, %var_1
+              %var_3 = xor i1 %var_2, true
+              br i1 %var_3, label %then_0, label %else_0
+            then_0:
+              br label %continue_0
+            else_0:
+              %var_4 = call i1 @__quantum__qis__read_result__body(%Result* null)
+              %var_5 = xor i1 %var_4, true
+              br label %continue_0
+            continue_0:
+              %var_6 = phi i1 [true, %then_0], [%var_5, %else_0]
+              call void @__quantum__rt__bool_record_output(i1 %var_6, i8* null)
+              ret void
+            }
+
+            declare void @__quantum__qis__cx__body(%Qubit*, %Qubit*)
+
+            declare void @__quantum__qis__h__body(%Qubit*)
+
+            declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+
+            declare i1 @__quantum__qis__read_result__body(%Result*)
+
+            declare void @__quantum__qis__reset__body(%Qubit*)
+
+            declare void @__quantum__rt__bool_record_output(i1, i8*)
+
+            attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="adaptive_profile" "required_num_qubits"="2" "required_num_results"="2" }
+            attributes #1 = { "irreversible" }
+
+            !llvm.module.flags = !{!0, !1, !2, !3, !4}
+
+            !0 = !{i32 1, !"qir_major_version", i32 1}
+            !1 = !{i32 7, !"qir_minor_version", i32 0}
+            !2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+            !3 = !{i32 1, !"dynamic_result_management", i1 false}
+            !4 = !{i32 5, !"int_computations", !{!"i64"}}
+        "#]],
+    );
+}
+
+#[test]
+fn adaptive_dynamic_loop_error() {
+    check_adaptive(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : Unit {
+                    use q = Qubit();
+                    repeat {
+                        H(q);
+                    } until MResetZ(q) == Zero;
+                }
+            }
+        "},
+        &expect![[r#"
+            [
+                DynamicLoop(
+                    Span {
+                        lo: 181,
+                        hi: 199,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn adaptive_dynamic_index_error() {
+    check_adaptive(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : Unit {
+                    use qs = Qubit[2];
+                    H(qs[0]);
+                    let i = MResetZ(qs[0]) == One ? 1 | 0;
+                    X(qs[i]);
+                }
+            }
+        "},
+        &expect![[r#"
+            [
+                DynamicArray(
+                    Span {
+                        lo: 210,
+                        hi: 211,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn adaptive_dynamic_rotation_error() {
+    check_adaptive(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : Unit {
+                    use q = Qubit();
+                    H(q);
+                    let angle = MResetZ(q) == One ? 1.0 | 2.0;
+                    Rx(angle, q);
+                }
+            }
+        "},
+        &expect![[r#"
+            [
+                DynamicValue(
+                    Span {
+                        lo: 164,
+                        hi: 193,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn adaptive_conditional_fail_error() {
+    check_adaptive(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Measurement;
+                @EntryPoint()
+                operation Main() : Unit {
+                    use q = Qubit();
+                    H(q);
+                    if MResetZ(q) == One {
+                        fail \"measured One\";
+                    }
+                }
+            }
+        "},
+        &expect![[r#"
+            [
+                DynamicExit(
+                    Span {
+                        lo: 187,
+                        hi: 206,
+                    },
+                ),
+            ]
+        "#]],
+    );
+}
@@ -0,0 +1,218 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use num_bigint::BigInt;
+use qsc_hir::hir::{LocalItemId, PackageId, Pauli};
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+pub(super) const DEFAULT_RANGE_STEP: i64 = 1;
+
+/// A value known while generating code. Classical values are computed ahead of time, while qubits
+/// and measurement results refer to the static IDs that they are assigned in the generated program.
+/// Values that depend on measurement results are held in variables of the generated program.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Value {
+    Array(Arc<[Value]>),
+    BigInt(BigInt),
+    Bool(bool),
+    Closure(Arc<[Value]>, GlobalId, FunctorApp),
+    Double(f64),
+    Global(GlobalId, FunctorApp),
+    Int(i64),
+    Pauli(Pauli),
+    Qubit(usize),
+    Range(Option<i64>, i64, Option<i64>),
+    Result(Result),
+    String(Arc<str>),
+    Tuple(Arc<[Value]>),
+    Var(Var),
+}
+
+/// A variable in the generated program whose value is only known at runtime.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct Var {
+    pub(super) id: usize,
+    pub(super) ty: VarTy,
+}
+
+/// The type of a runtime variable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum VarTy {
+    Bool,
+    Int,
+}
+
+/// A measurement result.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Result {
+    /// A result literal, which is known ahead of time.
+    Lit(bool),
+    /// The result of a measurement, identified by the result ID it is written to.
+    Measured(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct GlobalId {
+    pub(super) package: PackageId,
+    pub(super) item: LocalItemId,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(super) struct FunctorApp {
+    pub(super) adjoint: bool,
+    pub(super) controlled: u8,
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Value::Array(arr) => {
+                write!(f, "[")?;
+                join(f, arr.iter(), ", ")?;
+                write!(f, "]")
+            }
+            Value::BigInt(v) => write!(f, "{v}"),
+            Value::Bool(v) => write!(f, "{v}"),
+            Value::Closure(..) => f.write_str("<closure>"),
+            Value::Double(v) => {
+                if (v.floor() - v.ceil()).abs() < f64::EPSILON {
+                    write!(f, "{v:.1}")
+                } else {
+                    write!(f, "{v}")
+                }
+            }
+            Value::Global(id, _) => write!(f, "<item {} in package {}>", id.item, id.package),
+            Value::Int(v) => write!(f, "{v}"),
+            Value::Pauli(v) => match v {
+                Pauli::I => write!(f, "PauliI"),
+                Pauli::X => write!(f, "PauliX"),
+                Pauli::Z => write!(f, "PauliZ"),
+                Pauli::Y => write!(f, "PauliY"),
+            },
+            Value::Qubit(id) => write!(f, "Qubit{id}"),
+            &Value::Range(start, step, end) => match (start, step, end) {
+                (Some(start), DEFAULT_RANGE_STEP, Some(end)) => write!(f, "{start}..{end}"),
+                (Some(start), DEFAULT_RANGE_STEP, None) => write!(f, "{start}..."),
+                (Some(start), step, Some(end)) => write!(f, "{start}..{step}..{end}"),
+                (Some(start), step, None) => write!(f, "{start}..{step}..."),
+                (None, DEFAULT_RANGE_STEP, Some(end)) => write!(f, "...{end}"),
+                (None, DEFAULT_RANGE_STEP, None) => write!(f, "..."),
+                (None, step, Some(end)) => write!(f, "...{step}..{end}"),
+                (None, step, None) => write!(f, "...{step}..."),
+            },
+            Value::Result(Result::Lit(true)) => write!(f, "One"),
+            Value::Result(Result::Lit(false)) => write!(f, "Zero"),
+            Value::Result(Result::Measured(id)) => write!(f, "Result{id}"),
+            Value::String(v) => write!(f, "{v}"),
+            Value::Tuple(tup) => {
+                write!(f, "(")?;
+                join(f, tup.iter(), ", ")?;
+                if tup.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Value::Var(var) => write!(f, "Var{}", var.id),
+        }
+    }
+}
+
+impl Value {
+    pub(super) fn unit() -> Self {
+        Self::Tuple([].as_slice().into())
+    }
+
+    pub(super) fn unwrap_array(self) -> Arc<[Self]> {
+        let Value::Array(v) = self else {
+            panic!("value should be Array, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn unwrap_big_int(self) -> BigInt {
+        let Value::BigInt(v) = self else {
+            panic!("value should be BigInt, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn unwrap_bool(self) -> bool {
+        let Value::Bool(v) = self else {
+            panic!("value should be Bool, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn unwrap_double(self) -> f64 {
+        let Value::Double(v) = self else {
+            panic!("value should be Double, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn unwrap_int(self) -> i64 {
+        let Value::Int(v) = self else {
+            panic!("value should be Int, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn unwrap_qubit(self) -> usize {
+        let Value::Qubit(v) = self else {
+            panic!("value should be Qubit, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn unwrap_string(self) -> Arc<str> {
+        let Value::String(v) = self else {
+            panic!("value should be String, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn unwrap_tuple(self) -> Arc<[Self]> {
+        let Value::Tuple(v) = self else {
+            panic!("value should be Tuple, got {}", self.type_name());
+        };
+        v
+    }
+
+    pub(super) fn type_name(&self) -> &'static str {
+        match self {
+            Value::Array(_) => "Array",
+            Value::BigInt(_) => "BigInt",
+            Value::Bool(_)
+            | Value::Var(Var {
+                ty: VarTy::Bool, ..
+            }) => "Bool",
+            Value::Closure(..) | Value::Global(..) => "Callable",
+            Value::Double(_) => "Double",
+            Value::Int(_) | Value::Var(Var { ty: VarTy::Int, .. }) => "Int",
+            Value::Pauli(_) => "Pauli",
+            Value::Qubit(_) => "Qubit",
+            Value::Range(..) => "Range",
+            Value::Result(_) => "Result",
+            Value::String(_) => "String",
+            Value::Tuple(_) => "Tuple",
+        }
+    }
+}
+
+fn join<'a>(
+    f: &mut Formatter,
+    mut vals: impl Iterator<Item = &'a Value>,
+    sep: &str,
+) -> fmt::Result {
+    if let Some(v) = vals.next() {
+        v.fmt(f)?;
+    }
+    for v in vals {
+        write!(f, "{sep}")?;
+        v.fmt(f)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+mod counter;
+mod noise;
+mod sparse;
+mod stabilizer;
+
+use num_bigint::BigUint;
+use num_complex::Complex64;
+
+pub use counter::{LogicalCounter, LogicalResources, MeasurementPolicy};
+pub use noise::{NoiseModel, NoisySim};
+pub use sparse::SparseSim;
+pub use stabilizer::StabilizerSim;
+
+/// The quantum operations that the evaluator delegates to a simulator. Qubits are identified by the
+/// ids returned from [`Backend::qubit_allocate`], and measurement results are `true` for `One`.
+/// Operations return [`Error`] when the backend cannot simulate them. Measurement outcomes are
+/// sampled from a random number generator that is reproducible after [`Backend::set_seed`].
+#[allow(clippy::missing_errors_doc)]
+pub trait Backend {
+    fn ccx(&mut self, ctl0: usize, ctl1: usize, q: usize) -> Result<(), Error>;
+    fn cx(&mut self, ctl: usize, q: usize) -> Result<(), Error>;
+    fn cy(&mut self, ctl: usize, q: usize) -> Result<(), Error>;
+    fn cz(&mut self, ctl: usize, q: usize) -> Result<(), Error>;
+    fn h(&mut self, q: usize) -> Result<(), Error>;
+    fn m(&mut self, q: usize) -> Result<bool, Error>;
+    fn mresetz(&mut self, q: usize) -> Result<bool, Error>;
+    fn reset(&mut self, q: usize) -> Result<(), Error>;
+    fn rx(&mut self, theta: f64, q: usize) -> Result<(), Error>;
+    fn rxx(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error>;
+    fn ry(&mut self, theta: f64, q: usize) -> Result<(), Error>;
+    fn ryy(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error>;
+    fn rz(&mut self, theta: f64, q: usize) -> Result<(), Error>;
+    fn rzz(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error>;
+    fn sadj(&mut self, q: usize) -> Result<(), Error>;
+    fn s(&mut self, q: usize) -> Result<(), Error>;
+    fn swap(&mut self, q0: usize, q1: usize) -> Result<(), Error>;
+    fn tadj(&mut self, q: usize) -> Result<(), Error>;
+    fn t(&mut self, q: usize) -> Result<(), Error>;
+    fn x(&mut self, q: usize) -> Result<(), Error>;
+    fn y(&mut self, q: usize) -> Result<(), Error>;
+    fn z(&mut self, q: usize) -> Result<(), Error>;
+    fn qubit_allocate(&mut self) -> usize;
+    fn qubit_release(&mut self, q: usize) -> Result<(), Error>;
+    fn capture_quantum_state(&mut self) -> Result<(Vec<(BigUint, Complex64)>, usize), Error>;
+    fn qubit_is_zero(&mut self, q: usize) -> bool;
+    fn set_seed(&mut self, seed: u64);
+
+    /// True if [`Backend::qubit_release`] resets the qubit, so that it may be released in any
+    /// state.
+    fn resets_on_release(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Error;
+
+/// The simulators that programs can run on.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Simulator {
+    /// The sparse state-vector simulator, which supports every operation.
+    #[default]
+    Sparse,
+    /// The stabilizer simulator, which only supports Clifford operations but scales to many more
+    /// qubits.
+    Stabilizer,
+}
+
+impl Simulator {
+    /// Creates a backend for this simulator.
+    #[must_use]
+    pub fn backend(self) -> Box<dyn Backend> {
+        match self {
+            Simulator::Sparse => Box::new(SparseSim::new()),
+            Simulator::Stabilizer => Box::new(StabilizerSim::new()),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Logical resource estimation counts the qubits and operations that a program uses without
+//! simulating its state. Depth is the number of layers of operations when each operation is placed
+//! in the layer after the last operation on any of its qubits, and T-depth counts only the layers
+//! that contain a T or T† gate.
+
+#[cfg(test)]
+mod tests;
+
+use super::{Backend, Error};
+use num_bigint::BigUint;
+use num_complex::Complex64;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/// How a backend that does not simulate the state chooses the results of measurements.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum MeasurementPolicy {
+    /// Every measurement is `Zero`.
+    #[default]
+    Zero,
+    /// Every measurement is `Zero` or `One` with equal probability.
+    Random,
+    /// Measurements take the given results in order, where `true` is `One`, and are `Zero` once
+    /// the results run out.
+    Given(Vec<bool>),
+}
+
+impl FromStr for MeasurementPolicy {
+    type Err = String;
+
+    /// Parses `zero`, `random`, or the given results as a string of `0` and `1` like `0110`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zero" => Ok(Self::Zero),
+            "random" => Ok(Self::Random),
+            _ => s
+                .chars()
+                .map(|c| match c {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err("expected `zero`, `random`, or results like `0110`".to_string()),
+                })
+                .collect::<Result<_, _>>()
+                .map(Self::Given),
+        }
+    }
+}
+
+/// The logical resources used by a program.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LogicalResources {
+    /// The largest number of qubits allocated at the same time.
+    pub peak_qubits: usize,
+    /// The number of qubit allocations.
+    pub total_qubits: usize,
+    /// T and T† gates.
+    pub t_gates: usize,
+    /// Single- and two-qubit rotations.
+    pub rotations: usize,
+    /// CNOT gates.
+    pub cnot_gates: usize,
+    /// Toffoli gates.
+    pub ccx_gates: usize,
+    /// Measurements, including those that also reset the qubit.
+    pub measurements: usize,
+    /// Every other gate, all of which are Clifford gates, and resets.
+    pub other_gates: usize,
+    pub depth: usize,
+    pub t_depth: usize,
+}
+
+impl fmt::Display for LogicalResources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "peak qubits:  {}", self.peak_qubits)?;
+        writeln!(f, "total qubits: {}", self.total_qubits)?;
+        writeln!(f, "T gates:      {}", self.t_gates)?;
+        writeln!(f, "rotations:    {}", self.rotations)?;
+        writeln!(f, "CNOT gates:   {}", self.cnot_gates)?;
+        writeln!(f, "CCX gates:    {}", self.ccx_gates)?;
+        writeln!(f, "measurements: {}", self.measurements)?;
+        writeln!(f, "other gates:  {}", self.other_gates)?;
+        writeln!(f, "depth:        {}", self.depth)?;
+        write!(f, "T-depth:      {}", self.t_depth)
+    }
+}
+
+/// A backend that counts the [`LogicalResources`] used by the operations applied to it instead of
+/// simulating them. Measurement results follow a [`MeasurementPolicy`], and the quantum state
+/// cannot be captured.
+pub struct LogicalCounter {
+    resources: LogicalResources,
+    policy: MeasurementPolicy,
+    given: usize,
+    rng: StdRng,
+    free: BTreeSet<usize>,
+    allocated: usize,
+    layers: Vec<usize>,
+    t_layers: Vec<usize>,
+}
+
+impl LogicalCounter {
+    #[must_use]
+    pub fn new(policy: MeasurementPolicy) -> Self {
+        Self {
+            resources: LogicalResources::default(),
+            policy,
+            given: 0,
+            rng: StdRng::from_entropy(),
+            free: BTreeSet::new(),
+            allocated: 0,
+            layers: Vec::new(),
+            t_layers: Vec::new(),
+        }
+    }
+
+    /// The resources used by the operations applied so far.
+    #[must_use]
+    pub fn resources(&self) -> LogicalResources {
+        self.resources
+    }
+
+    /// Places an operation on the qubits in the layer after the last operation on any of them. A
+    /// T operation also starts a new T layer, and every other operation carries the latest T layer
+    /// of its qubits to all of them.
+    fn apply(&mut self, qubits: &[usize], is_t: bool) {
+        let needed = qubits.iter().copied().max().map_or(0, |q| q + 1);
+        if self.layers.len() < needed {
+            self.layers.resize(needed, 0);
+            self.t_layers.resize(needed, 0);
+        }
+
+        let layer = qubits.iter().map(|&q| self.layers[q]).max().unwrap_or(0) + 1;
+        let t_layer =
+            qubits.iter().map(|&q| self.t_layers[q]).max().unwrap_or(0) + usize::from(is_t);
+        for &q in qubits {
+            self.layers[q] = layer;
+            self.t_layers[q] = t_layer;
+        }
+        self.resources.depth = self.resources.depth.max(layer);
+        self.resources.t_depth = self.resources.t_depth.max(t_layer);
+    }
+
+    fn other(&mut self, qubits: &[usize]) {
+        self.resources.other_gates += 1;
+        self.apply(qubits, false);
+    }
+
+    fn rotation(&mut self, qubits: &[usize]) {
+        self.resources.rotations += 1;
+        self.apply(qubits, false);
+    }
+
+    fn t_gate(&mut self, q: usize) {
+        self.resources.t_gates += 1;
+        self.apply(&[q], true);
+    }
+
+    fn measure(&mut self, q: usize) -> bool {
+        self.resources.measurements += 1;
+        self.apply(&[q], false);
+        match &self.policy {
+            MeasurementPolicy::Zero => false,
+            MeasurementPolicy::Random => self.rng.gen_bool(0.5),
+            MeasurementPolicy::Given(results) => {
+                let result = results.get(self.given).copied().unwrap_or_default();
+                self.given += 1;
+                result
+            }
+        }
+    }
+}
+
+impl Backend for LogicalCounter {
+    fn ccx(&mut self, ctl0: usize, ctl1: usize, q: usize) -> Result<(), Error> {
+        self.resources.ccx_gates += 1;
+        self.apply(&[ctl0, ctl1, q], false);
+        Ok(())
+    }
+
+    fn cx(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.resources.cnot_gates += 1;
+        self.apply(&[ctl, q], false);
+        Ok(())
+    }
+
+    fn cy(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.other(&[ctl, q]);
+        Ok(())
+    }
+
+    fn cz(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.other(&[ctl, q]);
+        Ok(())
+    }
+
+    fn h(&mut self, q: usize) -> Result<(), Error> {
+        self.other(&[q]);
+        Ok(())
+    }
+
+    fn m(&mut self, q: usize) -> Result<bool, Error> {
+        Ok(self.measure(q))
+    }
+
+    fn mresetz(&mut self, q: usize) -> Result<bool, Error> {
+        Ok(self.measure(q))
+    }
+
+    fn reset(&mut self, q: usize) -> Result<(), Error> {
+        self.other(&[q]);
+        Ok(())
+    }
+
+    fn rx(&mut self, _: f64, q: usize) -> Result<(), Error> {
+        self.rotation(&[q]);
+        Ok(())
+    }
+
+    fn rxx(&mut self, _: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.rotation(&[q0, q1]);
+        Ok(())
+    }
+
+    fn ry(&mut self, _: f64, q: usize) -> Result<(), Error> {
+        self.rotation(&[q]);
+        Ok(())
+    }
+
+    fn ryy(&mut self, _: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.rotation(&[q0, q1]);
+        Ok(())
+    }
+
+    fn rz(&mut self, _: f64, q: usize) -> Result<(), Error> {
+        self.rotation(&[q]);
+        Ok(())
+    }
+
+    fn rzz(&mut self, _: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.rotation(&[q0, q1]);
+        Ok(())
+    }
+
+    fn sadj(&mut self, q: usize) -> Result<(), Error> {
+        self.other(&[q]);
+        Ok(())
+    }
+
+    fn s(&mut self, q: usize) -> Result<(), Error> {
+        self.other(&[q]);
+        Ok(())
+    }
+
+    fn swap(&mut self, q0: usize, q1: usize) -> Result<(), Error> {
+        self.other(&[q0, q1]);
+        Ok(())
+    }
+
+    fn tadj(&mut self, q: usize) -> Result<(), Error> {
+        self.t_gate(q);
+        Ok(())
+    }
+
+    fn t(&mut self, q: usize) -> Result<(), Error> {
+        self.t_gate(q);
+        Ok(())
+    }
+
+    fn x(&mut self, q: usize) -> Result<(), Error> {
+        self.other(&[q]);
+        Ok(())
+    }
+
+    fn y(&mut self, q: usize) -> Result<(), Error> {
+        self.other(&[q]);
+        Ok(())
+    }
+
+    fn z(&mut self, q: usize) -> Result<(), Error> {
+        self.other(&[q]);
+        Ok(())
+    }
+
+    fn qubit_allocate(&mut self) -> usize {
+        let q = self
+            .free
+            .pop_first()
+            .unwrap_or(self.allocated + self.free.len());
+        self.allocated += 1;
+        self.resources.total_qubits += 1;
+        self.resources.peak_qubits = self.resources.peak_qubits.max(self.allocated);
+        q
+    }
+
+    fn qubit_release(&mut self, q: usize) -> Result<(), Error> {
+        if self.free.insert(q) {
+            self.allocated = self.allocated.saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    fn capture_quantum_state(&mut self) -> Result<(Vec<(BigUint, Complex64)>, usize), Error> {
+        Err(Error)
+    }
+
+    fn qubit_is_zero(&mut self, _: usize) -> bool {
+        true
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{LogicalCounter, MeasurementPolicy};
+use crate::backend::Backend;
+use expect_test::expect;
+
+#[test]
+fn gates_are_counted_by_kind() {
+    let mut counter = LogicalCounter::new(MeasurementPolicy::Zero);
+    let q0 = counter.qubit_allocate();
+    let q1 = counter.qubit_allocate();
+    let q2 = counter.qubit_allocate();
+    counter.h(q0).expect("gate should succeed");
+    counter.t(q0).expect("gate should succeed");
+    counter.tadj(q1).expect("gate should succeed");
+    counter.rx(0.5, q2).expect("gate should succeed");
+    counter.rzz(0.5, q1, q2).expect("gate should succeed");
+    counter.cx(q0, q1).expect("gate should succeed");
+    counter.ccx(q0, q1, q2).expect("gate should succeed");
+    counter.mresetz(q0).expect("measurement should succeed");
+    expect![[r"
+        peak qubits:  3
+        total qubits: 3
+        T gates:      2
+        rotations:    2
+        CNOT gates:   1
+        CCX gates:    1
+        measurements: 1
+        other gates:  1
+        depth:        5
+        T-depth:      1"]]
+    .assert_eq(&counter.resources().to_string());
+}
+
+#[test]
+fn t_depth_follows_dependencies() {
+    let mut counter = LogicalCounter::new(MeasurementPolicy::Zero);
+    let q0 = counter.qubit_allocate();
+    let q1 = counter.qubit_allocate();
+    counter.t(q0).expect("gate should succeed");
+    counter.t(q1).expect("gate should succeed");
+    counter.cx(q0, q1).expect("gate should succeed");
+    counter.t(q1).expect("gate should succeed");
+    counter.h(q0).expect("gate should succeed");
+    let resources = counter.resources();
+    assert_eq!(resources.depth, 3);
+    assert_eq!(resources.t_depth, 2);
+}
+
+#[test]
+fn released_qubits_are_reused() {
+    let mut counter = LogicalCounter::new(MeasurementPolicy::Zero);
+    let q0 = counter.qubit_allocate();
+    let q1 = counter.qubit_allocate();
+    counter.qubit_release(q0).expect("release should succeed");
+    let q2 = counter.qubit_allocate();
+    assert_eq!(q0, q2);
+    counter.qubit_release(q1).expect("release should succeed");
+    counter.qubit_release(q2).expect("release should succeed");
+    let q3 = counter.qubit_allocate();
+    let q4 = counter.qubit_allocate();
+    let q5 = counter.qubit_allocate();
+    assert_eq!((q3, q4, q5), (0, 1, 2));
+    let resources = counter.resources();
+    assert_eq!(resources.peak_qubits, 3);
+    assert_eq!(resources.total_qubits, 6);
+}
+
+#[test]
+fn given_measurements_are_used_in_order() {
+    let mut counter = LogicalCounter::new(MeasurementPolicy::Given(vec![true, false, true]));
+    let q = counter.qubit_allocate();
+    let results: Vec<_> = (0..5)
+        .map(|_| counter.m(q).expect("measurement should succeed"))
+        .collect();
+    assert_eq!(results, [true, false, true, false, false]);
+}
+
+#[test]
+fn random_measurements_are_reproducible() {
+    let measure = || {
+        let mut counter = LogicalCounter::new(MeasurementPolicy::Random);
+        counter.set_seed(42);
+        let q = counter.qubit_allocate();
+        (0..16)
+            .map(|_| counter.m(q).expect("measurement should succeed"))
+            .collect::<Vec<_>>()
+    };
+    let results = measure();
+    assert_eq!(results, measure());
+    assert!(results.contains(&true) && results.contains(&false));
+}
+
+#[test]
+fn state_cannot_be_captured() {
+    let mut counter = LogicalCounter::new(MeasurementPolicy::Zero);
+    counter.qubit_allocate();
+    assert!(counter.capture_quantum_state().is_err());
+}
+
+#[test]
+fn policy_is_parsed() {
+    assert_eq!("zero".parse(), Ok(MeasurementPolicy::Zero));
+    assert_eq!("random".parse(), Ok(MeasurementPolicy::Random));
+    assert_eq!(
+        "0110".parse(),
+        Ok(MeasurementPolicy::Given(vec![false, true, true, false]))
+    );
+    assert!("01x".parse::<MeasurementPolicy>().is_err());
+}
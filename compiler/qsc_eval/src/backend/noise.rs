@@ -0,0 +1,280 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Noise is modeled as random Pauli errors applied by a wrapper around another backend. After each
+//! gate, the qubits the gate acted on may suffer depolarizing, bit-flip or phase-flip errors, and
+//! every other active qubit may suffer an idle depolarizing error. A qubit is active from the first
+//! gate that acts on it until it is reset, so qubits waiting to be used stay in the zero state.
+//! Measurements may also report the wrong result without changing the state. Since errors can
+//! leave a correctly uncomputed qubit in any state, qubits are reset when they are released.
+
+#[cfg(test)]
+mod tests;
+
+use super::{Backend, Error};
+use num_bigint::BigUint;
+use num_complex::Complex64;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::BTreeSet;
+
+/// The probabilities of the errors that can occur during simulation. Every probability must be
+/// between zero and one, and the default model has no noise.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NoiseModel {
+    /// The probability that a qubit suffers a uniformly random X, Y or Z error after a gate acts
+    /// on it.
+    pub depolarizing: f64,
+    /// The probability that a qubit suffers an X error after a gate acts on it.
+    pub bit_flip: f64,
+    /// The probability that a qubit suffers a Z error after a gate acts on it.
+    pub phase_flip: f64,
+    /// The probability that an active qubit suffers a uniformly random X, Y or Z error while a
+    /// gate or measurement acts on other qubits.
+    pub idle: f64,
+    /// The probability that a measurement reports the opposite of its result.
+    pub readout: f64,
+}
+
+impl NoiseModel {
+    /// True if none of the errors in this model can occur.
+    #[must_use]
+    pub fn is_noiseless(&self) -> bool {
+        self.probabilities().all(|p| p == 0.0)
+    }
+
+    /// True if every probability in this model is between zero and one.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.probabilities().all(|p| (0.0..=1.0).contains(&p))
+    }
+
+    /// Wraps the backend so that its operations are subject to this model. The backend is returned
+    /// unchanged if the model is noiseless.
+    #[must_use]
+    pub fn wrap(self, backend: Box<dyn Backend>) -> Box<dyn Backend> {
+        if self.is_noiseless() {
+            backend
+        } else {
+            Box::new(NoisySim::new(self, backend))
+        }
+    }
+
+    fn probabilities(&self) -> impl Iterator<Item = f64> {
+        [
+            self.depolarizing,
+            self.bit_flip,
+            self.phase_flip,
+            self.idle,
+            self.readout,
+        ]
+        .into_iter()
+    }
+}
+
+/// A backend that forwards operations to another backend and injects errors according to a
+/// [`NoiseModel`].
+pub struct NoisySim {
+    inner: Box<dyn Backend>,
+    noise: NoiseModel,
+    rng: StdRng,
+    active: BTreeSet<usize>,
+}
+
+impl NoisySim {
+    /// Creates a backend that applies the noise model to the inner backend.
+    #[must_use]
+    pub fn new(noise: NoiseModel, inner: Box<dyn Backend>) -> Self {
+        Self {
+            inner,
+            noise,
+            rng: StdRng::from_entropy(),
+            active: BTreeSet::new(),
+        }
+    }
+
+    /// Applies the errors that follow a gate acting on the target qubits.
+    fn gate_noise(&mut self, targets: &[usize]) -> Result<(), Error> {
+        for &q in targets {
+            self.active.insert(q);
+            if occurs(&mut self.rng, self.noise.depolarizing) {
+                random_pauli(self.inner.as_mut(), &mut self.rng, q)?;
+            }
+            if occurs(&mut self.rng, self.noise.bit_flip) {
+                self.inner.x(q)?;
+            }
+            if occurs(&mut self.rng, self.noise.phase_flip) {
+                self.inner.z(q)?;
+            }
+        }
+        self.idle_noise(targets)
+    }
+
+    /// Applies the errors that affect every active qubit except the targets.
+    fn idle_noise(&mut self, targets: &[usize]) -> Result<(), Error> {
+        for &q in self.active.iter().filter(|q| !targets.contains(q)) {
+            if occurs(&mut self.rng, self.noise.idle) {
+                random_pauli(self.inner.as_mut(), &mut self.rng, q)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn readout(&mut self, q: usize, result: bool) -> Result<bool, Error> {
+        self.idle_noise(&[q])?;
+        Ok(result ^ occurs(&mut self.rng, self.noise.readout))
+    }
+}
+
+impl Backend for NoisySim {
+    fn ccx(&mut self, ctl0: usize, ctl1: usize, q: usize) -> Result<(), Error> {
+        self.inner.ccx(ctl0, ctl1, q)?;
+        self.gate_noise(&[ctl0, ctl1, q])
+    }
+
+    fn cx(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.inner.cx(ctl, q)?;
+        self.gate_noise(&[ctl, q])
+    }
+
+    fn cy(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.inner.cy(ctl, q)?;
+        self.gate_noise(&[ctl, q])
+    }
+
+    fn cz(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.inner.cz(ctl, q)?;
+        self.gate_noise(&[ctl, q])
+    }
+
+    fn h(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.h(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn m(&mut self, q: usize) -> Result<bool, Error> {
+        let result = self.inner.m(q)?;
+        self.readout(q, result)
+    }
+
+    fn mresetz(&mut self, q: usize) -> Result<bool, Error> {
+        let result = self.inner.mresetz(q)?;
+        self.active.remove(&q);
+        self.readout(q, result)
+    }
+
+    fn reset(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.reset(q)?;
+        self.active.remove(&q);
+        self.idle_noise(&[q])
+    }
+
+    fn rx(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.inner.rx(theta, q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn rxx(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.inner.rxx(theta, q0, q1)?;
+        self.gate_noise(&[q0, q1])
+    }
+
+    fn ry(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.inner.ry(theta, q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn ryy(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.inner.ryy(theta, q0, q1)?;
+        self.gate_noise(&[q0, q1])
+    }
+
+    fn rz(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.inner.rz(theta, q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn rzz(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.inner.rzz(theta, q0, q1)?;
+        self.gate_noise(&[q0, q1])
+    }
+
+    fn sadj(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.sadj(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn s(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.s(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn swap(&mut self, q0: usize, q1: usize) -> Result<(), Error> {
+        self.inner.swap(q0, q1)?;
+        self.gate_noise(&[q0, q1])
+    }
+
+    fn tadj(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.tadj(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn t(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.t(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn x(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.x(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn y(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.y(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn z(&mut self, q: usize) -> Result<(), Error> {
+        self.inner.z(q)?;
+        self.gate_noise(&[q])
+    }
+
+    fn qubit_allocate(&mut self) -> usize {
+        self.inner.qubit_allocate()
+    }
+
+    fn qubit_release(&mut self, q: usize) -> Result<(), Error> {
+        self.active.remove(&q);
+        self.inner.reset(q)?;
+        self.inner.qubit_release(q)
+    }
+
+    fn capture_quantum_state(&mut self) -> Result<(Vec<(BigUint, Complex64)>, usize), Error> {
+        self.inner.capture_quantum_state()
+    }
+
+    fn qubit_is_zero(&mut self, q: usize) -> bool {
+        self.inner.qubit_is_zero(q)
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.inner.set_seed(self.rng.gen());
+    }
+
+    fn resets_on_release(&self) -> bool {
+        true
+    }
+}
+
+/// Samples whether an error with the given probability occurs.
+fn occurs(rng: &mut StdRng, probability: f64) -> bool {
+    probability > 0.0 && rng.gen::<f64>() < probability
+}
+
+fn random_pauli(backend: &mut dyn Backend, rng: &mut StdRng, q: usize) -> Result<(), Error> {
+    match rng.gen_range(0..3) {
+        0 => backend.x(q),
+        1 => backend.y(q),
+        _ => backend.z(q),
+    }
+}
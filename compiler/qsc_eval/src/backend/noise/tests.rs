@@ -0,0 +1,96 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{NoiseModel, NoisySim};
+use crate::backend::{Backend, StabilizerSim};
+
+fn noisy(noise: NoiseModel) -> NoisySim {
+    NoisySim::new(noise, Box::new(StabilizerSim::new()))
+}
+
+#[test]
+fn bit_flip_follows_every_gate() {
+    let mut sim = noisy(NoiseModel {
+        bit_flip: 1.0,
+        ..NoiseModel::default()
+    });
+    let q = sim.qubit_allocate();
+    sim.x(q).expect("gate should succeed");
+    assert!(sim.qubit_is_zero(q));
+    sim.z(q).expect("gate should succeed");
+    assert_eq!(sim.m(q), Ok(true));
+}
+
+#[test]
+fn phase_flip_follows_every_gate() {
+    let mut sim = noisy(NoiseModel {
+        phase_flip: 1.0,
+        ..NoiseModel::default()
+    });
+    let q = sim.qubit_allocate();
+    sim.h(q).expect("gate should succeed");
+    sim.h(q).expect("gate should succeed");
+    assert_eq!(sim.m(q), Ok(true));
+}
+
+#[test]
+fn readout_error_flips_results_but_not_state() {
+    let mut sim = noisy(NoiseModel {
+        readout: 1.0,
+        ..NoiseModel::default()
+    });
+    let q = sim.qubit_allocate();
+    assert_eq!(sim.m(q), Ok(true));
+    assert!(sim.qubit_is_zero(q));
+    sim.x(q).expect("gate should succeed");
+    assert_eq!(sim.mresetz(q), Ok(false));
+    assert!(sim.qubit_is_zero(q));
+}
+
+#[test]
+fn idle_noise_skips_targets_and_reset_qubits() {
+    let mut sim = noisy(NoiseModel {
+        idle: 1.0,
+        ..NoiseModel::default()
+    });
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    for _ in 0..10 {
+        sim.x(q0).expect("gate should succeed");
+    }
+    assert!(sim.qubit_is_zero(q0));
+    assert!(sim.qubit_is_zero(q1));
+    sim.h(q1).expect("gate should succeed");
+    sim.reset(q1).expect("reset should succeed");
+    for _ in 0..10 {
+        sim.x(q0).expect("gate should succeed");
+    }
+    assert!(sim.qubit_is_zero(q1));
+}
+
+#[test]
+fn release_resets_qubit() {
+    let mut sim = noisy(NoiseModel {
+        bit_flip: 1.0,
+        ..NoiseModel::default()
+    });
+    assert!(sim.resets_on_release());
+    let q = sim.qubit_allocate();
+    sim.h(q).expect("gate should succeed");
+    sim.qubit_release(q).expect("release should succeed");
+    let q = sim.qubit_allocate();
+    assert!(sim.qubit_is_zero(q));
+}
+
+#[test]
+fn noiseless_model_is_valid() {
+    assert!(NoiseModel::default().is_noiseless());
+    assert!(NoiseModel::default().is_valid());
+    let noise = NoiseModel {
+        depolarizing: 0.5,
+        readout: 1.5,
+        ..NoiseModel::default()
+    };
+    assert!(!noise.is_noiseless());
+    assert!(!noise.is_valid());
+}
@@ -0,0 +1,587 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! A sparse state-vector simulator. The state is stored as a map from computational basis states to
+//! their amplitudes, where only the nonzero amplitudes are kept. Each simulator owns its state and
+//! random number generator, so independent simulators can run side by side on different threads.
+//!
+//! Gates update the map in place, touching only the basis states whose amplitudes change. Basis
+//! states are keyed by a machine word while every qubit fits in one, which keeps hashing and copying
+//! them cheap, and by an arbitrary-precision integer once more qubits are allocated.
+//!
+//! Hadamard gates are not applied to the state until another gate needs the qubit in the
+//! computational basis, so that a pair of them cancels out and a CNOT between two qubits that both
+//! have one pending runs in reverse instead. Circuits that conjugate many qubits by Hadamard gates,
+//! like parity checks, then never put the state into a superposition of every basis state.
+
+#[cfg(test)]
+mod tests;
+
+use super::{Backend, Error};
+use num_bigint::BigUint;
+use num_complex::Complex64;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::HashMap,
+    f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4},
+    hash::{BuildHasherDefault, Hash, Hasher},
+    mem,
+};
+
+/// Amplitudes with a squared magnitude below this threshold are treated as zero and dropped from
+/// the state.
+const PRUNE_THRESHOLD: f64 = 1e-24;
+
+/// Qubits whose probability of being measured as one is below this threshold are treated as being
+/// in the zero state.
+const ZERO_THRESHOLD: f64 = 1e-10;
+
+type Matrix = [[Complex64; 2]; 2];
+
+const ZERO: Complex64 = Complex64::new(0.0, 0.0);
+const ONE: Complex64 = Complex64::new(1.0, 0.0);
+const I: Complex64 = Complex64::new(0.0, 1.0);
+
+/// A backend that simulates the full quantum state, which supports every operation but whose cost
+/// grows with the number of basis states in superposition.
+#[derive(Debug)]
+pub struct SparseSim {
+    state: State,
+    in_use: Vec<bool>,
+    /// The qubits with a Hadamard gate that has not been applied to the state yet.
+    pending_h: Vec<bool>,
+    rng: StdRng,
+}
+
+impl Default for SparseSim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SparseSim {
+    /// Creates a simulator with no qubits.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            state: State::Small(Amplitudes::new()),
+            in_use: Vec::new(),
+            pending_h: Vec::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Applies the pending Hadamard gates of the qubits to the state.
+    fn flush(&mut self, qubits: &[usize]) {
+        for &q in qubits {
+            if self.pending_h[q] {
+                self.pending_h[q] = false;
+                self.state.apply_matrix(&[], q, h_matrix());
+            }
+        }
+    }
+
+    fn measure(&mut self, q: usize) -> bool {
+        self.flush(&[q]);
+        let p = self.state.probability_of_one(q);
+        let outcome = self.rng.gen::<f64>() < p;
+        let norm = if outcome { p } else { 1.0 - p }.sqrt();
+        self.state.collapse(q, outcome, norm);
+        outcome
+    }
+}
+
+impl Backend for SparseSim {
+    fn ccx(&mut self, ctl0: usize, ctl1: usize, q: usize) -> Result<(), Error> {
+        self.flush(&[ctl0, ctl1, q]);
+        self.state.apply_x(&[ctl0, ctl1], q);
+        Ok(())
+    }
+
+    fn cx(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        if self.pending_h[ctl] && self.pending_h[q] {
+            // Conjugating a CNOT by Hadamard gates on both qubits swaps its control and target.
+            self.state.apply_x(&[q], ctl);
+        } else {
+            self.flush(&[ctl, q]);
+            self.state.apply_x(&[ctl], q);
+        }
+        Ok(())
+    }
+
+    fn cy(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.flush(&[ctl, q]);
+        self.state.apply_matrix(&[ctl], q, y_matrix());
+        Ok(())
+    }
+
+    fn cz(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.flush(&[ctl, q]);
+        self.state.apply_phase(&[ctl], q, -ONE);
+        Ok(())
+    }
+
+    fn h(&mut self, q: usize) -> Result<(), Error> {
+        self.pending_h[q] = !self.pending_h[q];
+        Ok(())
+    }
+
+    fn m(&mut self, q: usize) -> Result<bool, Error> {
+        Ok(self.measure(q))
+    }
+
+    fn mresetz(&mut self, q: usize) -> Result<bool, Error> {
+        let outcome = self.measure(q);
+        if outcome {
+            self.state.apply_x(&[], q);
+        }
+        Ok(outcome)
+    }
+
+    fn reset(&mut self, q: usize) -> Result<(), Error> {
+        self.mresetz(q).map(|_| ())
+    }
+
+    fn rx(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        let (cos, sin) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+        let matrix = [[cos * ONE, -sin * I], [-sin * I, cos * ONE]];
+        self.state.apply_matrix(&[], q, matrix);
+        Ok(())
+    }
+
+    fn rxx(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.flush(&[q0, q1]);
+        self.state.apply_matrix(&[], q0, h_matrix());
+        self.state.apply_matrix(&[], q1, h_matrix());
+        self.state.apply_rzz(theta, q0, q1);
+        self.state.apply_matrix(&[], q0, h_matrix());
+        self.state.apply_matrix(&[], q1, h_matrix());
+        Ok(())
+    }
+
+    fn ry(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        let (cos, sin) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+        let matrix = [[cos * ONE, -sin * ONE], [sin * ONE, cos * ONE]];
+        self.state.apply_matrix(&[], q, matrix);
+        Ok(())
+    }
+
+    fn ryy(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.flush(&[q0, q1]);
+        for q in [q0, q1] {
+            self.state.apply_phase(&[], q, -I);
+            self.state.apply_matrix(&[], q, h_matrix());
+        }
+        self.state.apply_rzz(theta, q0, q1);
+        for q in [q0, q1] {
+            self.state.apply_matrix(&[], q, h_matrix());
+            self.state.apply_phase(&[], q, I);
+        }
+        Ok(())
+    }
+
+    fn rz(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state.apply_rz(theta, q);
+        Ok(())
+    }
+
+    fn rzz(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.flush(&[q0, q1]);
+        self.state.apply_rzz(theta, q0, q1);
+        Ok(())
+    }
+
+    fn sadj(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state.apply_phase(&[], q, -I);
+        Ok(())
+    }
+
+    fn s(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state.apply_phase(&[], q, I);
+        Ok(())
+    }
+
+    fn swap(&mut self, q0: usize, q1: usize) -> Result<(), Error> {
+        // A swap moves the pending Hadamard gates along with the qubits.
+        self.state.swap(q0, q1);
+        self.pending_h.swap(q0, q1);
+        Ok(())
+    }
+
+    fn tadj(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state
+            .apply_phase(&[], q, Complex64::from_polar(1.0, -FRAC_PI_4));
+        Ok(())
+    }
+
+    fn t(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state
+            .apply_phase(&[], q, Complex64::from_polar(1.0, FRAC_PI_4));
+        Ok(())
+    }
+
+    fn x(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state.apply_x(&[], q);
+        Ok(())
+    }
+
+    fn y(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state.apply_matrix(&[], q, y_matrix());
+        Ok(())
+    }
+
+    fn z(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.state.apply_phase(&[], q, -ONE);
+        Ok(())
+    }
+
+    fn qubit_allocate(&mut self) -> usize {
+        if let Some(q) = self.in_use.iter().position(|&used| !used) {
+            self.in_use[q] = true;
+            q
+        } else {
+            self.in_use.push(true);
+            self.pending_h.push(false);
+            if self.in_use.len() > u64::BITS as usize {
+                self.state.widen();
+            }
+            self.in_use.len() - 1
+        }
+    }
+
+    fn qubit_release(&mut self, q: usize) -> Result<(), Error> {
+        self.flush(&[q]);
+        self.in_use[q] = false;
+        while self.in_use.last() == Some(&false) {
+            self.in_use.pop();
+            self.pending_h.pop();
+        }
+        Ok(())
+    }
+
+    fn capture_quantum_state(&mut self) -> Result<(Vec<(BigUint, Complex64)>, usize), Error> {
+        let qubits: Vec<_> = (0..self.in_use.len()).collect();
+        self.flush(&qubits);
+        Ok((self.state.basis_states(), self.in_use.len()))
+    }
+
+    fn qubit_is_zero(&mut self, q: usize) -> bool {
+        self.flush(&[q]);
+        self.state.probability_of_one(q) < ZERO_THRESHOLD
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
+
+/// The amplitudes of the basis states in superposition, keyed by a machine word while every qubit
+/// fits in one.
+#[derive(Debug)]
+enum State {
+    Small(Amplitudes<u64>),
+    Large(Amplitudes<BigUint>),
+}
+
+impl State {
+    /// Switches to arbitrary-precision basis states, so that qubits beyond the width of a machine
+    /// word can be used.
+    fn widen(&mut self) {
+        if let State::Small(amplitudes) = self {
+            let map = mem::take(&mut amplitudes.map)
+                .into_iter()
+                .map(|(basis, amplitude)| (BigUint::from(basis), amplitude))
+                .collect();
+            *self = State::Large(Amplitudes { map });
+        }
+    }
+
+    fn apply_matrix(&mut self, ctls: &[usize], q: usize, matrix: Matrix) {
+        match self {
+            State::Small(amplitudes) => amplitudes.apply_matrix(ctls, q, matrix),
+            State::Large(amplitudes) => amplitudes.apply_matrix(ctls, q, matrix),
+        }
+    }
+
+    fn apply_phase(&mut self, ctls: &[usize], q: usize, phase: Complex64) {
+        match self {
+            State::Small(amplitudes) => amplitudes.apply_phase(ctls, q, phase),
+            State::Large(amplitudes) => amplitudes.apply_phase(ctls, q, phase),
+        }
+    }
+
+    fn apply_x(&mut self, ctls: &[usize], q: usize) {
+        match self {
+            State::Small(amplitudes) => amplitudes.apply_x(ctls, q),
+            State::Large(amplitudes) => amplitudes.apply_x(ctls, q),
+        }
+    }
+
+    fn apply_rz(&mut self, theta: f64, q: usize) {
+        match self {
+            State::Small(amplitudes) => amplitudes.apply_rz(theta, q),
+            State::Large(amplitudes) => amplitudes.apply_rz(theta, q),
+        }
+    }
+
+    fn apply_rzz(&mut self, theta: f64, q0: usize, q1: usize) {
+        match self {
+            State::Small(amplitudes) => amplitudes.apply_rzz(theta, q0, q1),
+            State::Large(amplitudes) => amplitudes.apply_rzz(theta, q0, q1),
+        }
+    }
+
+    fn swap(&mut self, q0: usize, q1: usize) {
+        match self {
+            State::Small(amplitudes) => amplitudes.swap(q0, q1),
+            State::Large(amplitudes) => amplitudes.swap(q0, q1),
+        }
+    }
+
+    fn probability_of_one(&self, q: usize) -> f64 {
+        match self {
+            State::Small(amplitudes) => amplitudes.probability_of_one(q),
+            State::Large(amplitudes) => amplitudes.probability_of_one(q),
+        }
+    }
+
+    fn collapse(&mut self, q: usize, outcome: bool, norm: f64) {
+        match self {
+            State::Small(amplitudes) => amplitudes.collapse(q, outcome, norm),
+            State::Large(amplitudes) => amplitudes.collapse(q, outcome, norm),
+        }
+    }
+
+    /// The basis states with nonzero amplitudes, in ascending order.
+    fn basis_states(&self) -> Vec<(BigUint, Complex64)> {
+        let mut states: Vec<_> = match self {
+            State::Small(amplitudes) => amplitudes
+                .map
+                .iter()
+                .map(|(basis, amplitude)| (BigUint::from(*basis), *amplitude))
+                .collect(),
+            State::Large(amplitudes) => amplitudes
+                .map
+                .iter()
+                .map(|(basis, amplitude)| (basis.clone(), *amplitude))
+                .collect(),
+        };
+        states.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        states
+    }
+}
+
+/// A map from basis states to their nonzero amplitudes. The hasher is not randomly seeded, so the
+/// order the amplitudes are visited in, and so the rounding of sums over them, is the same on every
+/// run with the same seed.
+#[derive(Debug)]
+struct Amplitudes<B> {
+    map: HashMap<B, Complex64, BuildHasherDefault<BasisHasher>>,
+}
+
+impl<B: Basis> Amplitudes<B> {
+    fn new() -> Self {
+        let mut map = HashMap::default();
+        map.insert(B::default(), ONE);
+        Self { map }
+    }
+
+    /// Applies a single-qubit unitary to the target, conditioned on every control being one. Each
+    /// pair of basis states that differ only in the target is updated together, and the pairs are
+    /// found before any amplitude changes so that a pair is not visited twice.
+    fn apply_matrix(&mut self, ctls: &[usize], q: usize, matrix: Matrix) {
+        let mut pairs: Vec<B> = self
+            .map
+            .keys()
+            .filter(|basis| is_set(*basis, ctls))
+            .filter(|basis| !basis.bit(q) || !self.map.contains_key(&basis.with_bit(q, false)))
+            .map(|basis| basis.with_bit(q, false))
+            .collect();
+        for zero in pairs.drain(..) {
+            let one = zero.with_bit(q, true);
+            let a0 = self.map.get(&zero).copied().unwrap_or(ZERO);
+            let a1 = self.map.get(&one).copied().unwrap_or(ZERO);
+            self.set(zero, matrix[0][0] * a0 + matrix[0][1] * a1);
+            self.set(one, matrix[1][0] * a0 + matrix[1][1] * a1);
+        }
+    }
+
+    /// Multiplies the amplitude of every basis state where the controls and target are all one by
+    /// the phase.
+    fn apply_phase(&mut self, ctls: &[usize], q: usize, phase: Complex64) {
+        for (basis, amplitude) in &mut self.map {
+            if basis.bit(q) && is_set(basis, ctls) {
+                *amplitude *= phase;
+            }
+        }
+    }
+
+    /// Flips the target in every basis state where the controls are all one. Only those basis
+    /// states are taken out of the map and put back with the target flipped.
+    fn apply_x(&mut self, ctls: &[usize], q: usize) {
+        self.remap(
+            |basis| is_set(basis, ctls),
+            |basis| basis.with_bit(q, !basis.bit(q)),
+        );
+    }
+
+    fn apply_rz(&mut self, theta: f64, q: usize) {
+        let zero = Complex64::from_polar(1.0, -theta / 2.0);
+        let one = Complex64::from_polar(1.0, theta / 2.0);
+        for (basis, amplitude) in &mut self.map {
+            *amplitude *= if basis.bit(q) { one } else { zero };
+        }
+    }
+
+    fn apply_rzz(&mut self, theta: f64, q0: usize, q1: usize) {
+        let even = Complex64::from_polar(1.0, -theta / 2.0);
+        let odd = Complex64::from_polar(1.0, theta / 2.0);
+        for (basis, amplitude) in &mut self.map {
+            if basis.bit(q0) == basis.bit(q1) {
+                *amplitude *= even;
+            } else {
+                *amplitude *= odd;
+            }
+        }
+    }
+
+    /// Exchanges the two qubits, which moves only the basis states where they differ.
+    fn swap(&mut self, q0: usize, q1: usize) {
+        self.remap(
+            |basis| basis.bit(q0) != basis.bit(q1),
+            |basis| {
+                basis
+                    .with_bit(q0, !basis.bit(q0))
+                    .with_bit(q1, !basis.bit(q1))
+            },
+        );
+    }
+
+    fn probability_of_one(&self, q: usize) -> f64 {
+        self.map
+            .iter()
+            .filter(|(basis, _)| basis.bit(q))
+            .map(|(_, amplitude)| amplitude.norm_sqr())
+            .sum()
+    }
+
+    /// Keeps only the basis states where the qubit has the measured outcome, renormalizing their
+    /// amplitudes by the square root of the outcome's probability.
+    fn collapse(&mut self, q: usize, outcome: bool, norm: f64) {
+        self.map.retain(|basis, _| basis.bit(q) == outcome);
+        for amplitude in self.map.values_mut() {
+            *amplitude /= norm;
+        }
+    }
+
+    /// Sets the amplitude of the basis state, dropping it from the map if it is zero.
+    fn set(&mut self, basis: B, amplitude: Complex64) {
+        if amplitude.norm_sqr() > PRUNE_THRESHOLD {
+            self.map.insert(basis, amplitude);
+        } else {
+            self.map.remove(&basis);
+        }
+    }
+
+    /// Moves every basis state that matches the filter to the basis state given by the map, which
+    /// must be a permutation of the matching basis states.
+    fn remap(&mut self, filter: impl Fn(&B) -> bool, map: impl Fn(&B) -> B) {
+        let moved: Vec<_> = self
+            .map
+            .iter()
+            .filter(|(basis, _)| filter(basis))
+            .map(|(basis, &amplitude)| (map(basis), amplitude))
+            .collect();
+        self.map.retain(|basis, _| !filter(basis));
+        self.map.extend(moved);
+    }
+}
+
+/// A hasher for basis states that mixes in each word with a rotation and a multiplication, like the
+/// hasher of the Rust compiler. Basis states do not come from an adversary, so this is much cheaper
+/// than the default hasher without needing its resistance to collision attacks.
+#[derive(Default)]
+struct BasisHasher(u64);
+
+impl BasisHasher {
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+}
+
+impl Hasher for BasisHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u64(&mut self, word: u64) {
+        self.add(word);
+    }
+}
+
+/// A computational basis state, where bit `q` is the value of qubit `q`.
+trait Basis: Clone + Default + Eq + Hash {
+    fn bit(&self, q: usize) -> bool;
+
+    /// The basis state with the qubit set to the value.
+    #[must_use]
+    fn with_bit(&self, q: usize, value: bool) -> Self;
+}
+
+impl Basis for u64 {
+    fn bit(&self, q: usize) -> bool {
+        self >> q & 1 == 1
+    }
+
+    fn with_bit(&self, q: usize, value: bool) -> Self {
+        if value {
+            self | 1 << q
+        } else {
+            self & !(1 << q)
+        }
+    }
+}
+
+impl Basis for BigUint {
+    fn bit(&self, q: usize) -> bool {
+        BigUint::bit(self, q as u64)
+    }
+
+    fn with_bit(&self, q: usize, value: bool) -> Self {
+        let mut basis = self.clone();
+        basis.set_bit(q as u64, value);
+        basis
+    }
+}
+
+fn h_matrix() -> Matrix {
+    let r = FRAC_1_SQRT_2 * ONE;
+    [[r, r], [r, -r]]
+}
+
+fn y_matrix() -> Matrix {
+    [[ZERO, -I], [I, ZERO]]
+}
+
+/// True if every one of the qubits is one in the basis state.
+fn is_set(basis: &impl Basis, qubits: &[usize]) -> bool {
+    qubits.iter().all(|&q| basis.bit(q))
+}
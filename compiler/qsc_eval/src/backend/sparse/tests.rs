@@ -0,0 +1,184 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::SparseSim;
+use crate::{backend::Backend, output::format_state_id};
+use expect_test::{expect, Expect};
+use std::{f64::consts::PI, fmt::Write, thread};
+
+fn check_state(sim: &mut SparseSim, expect: &Expect) {
+    let (state, qubit_count) = sim
+        .capture_quantum_state()
+        .expect("state should be captured");
+    let mut actual = String::new();
+    for (id, amplitude) in state {
+        writeln!(
+            actual,
+            "{}: {:.4}",
+            format_state_id(&id, qubit_count),
+            amplitude
+        )
+        .expect("writing to string should succeed");
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn bell_state() {
+    let mut sim = SparseSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.h(q0).expect("gate should succeed");
+    sim.cx(q0, q1).expect("gate should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |00⟩: 0.7071+0.0000i
+            |11⟩: 0.7071+0.0000i
+        "]],
+    );
+}
+
+#[test]
+fn rotations_and_phases() {
+    let mut sim = SparseSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.rx(PI / 3.0, q0).expect("rotation should succeed");
+    sim.ry(PI / 2.0, q1).expect("rotation should succeed");
+    sim.t(q1).expect("gate should succeed");
+    sim.rzz(PI / 4.0, q0, q1).expect("rotation should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |00⟩: 0.5658-0.2343i
+            |01⟩: 0.1353-0.3266i
+            |10⟩: 0.2343+0.5658i
+            |11⟩: 0.1353-0.3266i
+        "]],
+    );
+}
+
+#[test]
+fn ccx_and_swap() {
+    let mut sim = SparseSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    let q2 = sim.qubit_allocate();
+    sim.x(q0).expect("gate should succeed");
+    sim.x(q1).expect("gate should succeed");
+    sim.ccx(q0, q1, q2).expect("gate should succeed");
+    sim.swap(q0, q2).expect("gate should succeed");
+    sim.x(q0).expect("gate should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |110⟩: 1.0000+0.0000i
+        "]],
+    );
+}
+
+#[test]
+fn cnot_between_hadamards_is_reversed() {
+    let mut sim = SparseSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.x(q1).expect("gate should succeed");
+    sim.h(q0).expect("gate should succeed");
+    sim.h(q1).expect("gate should succeed");
+    sim.cx(q0, q1).expect("gate should succeed");
+    sim.h(q0).expect("gate should succeed");
+    sim.h(q1).expect("gate should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |11⟩: 1.0000+0.0000i
+        "]],
+    );
+}
+
+#[test]
+fn parity_of_many_qubits_stays_sparse() {
+    let mut sim = SparseSim::new();
+    let qubits: Vec<_> = (0..1000).map(|_| sim.qubit_allocate()).collect();
+    let aux = sim.qubit_allocate();
+    for &q in &qubits {
+        sim.h(q).expect("gate should succeed");
+    }
+    sim.h(aux).expect("gate should succeed");
+    for &q in &qubits {
+        sim.cx(aux, q).expect("gate should succeed");
+    }
+    sim.h(aux).expect("gate should succeed");
+    assert_eq!(sim.mresetz(aux), Ok(false));
+    for &q in &qubits {
+        sim.h(q).expect("gate should succeed");
+        assert!(sim.qubit_is_zero(q));
+    }
+}
+
+#[test]
+fn state_is_kept_past_word_size() {
+    let mut sim = SparseSim::new();
+    let qubits: Vec<_> = (0..66).map(|_| sim.qubit_allocate()).collect();
+    sim.h(qubits[0]).expect("gate should succeed");
+    sim.cx(qubits[0], qubits[63]).expect("gate should succeed");
+    sim.qubit_allocate();
+    sim.swap(qubits[63], qubits[65])
+        .expect("gate should succeed");
+    let (state, qubit_count) = sim
+        .capture_quantum_state()
+        .expect("state should be captured");
+    assert_eq!(qubit_count, 67);
+    let ids: Vec<_> = state.iter().map(|(id, _)| id.to_string()).collect();
+    assert_eq!(ids, ["0", "36893488147419103233"]);
+    assert!(sim.qubit_is_zero(qubits[63]));
+    assert!(!sim.qubit_is_zero(qubits[65]));
+}
+
+#[test]
+fn measurement_collapses_state() {
+    let mut sim = SparseSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.h(q0).expect("gate should succeed");
+    sim.cx(q0, q1).expect("gate should succeed");
+    let outcome = sim.m(q0).expect("measurement should succeed");
+    assert_eq!(sim.m(q1), Ok(outcome));
+    assert_eq!(sim.mresetz(q0), Ok(outcome));
+    sim.reset(q1).expect("reset should succeed");
+    assert!(sim.qubit_is_zero(q0));
+    assert!(sim.qubit_is_zero(q1));
+}
+
+#[test]
+fn released_qubits_are_reused() {
+    let mut sim = SparseSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.qubit_release(q0).expect("release should succeed");
+    assert_eq!(sim.qubit_allocate(), q0);
+    assert_eq!(sim.qubit_allocate(), q1 + 1);
+}
+
+#[test]
+fn simulators_on_separate_threads_are_independent() {
+    let measure_all = || {
+        let mut sim = SparseSim::new();
+        sim.set_seed(7);
+        (0..64)
+            .map(|_| {
+                let q = sim.qubit_allocate();
+                sim.h(q).expect("gate should succeed");
+                let outcome = sim.mresetz(q).expect("measurement should succeed");
+                sim.qubit_release(q).expect("release should succeed");
+                outcome
+            })
+            .collect::<Vec<_>>()
+    };
+    let handles: Vec<_> = (0..4).map(|_| thread::spawn(measure_all)).collect();
+    let expected = measure_all();
+    for handle in handles {
+        assert_eq!(handle.join().expect("thread should succeed"), expected);
+    }
+}
@@ -0,0 +1,491 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! A stabilizer simulator based on the tableau algorithm from Aaronson and Gottesman, "Improved
+//! simulation of stabilizer circuits" (2004). Clifford operations and measurements take time
+//! polynomial in the number of qubits, so programs with hundreds of qubits can be simulated, but
+//! non-Clifford operations such as `T` or rotations by arbitrary angles are rejected.
+
+#[cfg(test)]
+mod tests;
+
+use super::{Backend, Error};
+use num_bigint::BigUint;
+use num_complex::Complex64;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::BTreeSet, f64::consts::FRAC_PI_2, mem};
+
+/// The largest number of independent superpositions in a state that can be captured. Capturing a
+/// state produces two to the power of this many amplitudes.
+const MAX_CAPTURE_RANK: usize = 16;
+
+/// A Pauli operator with a sign, stored as one row of the tableau. Each qubit has an X bit and a Z
+/// bit, where both bits together stand for Y.
+#[derive(Clone, Debug)]
+struct Row {
+    x: Vec<bool>,
+    z: Vec<bool>,
+    negative: bool,
+}
+
+impl Row {
+    fn identity(len: usize) -> Self {
+        Self {
+            x: vec![false; len],
+            z: vec![false; len],
+            negative: false,
+        }
+    }
+
+    /// Multiplies `other` into this row from the left, keeping track of the sign.
+    fn mul_assign(&mut self, other: &Row) {
+        let mut phase = 2 * i32::from(self.negative) + 2 * i32::from(other.negative);
+        for q in 0..self.x.len() {
+            phase += phase_exponent((other.x[q], other.z[q]), (self.x[q], self.z[q]));
+            self.x[q] ^= other.x[q];
+            self.z[q] ^= other.z[q];
+        }
+        self.negative = phase.rem_euclid(4) == 2;
+    }
+
+    /// Applies this operator to a computational basis state in place, returning the exponent of
+    /// `i` in the phase it picks up.
+    fn apply_to_basis(&self, basis: &mut [bool]) -> u32 {
+        let mut phase = 2 * u32::from(self.negative);
+        for ((bit, &x), &z) in basis.iter_mut().zip(&self.x).zip(&self.z) {
+            phase += u32::from(x && z) + 2 * u32::from(z && *bit);
+            *bit ^= x;
+        }
+        phase
+    }
+}
+
+/// The exponent of `i` picked up when multiplying the single-qubit Pauli with bits `(x1, z1)` by
+/// the one with bits `(x2, z2)`.
+fn phase_exponent((x1, z1): (bool, bool), (x2, z2): (bool, bool)) -> i32 {
+    let (x2, z2) = (i32::from(x2), i32::from(z2));
+    match (x1, z1) {
+        (false, false) => 0,
+        (true, true) => z2 - x2,
+        (true, false) => z2 * (2 * x2 - 1),
+        (false, true) => x2 * (1 - 2 * z2),
+    }
+}
+
+/// A backend that tracks the state as the group of Pauli operators that stabilize it, along with
+/// the destabilizers that complete the tableau.
+#[derive(Debug)]
+pub struct StabilizerSim {
+    destabilizers: Vec<Row>,
+    stabilizers: Vec<Row>,
+    free: BTreeSet<usize>,
+    rng: StdRng,
+}
+
+impl Default for StabilizerSim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StabilizerSim {
+    /// Creates a simulator with no qubits.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            destabilizers: Vec::new(),
+            stabilizers: Vec::new(),
+            free: BTreeSet::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    fn rows_mut(&mut self) -> impl Iterator<Item = &mut Row> {
+        self.destabilizers
+            .iter_mut()
+            .chain(self.stabilizers.iter_mut())
+    }
+
+    fn apply_h(&mut self, q: usize) {
+        for row in self.rows_mut() {
+            row.negative ^= row.x[q] && row.z[q];
+            mem::swap(&mut row.x[q], &mut row.z[q]);
+        }
+    }
+
+    fn apply_s(&mut self, q: usize) {
+        for row in self.rows_mut() {
+            row.negative ^= row.x[q] && row.z[q];
+            row.z[q] ^= row.x[q];
+        }
+    }
+
+    fn apply_sadj(&mut self, q: usize) {
+        for row in self.rows_mut() {
+            row.negative ^= row.x[q] && !row.z[q];
+            row.z[q] ^= row.x[q];
+        }
+    }
+
+    fn apply_x(&mut self, q: usize) {
+        for row in self.rows_mut() {
+            row.negative ^= row.z[q];
+        }
+    }
+
+    fn apply_y(&mut self, q: usize) {
+        for row in self.rows_mut() {
+            row.negative ^= row.x[q] ^ row.z[q];
+        }
+    }
+
+    fn apply_z(&mut self, q: usize) {
+        for row in self.rows_mut() {
+            row.negative ^= row.x[q];
+        }
+    }
+
+    fn apply_cx(&mut self, ctl: usize, q: usize) {
+        for row in self.rows_mut() {
+            row.negative ^= row.x[ctl] && row.z[q] && (row.x[q] == row.z[ctl]);
+            row.x[q] ^= row.x[ctl];
+            row.z[ctl] ^= row.z[q];
+        }
+    }
+
+    fn apply_rx(&mut self, turns: u8, q: usize) {
+        match turns {
+            1 => {
+                self.apply_h(q);
+                self.apply_s(q);
+                self.apply_h(q);
+            }
+            2 => self.apply_x(q),
+            3 => {
+                self.apply_h(q);
+                self.apply_sadj(q);
+                self.apply_h(q);
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_ry(&mut self, turns: u8, q: usize) {
+        match turns {
+            1 => {
+                self.apply_z(q);
+                self.apply_h(q);
+            }
+            2 => self.apply_y(q),
+            3 => {
+                self.apply_h(q);
+                self.apply_z(q);
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_rz(&mut self, turns: u8, q: usize) {
+        match turns {
+            1 => self.apply_s(q),
+            2 => self.apply_z(q),
+            3 => self.apply_sadj(q),
+            _ => {}
+        }
+    }
+
+    fn apply_rzz(&mut self, turns: u8, q0: usize, q1: usize) {
+        self.apply_cx(q0, q1);
+        self.apply_rz(turns, q1);
+        self.apply_cx(q0, q1);
+    }
+
+    fn measure(&mut self, q: usize) -> bool {
+        let Some(p) = self.stabilizers.iter().position(|row| row.x[q]) else {
+            return self.deterministic_outcome(q);
+        };
+
+        let pivot = self.stabilizers[p].clone();
+        for (i, row) in self.stabilizers.iter_mut().enumerate() {
+            if i != p && row.x[q] {
+                row.mul_assign(&pivot);
+            }
+        }
+        for row in &mut self.destabilizers {
+            if row.x[q] {
+                row.mul_assign(&pivot);
+            }
+        }
+
+        let outcome = self.rng.gen_bool(0.5);
+        let mut stabilizer = Row::identity(pivot.x.len());
+        stabilizer.z[q] = true;
+        stabilizer.negative = outcome;
+        self.destabilizers[p] = pivot;
+        self.stabilizers[p] = stabilizer;
+        outcome
+    }
+
+    /// The outcome of measuring a qubit that is not in superposition, without changing the state.
+    fn deterministic_outcome(&self, q: usize) -> bool {
+        let mut scratch = Row::identity(self.stabilizers.len());
+        for (destabilizer, stabilizer) in self.destabilizers.iter().zip(&self.stabilizers) {
+            if destabilizer.x[q] {
+                scratch.mul_assign(stabilizer);
+            }
+        }
+        scratch.negative
+    }
+}
+
+impl Backend for StabilizerSim {
+    fn ccx(&mut self, _: usize, _: usize, _: usize) -> Result<(), Error> {
+        Err(Error)
+    }
+
+    fn cx(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.apply_cx(ctl, q);
+        Ok(())
+    }
+
+    fn cy(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.apply_sadj(q);
+        self.apply_cx(ctl, q);
+        self.apply_s(q);
+        Ok(())
+    }
+
+    fn cz(&mut self, ctl: usize, q: usize) -> Result<(), Error> {
+        self.apply_h(q);
+        self.apply_cx(ctl, q);
+        self.apply_h(q);
+        Ok(())
+    }
+
+    fn h(&mut self, q: usize) -> Result<(), Error> {
+        self.apply_h(q);
+        Ok(())
+    }
+
+    fn m(&mut self, q: usize) -> Result<bool, Error> {
+        Ok(self.measure(q))
+    }
+
+    fn mresetz(&mut self, q: usize) -> Result<bool, Error> {
+        let outcome = self.measure(q);
+        if outcome {
+            self.apply_x(q);
+        }
+        Ok(outcome)
+    }
+
+    fn reset(&mut self, q: usize) -> Result<(), Error> {
+        self.mresetz(q).map(|_| ())
+    }
+
+    fn rx(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.apply_rx(quarter_turns(theta)?, q);
+        Ok(())
+    }
+
+    fn rxx(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        let turns = quarter_turns(theta)?;
+        self.apply_h(q0);
+        self.apply_h(q1);
+        self.apply_rzz(turns, q0, q1);
+        self.apply_h(q0);
+        self.apply_h(q1);
+        Ok(())
+    }
+
+    fn ry(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.apply_ry(quarter_turns(theta)?, q);
+        Ok(())
+    }
+
+    fn ryy(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        let turns = quarter_turns(theta)?;
+        self.apply_rx(3, q0);
+        self.apply_rx(3, q1);
+        self.apply_rzz(turns, q0, q1);
+        self.apply_rx(1, q0);
+        self.apply_rx(1, q1);
+        Ok(())
+    }
+
+    fn rz(&mut self, theta: f64, q: usize) -> Result<(), Error> {
+        self.apply_rz(quarter_turns(theta)?, q);
+        Ok(())
+    }
+
+    fn rzz(&mut self, theta: f64, q0: usize, q1: usize) -> Result<(), Error> {
+        self.apply_rzz(quarter_turns(theta)?, q0, q1);
+        Ok(())
+    }
+
+    fn sadj(&mut self, q: usize) -> Result<(), Error> {
+        self.apply_sadj(q);
+        Ok(())
+    }
+
+    fn s(&mut self, q: usize) -> Result<(), Error> {
+        self.apply_s(q);
+        Ok(())
+    }
+
+    fn swap(&mut self, q0: usize, q1: usize) -> Result<(), Error> {
+        for row in self.rows_mut() {
+            row.x.swap(q0, q1);
+            row.z.swap(q0, q1);
+        }
+        Ok(())
+    }
+
+    fn tadj(&mut self, _: usize) -> Result<(), Error> {
+        Err(Error)
+    }
+
+    fn t(&mut self, _: usize) -> Result<(), Error> {
+        Err(Error)
+    }
+
+    fn x(&mut self, q: usize) -> Result<(), Error> {
+        self.apply_x(q);
+        Ok(())
+    }
+
+    fn y(&mut self, q: usize) -> Result<(), Error> {
+        self.apply_y(q);
+        Ok(())
+    }
+
+    fn z(&mut self, q: usize) -> Result<(), Error> {
+        self.apply_z(q);
+        Ok(())
+    }
+
+    fn qubit_allocate(&mut self) -> usize {
+        if let Some(q) = self.free.pop_first() {
+            return q;
+        }
+
+        let q = self.stabilizers.len();
+        for row in self.rows_mut() {
+            row.x.push(false);
+            row.z.push(false);
+        }
+        let mut destabilizer = Row::identity(q + 1);
+        destabilizer.x[q] = true;
+        self.destabilizers.push(destabilizer);
+        let mut stabilizer = Row::identity(q + 1);
+        stabilizer.z[q] = true;
+        self.stabilizers.push(stabilizer);
+        q
+    }
+
+    fn qubit_release(&mut self, q: usize) -> Result<(), Error> {
+        self.free.insert(q);
+        Ok(())
+    }
+
+    fn capture_quantum_state(&mut self) -> Result<(Vec<(BigUint, Complex64)>, usize), Error> {
+        let qubit_count = self.stabilizers.len();
+        let mut rows = self.stabilizers.clone();
+
+        // Reduce the stabilizers so that the first `rank` rows have independent X parts and the
+        // remaining rows only contain Z operators.
+        let rank = reduce(&mut rows, 0, |row, q| row.x[q]);
+        if rank > MAX_CAPTURE_RANK {
+            return Err(Error);
+        }
+
+        // The Z-only stabilizers fix the value of one qubit each, which gives a basis state with a
+        // nonzero amplitude. All other basis states in the superposition come from applying the
+        // products of the remaining stabilizers to it.
+        reduce(&mut rows, rank, |row, q| row.z[q]);
+        let mut seed = vec![false; qubit_count];
+        for row in &rows[rank..] {
+            let pivot = row
+                .z
+                .iter()
+                .position(|&z| z)
+                .expect("row should have a pivot");
+            seed[pivot] = row.negative;
+        }
+
+        let amplitude = f64::from(1 << rank).sqrt().recip();
+        let mut state = Vec::with_capacity(1 << rank);
+        for subset in 0..1usize << rank {
+            let mut basis = seed.clone();
+            let mut phase = 0;
+            for (i, row) in rows[..rank].iter().enumerate() {
+                if subset & (1 << i) != 0 {
+                    phase += row.apply_to_basis(&mut basis);
+                }
+            }
+
+            let mut id = BigUint::default();
+            for (q, &bit) in basis.iter().enumerate() {
+                id.set_bit(q as u64, bit);
+            }
+            let phase = match phase % 4 {
+                0 => Complex64::new(amplitude, 0.0),
+                1 => Complex64::new(0.0, amplitude),
+                2 => Complex64::new(-amplitude, 0.0),
+                _ => Complex64::new(0.0, -amplitude),
+            };
+            state.push((id, phase));
+        }
+
+        // The tableau does not track the global phase, so choose the one that makes the amplitude
+        // of the first basis state real and positive.
+        state.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let global = state[0].1.conj() / amplitude;
+        for (_, value) in &mut state {
+            *value *= global;
+        }
+
+        Ok((state, qubit_count))
+    }
+
+    fn qubit_is_zero(&mut self, q: usize) -> bool {
+        self.stabilizers.iter().all(|row| !row.x[q]) && !self.deterministic_outcome(q)
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
+
+/// Puts the rows from `start` onwards in reduced row echelon form, where `bit` selects the column
+/// entries, and returns the index of the first row without a pivot.
+fn reduce(rows: &mut [Row], start: usize, bit: impl Fn(&Row, usize) -> bool) -> usize {
+    let mut next = start;
+    for q in 0..rows.first().map_or(0, |row| row.x.len()) {
+        let Some(p) = (next..rows.len()).find(|&i| bit(&rows[i], q)) else {
+            continue;
+        };
+        rows.swap(next, p);
+        let pivot = rows[next].clone();
+        for (i, row) in rows.iter_mut().enumerate().skip(start) {
+            if i != next && bit(row, q) {
+                row.mul_assign(&pivot);
+            }
+        }
+        next += 1;
+    }
+    next
+}
+
+/// Returns the angle as a number of quarter turns between zero and three, or an error if the angle
+/// is not a multiple of a quarter turn.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn quarter_turns(theta: f64) -> Result<u8, Error> {
+    let turns = theta / FRAC_PI_2;
+    let rounded = turns.round();
+    if (turns - rounded).abs() < 1e-10 {
+        Ok(rounded.rem_euclid(4.0) as u8)
+    } else {
+        Err(Error)
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::StabilizerSim;
+use crate::{
+    backend::{Backend, Error},
+    output::format_state_id,
+};
+use expect_test::{expect, Expect};
+use std::{
+    f64::consts::{FRAC_PI_2, PI},
+    fmt::Write,
+};
+
+fn check_state(sim: &mut StabilizerSim, expect: &Expect) {
+    let (state, qubit_count) = sim
+        .capture_quantum_state()
+        .expect("state should be captured");
+    let mut actual = String::new();
+    for (id, amplitude) in state {
+        writeln!(
+            actual,
+            "{}: {:.4}",
+            format_state_id(&id, qubit_count),
+            amplitude
+        )
+        .expect("writing to string should succeed");
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn bell_state() {
+    let mut sim = StabilizerSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.h(q0).expect("gate should succeed");
+    sim.cx(q0, q1).expect("gate should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |00⟩: 0.7071+0.0000i
+            |11⟩: 0.7071+0.0000i
+        "]],
+    );
+}
+
+#[test]
+fn phases_are_tracked() {
+    let mut sim = StabilizerSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.h(q0).expect("gate should succeed");
+    sim.h(q1).expect("gate should succeed");
+    sim.s(q0).expect("gate should succeed");
+    sim.cz(q0, q1).expect("gate should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |00⟩: 0.5000+0.0000i
+            |01⟩: 0.0000+0.5000i
+            |10⟩: 0.5000+0.0000i
+            |11⟩: 0.0000-0.5000i
+        "]],
+    );
+}
+
+#[test]
+fn cy_and_swap() {
+    let mut sim = StabilizerSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    let q2 = sim.qubit_allocate();
+    sim.x(q0).expect("gate should succeed");
+    sim.cy(q0, q1).expect("gate should succeed");
+    sim.swap(q1, q2).expect("gate should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |101⟩: 1.0000+0.0000i
+        "]],
+    );
+}
+
+#[test]
+fn quarter_turn_rotations() {
+    let mut sim = StabilizerSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.rx(FRAC_PI_2, q0).expect("rotation should succeed");
+    sim.ry(-3.0 * FRAC_PI_2, q1)
+        .expect("rotation should succeed");
+    sim.rzz(PI, q0, q1).expect("rotation should succeed");
+    check_state(
+        &mut sim,
+        &expect![[r"
+            |00⟩: 0.5000+0.0000i
+            |01⟩: 0.0000+0.5000i
+            |10⟩: -0.5000+0.0000i
+            |11⟩: 0.0000-0.5000i
+        "]],
+    );
+}
+
+#[test]
+fn non_clifford_operations_are_unsupported() {
+    let mut sim = StabilizerSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    let q2 = sim.qubit_allocate();
+    assert_eq!(sim.t(q0), Err(Error));
+    assert_eq!(sim.tadj(q0), Err(Error));
+    assert_eq!(sim.rx(0.3, q0), Err(Error));
+    assert_eq!(sim.ryy(PI / 3.0, q0, q1), Err(Error));
+    assert_eq!(sim.ccx(q0, q1, q2), Err(Error));
+}
+
+#[test]
+fn measurement_collapses_state() {
+    let mut sim = StabilizerSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.h(q0).expect("gate should succeed");
+    sim.cx(q0, q1).expect("gate should succeed");
+    let outcome = sim.m(q0).expect("measurement should succeed");
+    assert_eq!(sim.m(q0), Ok(outcome));
+    assert_eq!(sim.m(q1), Ok(outcome));
+    assert_eq!(sim.mresetz(q1), Ok(outcome));
+    assert!(sim.qubit_is_zero(q1));
+}
+
+#[test]
+fn ghz_state_on_many_qubits() {
+    let mut sim = StabilizerSim::new();
+    let qubits: Vec<_> = (0..500).map(|_| sim.qubit_allocate()).collect();
+    sim.h(qubits[0]).expect("gate should succeed");
+    for pair in qubits.windows(2) {
+        sim.cx(pair[0], pair[1]).expect("gate should succeed");
+    }
+    let outcome = sim.m(qubits[0]).expect("measurement should succeed");
+    for &q in &qubits {
+        assert_eq!(sim.mresetz(q), Ok(outcome));
+    }
+    assert!(qubits.iter().all(|&q| sim.qubit_is_zero(q)));
+}
+
+#[test]
+fn released_qubits_are_reused() {
+    let mut sim = StabilizerSim::new();
+    let q0 = sim.qubit_allocate();
+    let q1 = sim.qubit_allocate();
+    sim.qubit_release(q0).expect("release should succeed");
+    assert_eq!(sim.qubit_allocate(), q0);
+    assert_eq!(sim.qubit_allocate(), q1 + 1);
+}
+
+#[test]
+fn large_superposition_cannot_be_captured() {
+    let mut sim = StabilizerSim::new();
+    for _ in 0..20 {
+        let q = sim.qubit_allocate();
+        sim.h(q).expect("gate should succeed");
+    }
+    assert_eq!(sim.capture_quantum_state(), Err(Error));
+}
+
+#[test]
+fn seeded_measurements_are_reproducible() {
+    let measure_all = || {
+        let mut sim = StabilizerSim::new();
+        sim.set_seed(42);
+        (0..64)
+            .map(|_| {
+                let q = sim.qubit_allocate();
+                sim.h(q).expect("gate should succeed");
+                sim.mresetz(q).expect("measurement should succeed")
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(measure_all(), measure_all());
+}
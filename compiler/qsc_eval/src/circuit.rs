@@ -0,0 +1,172 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! A trace of the quantum operations that evaluations apply, in the order they apply them. Each
+//! operation is recorded as the intrinsic that the evaluation dispatched, so the trace is the
+//! circuit that a program actually ran, after its classical control flow has been resolved.
+
+#[cfg(test)]
+mod tests;
+
+use crate::val::Value;
+use qsc_data_structures::span::Span;
+
+/// A gate applied to qubits by an intrinsic operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gate {
+    Ccx,
+    Cx,
+    Cy,
+    Cz,
+    H,
+    Rx(f64),
+    Rxx(f64),
+    Ry(f64),
+    Ryy(f64),
+    Rz(f64),
+    Rzz(f64),
+    S,
+    SAdj,
+    Swap,
+    T,
+    TAdj,
+    X,
+    Y,
+    Z,
+}
+
+impl Gate {
+    /// The number of qubits at the start of the gate's qubits that control it.
+    #[must_use]
+    pub fn controls(self) -> usize {
+        match self {
+            Gate::Ccx => 2,
+            Gate::Cx | Gate::Cy | Gate::Cz => 1,
+            _ => 0,
+        }
+    }
+
+    /// The rotation angle of the gate, or `None` if it is not a rotation.
+    #[must_use]
+    pub fn angle(self) -> Option<f64> {
+        match self {
+            Gate::Rx(theta)
+            | Gate::Rxx(theta)
+            | Gate::Ry(theta)
+            | Gate::Ryy(theta)
+            | Gate::Rz(theta)
+            | Gate::Rzz(theta) => Some(theta),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperationKind {
+    /// A qubit was allocated.
+    Allocate,
+    /// A qubit was released.
+    Release,
+    Gate(Gate),
+    /// A qubit was measured in the Z basis, with the result where `true` is `One`.
+    Measure(bool),
+    /// A qubit was measured in the Z basis and then reset to |0⟩, with the result where `true` is
+    /// `One`.
+    MResetZ(bool),
+    /// A qubit was reset to |0⟩.
+    Reset,
+}
+
+/// A quantum operation applied by an evaluation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    /// The qubits that the operation acts on, starting with the controls of a controlled gate.
+    pub qubits: Vec<usize>,
+    /// The span of the innermost call made by the code that evaluation started in that led to the
+    /// operation, such as a call to `H` in the program rather than the intrinsic in the standard
+    /// library that `H` calls. It is `None` if there is no such call.
+    pub span: Option<Span>,
+}
+
+/// Records the quantum operations applied by evaluations it is given to. A tracer can be given to
+/// several evaluations in turn, and accumulates the operations of all of them.
+#[derive(Debug, Default)]
+pub struct Tracer {
+    operations: Vec<Operation>,
+}
+
+impl Tracer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The operations that were applied, in the order that they were applied.
+    #[must_use]
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    #[must_use]
+    pub fn into_operations(self) -> Vec<Operation> {
+        self.operations
+    }
+
+    /// Records a call of the intrinsic with the argument that returned the value, if the intrinsic
+    /// is a quantum operation.
+    pub(crate) fn record(&mut self, name: &str, arg: &Value, val: &Value, span: Option<Span>) {
+        let gate = |gate| Some(OperationKind::Gate(gate));
+        let kind = match name {
+            "__quantum__rt__qubit_allocate" => Some(OperationKind::Allocate),
+            "__quantum__rt__qubit_release" => Some(OperationKind::Release),
+            "__quantum__qis__ccx__body" => gate(Gate::Ccx),
+            "__quantum__qis__cx__body" => gate(Gate::Cx),
+            "__quantum__qis__cy__body" => gate(Gate::Cy),
+            "__quantum__qis__cz__body" => gate(Gate::Cz),
+            "__quantum__qis__h__body" => gate(Gate::H),
+            "__quantum__qis__rx__body" => gate(Gate::Rx(angle(arg))),
+            "__quantum__qis__rxx__body" => gate(Gate::Rxx(angle(arg))),
+            "__quantum__qis__ry__body" => gate(Gate::Ry(angle(arg))),
+            "__quantum__qis__ryy__body" => gate(Gate::Ryy(angle(arg))),
+            "__quantum__qis__rz__body" => gate(Gate::Rz(angle(arg))),
+            "__quantum__qis__rzz__body" => gate(Gate::Rzz(angle(arg))),
+            "__quantum__qis__s__body" => gate(Gate::S),
+            "__quantum__qis__s__adj" => gate(Gate::SAdj),
+            "__quantum__qis__swap__body" => gate(Gate::Swap),
+            "__quantum__qis__t__body" => gate(Gate::T),
+            "__quantum__qis__t__adj" => gate(Gate::TAdj),
+            "__quantum__qis__x__body" => gate(Gate::X),
+            "__quantum__qis__y__body" => gate(Gate::Y),
+            "__quantum__qis__z__body" => gate(Gate::Z),
+            "__quantum__qis__m__body" => Some(OperationKind::Measure(val.clone().unwrap_result())),
+            "__quantum__qis__mresetz__body" => {
+                Some(OperationKind::MResetZ(val.clone().unwrap_result()))
+            }
+            "__quantum__qis__reset__body" => Some(OperationKind::Reset),
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            let qubits = match kind {
+                OperationKind::Allocate => vec![val.clone().unwrap_qubit().0],
+                _ => qubits(arg),
+            };
+            self.operations.push(Operation { kind, qubits, span });
+        }
+    }
+}
+
+/// The angle of a rotation, which is the first item of its argument.
+fn angle(arg: &Value) -> f64 {
+    arg.clone().unwrap_tuple()[0].clone().unwrap_double()
+}
+
+/// The qubits in the argument of an intrinsic, in order.
+fn qubits(arg: &Value) -> Vec<usize> {
+    match arg {
+        Value::Qubit(q) => vec![q.0],
+        Value::Tuple(items) => items.iter().flat_map(qubits).collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::Tracer;
+use crate::{
+    backend::{Backend, SparseSim},
+    eval_expr,
+    output::GenericReceiver,
+    tests::get_global,
+    Env, EvalContext, Recorders,
+};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_passes::{run_core_passes, run_default_passes};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fmt::Write;
+
+fn check_trace(file: &str, expr: &str, expect: &Expect) {
+    let mut core = compile::core();
+    run_core_passes(&mut core);
+    let mut store = PackageStore::new(core);
+    let mut std = compile::std(&store);
+    assert!(std.errors.is_empty());
+    assert!(run_default_passes(store.core(), &mut std).is_empty());
+
+    let std_id = store.insert(std);
+    let sources = SourceMap::new([("test".into(), file.into())], Some(expr.into()));
+    let mut unit = compile(&store, &[std_id], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    assert!(run_default_passes(store.core(), &mut unit).is_empty());
+    let id = store.insert(unit);
+
+    let entry = store
+        .get(id)
+        .and_then(|unit| unit.package.entry.as_ref())
+        .expect("package should have entry");
+    let mut out = Vec::new();
+    let mut tracer = Tracer::new();
+    let mut sim = SparseSim::new();
+    sim.set_seed(0);
+    // The trace is checked whether or not evaluation succeeds.
+    let _ = eval_expr(
+        entry,
+        &|id| get_global(&store, id),
+        id,
+        &mut Env::default(),
+        &mut GenericReceiver::new(&mut out),
+        EvalContext::new(&mut sim, &mut StdRng::seed_from_u64(0)).with_recorders(Recorders {
+            tracer: Some(&mut tracer),
+            ..Recorders::default()
+        }),
+    );
+
+    let mut actual = String::new();
+    for operation in tracer.operations() {
+        let source = operation.span.map_or("", |span| &file[span]);
+        writeln!(
+            actual,
+            "{:?} {:?} `{source}`",
+            operation.kind, operation.qubits
+        )
+        .expect("writing to string should succeed");
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn gates_are_traced_at_their_call_sites() {
+    check_trace(
+        indoc! {"
+            namespace Test {
+                operation Main() : Result[] {
+                    use qs = Qubit[2];
+                    H(qs[0]);
+                    CNOT(qs[0], qs[1]);
+                    Rx(0.5, qs[1]);
+                    Adjoint T(qs[0]);
+                    let results = [M(qs[0]), M(qs[1])];
+                    ResetAll(qs);
+                    results
+                }
+            }
+        "},
+        "Test.Main()",
+        &expect![[r"
+            Allocate [0] `qs`
+            Allocate [1] `qs`
+            Gate(H) [0] `H`
+            Gate(Cx) [0, 1] `CNOT`
+            Gate(Rx(0.5)) [1] `Rx`
+            Gate(TAdj) [0] `Adjoint T`
+            Measure(false) [0] `M`
+            Measure(false) [1] `M`
+            Reset [0] `ResetAll`
+            Reset [1] `ResetAll`
+            Release [0] `qs`
+            Release [1] `qs`
+        "]],
+    );
+}
+
+#[test]
+fn controlled_gates_list_controls_first() {
+    check_trace(
+        indoc! {"
+            namespace Test {
+                operation Main() : Unit {
+                    use (c, q) = (Qubit(), Qubit());
+                    Controlled X([c], q);
+                    CCNOT(q, c, q);
+                }
+            }
+        "},
+        "Test.Main()",
+        &expect![[r"
+            Allocate [0] `Qubit()`
+            Allocate [1] `Qubit()`
+            Gate(Cx) [0, 1] `Controlled X`
+        "]],
+    );
+}
+
+#[test]
+fn operations_in_loops_are_each_traced() {
+    check_trace(
+        indoc! {"
+            namespace Test {
+                operation Main() : Unit {
+                    use q = Qubit();
+                    for i in 1..3 {
+                        Rz(Microsoft.Quantum.Convert.IntAsDouble(i), q);
+                    }
+                    Reset(q);
+                }
+            }
+        "},
+        "Test.Main()",
+        &expect![[r"
+            Allocate [0] `q`
+            Gate(Rz(1.0)) [0] `Rz`
+            Gate(Rz(2.0)) [0] `Rz`
+            Gate(Rz(3.0)) [0] `Rz`
+            Reset [0] `Reset`
+            Release [0] `q`
+        "]],
+    );
+}
+
+#[test]
+fn trace_stops_at_error() {
+    check_trace(
+        indoc! {"
+            namespace Test {
+                operation Main() : Unit {
+                    use q = Qubit();
+                    X(q);
+                    fail \"stop\";
+                    H(q);
+                }
+            }
+        "},
+        "Test.Main()",
+        &expect![[r"
+            Allocate [0] `q`
+            Gate(X) [0] `X`
+        "]],
+    );
+}
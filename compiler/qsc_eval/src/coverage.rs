@@ -0,0 +1,55 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Source coverage of evaluations. Coverage is recorded by span, so code that passes generate from
+//! the original source, like the adjoint of an operation or the loop that a `for` loop is lowered
+//! into, counts toward the source it was generated from.
+
+#[cfg(test)]
+mod tests;
+
+use qsc_data_structures::span::Span;
+use qsc_hir::hir::PackageId;
+use std::collections::HashMap;
+
+/// Counts how many times each statement and expression is evaluated, by the package it belongs to
+/// and its span. A coverage can be given to several evaluations in turn, and accumulates the
+/// counts of all of them.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    hits: HashMap<(PackageId, Span), u64>,
+    last: Option<(PackageId, Span)>,
+}
+
+impl Coverage {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of times that statements or expressions with the span in the package were
+    /// evaluated.
+    #[must_use]
+    pub fn hits(&self, package: PackageId, span: Span) -> u64 {
+        self.hits.get(&(package, span)).copied().unwrap_or_default()
+    }
+
+    /// The spans that were evaluated in the package, with the number of times each was evaluated,
+    /// in no particular order.
+    pub fn spans(&self, package: PackageId) -> impl Iterator<Item = (Span, u64)> + '_ {
+        self.hits
+            .iter()
+            .filter(move |((id, _), _)| *id == package)
+            .map(|(&(_, span), &hits)| (span, hits))
+    }
+
+    /// Counts an evaluation of the span. Empty spans belong to code that passes generate without
+    /// any source of its own, so they are not counted. A statement and the expression in it, or
+    /// the nodes that a pass lowers a construct into, often share a span and are evaluated one
+    /// right after the other, so that is counted as a single evaluation.
+    pub(crate) fn record(&mut self, package: PackageId, span: Span) {
+        if span.lo < span.hi && self.last.replace((package, span)) != Some((package, span)) {
+            *self.hits.entry((package, span)).or_default() += 1;
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::Coverage;
+use crate::{
+    backend::SparseSim, eval_expr, output::GenericReceiver, tests::get_global, Env, EvalContext,
+    Recorders,
+};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_passes::{run_core_passes, run_default_passes};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fmt::Write;
+
+/// Checks each line of the file with the most times that a statement or expression starting on
+/// that line was evaluated, or `-` if none was.
+fn check_line_hits(file: &str, expr: &str, expect: &Expect) {
+    let mut core = compile::core();
+    run_core_passes(&mut core);
+    let mut store = PackageStore::new(core);
+    let mut std = compile::std(&store);
+    assert!(std.errors.is_empty());
+    assert!(run_default_passes(store.core(), &mut std).is_empty());
+
+    let std_id = store.insert(std);
+    let sources = SourceMap::new([("test".into(), file.into())], Some(expr.into()));
+    let mut unit = compile(&store, &[std_id], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    assert!(run_default_passes(store.core(), &mut unit).is_empty());
+    let id = store.insert(unit);
+
+    let entry = store
+        .get(id)
+        .and_then(|unit| unit.package.entry.as_ref())
+        .expect("package should have entry");
+    let mut coverage = Coverage::new();
+    let mut out = Vec::new();
+    eval_expr(
+        entry,
+        &|id| get_global(&store, id),
+        id,
+        &mut Env::default(),
+        &mut GenericReceiver::new(&mut out),
+        EvalContext::new(&mut SparseSim::new(), &mut StdRng::seed_from_u64(0)).with_recorders(
+            Recorders {
+                coverage: Some(&mut coverage),
+                ..Recorders::default()
+            },
+        ),
+    )
+    .expect("evaluation should succeed");
+
+    let mut line_hits: Vec<Option<u64>> = vec![None; file.lines().count()];
+    for (span, hits) in coverage.spans(id) {
+        let lo = span.lo as usize;
+        if lo >= file.len() {
+            // The span is in the entry expression, which follows the file.
+            continue;
+        }
+        let line = &mut line_hits[file[..lo].matches('\n').count()];
+        *line = Some(line.unwrap_or_default().max(hits));
+    }
+
+    let mut actual = String::new();
+    for (line, hits) in file.lines().zip(line_hits) {
+        let hits = hits.map_or_else(|| "-".to_string(), |hits| hits.to_string());
+        writeln!(actual, "{hits:<2} | {line}").expect("writing to string should succeed");
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn generated_code_counts_toward_its_source() {
+    check_line_hits(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Intrinsic;
+                operation Rotate(q : Qubit, n : Int) : Unit is Adj {
+                    for i in 1..n {
+                        T(q);
+                    }
+                    H(q);
+                }
+                operation Main() : Unit {
+                    use q = Qubit();
+                    within {
+                        X(q);
+                    } apply {
+                        Adjoint Rotate(q, 2);
+                    }
+                    if false {
+                        Z(q);
+                    }
+                    Reset(q);
+                }
+            }
+        "},
+        "Test.Main()",
+        &expect![[r"
+            -  | namespace Test {
+            -  |     open Microsoft.Quantum.Intrinsic;
+            -  |     operation Rotate(q : Qubit, n : Int) : Unit is Adj {
+            1  |         for i in 1..n {
+            2  |             T(q);
+            -  |         }
+            1  |         H(q);
+            -  |     }
+            -  |     operation Main() : Unit {
+            2  |         use q = Qubit();
+            1  |         within {
+            2  |             X(q);
+            -  |         } apply {
+            1  |             Adjoint Rotate(q, 2);
+            -  |         }
+            1  |         if false {
+            -  |             Z(q);
+            -  |         }
+            1  |         Reset(q);
+            -  |     }
+            -  | }
+        "]],
+    );
+}
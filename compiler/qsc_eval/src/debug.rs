@@ -1,10 +1,22 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+#[cfg(test)]
+mod tests;
+
 use qsc_data_structures::span::Span;
 
-use crate::{val::FunctorApp, GlobalId};
-use qsc_hir::hir::PackageId;
+use crate::{
+    backend::{self, Backend},
+    output::Receiver,
+    val::{FunctorApp, Value},
+    Env, Error, EvalContext, GlobalId, GlobalLookup, State,
+};
+use num_bigint::BigUint;
+use num_complex::Complex64;
+use qsc_hir::hir::{Expr, PackageId, Stmt};
+use rand::rngs::StdRng;
+use std::sync::Arc;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Frame {
@@ -25,6 +37,11 @@ impl CallStack {
         self.frames.is_empty()
     }
 
+    #[must_use]
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
     #[must_use]
     pub fn into_frames(self) -> Vec<Frame> {
         self.frames
@@ -38,3 +55,204 @@ impl CallStack {
         self.frames.pop()
     }
 }
+
+/// A span of source code in a package.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Location {
+    pub package: PackageId,
+    pub span: Span,
+}
+
+/// How far to run before pausing again.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Step {
+    /// Run until a breakpoint is hit.
+    Continue,
+    /// Pause at the next statement, including statements inside a callable called by the current
+    /// statement.
+    In,
+    /// Pause at the next statement in the current callable or its callers.
+    Over,
+    /// Pause at the next statement after the current callable returns.
+    Out,
+}
+
+/// Why the debugger stopped running.
+#[derive(Debug, PartialEq)]
+pub enum StepResult {
+    /// Evaluation paused at a statement inside a breakpoint.
+    BreakpointHit(Location),
+    /// Evaluation paused at a statement because the step finished.
+    StepComplete(Location),
+    /// Evaluation finished with the value.
+    Return(Value),
+}
+
+/// A variable bound in a scope.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Local {
+    pub name: Arc<str>,
+    pub value: Value,
+}
+
+/// A frame on the stack at a pause point.
+#[derive(Debug, PartialEq)]
+pub struct StackFrame {
+    /// The callable running in this frame, or `None` for the expression that evaluation started
+    /// with.
+    pub callable: Option<(GlobalId, FunctorApp)>,
+    /// The paused statement for the innermost frame, or the call into the next frame for the
+    /// others.
+    pub location: Location,
+    /// The variables bound in each scope of the frame, from the outermost scope to the innermost.
+    pub scopes: Vec<Vec<Local>>,
+}
+
+/// Evaluates an expression one piece at a time, pausing at breakpoints and after steps so that the
+/// call stack and variables can be inspected. Breakpoints and steps apply to statements: evaluation
+/// pauses just before a statement runs.
+pub struct Debugger<'a, G, B: ?Sized> {
+    state: State<'a, G, B>,
+    breakpoints: Vec<Location>,
+    paused: Option<&'a Stmt>,
+}
+
+impl<'a, G: GlobalLookup<'a>, B: Backend + ?Sized> Debugger<'a, G, B> {
+    /// Creates a debugger for the expression that has not started running yet.
+    pub fn new(
+        expr: &'a Expr,
+        globals: &'a G,
+        package: PackageId,
+        env: &'a mut Env,
+        sim: &'a mut B,
+        rng: &'a mut StdRng,
+        out: &'a mut dyn Receiver,
+    ) -> Self {
+        let mut state = State::new(globals, package, env, out, EvalContext::new(sim, rng));
+        state.push_expr(expr);
+        Self {
+            state,
+            breakpoints: Vec::new(),
+            paused: None,
+        }
+    }
+
+    /// Replaces the breakpoints. Evaluation pauses at any statement that starts inside one of the
+    /// breakpoint spans.
+    pub fn set_breakpoints(&mut self, breakpoints: Vec<Location>) {
+        self.breakpoints = breakpoints;
+    }
+
+    /// Runs until the step finishes, a breakpoint is hit or evaluation returns. Resuming after
+    /// evaluation has returned or failed is not allowed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered during execution.
+    ///
+    /// # Panics
+    ///
+    /// Panics if evaluation has already returned.
+    pub fn resume(&mut self, step: Step) -> Result<StepResult, (Error, CallStack)> {
+        let start_depth = self.state.call_stack.frames().len();
+        let breakpoints = &self.breakpoints;
+        let mut hit_breakpoint = false;
+        let paused = self.state.run(self.paused.is_some(), |state, stmt| {
+            let depth = state.call_stack.frames().len();
+            hit_breakpoint = breakpoints.iter().any(|breakpoint| {
+                breakpoint.package == state.package
+                    && breakpoint.span.lo <= stmt.span.lo
+                    && stmt.span.lo < breakpoint.span.hi
+            });
+            hit_breakpoint
+                || match step {
+                    Step::Continue => false,
+                    Step::In => true,
+                    Step::Over => depth <= start_depth,
+                    Step::Out => depth < start_depth,
+                }
+        });
+
+        self.paused = paused?;
+        Ok(match self.location() {
+            Some(location) if hit_breakpoint => StepResult::BreakpointHit(location),
+            Some(location) => StepResult::StepComplete(location),
+            None => StepResult::Return(self.state.pop_val()),
+        })
+    }
+
+    /// The statement that evaluation is paused at.
+    #[must_use]
+    pub fn location(&self) -> Option<Location> {
+        self.paused.map(|stmt| Location {
+            package: self.state.package,
+            span: stmt.span,
+        })
+    }
+
+    #[must_use]
+    pub fn call_stack(&self) -> &CallStack {
+        &self.state.call_stack
+    }
+
+    /// The frames on the stack with their variables, starting from the innermost frame. Returns no
+    /// frames unless evaluation is paused.
+    #[must_use]
+    pub fn stack_frames(&self) -> Vec<StackFrame> {
+        let Some(location) = self.location() else {
+            return Vec::new();
+        };
+
+        let calls = self.state.call_stack.frames();
+        let mut stack_frames = Vec::with_capacity(calls.len() + 1);
+        let mut location = location;
+        for depth in (0..=calls.len()).rev() {
+            let callable = depth
+                .checked_sub(1)
+                .map(|index| (calls[index].id, calls[index].functor));
+            stack_frames.push(StackFrame {
+                callable,
+                location,
+                scopes: self.scopes(depth),
+            });
+            if let Some(call) = depth.checked_sub(1).map(|index| calls[index]) {
+                location = Location {
+                    package: call.caller,
+                    span: call.span.unwrap_or_default(),
+                };
+            }
+        }
+        stack_frames
+    }
+
+    /// Captures the quantum state of the simulator at the pause point.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the simulator cannot capture its state.
+    pub fn capture_quantum_state(
+        &mut self,
+    ) -> Result<(Vec<(BigUint, Complex64)>, usize), backend::Error> {
+        self.state.sim.capture_quantum_state()
+    }
+
+    fn scopes(&self, depth: usize) -> Vec<Vec<Local>> {
+        self.state
+            .env
+            .scopes
+            .iter()
+            .filter(|scope| scope.depth == depth)
+            .map(|scope| {
+                let mut bindings: Vec<_> = scope.bindings.iter().collect();
+                bindings.sort_by_key(|&(id, _)| *id);
+                bindings
+                    .into_iter()
+                    .map(|(_, variable)| Local {
+                        name: Arc::clone(&variable.name),
+                        value: variable.value.clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{Debugger, Location, Step, StepResult};
+use crate::{backend::SparseSim, output::GenericReceiver, tests::get_global, Env};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_data_structures::span::Span;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_hir::hir::PackageId;
+use qsc_passes::{run_core_passes, run_default_passes};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fmt::Write;
+
+const SOURCE: &str = indoc! {"
+    namespace Test {
+        function Add(a : Int, b : Int) : Int {
+            let sum = a + b;
+            sum
+        }
+
+        function Main() : Int {
+            mutable x = 1;
+            let y = Add(x, 2);
+            set x = y * 2;
+            x
+        }
+    }
+"};
+
+fn compile_source() -> (PackageStore, PackageId) {
+    let mut core = compile::core();
+    run_core_passes(&mut core);
+    let mut store = PackageStore::new(core);
+    let sources = SourceMap::new([("test".into(), SOURCE.into())], Some("Test.Main()".into()));
+    let mut unit = compile(&store, &[], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    let pass_errors = run_default_passes(store.core(), &mut unit);
+    assert!(pass_errors.is_empty(), "{pass_errors:?}");
+    let id = store.insert(unit);
+    (store, id)
+}
+
+/// Runs the program with the breakpoints, taking the steps in order, and describes each pause.
+fn check_steps(breakpoints: &[&str], steps: &[Step], expect: &Expect) {
+    let (store, id) = compile_source();
+    let entry = store
+        .get(id)
+        .and_then(|unit| unit.package.entry.as_ref())
+        .expect("package should have entry");
+    let globals = |global| get_global(&store, global);
+    let mut env = Env::default();
+    let mut sim = SparseSim::new();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut out = Vec::new();
+    let mut receiver = GenericReceiver::new(&mut out);
+    let mut debugger = Debugger::new(
+        entry,
+        &globals,
+        id,
+        &mut env,
+        &mut sim,
+        &mut rng,
+        &mut receiver,
+    );
+    debugger.set_breakpoints(
+        breakpoints
+            .iter()
+            .map(|text| {
+                let lo = SOURCE.find(text).expect("breakpoint should be in source");
+                Location {
+                    package: id,
+                    span: Span {
+                        lo: lo.try_into().expect("offset should fit"),
+                        hi: (lo + text.len()).try_into().expect("offset should fit"),
+                    },
+                }
+            })
+            .collect(),
+    );
+
+    let mut actual = String::new();
+    for &step in steps {
+        let result = debugger.resume(step).expect("evaluation should succeed");
+        let location = match &result {
+            StepResult::BreakpointHit(location) => {
+                write!(actual, "breakpoint ").expect("writing should succeed");
+                location
+            }
+            StepResult::StepComplete(location) => location,
+            StepResult::Return(value) => {
+                writeln!(actual, "return {value}").expect("writing should succeed");
+                break;
+            }
+        };
+        let span = location.span;
+        write!(
+            actual,
+            "at `{}`:",
+            &SOURCE[span.lo as usize..span.hi as usize]
+        )
+        .expect("writing should succeed");
+        for frame in debugger.stack_frames() {
+            let name = frame
+                .callable
+                .map_or("<entry>".to_string(), |(id, _)| id.item.to_string());
+            write!(actual, " {name}(").expect("writing should succeed");
+            for local in frame.scopes.iter().flatten() {
+                write!(actual, " {}={}", local.name, local.value).expect("writing should succeed");
+            }
+            write!(actual, " )").expect("writing should succeed");
+        }
+        writeln!(actual).expect("writing should succeed");
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn continue_without_breakpoints_returns() {
+    check_steps(
+        &[],
+        &[Step::Continue],
+        &expect![[r"
+            return 6
+        "]],
+    );
+}
+
+#[test]
+fn step_in_visits_every_statement() {
+    check_steps(
+        &[],
+        &[Step::In; 10],
+        &expect![[r"
+        at `mutable x = 1;`: 2( ) <entry>( )
+        at `let y = Add(x, 2);`: 2( x=1 ) <entry>( )
+        at `let sum = a + b;`: 1( a=1 b=2 ) 2( x=1 ) <entry>( )
+        at `sum`: 1( a=1 b=2 sum=3 ) 2( x=1 ) <entry>( )
+        at `set x = y * 2;`: 2( x=1 y=3 ) <entry>( )
+        at `x`: 2( x=6 y=3 ) <entry>( )
+        return 6
+    "]],
+    );
+}
+
+#[test]
+fn step_over_skips_calls() {
+    check_steps(
+        &[],
+        &[Step::In, Step::Over, Step::Over, Step::Over, Step::Over],
+        &expect![[r"
+        at `mutable x = 1;`: 2( ) <entry>( )
+        at `let y = Add(x, 2);`: 2( x=1 ) <entry>( )
+        at `set x = y * 2;`: 2( x=1 y=3 ) <entry>( )
+        at `x`: 2( x=6 y=3 ) <entry>( )
+        return 6
+    "]],
+    );
+}
+
+#[test]
+fn step_out_returns_to_caller() {
+    check_steps(
+        &["let sum"],
+        &[Step::Continue, Step::Out, Step::Continue],
+        &expect![[r"
+        breakpoint at `let sum = a + b;`: 1( a=1 b=2 ) 2( x=1 ) <entry>( )
+        at `set x = y * 2;`: 2( x=1 y=3 ) <entry>( )
+        return 6
+    "]],
+    );
+}
+
+#[test]
+fn breakpoints_pause_on_each_hit() {
+    check_steps(
+        &["let sum", "set x"],
+        &[Step::Continue, Step::Continue, Step::Continue],
+        &expect![[r"
+            breakpoint at `let sum = a + b;`: 1( a=1 b=2 ) 2( x=1 ) <entry>( )
+            breakpoint at `set x = y * 2;`: 2( x=1 y=3 ) <entry>( )
+            return 6
+        "]],
+    );
+}
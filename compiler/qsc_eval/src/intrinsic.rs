@@ -5,34 +5,39 @@
 mod tests;
 
 use crate::{
-    output::Receiver,
+    backend::{self, Backend},
+    output::{Receiver, RegisterState},
     val::{Qubit, Value},
     Error,
 };
-use num_bigint::BigInt;
-use qir_backend::{
-    __quantum__qis__ccx__body, __quantum__qis__cx__body, __quantum__qis__cy__body,
-    __quantum__qis__cz__body, __quantum__qis__h__body, __quantum__qis__m__body,
-    __quantum__qis__mresetz__body, __quantum__qis__reset__body, __quantum__qis__rx__body,
-    __quantum__qis__rxx__body, __quantum__qis__ry__body, __quantum__qis__ryy__body,
-    __quantum__qis__rz__body, __quantum__qis__rzz__body, __quantum__qis__s__adj,
-    __quantum__qis__s__body, __quantum__qis__swap__body, __quantum__qis__t__adj,
-    __quantum__qis__t__body, __quantum__qis__x__body, __quantum__qis__y__body,
-    __quantum__qis__z__body, __quantum__rt__qubit_allocate, __quantum__rt__qubit_release,
-    capture_quantum_state, qubit_is_zero,
-    result_bool::{__quantum__rt__result_equal, __quantum__rt__result_get_one},
-};
+use num_bigint::{BigInt, BigUint};
+use num_complex::Complex64;
 use qsc_data_structures::span::Span;
-use rand::Rng;
-use std::{array, ffi::c_void};
+use qsc_hir::hir::Pauli;
+use rand::{rngs::StdRng, Rng};
+use std::{
+    array,
+    collections::{BTreeMap, HashMap, HashSet},
+};
+
+/// A register whose state differs from a separable state by less than this squared norm is treated
+/// as separable.
+const SEPARABLE_THRESHOLD: f64 = 1e-10;
+
+/// The tolerance for the probability that a qubit is in the zero state when asserting that it is.
+const ZERO_TOLERANCE: f64 = 1e-10;
 
+#[allow(clippy::too_many_lines)]
 pub(crate) fn call(
     name: &str,
     name_span: Span,
     arg: Value,
     arg_span: Span,
+    sim: &mut (impl Backend + ?Sized),
+    rng: &mut StdRng,
     out: &mut dyn Receiver,
 ) -> Result<Value, Error> {
+    let unsupported = |_| Error::UnsupportedOperation(name.to_string(), name_span);
     match name {
         "Length" => match arg.unwrap_array().len().try_into() {
             Ok(len) => Ok(Value::Int(len)),
@@ -42,17 +47,95 @@ pub(crate) fn call(
         "IntAsDouble" => Ok(Value::Double(arg.unwrap_int() as f64)),
         "IntAsBigInt" => Ok(Value::BigInt(BigInt::from(arg.unwrap_int()))),
         "DumpMachine" => {
-            let (state, qubit_count) = capture_quantum_state();
+            let (state, qubit_count) = sim.capture_quantum_state().map_err(unsupported)?;
             match out.state(state, qubit_count) {
                 Ok(_) => Ok(Value::unit()),
                 Err(_) => Err(Error::OutputFail(name_span)),
             }
         }
+        "DumpRegister" => {
+            let qubits = unwrap_qubits(arg, arg_span)?;
+            let (state, _) = sim.capture_quantum_state().map_err(unsupported)?;
+            match out.register_state(register_state(state, &qubits), qubits.len()) {
+                Ok(()) => Ok(Value::unit()),
+                Err(_) => Err(Error::OutputFail(name_span)),
+            }
+        }
+        "AssertMeasurementProbabilityImpl" => {
+            let [bases, qubits, result, prob, msg, tolerance] = unwrap_tuple(arg);
+            let bases: Vec<_> = bases
+                .unwrap_array()
+                .iter()
+                .map(|b| b.clone().unwrap_pauli())
+                .collect();
+            let qubits = unwrap_qubits(qubits, arg_span)?;
+            if bases.len() != qubits.len() {
+                return Err(Error::UserFail(
+                    "Arrays 'bases' and 'qubits' must be of the same length.".to_string(),
+                    arg_span,
+                ));
+            }
+            let (state, _) = sim.capture_quantum_state().map_err(unsupported)?;
+            let actual = measurement_probability(&state, &bases, &qubits, result.unwrap_result());
+            check_probability(
+                &msg.unwrap_string(),
+                prob.unwrap_double(),
+                actual,
+                tolerance.unwrap_double(),
+                name_span,
+            )
+        }
+        "AssertQubitIsInStateImpl" => {
+            let [qubit, expected, tolerance] = unwrap_tuple(arg);
+            let q = qubit.unwrap_qubit().0;
+            let tolerance = tolerance.unwrap_double();
+            let [zero, one] = unwrap_tuple(expected).map(|amplitude| {
+                let [re, im] = unwrap_tuple(amplitude);
+                Complex64::new(re.unwrap_double(), im.unwrap_double())
+            });
+            let (state, _) = sim.capture_quantum_state().map_err(unsupported)?;
+
+            // The state of a qubit is fixed, up to a global phase, by its measurement probabilities
+            // in the Z, X and Y bases.
+            let expected = [
+                (Pauli::Z, zero.norm_sqr()),
+                (Pauli::X, (zero + one).norm_sqr() / 2.0),
+                (Pauli::Y, (zero - Complex64::i() * one).norm_sqr() / 2.0),
+            ];
+            for (basis, prob) in expected {
+                let actual = measurement_probability(&state, &[basis], &[q], false);
+                check_probability(
+                    &format!(
+                        "qubit is not in the expected state when measured in the {basis:?} basis"
+                    ),
+                    prob,
+                    actual,
+                    tolerance,
+                    name_span,
+                )?;
+            }
+            Ok(Value::unit())
+        }
+        "AssertAllZeroImpl" => {
+            let qubits = unwrap_qubits(arg, arg_span)?;
+            let (state, _) = sim.capture_quantum_state().map_err(unsupported)?;
+            for q in qubits {
+                let actual = measurement_probability(&state, &[Pauli::Z], &[q], false);
+                check_probability(
+                    &format!("Qubit{q} is not in the |0⟩ state"),
+                    1.0,
+                    actual,
+                    ZERO_TOLERANCE,
+                    name_span,
+                )?;
+            }
+            Ok(Value::unit())
+        }
         "Message" => match out.message(&arg.unwrap_string()) {
             Ok(_) => Ok(Value::unit()),
             Err(_) => Err(Error::OutputFail(name_span)),
         },
-        "CheckZero" => Ok(Value::Bool(qubit_is_zero(arg.unwrap_qubit().0))),
+        "CheckZero" => Ok(Value::Bool(sim.qubit_is_zero(arg.unwrap_qubit().0))),
         "ArcCos" => Ok(Value::Double(arg.unwrap_double().acos())),
         "ArcSin" => Ok(Value::Double(arg.unwrap_double().asin())),
         "ArcTan" => Ok(Value::Double(arg.unwrap_double().atan())),
@@ -75,108 +158,147 @@ pub(crate) fn call(
             if lo > hi {
                 Err(Error::EmptyRange(arg_span))
             } else {
-                Ok(Value::Int(rand::thread_rng().gen_range(lo..=hi)))
+                Ok(Value::Int(rng.gen_range(lo..=hi)))
             }
         }
         #[allow(clippy::cast_possible_truncation)]
         "Truncate" => Ok(Value::Int(arg.unwrap_double() as i64)),
-        "__quantum__rt__qubit_allocate" => Ok(Value::Qubit(Qubit(__quantum__rt__qubit_allocate()))),
+        "__quantum__rt__qubit_allocate" => Ok(Value::Qubit(Qubit(sim.qubit_allocate()))),
         "__quantum__rt__qubit_release" => {
             let qubit = arg.unwrap_qubit().0;
-            if qubit_is_zero(qubit) {
-                __quantum__rt__qubit_release(qubit);
+            if sim.resets_on_release() || sim.qubit_is_zero(qubit) {
+                sim.qubit_release(qubit).map_err(unsupported)?;
                 Ok(Value::unit())
             } else {
-                Err(Error::ReleasedQubitNotZero(qubit as usize))
+                Err(Error::ReleasedQubitNotZero(qubit))
             }
         }
-        "__quantum__qis__ccx__body" => three_qubit_gate(__quantum__qis__ccx__body, arg, arg_span),
-        "__quantum__qis__cx__body" => two_qubit_gate(__quantum__qis__cx__body, arg, arg_span),
-        "__quantum__qis__cy__body" => two_qubit_gate(__quantum__qis__cy__body, arg, arg_span),
-        "__quantum__qis__cz__body" => two_qubit_gate(__quantum__qis__cz__body, arg, arg_span),
-        "__quantum__qis__rx__body" => Ok(one_qubit_rotation(__quantum__qis__rx__body, arg)),
-        "__quantum__qis__rxx__body" => two_qubit_rotation(__quantum__qis__rxx__body, arg, arg_span),
-        "__quantum__qis__ry__body" => Ok(one_qubit_rotation(__quantum__qis__ry__body, arg)),
-        "__quantum__qis__ryy__body" => two_qubit_rotation(__quantum__qis__ryy__body, arg, arg_span),
-        "__quantum__qis__rz__body" => Ok(one_qubit_rotation(__quantum__qis__rz__body, arg)),
-        "__quantum__qis__rzz__body" => two_qubit_rotation(__quantum__qis__rzz__body, arg, arg_span),
-        "__quantum__qis__h__body" => Ok(one_qubit_gate(__quantum__qis__h__body, arg)),
-        "__quantum__qis__s__body" => Ok(one_qubit_gate(__quantum__qis__s__body, arg)),
-        "__quantum__qis__s__adj" => Ok(one_qubit_gate(__quantum__qis__s__adj, arg)),
-        "__quantum__qis__t__body" => Ok(one_qubit_gate(__quantum__qis__t__body, arg)),
-        "__quantum__qis__t__adj" => Ok(one_qubit_gate(__quantum__qis__t__adj, arg)),
-        "__quantum__qis__x__body" => Ok(one_qubit_gate(__quantum__qis__x__body, arg)),
-        "__quantum__qis__y__body" => Ok(one_qubit_gate(__quantum__qis__y__body, arg)),
-        "__quantum__qis__z__body" => Ok(one_qubit_gate(__quantum__qis__z__body, arg)),
-        "__quantum__qis__swap__body" => two_qubit_gate(__quantum__qis__swap__body, arg, arg_span),
-        "__quantum__qis__reset__body" => Ok(one_qubit_gate(__quantum__qis__reset__body, arg)),
-        "__quantum__qis__m__body" => {
-            let res = __quantum__qis__m__body(arg.unwrap_qubit().0);
-            Ok(Value::Result(__quantum__rt__result_equal(
-                res,
-                __quantum__rt__result_get_one(),
-            )))
-        }
-        "__quantum__qis__mresetz__body" => {
-            let res = __quantum__qis__mresetz__body(arg.unwrap_qubit().0);
-            Ok(Value::Result(__quantum__rt__result_equal(
-                res,
-                __quantum__rt__result_get_one(),
-            )))
+        "__quantum__qis__ccx__body" => three_qubit_gate(
+            |ctl0, ctl1, q| sim.ccx(ctl0, ctl1, q),
+            arg,
+            arg_span,
+            unsupported,
+        ),
+        "__quantum__qis__cx__body" => {
+            two_qubit_gate(|ctl, q| sim.cx(ctl, q), arg, arg_span, unsupported)
         }
+        "__quantum__qis__cy__body" => {
+            two_qubit_gate(|ctl, q| sim.cy(ctl, q), arg, arg_span, unsupported)
+        }
+        "__quantum__qis__cz__body" => {
+            two_qubit_gate(|ctl, q| sim.cz(ctl, q), arg, arg_span, unsupported)
+        }
+        "__quantum__qis__rx__body" => {
+            one_qubit_rotation(|theta, q| sim.rx(theta, q), arg, unsupported)
+        }
+        "__quantum__qis__rxx__body" => two_qubit_rotation(
+            |theta, q0, q1| sim.rxx(theta, q0, q1),
+            arg,
+            arg_span,
+            unsupported,
+        ),
+        "__quantum__qis__ry__body" => {
+            one_qubit_rotation(|theta, q| sim.ry(theta, q), arg, unsupported)
+        }
+        "__quantum__qis__ryy__body" => two_qubit_rotation(
+            |theta, q0, q1| sim.ryy(theta, q0, q1),
+            arg,
+            arg_span,
+            unsupported,
+        ),
+        "__quantum__qis__rz__body" => {
+            one_qubit_rotation(|theta, q| sim.rz(theta, q), arg, unsupported)
+        }
+        "__quantum__qis__rzz__body" => two_qubit_rotation(
+            |theta, q0, q1| sim.rzz(theta, q0, q1),
+            arg,
+            arg_span,
+            unsupported,
+        ),
+        "__quantum__qis__h__body" => one_qubit_gate(|q| sim.h(q), arg, unsupported),
+        "__quantum__qis__s__body" => one_qubit_gate(|q| sim.s(q), arg, unsupported),
+        "__quantum__qis__s__adj" => one_qubit_gate(|q| sim.sadj(q), arg, unsupported),
+        "__quantum__qis__t__body" => one_qubit_gate(|q| sim.t(q), arg, unsupported),
+        "__quantum__qis__t__adj" => one_qubit_gate(|q| sim.tadj(q), arg, unsupported),
+        "__quantum__qis__x__body" => one_qubit_gate(|q| sim.x(q), arg, unsupported),
+        "__quantum__qis__y__body" => one_qubit_gate(|q| sim.y(q), arg, unsupported),
+        "__quantum__qis__z__body" => one_qubit_gate(|q| sim.z(q), arg, unsupported),
+        "__quantum__qis__swap__body" => {
+            two_qubit_gate(|q0, q1| sim.swap(q0, q1), arg, arg_span, unsupported)
+        }
+        "__quantum__qis__reset__body" => one_qubit_gate(|q| sim.reset(q), arg, unsupported),
+        "__quantum__qis__m__body" => sim
+            .m(arg.unwrap_qubit().0)
+            .map(Value::Result)
+            .map_err(unsupported),
+        "__quantum__qis__mresetz__body" => sim
+            .mresetz(arg.unwrap_qubit().0)
+            .map(Value::Result)
+            .map_err(unsupported),
         _ => Err(Error::UnknownIntrinsic(name.to_string(), name_span)),
     }
 }
 
-fn one_qubit_gate(gate: extern "C" fn(*mut c_void), arg: Value) -> Value {
-    gate(arg.unwrap_qubit().0);
-    Value::unit()
+fn one_qubit_gate(
+    gate: impl FnOnce(usize) -> Result<(), backend::Error>,
+    arg: Value,
+    unsupported: impl FnOnce(backend::Error) -> Error,
+) -> Result<Value, Error> {
+    gate(arg.unwrap_qubit().0).map_err(unsupported)?;
+    Ok(Value::unit())
 }
 
 fn two_qubit_gate(
-    gate: extern "C" fn(*mut c_void, *mut c_void),
+    gate: impl FnOnce(usize, usize) -> Result<(), backend::Error>,
     arg: Value,
     arg_span: Span,
+    unsupported: impl FnOnce(backend::Error) -> Error,
 ) -> Result<Value, Error> {
     let [x, y] = unwrap_tuple(arg);
     if x == y {
         Err(Error::QubitUniqueness(arg_span))
     } else {
-        gate(x.unwrap_qubit().0, y.unwrap_qubit().0);
+        gate(x.unwrap_qubit().0, y.unwrap_qubit().0).map_err(unsupported)?;
         Ok(Value::unit())
     }
 }
 
-fn one_qubit_rotation(gate: extern "C" fn(f64, *mut c_void), arg: Value) -> Value {
+fn one_qubit_rotation(
+    gate: impl FnOnce(f64, usize) -> Result<(), backend::Error>,
+    arg: Value,
+    unsupported: impl FnOnce(backend::Error) -> Error,
+) -> Result<Value, Error> {
     let [x, y] = unwrap_tuple(arg);
-    gate(x.unwrap_double(), y.unwrap_qubit().0);
-    Value::unit()
+    gate(x.unwrap_double(), y.unwrap_qubit().0).map_err(unsupported)?;
+    Ok(Value::unit())
 }
 
 fn three_qubit_gate(
-    gate: extern "C" fn(*mut c_void, *mut c_void, *mut c_void),
+    gate: impl FnOnce(usize, usize, usize) -> Result<(), backend::Error>,
     arg: Value,
     arg_span: Span,
+    unsupported: impl FnOnce(backend::Error) -> Error,
 ) -> Result<Value, Error> {
     let [x, y, z] = unwrap_tuple(arg);
     if x == y || y == z || x == z {
         Err(Error::QubitUniqueness(arg_span))
     } else {
-        gate(x.unwrap_qubit().0, y.unwrap_qubit().0, z.unwrap_qubit().0);
+        gate(x.unwrap_qubit().0, y.unwrap_qubit().0, z.unwrap_qubit().0).map_err(unsupported)?;
         Ok(Value::unit())
     }
 }
 
 fn two_qubit_rotation(
-    gate: extern "C" fn(f64, *mut c_void, *mut c_void),
+    gate: impl FnOnce(f64, usize, usize) -> Result<(), backend::Error>,
     arg: Value,
     arg_span: Span,
+    unsupported: impl FnOnce(backend::Error) -> Error,
 ) -> Result<Value, Error> {
     let [x, y, z] = unwrap_tuple(arg);
     if y == z {
         Err(Error::QubitUniqueness(arg_span))
     } else {
-        gate(x.unwrap_double(), y.unwrap_qubit().0, z.unwrap_qubit().0);
+        gate(x.unwrap_double(), y.unwrap_qubit().0, z.unwrap_qubit().0).map_err(unsupported)?;
         Ok(Value::unit())
     }
 }
@@ -185,3 +307,140 @@ fn unwrap_tuple<const N: usize>(value: Value) -> [Value; N] {
     let values = value.unwrap_tuple();
     array::from_fn(|i| values[i].clone())
 }
+
+/// Unwraps an array of distinct qubits.
+fn unwrap_qubits(arg: Value, arg_span: Span) -> Result<Vec<usize>, Error> {
+    let qubits: Vec<_> = arg
+        .unwrap_array()
+        .iter()
+        .map(|q| q.clone().unwrap_qubit().0)
+        .collect();
+    if qubits.iter().collect::<HashSet<_>>().len() < qubits.len() {
+        Err(Error::QubitUniqueness(arg_span))
+    } else {
+        Ok(qubits)
+    }
+}
+
+fn check_probability(
+    msg: &str,
+    expected: f64,
+    actual: f64,
+    tolerance: f64,
+    span: Span,
+) -> Result<Value, Error> {
+    if (expected - actual).abs() > tolerance {
+        Err(Error::AssertProbability(
+            msg.to_string(),
+            expected,
+            actual,
+            span,
+        ))
+    } else {
+        Ok(Value::unit())
+    }
+}
+
+/// The probability that a joint measurement of the qubits in the Pauli bases gives the result,
+/// where `true` is `One`. This is found from the expectation value of the Pauli operator, without
+/// changing the state.
+fn measurement_probability(
+    state: &[(BigUint, Complex64)],
+    bases: &[Pauli],
+    qubits: &[usize],
+    result: bool,
+) -> f64 {
+    let amplitudes: HashMap<_, _> = state.iter().map(|(basis, a)| (basis, *a)).collect();
+    let mut expectation = Complex64::default();
+    for (basis, amplitude) in state {
+        // Apply the Pauli operator to the basis state, tracking the phase that it picks up.
+        let mut image = basis.clone();
+        let mut phase = Complex64::new(1.0, 0.0);
+        for (&pauli, &q) in bases.iter().zip(qubits) {
+            let bit = basis.bit(q as u64);
+            match pauli {
+                Pauli::I => {}
+                Pauli::X => image.set_bit(q as u64, !bit),
+                Pauli::Y => {
+                    image.set_bit(q as u64, !bit);
+                    phase *= if bit { -Complex64::i() } else { Complex64::i() };
+                }
+                Pauli::Z => {
+                    if bit {
+                        phase = -phase;
+                    }
+                }
+            }
+        }
+        if let Some(image_amplitude) = amplitudes.get(&image) {
+            expectation += image_amplitude.conj() * phase * amplitude;
+        }
+    }
+
+    let sign = if result { -0.5 } else { 0.5 };
+    0.5 + sign * expectation.re
+}
+
+/// Finds the state of the register of qubits within the state of all qubits. Each basis state is
+/// split into the bits of the register, with the first qubit of the register as the lowest bit, and
+/// the bits of the other qubits. The register is separable when the amplitudes of the register are
+/// the same up to a factor for every basis state of the other qubits.
+fn register_state(state: Vec<(BigUint, Complex64)>, qubits: &[usize]) -> RegisterState {
+    let mut branches: BTreeMap<BigUint, BTreeMap<BigUint, Complex64>> = BTreeMap::new();
+    for (mut basis, amplitude) in state {
+        let mut register = BigUint::default();
+        for (i, &q) in qubits.iter().enumerate() {
+            if basis.bit(q as u64) {
+                register.set_bit(i as u64, true);
+                basis.set_bit(q as u64, false);
+            }
+        }
+        branches
+            .entry(basis)
+            .or_default()
+            .insert(register, amplitude);
+    }
+
+    let norm = |branch: &BTreeMap<BigUint, Complex64>| -> f64 {
+        branch.values().map(Complex64::norm_sqr).sum()
+    };
+    let overlap = |a: &BTreeMap<BigUint, Complex64>, b: &BTreeMap<BigUint, Complex64>| {
+        a.iter()
+            .filter_map(|(id, x)| b.get(id).map(|y| x.conj() * y))
+            .sum::<Complex64>()
+    };
+
+    // The largest branch gives the state the register would have if it were separable.
+    let largest = branches
+        .values()
+        .max_by(|a, b| norm(a).total_cmp(&norm(b)))
+        .cloned()
+        .unwrap_or_default();
+    let scale = norm(&largest).sqrt();
+    let candidate: BTreeMap<_, _> = largest
+        .into_iter()
+        .map(|(id, amplitude)| (id, amplitude / scale))
+        .collect();
+    let separable = branches
+        .values()
+        .all(|branch| norm(branch) - overlap(&candidate, branch).norm_sqr() < SEPARABLE_THRESHOLD);
+    if separable {
+        return RegisterState::Separable(candidate.into_iter().collect());
+    }
+
+    let mut probabilities: BTreeMap<BigUint, f64> = BTreeMap::new();
+    for branch in branches.values() {
+        for (id, amplitude) in branch {
+            *probabilities.entry(id.clone()).or_default() += amplitude.norm_sqr();
+        }
+    }
+    let branches: Vec<_> = branches.into_values().collect();
+    let purity = branches
+        .iter()
+        .flat_map(|a| branches.iter().map(|b| overlap(a, b).norm_sqr()))
+        .sum();
+    RegisterState::Entangled {
+        probabilities: probabilities.into_iter().collect(),
+        purity,
+    }
+}
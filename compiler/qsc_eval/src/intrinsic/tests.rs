@@ -1,23 +1,31 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use std::f64::consts;
+use std::{f64::consts, fmt::Write};
 
 use expect_test::{expect, Expect};
 use indoc::indoc;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, BigUint};
+use num_complex::Complex64;
 use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
 use qsc_passes::{run_core_passes, run_default_passes};
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
+    backend::{self, Backend, SparseSim, StabilizerSim},
     eval_expr,
     output::{GenericReceiver, Receiver},
     tests::get_global,
     val::Value,
-    Env, Error,
+    Env, Error, EvalContext,
 };
 
-fn check_intrinsic(file: &str, expr: &str, out: &mut dyn Receiver) -> Result<Value, Error> {
+fn check_intrinsic(
+    file: &str,
+    expr: &str,
+    sim: &mut impl Backend,
+    out: &mut dyn Receiver,
+) -> Result<Value, Error> {
     let mut core = compile::core();
     run_core_passes(&mut core);
     let mut store = PackageStore::new(core);
@@ -43,6 +51,7 @@ fn check_intrinsic(file: &str, expr: &str, out: &mut dyn Receiver) -> Result<Val
         id,
         &mut Env::default(),
         out,
+        EvalContext::new(sim, &mut StdRng::from_entropy()),
     )
     .map_err(|e| e.0)
 }
@@ -50,7 +59,7 @@ fn check_intrinsic(file: &str, expr: &str, out: &mut dyn Receiver) -> Result<Val
 fn check_intrinsic_result(file: &str, expr: &str, expect: &Expect) {
     let mut stdout = vec![];
     let mut out = GenericReceiver::new(&mut stdout);
-    match check_intrinsic(file, expr, &mut out) {
+    match check_intrinsic(file, expr, &mut SparseSim::new(), &mut out) {
         Ok(result) => expect.assert_eq(&result.to_string()),
         Err(e) => expect.assert_eq(&e.to_string()),
     }
@@ -59,7 +68,7 @@ fn check_intrinsic_result(file: &str, expr: &str, expect: &Expect) {
 fn check_intrinsic_output(file: &str, expr: &str, expect: &Expect) {
     let mut stdout = vec![];
     let mut out = GenericReceiver::new(&mut stdout);
-    match check_intrinsic(file, expr, &mut out) {
+    match check_intrinsic(file, expr, &mut SparseSim::new(), &mut out) {
         Ok(..) => expect.assert_eq(
             &String::from_utf8(stdout).expect("content should be convertable to string"),
         ),
@@ -70,12 +79,161 @@ fn check_intrinsic_output(file: &str, expr: &str, expect: &Expect) {
 fn check_intrinsic_value(file: &str, expr: &str, val: &Value) {
     let mut stdout = vec![];
     let mut out = GenericReceiver::new(&mut stdout);
-    match check_intrinsic(file, expr, &mut out) {
+    match check_intrinsic(file, expr, &mut SparseSim::new(), &mut out) {
         Ok(result) => assert_eq!(&result, val),
         Err(e) => panic!("{e:?}"),
     }
 }
 
+/// A backend that records the operations it receives and measures every qubit as `One`.
+#[derive(Default)]
+struct TraceBackend {
+    trace: String,
+    next_qubit: usize,
+}
+
+impl TraceBackend {
+    fn record(&mut self, op: &str, qubits: &[usize]) {
+        writeln!(self.trace, "{op} {qubits:?}").expect("writing to string should succeed");
+    }
+}
+
+impl Backend for TraceBackend {
+    fn ccx(&mut self, ctl0: usize, ctl1: usize, q: usize) -> Result<(), backend::Error> {
+        self.record("ccx", &[ctl0, ctl1, q]);
+        Ok(())
+    }
+
+    fn cx(&mut self, ctl: usize, q: usize) -> Result<(), backend::Error> {
+        self.record("cx", &[ctl, q]);
+        Ok(())
+    }
+
+    fn cy(&mut self, ctl: usize, q: usize) -> Result<(), backend::Error> {
+        self.record("cy", &[ctl, q]);
+        Ok(())
+    }
+
+    fn cz(&mut self, ctl: usize, q: usize) -> Result<(), backend::Error> {
+        self.record("cz", &[ctl, q]);
+        Ok(())
+    }
+
+    fn h(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("h", &[q]);
+        Ok(())
+    }
+
+    fn m(&mut self, q: usize) -> Result<bool, backend::Error> {
+        self.record("m", &[q]);
+        Ok(true)
+    }
+
+    fn mresetz(&mut self, q: usize) -> Result<bool, backend::Error> {
+        self.record("mresetz", &[q]);
+        Ok(true)
+    }
+
+    fn reset(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("reset", &[q]);
+        Ok(())
+    }
+
+    fn rx(&mut self, _: f64, q: usize) -> Result<(), backend::Error> {
+        self.record("rx", &[q]);
+        Ok(())
+    }
+
+    fn rxx(&mut self, _: f64, q0: usize, q1: usize) -> Result<(), backend::Error> {
+        self.record("rxx", &[q0, q1]);
+        Ok(())
+    }
+
+    fn ry(&mut self, _: f64, q: usize) -> Result<(), backend::Error> {
+        self.record("ry", &[q]);
+        Ok(())
+    }
+
+    fn ryy(&mut self, _: f64, q0: usize, q1: usize) -> Result<(), backend::Error> {
+        self.record("ryy", &[q0, q1]);
+        Ok(())
+    }
+
+    fn rz(&mut self, _: f64, q: usize) -> Result<(), backend::Error> {
+        self.record("rz", &[q]);
+        Ok(())
+    }
+
+    fn rzz(&mut self, _: f64, q0: usize, q1: usize) -> Result<(), backend::Error> {
+        self.record("rzz", &[q0, q1]);
+        Ok(())
+    }
+
+    fn sadj(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("sadj", &[q]);
+        Ok(())
+    }
+
+    fn s(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("s", &[q]);
+        Ok(())
+    }
+
+    fn swap(&mut self, q0: usize, q1: usize) -> Result<(), backend::Error> {
+        self.record("swap", &[q0, q1]);
+        Ok(())
+    }
+
+    fn tadj(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("tadj", &[q]);
+        Ok(())
+    }
+
+    fn t(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("t", &[q]);
+        Ok(())
+    }
+
+    fn x(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("x", &[q]);
+        Ok(())
+    }
+
+    fn y(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("y", &[q]);
+        Ok(())
+    }
+
+    fn z(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("z", &[q]);
+        Ok(())
+    }
+
+    fn qubit_allocate(&mut self) -> usize {
+        let q = self.next_qubit;
+        self.next_qubit += 1;
+        self.record("allocate", &[q]);
+        q
+    }
+
+    fn qubit_release(&mut self, q: usize) -> Result<(), backend::Error> {
+        self.record("release", &[q]);
+        Ok(())
+    }
+
+    fn capture_quantum_state(
+        &mut self,
+    ) -> Result<(Vec<(BigUint, Complex64)>, usize), backend::Error> {
+        Ok((Vec::new(), self.next_qubit))
+    }
+
+    fn qubit_is_zero(&mut self, _: usize) -> bool {
+        true
+    }
+
+    fn set_seed(&mut self, _: u64) {}
+}
+
 #[test]
 fn int_as_double() {
     check_intrinsic_result(
@@ -138,6 +296,222 @@ fn dump_machine_endianness() {
     );
 }
 
+#[test]
+fn dump_register_of_separable_qubits() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use qs = Qubit[3];
+            X(qs[0]);
+            H(qs[2]);
+            Microsoft.Quantum.Diagnostics.DumpRegister([qs[2], qs[0]]);
+            ResetAll(qs);
+        }"},
+        &expect![[r"
+            REGISTER:
+            |10⟩: 0.7071067811865476+0i
+            |11⟩: 0.7071067811865476+0i
+        "]],
+    );
+}
+
+#[test]
+fn dump_register_keeps_phase_of_qubits() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use (q, ancilla) = (Qubit(), Qubit());
+            H(ancilla);
+            X(q);
+            S(q);
+            Microsoft.Quantum.Diagnostics.DumpRegister([q]);
+            Reset(q);
+            Reset(ancilla);
+        }"},
+        &expect![[r"
+            REGISTER:
+            |1⟩: 0+1i
+        "]],
+    );
+}
+
+#[test]
+fn dump_register_of_entangled_qubits() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use qs = Qubit[3];
+            H(qs[0]);
+            CNOT(qs[0], qs[1]);
+            H(qs[2]);
+            Microsoft.Quantum.Diagnostics.DumpRegister([qs[0], qs[2]]);
+            ResetAll(qs);
+        }"},
+        &expect![[r"
+            REGISTER: entangled with other qubits (purity 0.5000)
+            |00⟩: 25.0000%
+            |01⟩: 25.0000%
+            |10⟩: 25.0000%
+            |11⟩: 25.0000%
+        "]],
+    );
+}
+
+#[test]
+fn dump_register_of_all_entangled_qubits_is_separable() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use qs = Qubit[2];
+            H(qs[0]);
+            CNOT(qs[0], qs[1]);
+            Microsoft.Quantum.Diagnostics.DumpRegister(qs);
+            ResetAll(qs);
+        }"},
+        &expect![[r"
+            REGISTER:
+            |00⟩: 0.7071067811865476+0i
+            |11⟩: 0.7071067811865476+0i
+        "]],
+    );
+}
+
+#[test]
+fn dump_register_qubits_not_unique() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use q = Qubit();
+            Microsoft.Quantum.Diagnostics.DumpRegister([q, q]);
+        }"},
+        &expect!["qubits in gate invocation are not unique"],
+    );
+}
+
+#[test]
+fn assert_measurement_probability() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use qs = Qubit[2];
+            H(qs[0]);
+            Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliZ], [qs[0]], One, 0.5, \"\", 1e-10);
+            Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliX], [qs[0]], Zero, 1.0, \"\", 1e-10);
+            CNOT(qs[0], qs[1]);
+            Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliZ, PauliZ], qs, Zero, 1.0, \"\", 1e-10);
+            Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliY, PauliY], qs, One, 1.0, \"\", 1e-10);
+            Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliI, PauliI], qs, Zero, 1.0, \"\", 1e-10);
+            ResetAll(qs);
+        }"},
+        &expect![""],
+    );
+}
+
+#[test]
+fn assert_measurement_probability_fails() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use q = Qubit();
+            H(q);
+            Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliZ], [q], One, 0.75, \"not biased\", 0.1);
+        }"},
+        &expect!["assertion failed: not biased: expected probability 0.75, found 0.5"],
+    );
+}
+
+#[test]
+fn assert_measurement_probability_length_mismatch() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use q = Qubit();
+            Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliZ, PauliX], [q], One, 0.0, \"\", 0.0);
+        }"},
+        &expect!["program failed: Arrays 'bases' and 'qubits' must be of the same length."],
+    );
+}
+
+#[test]
+fn assert_qubit_is_in_state() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use q = Qubit();
+            H(q);
+            S(q);
+            let r = Microsoft.Quantum.Math.Sqrt(0.5);
+            Microsoft.Quantum.Diagnostics.AssertQubitIsInState(q, (Microsoft.Quantum.Math.Complex(r, 0.0), Microsoft.Quantum.Math.Complex(0.0, r)), 1e-10);
+            Microsoft.Quantum.Diagnostics.AssertQubitIsInState(q, (Microsoft.Quantum.Math.Complex(0.0, r), Microsoft.Quantum.Math.Complex(-r, 0.0)), 1e-10);
+            Reset(q);
+        }"},
+        &expect![""],
+    );
+}
+
+#[test]
+fn assert_qubit_is_in_state_fails_for_other_phase() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use q = Qubit();
+            H(q);
+            S(q);
+            let r = Microsoft.Quantum.Math.Sqrt(0.5);
+            Microsoft.Quantum.Diagnostics.AssertQubitIsInState(q, (Microsoft.Quantum.Math.Complex(r, 0.0), Microsoft.Quantum.Math.Complex(r, 0.0)), 1e-10);
+        }"},
+        &expect!["assertion failed: qubit is not in the expected state when measured in the X basis: expected probability 1.0000000000000002, found 0.5"],
+    );
+}
+
+#[test]
+fn assert_qubit_is_in_state_fails_when_entangled() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use qs = Qubit[2];
+            H(qs[0]);
+            CNOT(qs[0], qs[1]);
+            let r = Microsoft.Quantum.Math.Sqrt(0.5);
+            Microsoft.Quantum.Diagnostics.AssertQubitIsInState(qs[0], (Microsoft.Quantum.Math.Complex(r, 0.0), Microsoft.Quantum.Math.Complex(r, 0.0)), 1e-10);
+        }"},
+        &expect!["assertion failed: qubit is not in the expected state when measured in the X basis: expected probability 1.0000000000000002, found 0.5"],
+    );
+}
+
+#[test]
+fn assert_in_adjoint_and_controlled() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use (c, q) = (Qubit(), Qubit());
+            H(q);
+            let r = Microsoft.Quantum.Math.Sqrt(0.5);
+            Adjoint Microsoft.Quantum.Diagnostics.AssertMeasurementProbability([PauliX], [q], Zero, 1.0, \"\", 1e-10);
+            Controlled Microsoft.Quantum.Diagnostics.AssertQubitIsInState([c], (q, (Microsoft.Quantum.Math.Complex(r, 0.0), Microsoft.Quantum.Math.Complex(r, 0.0)), 1e-10));
+            Controlled Adjoint Microsoft.Quantum.Diagnostics.AssertAllZero([q], [c]);
+            Reset(q);
+        }"},
+        &expect![""],
+    );
+}
+
+#[test]
+fn assert_all_zero() {
+    check_intrinsic_output(
+        "",
+        indoc! {"{
+            use qs = Qubit[3];
+            Microsoft.Quantum.Diagnostics.AssertAllZero(qs);
+            X(qs[2]);
+            Microsoft.Quantum.Diagnostics.AssertAllZero(qs);
+        }"},
+        &expect![
+            "assertion failed: Qubit2 is not in the |0⟩ state: expected probability 1, found 0"
+        ],
+    );
+}
+
 #[test]
 fn message() {
     check_intrinsic_output(
@@ -937,3 +1311,113 @@ fn qubit_not_unique_three_qubit_error_second_third() {
         &expect!["qubits in gate invocation are not unique"],
     );
 }
+
+#[test]
+fn custom_backend() {
+    let mut sim = TraceBackend::default();
+    let mut stdout = vec![];
+    let mut out = GenericReceiver::new(&mut stdout);
+    let result = check_intrinsic(
+        "",
+        indoc! {"{
+            use (q0, q1) = (Qubit(), Qubit());
+            H(q0);
+            CNOT(q0, q1);
+            let r = [M(q0), M(q1)];
+            ResetAll([q0, q1]);
+            r
+        }"},
+        &mut sim,
+        &mut out,
+    )
+    .expect("evaluation should succeed");
+    assert_eq!(result.to_string(), "[One, One]");
+    expect![[r"
+        allocate [0]
+        allocate [1]
+        h [0]
+        cx [0, 1]
+        m [0]
+        m [1]
+        reset [0]
+        reset [1]
+        release [1]
+        release [0]
+    "]]
+    .assert_eq(&sim.trace);
+}
+
+#[test]
+fn stabilizer_backend_dump_machine() {
+    let mut stdout = vec![];
+    let mut out = GenericReceiver::new(&mut stdout);
+    check_intrinsic(
+        "",
+        indoc! {"{
+            use qs = Qubit[3];
+            H(qs[0]);
+            CNOT(qs[0], qs[1]);
+            S(qs[1]);
+            X(qs[2]);
+            Microsoft.Quantum.Diagnostics.DumpMachine();
+            ResetAll(qs);
+        }"},
+        &mut StabilizerSim::new(),
+        &mut out,
+    )
+    .expect("evaluation should succeed");
+    expect![[r"
+        STATE:
+        |100⟩: 0.7071067811865475+0i
+        |111⟩: 0+0.7071067811865475i
+    "]]
+    .assert_eq(&String::from_utf8(stdout).expect("content should be convertable to string"));
+}
+
+#[test]
+fn stabilizer_backend_rejects_non_clifford_operation() {
+    let mut stdout = vec![];
+    let mut out = GenericReceiver::new(&mut stdout);
+    let error = check_intrinsic(
+        "",
+        indoc! {"{
+            use q = Qubit();
+            H(q);
+            T(q);
+        }"},
+        &mut StabilizerSim::new(),
+        &mut out,
+    )
+    .expect_err("evaluation should fail");
+    expect![[r#"
+        UnsupportedOperation(
+            "T",
+            Span {
+                lo: 37,
+                hi: 38,
+            },
+        )
+    "#]]
+    .assert_debug_eq(&error);
+}
+
+#[test]
+fn stabilizer_backend_allows_quarter_turn_rotations() {
+    let mut stdout = vec![];
+    let mut out = GenericReceiver::new(&mut stdout);
+    let result = check_intrinsic(
+        "",
+        indoc! {"{
+            let pi = Microsoft.Quantum.Math.PI();
+            use q = Qubit();
+            Rx(pi, q);
+            Ry(pi / 2.0, q);
+            Ry(-pi / 2.0, q);
+            Microsoft.Quantum.Measurement.MResetZ(q)
+        }"},
+        &mut StabilizerSim::new(),
+        &mut out,
+    )
+    .expect("evaluation should succeed");
+    expect!["One"].assert_eq(&result.to_string());
+}
@@ -6,31 +6,41 @@
 #[cfg(test)]
 mod tests;
 
+pub mod backend;
+pub mod circuit;
+pub mod coverage;
 pub mod debug;
 mod intrinsic;
 pub mod output;
+pub mod profile;
 pub mod val;
 
 use crate::val::{FunctorApp, Value};
+use backend::Backend;
+use circuit::Tracer;
+use coverage::Coverage;
 use debug::{CallStack, Frame};
 use miette::Diagnostic;
 use num_bigint::BigInt;
 use output::Receiver;
-use qir_backend::__quantum__rt__initialize;
+use profile::Profiler;
 use qsc_data_structures::span::Span;
 use qsc_hir::hir::{
     self, BinOp, Block, CallableDecl, Expr, ExprKind, Field, Functor, Lit, LocalItemId, Mutability,
     NodeId, PackageId, Pat, PatKind, PrimField, Res, SpecBody, SpecGen, Stmt, StmtKind,
     StringComponent, UnOp,
 };
+use rand::rngs::StdRng;
 use std::{
     collections::{hash_map::Entry, HashMap},
     convert::AsRef,
-    fmt::{self, Display, Formatter, Write},
+    fmt::{self, Debug, Display, Formatter, Write},
     iter,
     ops::Neg,
-    ptr::null_mut,
-    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 use thiserror::Error;
 use val::GlobalId;
@@ -41,10 +51,18 @@ pub enum Error {
     #[diagnostic(code("Qsc.Eval.ArrayTooLarge"))]
     ArrayTooLarge(#[label("this array has too many items")] Span),
 
+    #[error("array length exceeds the limit of {0}")]
+    #[diagnostic(code("Qsc.Eval.ArrayLimitExceeded"))]
+    ArrayLimitExceeded(usize, #[label("this array is too long")] Span),
+
     #[error("invalid array length: {0}")]
     #[diagnostic(code("Qsc.Eval.InvalidArrayLength"))]
     InvalidArrayLength(i64, #[label("cannot be used as a length")] Span),
 
+    #[error("assertion failed: {0}: expected probability {1}, found {2}")]
+    #[diagnostic(code("Qsc.Eval.AssertProbability"))]
+    AssertProbability(String, f64, f64, #[label("assertion failed")] Span),
+
     #[error("division by zero")]
     #[diagnostic(code("Qsc.Eval.DivZero"))]
     DivZero(#[label("cannot divide by zero")] Span),
@@ -57,6 +75,10 @@ pub enum Error {
     #[diagnostic(code("Qsc.Eval.InvalidIndex"))]
     InvalidIndex(i64, #[label("invalid index")] Span),
 
+    #[error("evaluation was interrupted")]
+    #[diagnostic(code("Qsc.Eval.Interrupted"))]
+    Interrupted(#[label("evaluation stopped here")] Span),
+
     #[error("integer too large for operation")]
     #[diagnostic(code("Qsc.Eval.IntTooLarge"))]
     IntTooLarge(i64, #[label("this value is too large")] Span),
@@ -85,14 +107,27 @@ pub enum Error {
     #[diagnostic(code("Qsc.Eval.RangeStepZero"))]
     RangeStepZero(#[label("invalid range")] Span),
 
+    #[error("qubits allocated at the same time exceed the limit of {0}")]
+    #[diagnostic(code("Qsc.Eval.QubitLimitExceeded"))]
+    QubitLimitExceeded(usize, #[label("this allocation is over the limit")] Span),
+
     #[error("Qubit{0} released while not in |0⟩ state")]
     #[diagnostic(code("Qsc.Eval.ReleasedQubitNotZero"))]
     ReleasedQubitNotZero(usize),
 
+    #[error("evaluation exceeded the step limit")]
+    #[diagnostic(code("Qsc.Eval.StepLimitExceeded"))]
+    #[diagnostic(help("the program may be stuck in an infinite loop or infinite recursion"))]
+    StepLimitExceeded(#[label("evaluation stopped here")] Span),
+
     #[error("name is not bound")]
     #[diagnostic(code("Qsc.Eval.UnboundName"))]
     UnboundName(#[label] Span),
 
+    #[error("`{0}` is not supported by the simulator")]
+    #[diagnostic(code("Qsc.Eval.UnsupportedOperation"))]
+    UnsupportedOperation(String, #[label("cannot be simulated")] Span),
+
     #[error("unknown intrinsic `{0}`")]
     #[diagnostic(code("Qsc.Eval.UnknownIntrinsic"))]
     UnknownIntrinsic(String, #[label("callable has no implementation")] Span),
@@ -134,8 +169,9 @@ pub fn eval_stmt<'a>(
     package: PackageId,
     env: &'a mut Env,
     out: &'a mut dyn Receiver,
+    context: EvalContext<'a, impl Backend + ?Sized>,
 ) -> Result<Value, (Error, CallStack)> {
-    let mut state = State::new(globals, package, env, out);
+    let mut state = State::new(globals, package, env, out, context);
     state.push_stmt(stmt);
     state.eval()
 }
@@ -149,14 +185,126 @@ pub fn eval_expr<'a>(
     package: PackageId,
     env: &'a mut Env,
     out: &'a mut dyn Receiver,
+    context: EvalContext<'a, impl Backend + ?Sized>,
 ) -> Result<Value, (Error, CallStack)> {
-    let mut state = State::new(globals, package, env, out);
+    let mut state = State::new(globals, package, env, out, context);
     state.push_expr(expr);
     state.eval()
 }
 
-pub fn init() {
-    __quantum__rt__initialize(null_mut());
+/// The simulator and random number generator that an evaluation runs on, the limits that stop it,
+/// and the recorders that observe it.
+pub struct EvalContext<'a, B: ?Sized> {
+    pub sim: &'a mut B,
+    pub rng: &'a mut StdRng,
+    pub limits: Limits,
+    pub recorders: Recorders<'a>,
+}
+
+impl<'a, B: ?Sized> EvalContext<'a, B> {
+    /// Creates a context with no limits and no recorders.
+    pub fn new(sim: &'a mut B, rng: &'a mut StdRng) -> Self {
+        Self {
+            sim,
+            rng,
+            limits: Limits::default(),
+            recorders: Recorders::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Self { limits, ..self }
+    }
+
+    #[must_use]
+    pub fn with_recorders(self, recorders: Recorders<'a>) -> Self {
+        Self { recorders, ..self }
+    }
+}
+
+/// Limits on the work that an evaluation may do and the resources it may use, so that a program
+/// cannot run forever or exhaust the memory of its host.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    /// The number of steps evaluation may take, where a step is the evaluation of one statement,
+    /// expression or intermediate action, or `None` if there is no limit.
+    pub steps: Option<u64>,
+    /// A token that is polled every [`CANCELLATION_POLL_INTERVAL`] steps. Evaluation stops once
+    /// the token is cancelled.
+    pub cancellation: Option<CancellationToken>,
+    /// The number of qubits that may be allocated at the same time, or `None` if there is no
+    /// limit. Qubits allocated by earlier evaluations in the same environment count too.
+    pub qubits: Option<usize>,
+    /// The length that arrays built by repetition or concatenation may have, or `None` if there
+    /// is no limit.
+    pub array_len: Option<usize>,
+}
+
+/// Recorders that observe an evaluation without changing its result.
+#[derive(Default)]
+pub struct Recorders<'a> {
+    /// Records the calls made by the evaluation.
+    pub profiler: Option<&'a mut Profiler>,
+    /// Records the statements and expressions that the evaluation runs.
+    pub coverage: Option<&'a mut Coverage>,
+    /// Records the quantum operations that the evaluation applies.
+    pub tracer: Option<&'a mut Tracer>,
+}
+
+/// The number of steps evaluation takes between polls of its cancellation token.
+pub const CANCELLATION_POLL_INTERVAL: u64 = 1024;
+
+/// A token that stops the evaluations it is given to once it is cancelled. Clones of a token share
+/// its state, so a token can be cancelled from another thread while an evaluation is running.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    poll: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a token that is also cancelled when `poll` returns true. The function is called
+    /// on the evaluating thread whenever the token is polled, so a host can check for interrupts
+    /// that can only be observed on that thread, such as signals.
+    #[must_use]
+    pub fn with_poll(poll: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            poll: Some(Arc::new(poll)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Clears the cancellation, so that the token no longer stops evaluations.
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        let polled = || self.poll.as_ref().is_some_and(|poll| poll());
+        if !self.cancelled.load(Ordering::Relaxed) && polled() {
+            self.cancel();
+        }
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Debug for CancellationToken {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.cancelled)
+            .finish_non_exhaustive()
+    }
 }
 
 trait AsIndex {
@@ -178,6 +326,7 @@ impl AsIndex for i64 {
 
 #[derive(Debug)]
 struct Variable {
+    name: Arc<str>,
     value: Value,
     mutability: Mutability,
 }
@@ -234,18 +383,22 @@ impl<'a, F: Fn(GlobalId) -> Option<Global<'a>>> GlobalLookup<'a> for F {
 }
 
 #[derive(Default)]
-pub struct Env(Vec<Scope>);
+pub struct Env {
+    scopes: Vec<Scope>,
+    /// The number of qubits allocated and not yet released.
+    qubits: usize,
+}
 
 impl Env {
     fn get(&self, id: NodeId) -> Option<&Variable> {
-        self.0
+        self.scopes
             .iter()
             .rev()
             .find_map(|scope| scope.bindings.get(&id))
     }
 
     fn get_mut(&mut self, id: NodeId) -> Option<&mut Variable> {
-        self.0
+        self.scopes
             .iter_mut()
             .rev()
             .find_map(|scope| scope.bindings.get_mut(&id))
@@ -255,12 +408,38 @@ impl Env {
 #[derive(Default)]
 struct Scope {
     bindings: HashMap<NodeId, Variable>,
+    /// The number of call frames that were active when the scope was entered.
+    depth: usize,
 }
 
 impl Env {
     #[must_use]
     pub fn with_empty_scope() -> Self {
-        Self(vec![Scope::default()])
+        Self {
+            scopes: vec![Scope::default()],
+            qubits: 0,
+        }
+    }
+
+    /// Forgets every allocated qubit, removing the variables that hold one. This is needed when
+    /// the simulator is replaced, since the qubits do not exist in the new simulator.
+    pub fn release_qubits(&mut self) {
+        for scope in &mut self.scopes {
+            scope
+                .bindings
+                .retain(|_, variable| !holds_qubit(&variable.value));
+        }
+        self.qubits = 0;
+    }
+}
+
+fn holds_qubit(value: &Value) -> bool {
+    match value {
+        Value::Qubit(_) => true,
+        Value::Array(vals) | Value::Closure(vals, ..) | Value::Tuple(vals) => {
+            vals.iter().any(holds_qubit)
+        }
+        _ => false,
     }
 }
 
@@ -275,7 +454,7 @@ enum Cont<'a> {
 #[derive(Copy, Clone)]
 enum Action<'a> {
     Array(usize),
-    ArrayRepeat(Span),
+    ArrayRepeat(Span, Span),
     Assign(&'a Expr),
     Bind(&'a Pat, Mutability),
     BinOp(BinOp, Span, Option<&'a Expr>),
@@ -288,7 +467,7 @@ enum Action<'a> {
     Range(bool, bool, bool),
     Return,
     StringConcat(usize),
-    StringLit(&'a Rc<str>),
+    StringLit(&'a Arc<str>),
     UpdateIndex(Span),
     Tuple(usize),
     UnOp(UnOp),
@@ -296,22 +475,37 @@ enum Action<'a> {
     While(&'a Expr, &'a Block),
 }
 
-pub(crate) struct State<'a, G> {
+pub(crate) struct State<'a, G, B: ?Sized> {
     stack: Vec<Cont<'a>>,
     vals: Vec<Value>,
     package: PackageId,
     globals: &'a G,
     env: &'a mut Env,
+    sim: &'a mut B,
+    rng: &'a mut StdRng,
     out: &'a mut dyn Receiver,
     call_stack: CallStack,
+    /// The number of steps evaluation may still take, or `None` if there is no limit.
+    steps_left: Option<u64>,
+    /// The number of steps until the cancellation token is next polled.
+    steps_until_poll: u64,
+    cancellation: Option<CancellationToken>,
+    /// The number of qubits that may be allocated at the same time, or `None` if there is no limit.
+    qubit_limit: Option<usize>,
+    /// The length that arrays built at runtime may have, or `None` if there is no limit.
+    array_limit: Option<usize>,
+    recorders: Recorders<'a>,
+    /// The span of the statement or expression that was evaluated most recently.
+    span: Span,
 }
 
-impl<'a, G: GlobalLookup<'a>> State<'a, G> {
+impl<'a, G: GlobalLookup<'a>, B: Backend + ?Sized> State<'a, G, B> {
     fn new(
         globals: &'a G,
         package: PackageId,
         env: &'a mut Env,
         out: &'a mut dyn Receiver,
+        context: EvalContext<'a, B>,
     ) -> Self {
         Self {
             stack: Vec::new(),
@@ -319,8 +513,17 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
             package,
             globals,
             env,
+            sim: context.sim,
+            rng: context.rng,
             out,
             call_stack: CallStack::default(),
+            steps_left: context.limits.steps,
+            steps_until_poll: 0,
+            cancellation: context.limits.cancellation,
+            qubit_limit: context.limits.qubits,
+            array_limit: context.limits.array_len,
+            recorders: context.recorders,
+            span: Span::default(),
         }
     }
 
@@ -345,6 +548,9 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
         });
         self.stack.push(Cont::Frame(self.vals.len()));
         self.package = id.package;
+        if let Some(profiler) = &mut self.recorders.profiler {
+            profiler.enter(id, functor);
+        }
     }
 
     fn leave_frame(&mut self, len: usize) {
@@ -353,19 +559,25 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
             .pop_frame()
             .expect("frame should be present");
         self.package = frame.caller;
+        if let Some(profiler) = &mut self.recorders.profiler {
+            profiler.exit();
+        }
         let frame_val = self.pop_val();
         self.vals.drain(len..);
         self.push_val(frame_val);
     }
 
     fn push_scope(&mut self) {
-        self.env.0.push(Scope::default());
+        self.env.scopes.push(Scope {
+            bindings: HashMap::new(),
+            depth: self.call_stack.frames().len(),
+        });
         self.stack.push(Cont::Scope);
     }
 
     fn leave_scope(&mut self) {
         self.env
-            .0
+            .scopes
             .pop()
             .expect("scope should be entered first before leaving");
     }
@@ -400,8 +612,40 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
     }
 
     pub(crate) fn eval(&mut self) -> Result<Value, (Error, CallStack)> {
+        let result = self.run(false, |_, _| false);
+        if let Some(profiler) = &mut self.recorders.profiler {
+            // Calls that were active when evaluation failed never return.
+            profiler.exit_all();
+        }
+        result?;
+        Ok(self.pop_val())
+    }
+
+    /// Evaluates continuations until the stack is empty, or until `pause` returns true for a
+    /// statement that is about to be evaluated. The statement is left on the stack and returned so
+    /// that evaluation can resume from it later. When `resuming` is true, the statement on top of
+    /// the stack is evaluated without asking `pause` first.
+    fn run(
+        &mut self,
+        mut resuming: bool,
+        mut pause: impl FnMut(&Self, &'a Stmt) -> bool,
+    ) -> Result<Option<&'a Stmt>, (Error, CallStack)> {
         while let Some(cont) = self.pop_cont() {
+            if let Err(e) = self.take_step(&cont) {
+                return Err((e, self.call_stack.clone()));
+            }
+            if let Some(coverage) = &mut self.recorders.coverage {
+                match &cont {
+                    Cont::Expr(expr) => coverage.record(self.package, expr.span),
+                    Cont::Stmt(stmt) => coverage.record(self.package, stmt.span),
+                    Cont::Action(_) | Cont::Frame(_) | Cont::Scope => {}
+                }
+            }
             let res = match cont {
+                Cont::Stmt(stmt) if !resuming && pause(self, stmt) => {
+                    self.push_stmt(stmt);
+                    return Ok(Some(stmt));
+                }
                 Cont::Action(action) => self.cont_action(action),
                 Cont::Expr(expr) => self.cont_expr(expr),
                 Cont::Frame(len) => {
@@ -420,22 +664,52 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
             if let Err(e) = res {
                 return Err((e, self.call_stack.clone()));
             }
+            resuming = false;
         }
 
-        Ok(self.pop_val())
+        Ok(None)
+    }
+
+    /// Counts the continuation as one step against the step limit, failing if the limit has been
+    /// reached or if the cancellation token has been cancelled.
+    fn take_step(&mut self, cont: &Cont) -> Result<(), Error> {
+        match cont {
+            Cont::Expr(expr) => self.span = expr.span,
+            Cont::Stmt(stmt) => self.span = stmt.span,
+            Cont::Action(_) | Cont::Frame(_) | Cont::Scope => {}
+        }
+        if self.steps_until_poll == 0 {
+            self.steps_until_poll = CANCELLATION_POLL_INTERVAL;
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                return Err(Error::Interrupted(self.span));
+            }
+        }
+        self.steps_until_poll -= 1;
+        match &mut self.steps_left {
+            Some(0) => Err(Error::StepLimitExceeded(self.span)),
+            Some(steps_left) => {
+                *steps_left -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
     }
 
     fn cont_expr(&mut self, expr: &'a Expr) -> Result<(), Error> {
         match &expr.kind {
             ExprKind::Array(arr) => self.cont_arr(arr),
-            ExprKind::ArrayRepeat(item, size) => self.cont_arr_repeat(item, size),
+            ExprKind::ArrayRepeat(item, size) => self.cont_arr_repeat(expr.span, item, size),
             ExprKind::Assign(lhs, rhs) => self.cont_assign(lhs, rhs),
-            ExprKind::AssignOp(op, lhs, rhs) => self.cont_assign_op(*op, lhs, rhs),
+            ExprKind::AssignOp(op, lhs, rhs) => self.cont_assign_op(expr.span, *op, lhs, rhs),
             ExprKind::AssignField(record, field, replace) => {
                 self.cont_assign_field(record, field, replace);
             }
             ExprKind::AssignIndex(lhs, mid, rhs) => self.cont_assign_index(lhs, mid, rhs),
-            ExprKind::BinOp(op, lhs, rhs) => self.cont_binop(*op, rhs, lhs),
+            ExprKind::BinOp(op, lhs, rhs) => self.cont_binop(expr.span, *op, rhs, lhs),
             ExprKind::Block(block) => self.push_block(block),
             ExprKind::Call(callee_expr, args_expr) => self.cont_call(callee_expr, args_expr),
             ExprKind::Closure(args, callable) => {
@@ -486,8 +760,8 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
         }
     }
 
-    fn cont_arr_repeat(&mut self, item: &'a Expr, size: &'a Expr) {
-        self.push_action(Action::ArrayRepeat(size.span));
+    fn cont_arr_repeat(&mut self, span: Span, item: &'a Expr, size: &'a Expr) {
+        self.push_action(Action::ArrayRepeat(span, size.span));
         self.push_expr(size);
         self.push_expr(item);
     }
@@ -513,9 +787,9 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
         self.push_val(Value::unit());
     }
 
-    fn cont_assign_op(&mut self, op: BinOp, lhs: &'a Expr, rhs: &'a Expr) {
+    fn cont_assign_op(&mut self, span: Span, op: BinOp, lhs: &'a Expr, rhs: &'a Expr) {
         self.push_action(Action::Assign(lhs));
-        self.cont_binop(op, rhs, lhs);
+        self.cont_binop(span, op, rhs, lhs);
         self.push_val(Value::unit());
     }
 
@@ -566,7 +840,7 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
 
     fn cont_string(&mut self, components: &'a [StringComponent]) {
         if let [StringComponent::Lit(str)] = components {
-            self.push_val(Value::String(Rc::clone(str)));
+            self.push_val(Value::String(Arc::clone(str)));
             return;
         }
 
@@ -590,10 +864,15 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
         self.push_expr(callee);
     }
 
-    fn cont_binop(&mut self, op: BinOp, rhs: &'a Expr, lhs: &'a Expr) {
+    fn cont_binop(&mut self, span: Span, op: BinOp, rhs: &'a Expr, lhs: &'a Expr) {
         match op {
-            BinOp::Add
-            | BinOp::AndB
+            BinOp::Add => {
+                // Concatenation reports an array that is too long at the whole expression.
+                self.push_action(Action::BinOp(op, span, None));
+                self.push_expr(rhs);
+                self.push_expr(lhs);
+            }
+            BinOp::AndB
             | BinOp::Div
             | BinOp::Eq
             | BinOp::Exp
@@ -659,7 +938,7 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
     fn cont_action(&mut self, action: Action<'a>) -> Result<(), Error> {
         match action {
             Action::Array(len) => self.eval_arr(len),
-            Action::ArrayRepeat(span) => self.eval_arr_repeat(span)?,
+            Action::ArrayRepeat(span, size_span) => self.eval_arr_repeat(span, size_span)?,
             Action::Assign(lhs) => self.eval_assign(lhs)?,
             Action::BinOp(op, span, rhs) => self.eval_binop(op, span, rhs)?,
             Action::Bind(pat, mutability) => self.eval_bind(pat, mutability),
@@ -681,7 +960,7 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
             }
             Action::Return => self.eval_ret(),
             Action::StringConcat(len) => self.eval_string_concat(len),
-            Action::StringLit(str) => self.push_val(Value::String(Rc::clone(str))),
+            Action::StringLit(str) => self.push_val(Value::String(Arc::clone(str))),
             Action::UpdateIndex(span) => self.eval_update_index(span)?,
             Action::Tuple(len) => self.eval_tup(len),
             Action::UnOp(op) => self.eval_unop(op),
@@ -696,13 +975,14 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
         self.push_val(Value::Array(arr.into()));
     }
 
-    fn eval_arr_repeat(&mut self, span: Span) -> Result<(), Error> {
+    fn eval_arr_repeat(&mut self, span: Span, size_span: Span) -> Result<(), Error> {
         let size_val = self.pop_val().unwrap_int();
         let item_val = self.pop_val();
         let s = match size_val.try_into() {
             Ok(i) => Ok(i),
-            Err(_) => Err(Error::InvalidArrayLength(size_val, span)),
+            Err(_) => Err(Error::InvalidArrayLength(size_val, size_span)),
         }?;
+        check_array_limit(self.array_limit, s, span)?;
         self.push_val(Value::Array(vec![item_val; s].into()));
         Ok(())
     }
@@ -719,7 +999,12 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
 
     fn eval_binop(&mut self, op: BinOp, span: Span, rhs: Option<&'a Expr>) -> Result<(), Error> {
         match op {
-            BinOp::Add => self.eval_binop_simple(eval_binop_add),
+            BinOp::Add => {
+                let limit = self.array_limit;
+                self.eval_binop_with_error(span, |lhs, rhs, span| {
+                    eval_binop_add(lhs, rhs, limit, span)
+                })?;
+            }
             BinOp::AndB => self.eval_binop_simple(eval_binop_andb),
             BinOp::AndL => {
                 if self.pop_val().unwrap_bool() {
@@ -805,6 +1090,13 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
         let spec = spec_from_functor_app(functor);
         self.push_frame(Some(callee_span), callee_id, functor);
         self.push_scope();
+        // A qubit array over the limit is not allocated at all, rather than up to the limit.
+        if callee_id.package == PackageId::CORE && callee.name.name.as_ref() == "AllocateQubitArray"
+        {
+            if let Value::Int(size) = arg {
+                self.check_qubit_limit(usize::try_from(size).unwrap_or_default(), callee_span)?;
+            }
+        }
         let block_body = &match spec {
             Spec::Body => Some(&callee.body),
             Spec::Adj => callee.adj.as_ref(),
@@ -821,7 +1113,38 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
             }
             SpecBody::Gen(SpecGen::Intrinsic) => {
                 let name = &callee.name.name;
-                let val = intrinsic::call(name, callee_span, arg, arg_span, self.out)?;
+                if name.starts_with("__quantum__qis__") {
+                    if let Some(profiler) = &mut self.recorders.profiler {
+                        profiler.gate();
+                    }
+                }
+                if name.as_ref() == "__quantum__rt__qubit_allocate" {
+                    self.check_qubit_limit(1, callee_span)?;
+                    self.env.qubits += 1;
+                }
+                let traced_arg = self.recorders.tracer.is_some().then(|| arg.clone());
+                let val = intrinsic::call(
+                    name,
+                    callee_span,
+                    arg,
+                    arg_span,
+                    self.sim,
+                    self.rng,
+                    self.out,
+                )
+                .map_err(|error| match error {
+                    Error::UnsupportedOperation(name, span) => self.unsupported(name, span),
+                    error => error,
+                })?;
+                if name.as_ref() == "__quantum__rt__qubit_release" {
+                    self.env.qubits = self.env.qubits.saturating_sub(1);
+                }
+                if let Some(arg) = traced_arg {
+                    let span = self.entry_call().and_then(|frame| frame.span);
+                    if let Some(tracer) = &mut self.recorders.tracer {
+                        tracer.record(name, &arg, &val, span);
+                    }
+                }
                 self.push_val(val);
                 Ok(())
             }
@@ -829,6 +1152,48 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
         }
     }
 
+    /// Reports an operation the simulator cannot run at the innermost call made from the package
+    /// where evaluation started, since the intrinsic itself is usually deep inside the standard
+    /// library.
+    fn unsupported(&self, name: String, span: Span) -> Error {
+        match self
+            .entry_call()
+            .map(|frame| (self.globals.get(frame.id), frame.span))
+        {
+            Some((Some(Global::Callable(callable)), Some(span))) => {
+                Error::UnsupportedOperation(callable.name.name.to_string(), span)
+            }
+            _ => Error::UnsupportedOperation(name, span),
+        }
+    }
+
+    /// Fails if allocating the given number of qubits would go over the qubit limit. Like
+    /// unsupported operations, going over the limit is reported at the innermost call made from
+    /// the package where evaluation started, since qubits are allocated by the core library.
+    fn check_qubit_limit(&self, count: usize, span: Span) -> Result<(), Error> {
+        match self.qubit_limit {
+            Some(limit) if self.env.qubits.saturating_add(count) > limit => {
+                let span = self
+                    .entry_call()
+                    .and_then(|frame| frame.span)
+                    .unwrap_or(span);
+                Err(Error::QubitLimitExceeded(limit, span))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The innermost call made from the package where evaluation started.
+    fn entry_call(&self) -> Option<&Frame> {
+        let frames = self.call_stack.frames();
+        frames.first().and_then(|entry| {
+            frames
+                .iter()
+                .rev()
+                .find(|frame| frame.caller == entry.caller && frame.span.is_some())
+        })
+    }
+
     fn eval_field(&mut self, field: &'a Field) {
         let record = self.pop_val();
         let val = match (record, field) {
@@ -1000,9 +1365,10 @@ impl<'a, G: GlobalLookup<'a>> State<'a, G> {
 fn bind_value(env: &mut Env, pat: &Pat, val: Value, mutability: Mutability) {
     match &pat.kind {
         PatKind::Bind(variable) => {
-            let scope = env.0.last_mut().expect("binding should have a scope");
+            let scope = env.scopes.last_mut().expect("binding should have a scope");
             match scope.bindings.entry(variable.id) {
                 Entry::Vacant(entry) => entry.insert(Variable {
+                    name: Arc::clone(&variable.name),
                     value: val,
                     mutability,
                 }),
@@ -1072,8 +1438,8 @@ fn bind_args_for_spec(
             let mut ctls = vec![];
             for _ in 0..ctl_count {
                 let [c, rest] = &*tup.unwrap_tuple() else {
-                        panic!("tuple should be arity 2");
-                    };
+                    panic!("tuple should be arity 2");
+                };
                 ctls.extend_from_slice(&c.clone().unwrap_array());
                 tup = rest.clone();
             }
@@ -1168,10 +1534,16 @@ fn slice_array(
     }
 }
 
-fn eval_binop_add(lhs_val: Value, rhs_val: Value) -> Value {
-    match lhs_val {
+fn eval_binop_add(
+    lhs_val: Value,
+    rhs_val: Value,
+    array_limit: Option<usize>,
+    span: Span,
+) -> Result<Value, Error> {
+    Ok(match lhs_val {
         Value::Array(arr) => {
             let rhs_arr = rhs_val.unwrap_array();
+            check_array_limit(array_limit, arr.len() + rhs_arr.len(), span)?;
             let items: Vec<_> = arr.iter().cloned().chain(rhs_arr.iter().cloned()).collect();
             Value::Array(items.into())
         }
@@ -1192,6 +1564,13 @@ fn eval_binop_add(lhs_val: Value, rhs_val: Value) -> Value {
             Value::String((val.to_string() + &rhs).into())
         }
         _ => panic!("value is not addable"),
+    })
+}
+
+fn check_array_limit(limit: Option<usize>, len: usize, span: Span) -> Result<(), Error> {
+    match limit {
+        Some(limit) if len > limit => Err(Error::ArrayLimitExceeded(limit, span)),
+        _ => Ok(()),
     }
 }
 
@@ -1483,7 +1862,9 @@ fn update_functor_app(functor: Functor, app: FunctorApp) -> FunctorApp {
 
 fn follow_field_path(mut value: Value, path: &[usize]) -> Option<Value> {
     for &index in path {
-        let Value::Tuple(items) = value else { return None; };
+        let Value::Tuple(items) = value else {
+            return None;
+        };
         value = items[index].clone();
     }
     Some(value)
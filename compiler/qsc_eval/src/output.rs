@@ -14,12 +14,73 @@ pub fn format_state_id(id: &BigUint, qubit_count: usize) -> String {
     format!("|{:0>qubit_count$}⟩", id.to_str_radix(2))
 }
 
+/// The state of a register of qubits, which is part of the state of all qubits.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegisterState {
+    /// The register is not entangled with the other qubits, so it has a state of its own, given by
+    /// its nonzero amplitudes.
+    Separable(Vec<(BigUint, Complex64)>),
+    /// The register is entangled with the other qubits, so it has no state of its own. It is
+    /// summarized by the probability of measuring each basis state and by its purity, the trace of
+    /// the square of its density matrix, which is one for a separable register and falls towards
+    /// `1 / 2ⁿ` as the register becomes more entangled.
+    Entangled {
+        probabilities: Vec<(BigUint, f64)>,
+        purity: f64,
+    },
+}
+
+/// Writes the register state as lines of text, with a header line naming the register.
+///
+/// # Errors
+/// This will return an error if writing to the writer fails.
+pub fn write_register_state(
+    writer: &mut dyn Write,
+    state: &RegisterState,
+    qubit_count: usize,
+) -> Result<(), Error> {
+    match state {
+        RegisterState::Separable(state) => {
+            writeln!(writer, "REGISTER:").map_err(|_| Error)?;
+            for (id, amplitude) in state {
+                writeln!(writer, "{}: {amplitude}", format_state_id(id, qubit_count))
+                    .map_err(|_| Error)?;
+            }
+        }
+        RegisterState::Entangled {
+            probabilities,
+            purity,
+        } => {
+            writeln!(
+                writer,
+                "REGISTER: entangled with other qubits (purity {purity:.4})"
+            )
+            .map_err(|_| Error)?;
+            for (id, probability) in probabilities {
+                writeln!(
+                    writer,
+                    "{}: {:.4}%",
+                    format_state_id(id, qubit_count),
+                    probability * 100.0
+                )
+                .map_err(|_| Error)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub trait Receiver {
     /// Receive state output
     /// # Errors
     /// This will return an error if handling the output fails.
     fn state(&mut self, state: Vec<(BigUint, Complex64)>, qubit_count: usize) -> Result<(), Error>;
 
+    /// Receive the state of a register of qubits
+    /// # Errors
+    /// This will return an error if handling the output fails.
+    fn register_state(&mut self, state: RegisterState, qubit_count: usize) -> Result<(), Error>;
+
     /// Receive generic message output
     /// # Errors
     /// This will return an error if handling the output fails.
@@ -51,6 +112,10 @@ impl<'a> Receiver for GenericReceiver<'a> {
         Ok(())
     }
 
+    fn register_state(&mut self, state: RegisterState, qubit_count: usize) -> Result<(), Error> {
+        write_register_state(self.writer, &state, qubit_count)
+    }
+
     fn message(&mut self, msg: &str) -> Result<(), Error> {
         writeln!(self.writer, "{msg}").map_err(|_| Error)
     }
@@ -90,6 +155,10 @@ impl<'a> Receiver for CursorReceiver<'a> {
         Ok(())
     }
 
+    fn register_state(&mut self, state: RegisterState, qubit_count: usize) -> Result<(), Error> {
+        write_register_state(self.cursor, &state, qubit_count)
+    }
+
     fn message(&mut self, msg: &str) -> Result<(), Error> {
         writeln!(self.cursor, "{msg}").map_err(|_| Error)
     }
@@ -0,0 +1,164 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! An opt-in profiler that records how often each callable is called, how long its calls take and
+//! how many intrinsic gates they apply. Callables are identified by their [`GlobalId`] and the
+//! functors applied to them, so the body and adjoint of an operation are profiled separately.
+
+#[cfg(test)]
+mod tests;
+
+use crate::val::{FunctorApp, GlobalId};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The statistics for every call of one callable with one functor application.
+#[derive(Clone, Debug)]
+pub struct CallableProfile {
+    pub id: GlobalId,
+    pub functor: FunctorApp,
+    /// The number of times the callable was called.
+    pub calls: u64,
+    /// The time spent in calls of the callable, including the time spent in its callees. Time in
+    /// recursive calls is only counted once, for the outermost call.
+    pub inclusive: Duration,
+    /// The time spent in calls of the callable, not including the time spent in its callees.
+    pub exclusive: Duration,
+    /// The number of intrinsic gates applied by calls of the callable, including the gates applied
+    /// by its callees. Gates in recursive calls are only counted once, for the outermost call.
+    pub gates: u64,
+}
+
+/// A single call of a callable, as a span of time since the profiler was created.
+#[derive(Clone, Copy, Debug)]
+pub struct TraceEvent {
+    pub id: GlobalId,
+    pub functor: FunctorApp,
+    /// When the call started, relative to when the profiler was created.
+    pub start: Duration,
+    /// How long the call took, including its callees.
+    pub duration: Duration,
+}
+
+/// A call that has started but not yet returned.
+struct ActiveCall {
+    index: usize,
+    start: Instant,
+    /// The time spent in calls made by this call that have already returned.
+    callees: Duration,
+    gates: u64,
+}
+
+/// Records the calls made by evaluations it is given to. A profiler can be given to several
+/// evaluations in turn, and accumulates the statistics of all of them.
+pub struct Profiler {
+    start: Instant,
+    callables: Vec<CallableProfile>,
+    indices: HashMap<(GlobalId, FunctorApp), usize>,
+    active: Vec<ActiveCall>,
+    trace: Option<Vec<TraceEvent>>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    /// Creates a profiler that records the statistics of each callable.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            callables: Vec::new(),
+            indices: HashMap::new(),
+            active: Vec::new(),
+            trace: None,
+        }
+    }
+
+    /// Also records every call as a trace event. A trace grows with the number of calls, so it
+    /// uses much more memory than the statistics for long-running programs.
+    #[must_use]
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    /// The statistics of each callable that was called, in the order that they were first called.
+    #[must_use]
+    pub fn callables(&self) -> &[CallableProfile] {
+        &self.callables
+    }
+
+    /// Every call that returned, in the order that they returned, or `None` if the profiler is not
+    /// recording a trace.
+    #[must_use]
+    pub fn trace(&self) -> Option<&[TraceEvent]> {
+        self.trace.as_deref()
+    }
+
+    pub(crate) fn enter(&mut self, id: GlobalId, functor: FunctorApp) {
+        let index = *self.indices.entry((id, functor)).or_insert_with(|| {
+            self.callables.push(CallableProfile {
+                id,
+                functor,
+                calls: 0,
+                inclusive: Duration::ZERO,
+                exclusive: Duration::ZERO,
+                gates: 0,
+            });
+            self.callables.len() - 1
+        });
+        self.callables[index].calls += 1;
+        self.active.push(ActiveCall {
+            index,
+            start: Instant::now(),
+            callees: Duration::ZERO,
+            gates: 0,
+        });
+    }
+
+    pub(crate) fn exit(&mut self) {
+        let call = self.active.pop().expect("call should be active");
+        let duration = call.start.elapsed();
+        let recursive = self.active.iter().any(|outer| outer.index == call.index);
+        let callable = &mut self.callables[call.index];
+        callable.exclusive += duration.saturating_sub(call.callees);
+        if !recursive {
+            callable.inclusive += duration;
+            callable.gates += call.gates;
+        }
+
+        if let Some(caller) = self.active.last_mut() {
+            caller.callees += duration;
+            caller.gates += call.gates;
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEvent {
+                id: callable.id,
+                functor: callable.functor,
+                start: call.start.duration_since(self.start),
+                duration,
+            });
+        }
+    }
+
+    /// Ends every active call, as when evaluation stops with an error.
+    pub(crate) fn exit_all(&mut self) {
+        while !self.active.is_empty() {
+            self.exit();
+        }
+    }
+
+    /// Counts a gate applied by the innermost active call.
+    pub(crate) fn gate(&mut self) {
+        if let Some(call) = self.active.last_mut() {
+            call.gates += 1;
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::Profiler;
+use crate::{
+    backend::SparseSim,
+    eval_expr,
+    output::GenericReceiver,
+    tests::get_global,
+    val::{FunctorApp, GlobalId},
+    Env, EvalContext, Global, Recorders,
+};
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_passes::{run_core_passes, run_default_passes};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fmt::Write;
+
+fn profile(file: &str, expr: &str, mut profiler: Profiler) -> (PackageStore, Profiler) {
+    let mut core = compile::core();
+    run_core_passes(&mut core);
+    let mut store = PackageStore::new(core);
+    let mut std = compile::std(&store);
+    assert!(std.errors.is_empty());
+    assert!(run_default_passes(store.core(), &mut std).is_empty());
+
+    let std_id = store.insert(std);
+    let sources = SourceMap::new([("test".into(), file.into())], Some(expr.into()));
+    let mut unit = compile(&store, &[std_id], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    assert!(run_default_passes(store.core(), &mut unit).is_empty());
+    let id = store.insert(unit);
+
+    let entry = store
+        .get(id)
+        .and_then(|unit| unit.package.entry.as_ref())
+        .expect("package should have entry");
+    let mut out = Vec::new();
+    // The profile is checked whether or not evaluation succeeds.
+    let _ = eval_expr(
+        entry,
+        &|id| get_global(&store, id),
+        id,
+        &mut Env::default(),
+        &mut GenericReceiver::new(&mut out),
+        EvalContext::new(&mut SparseSim::new(), &mut StdRng::seed_from_u64(0)).with_recorders(
+            Recorders {
+                profiler: Some(&mut profiler),
+                ..Recorders::default()
+            },
+        ),
+    );
+    (store, profiler)
+}
+
+fn name(store: &PackageStore, id: GlobalId, functor: FunctorApp) -> String {
+    let Some(Global::Callable(callable)) = get_global(store, id) else {
+        panic!("global should be a callable");
+    };
+    let mut name = String::new();
+    if functor.adjoint {
+        name.push_str("Adjoint ");
+    }
+    if functor.controlled > 0 {
+        write!(name, "Controlled({}) ", functor.controlled)
+            .expect("writing to string should succeed");
+    }
+    name.push_str(&callable.name.name);
+    name
+}
+
+fn check_callables(file: &str, expr: &str, expect: &Expect) {
+    let (store, profiler) = profile(file, expr, Profiler::new());
+    let mut actual = String::new();
+    for callable in profiler.callables() {
+        assert!(callable.exclusive <= callable.inclusive);
+        writeln!(
+            actual,
+            "{}: calls {}, gates {}",
+            name(&store, callable.id, callable.functor),
+            callable.calls,
+            callable.gates
+        )
+        .expect("writing to string should succeed");
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn calls_and_gates_are_counted_per_functor_application() {
+    check_callables(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Intrinsic;
+                operation Prepare(q : Qubit) : Unit is Adj {
+                    H(q);
+                    S(q);
+                }
+                operation Main() : Unit {
+                    use q = Qubit();
+                    Prepare(q);
+                    Prepare(q);
+                    Adjoint Prepare(q);
+                }
+            }
+        "},
+        "Test.Main()",
+        &expect![[r"
+            Main: calls 1, gates 6
+            __quantum__rt__qubit_allocate: calls 1, gates 0
+            Prepare: calls 2, gates 4
+            H: calls 2, gates 2
+            __quantum__qis__h__body: calls 3, gates 3
+            S: calls 2, gates 2
+            __quantum__qis__s__body: calls 2, gates 2
+            Adjoint Prepare: calls 1, gates 2
+            Adjoint S: calls 1, gates 1
+            __quantum__qis__s__adj: calls 1, gates 1
+            Adjoint H: calls 1, gates 1
+            __quantum__rt__qubit_release: calls 1, gates 0
+        "]],
+    );
+}
+
+#[test]
+fn recursive_calls_count_gates_once() {
+    check_callables(
+        indoc! {"
+            namespace Test {
+                open Microsoft.Quantum.Intrinsic;
+                operation Flip(q : Qubit, n : Int) : Unit {
+                    if n > 0 {
+                        X(q);
+                        Flip(q, n - 1);
+                    }
+                }
+                operation Main() : Unit {
+                    use q = Qubit();
+                    Flip(q, 3);
+                    X(q);
+                }
+            }
+        "},
+        "Test.Main()",
+        &expect![[r"
+            Main: calls 1, gates 4
+            __quantum__rt__qubit_allocate: calls 1, gates 0
+            Flip: calls 4, gates 3
+            X: calls 4, gates 4
+            __quantum__qis__x__body: calls 4, gates 4
+            __quantum__rt__qubit_release: calls 1, gates 0
+        "]],
+    );
+}
+
+#[test]
+fn calls_active_when_evaluation_fails_are_recorded() {
+    check_callables(
+        indoc! {r#"
+            namespace Test {
+                open Microsoft.Quantum.Intrinsic;
+                operation Fail(q : Qubit) : Unit {
+                    X(q);
+                    fail "failed";
+                }
+                operation Main() : Unit {
+                    use q = Qubit();
+                    Fail(q);
+                }
+            }
+        "#},
+        "Test.Main()",
+        &expect![[r"
+            Main: calls 1, gates 1
+            __quantum__rt__qubit_allocate: calls 1, gates 0
+            Fail: calls 1, gates 1
+            X: calls 1, gates 1
+            __quantum__qis__x__body: calls 1, gates 1
+        "]],
+    );
+}
+
+#[test]
+fn trace_records_calls_in_the_order_they_return() {
+    let (store, profiler) = profile(
+        indoc! {"
+            namespace Test {
+                function Square(x : Int) : Int {
+                    x * x
+                }
+                function SumOfSquares(x : Int, y : Int) : Int {
+                    Square(x) + Square(y)
+                }
+            }
+        "},
+        "Test.SumOfSquares(2, 3)",
+        Profiler::new().with_trace(),
+    );
+    let trace = profiler.trace().expect("profiler should record a trace");
+    let names: Vec<_> = trace
+        .iter()
+        .map(|event| name(&store, event.id, event.functor))
+        .collect();
+    assert_eq!(names, ["Square", "Square", "SumOfSquares"]);
+    let outer = trace[2];
+    for inner in &trace[..2] {
+        assert!(outer.start <= inner.start);
+        assert!(inner.start + inner.duration <= outer.start + outer.duration);
+    }
+}
+
+#[test]
+fn trace_is_not_recorded_by_default() {
+    let (_, profiler) = profile(
+        "namespace Test { function Answer() : Int { 42 } }",
+        "Test.Answer()",
+        Profiler::new(),
+    );
+    assert_eq!(profiler.callables().len(), 1);
+    assert!(profiler.trace().is_none());
+}
@@ -1,14 +1,26 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use crate::{eval_expr, output::GenericReceiver, val::GlobalId, Env, Global};
+use crate::{
+    backend::SparseSim, eval_expr, output::GenericReceiver, val::GlobalId, CancellationToken, Env,
+    EvalContext, Global, Limits,
+};
 use expect_test::{expect, Expect};
 use indoc::indoc;
 use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
 use qsc_hir::hir::ItemKind;
 use qsc_passes::{run_core_passes, run_default_passes};
+use rand::{rngs::StdRng, SeedableRng};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 
 fn check_expr(file: &str, expr: &str, expect: &Expect) {
+    check_expr_with_limits(file, expr, Limits::default(), expect);
+}
+
+fn check_expr_with_limits(file: &str, expr: &str, limits: Limits, expect: &Expect) {
     let mut core = compile::core();
     run_core_passes(&mut core);
     let mut store = PackageStore::new(core);
@@ -37,6 +49,7 @@ fn check_expr(file: &str, expr: &str, expect: &Expect) {
         id,
         &mut Env::default(),
         &mut GenericReceiver::new(&mut out),
+        EvalContext::new(&mut SparseSim::new(), &mut StdRng::from_entropy()).with_limits(limits),
     ) {
         Ok(value) => expect.assert_eq(&value.to_string()),
         Err(err) => expect.assert_debug_eq(&err),
@@ -2752,3 +2765,262 @@ fn partial_app_mutable_arg() {
         &expect!["((0, 1), (0, 2))"],
     );
 }
+
+#[test]
+fn step_limit_allows_short_evaluation() {
+    check_expr_with_limits(
+        "",
+        indoc! {"{
+            mutable x = 0;
+            while x < 3 {
+                set x += 1;
+            }
+            x
+        }"},
+        Limits {
+            steps: Some(1000),
+            ..Limits::default()
+        },
+        &expect!["3"],
+    );
+}
+
+#[test]
+fn step_limit_stops_infinite_loop() {
+    check_expr_with_limits(
+        "",
+        indoc! {"{
+            mutable x = 0;
+            while true {
+                set x += 1;
+            }
+            x
+        }"},
+        Limits {
+            steps: Some(1000),
+            ..Limits::default()
+        },
+        &expect![[r"
+            (
+                StepLimitExceeded(
+                    Span {
+                        lo: 46,
+                        hi: 56,
+                    },
+                ),
+                CallStack {
+                    frames: [],
+                },
+            )
+        "]],
+    );
+}
+
+#[test]
+fn cancelled_token_interrupts_evaluation() {
+    let token = CancellationToken::new();
+    token.cancel();
+    check_expr_with_limits(
+        "",
+        "{ while true {} }",
+        Limits {
+            cancellation: Some(token),
+            ..Limits::default()
+        },
+        &expect![[r"
+            (
+                Interrupted(
+                    Span {
+                        lo: 0,
+                        hi: 17,
+                    },
+                ),
+                CallStack {
+                    frames: [],
+                },
+            )
+        "]],
+    );
+}
+
+#[test]
+fn cancellation_token_is_polled_periodically() {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let token = CancellationToken::with_poll({
+        let polls = Arc::clone(&polls);
+        move || polls.fetch_add(1, Ordering::Relaxed) == 2
+    });
+    check_expr_with_limits(
+        "",
+        indoc! {"{
+            mutable x = 0;
+            while true {
+                set x += 1;
+            }
+            x
+        }"},
+        Limits {
+            cancellation: Some(token.clone()),
+            ..Limits::default()
+        },
+        &expect![[r"
+            (
+                Interrupted(
+                    Span {
+                        lo: 55,
+                        hi: 56,
+                    },
+                ),
+                CallStack {
+                    frames: [],
+                },
+            )
+        "]],
+    );
+    assert_eq!(polls.load(Ordering::Relaxed), 3);
+    assert!(token.is_cancelled());
+    token.reset();
+    assert!(!token.is_cancelled());
+}
+
+#[test]
+fn array_limit_allows_short_arrays() {
+    check_expr_with_limits(
+        "",
+        "[0, size = 2] + [1, size = 2]",
+        Limits {
+            array_len: Some(4),
+            ..Limits::default()
+        },
+        &expect!["[0, 0, 1, 1]"],
+    );
+}
+
+#[test]
+fn array_limit_stops_array_repeat() {
+    check_expr_with_limits(
+        "",
+        "[0, size = 1000000000]",
+        Limits {
+            array_len: Some(100),
+            ..Limits::default()
+        },
+        &expect![[r"
+            (
+                ArrayLimitExceeded(
+                    100,
+                    Span {
+                        lo: 0,
+                        hi: 22,
+                    },
+                ),
+                CallStack {
+                    frames: [],
+                },
+            )
+        "]],
+    );
+}
+
+#[test]
+fn array_limit_stops_concatenation() {
+    check_expr_with_limits(
+        "",
+        indoc! {"{
+            mutable xs = [];
+            while true {
+                set xs += [1];
+            }
+            xs
+        }"},
+        Limits {
+            array_len: Some(3),
+            ..Limits::default()
+        },
+        &expect![[r"
+            (
+                ArrayLimitExceeded(
+                    3,
+                    Span {
+                        lo: 48,
+                        hi: 61,
+                    },
+                ),
+                CallStack {
+                    frames: [],
+                },
+            )
+        "]],
+    );
+}
+
+#[test]
+fn qubit_limit_allows_released_qubits() {
+    check_expr_with_limits(
+        "",
+        indoc! {"{
+            for _ in 1..3 {
+                use qs = Qubit[2];
+            }
+            use q = Qubit();
+        }"},
+        Limits {
+            qubits: Some(2),
+            ..Limits::default()
+        },
+        &expect!["()"],
+    );
+}
+
+#[test]
+fn qubit_limit_stops_allocation() {
+    check_expr_with_limits(
+        "",
+        indoc! {"{
+            use q = Qubit();
+            use qs = Qubit[2];
+        }"},
+        Limits {
+            qubits: Some(2),
+            ..Limits::default()
+        },
+        &expect![[r"
+            (
+                QubitLimitExceeded(
+                    2,
+                    Span {
+                        lo: 31,
+                        hi: 33,
+                    },
+                ),
+                CallStack {
+                    frames: [
+                        Frame {
+                            span: Some(
+                                Span {
+                                    lo: 31,
+                                    hi: 33,
+                                },
+                            ),
+                            id: GlobalId {
+                                package: PackageId(
+                                    0,
+                                ),
+                                item: LocalItemId(
+                                    6,
+                                ),
+                            },
+                            caller: PackageId(
+                                2,
+                            ),
+                            functor: FunctorApp {
+                                adjoint: false,
+                                controlled: 0,
+                            },
+                        },
+                    ],
+                },
+            )
+        "]],
+    );
+}
@@ -1,23 +1,24 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+mod json;
+
 use num_bigint::BigInt;
 use qsc_hir::hir::{LocalItemId, PackageId, Pauli};
 use std::{
-    ffi::c_void,
     fmt::{self, Display, Formatter},
     iter,
-    rc::Rc,
+    sync::Arc,
 };
 
 pub(super) const DEFAULT_RANGE_STEP: i64 = 1;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
-    Array(Rc<[Value]>),
+    Array(Arc<[Value]>),
     BigInt(BigInt),
     Bool(bool),
-    Closure(Rc<[Value]>, GlobalId, FunctorApp),
+    Closure(Arc<[Value]>, GlobalId, FunctorApp),
     Double(f64),
     Global(GlobalId, FunctorApp),
     Int(i64),
@@ -25,11 +26,11 @@ pub enum Value {
     Qubit(Qubit),
     Range(Option<i64>, i64, Option<i64>),
     Result(bool),
-    String(Rc<str>),
-    Tuple(Rc<[Value]>),
+    String(Arc<str>),
+    Tuple(Arc<[Value]>),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct GlobalId {
     pub package: PackageId,
     pub item: LocalItemId,
@@ -42,9 +43,9 @@ impl Display for GlobalId {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Qubit(pub *mut c_void);
+pub struct Qubit(pub usize);
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 pub struct FunctorApp {
     /// An invocation is either adjoint or not, with each successive use of `Adjoint` functor switching
     /// between the two, so a bool is sufficient to track.
@@ -92,7 +93,7 @@ impl Display for Value {
                 Pauli::Z => write!(f, "PauliZ"),
                 Pauli::Y => write!(f, "PauliY"),
             },
-            Value::Qubit(v) => write!(f, "Qubit{}", (v.0 as usize)),
+            Value::Qubit(v) => write!(f, "Qubit{}", v.0),
             &Value::Range(start, step, end) => match (start, step, end) {
                 (Some(start), DEFAULT_RANGE_STEP, Some(end)) => write!(f, "{start}..{end}"),
                 (Some(start), DEFAULT_RANGE_STEP, None) => write!(f, "{start}..."),
@@ -133,7 +134,7 @@ impl Value {
     /// # Panics
     /// This will panic if the [Value] is not a [`Value::Array`].
     #[must_use]
-    pub fn unwrap_array(self) -> Rc<[Self]> {
+    pub fn unwrap_array(self) -> Arc<[Self]> {
         let Value::Array(v) = self else {
             panic!("value should be Array, got {}", self.type_name());
         };
@@ -243,7 +244,7 @@ impl Value {
     /// # Panics
     /// This will panic if the [Value] is not a [`Value::String`].
     #[must_use]
-    pub fn unwrap_string(self) -> Rc<str> {
+    pub fn unwrap_string(self) -> Arc<str> {
         let Value::String(v) = self else {
             panic!("value should be String, got {}", self.type_name());
         };
@@ -254,7 +255,7 @@ impl Value {
     /// # Panics
     /// This will panic if the [Value] is not a [`Value::Tuple`].
     #[must_use]
-    pub fn unwrap_tuple(self) -> Rc<[Self]> {
+    pub fn unwrap_tuple(self) -> Arc<[Self]> {
         let Value::Tuple(v) = self else {
             panic!("value should be Tuple, got {}", self.type_name());
         };
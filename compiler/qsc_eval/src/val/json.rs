@@ -0,0 +1,217 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! A typed JSON representation of values. Each value is an object with the name of its type and
+//! its contents, such as `{"type": "Int", "value": 3}`, so that values of different types with the
+//! same display form, like `1` and `1.0` or `(1,)` and `[1]`, can be told apart and read back.
+
+#[cfg(test)]
+mod tests;
+
+use super::{FunctorApp, GlobalId, Qubit, Value};
+use num_bigint::BigInt;
+use qsc_hir::hir::Pauli;
+use serde::{
+    de::{self, Deserializer},
+    ser::{SerializeStruct, Serializer},
+    Deserialize, Serialize,
+};
+use std::sync::Arc;
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Value", 2)?;
+        state.serialize_field("type", self.type_name())?;
+        match self {
+            Value::Array(vals) | Value::Tuple(vals) => state.serialize_field("value", &**vals)?,
+            Value::BigInt(v) => state.serialize_field("value", &v.to_string())?,
+            Value::Bool(v) => state.serialize_field("value", v)?,
+            Value::Closure(captures, id, functor) => state.serialize_field(
+                "value",
+                &Callable {
+                    package: id.package.into(),
+                    item: id.item.into(),
+                    adjoint: functor.adjoint,
+                    controlled: functor.controlled,
+                    captures: Some(&**captures),
+                },
+            )?,
+            &Value::Double(v) => state.serialize_field("value", &Double::from(v))?,
+            Value::Global(id, functor) => state.serialize_field(
+                "value",
+                &Callable {
+                    package: id.package.into(),
+                    item: id.item.into(),
+                    adjoint: functor.adjoint,
+                    controlled: functor.controlled,
+                    captures: None::<&[Value]>,
+                },
+            )?,
+            Value::Int(v) => state.serialize_field("value", v)?,
+            Value::Pauli(v) => state.serialize_field("value", pauli_name(*v))?,
+            Value::Qubit(v) => state.serialize_field("value", &v.0)?,
+            &Value::Range(start, step, end) => {
+                state.serialize_field("value", &Range { start, step, end })?;
+            }
+            Value::Result(v) => state.serialize_field("value", if *v { "One" } else { "Zero" })?,
+            Value::String(v) => state.serialize_field("value", &**v)?,
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Repr::deserialize(deserializer)?
+            .try_into()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// The owned form of a value read from JSON, which is checked as it is converted into a [`Value`].
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "value")]
+enum Repr {
+    Array(Vec<Repr>),
+    BigInt(String),
+    Bool(bool),
+    Closure(Callable<Vec<Repr>>),
+    Double(Double),
+    Global(Callable<Vec<Repr>>),
+    Int(i64),
+    Pauli(String),
+    Qubit(usize),
+    Range(Range),
+    Result(String),
+    String(String),
+    Tuple(Vec<Repr>),
+}
+
+impl TryFrom<Repr> for Value {
+    type Error = String;
+
+    fn try_from(repr: Repr) -> Result<Self, Self::Error> {
+        match repr {
+            Repr::Array(vals) => Ok(Value::Array(collect(vals)?)),
+            Repr::BigInt(v) => v
+                .parse::<BigInt>()
+                .map(Value::BigInt)
+                .map_err(|_| format!("invalid BigInt `{v}`")),
+            Repr::Bool(v) => Ok(Value::Bool(v)),
+            Repr::Closure(callable) => {
+                let (id, functor) = callable.id();
+                let captures = callable
+                    .captures
+                    .ok_or_else(|| "closure is missing captures".to_string())?;
+                Ok(Value::Closure(collect(captures)?, id, functor))
+            }
+            Repr::Double(v) => v.try_into().map(Value::Double),
+            Repr::Global(callable) => {
+                let (id, functor) = callable.id();
+                Ok(Value::Global(id, functor))
+            }
+            Repr::Int(v) => Ok(Value::Int(v)),
+            Repr::Pauli(v) => match v.as_str() {
+                "PauliI" => Ok(Value::Pauli(Pauli::I)),
+                "PauliX" => Ok(Value::Pauli(Pauli::X)),
+                "PauliY" => Ok(Value::Pauli(Pauli::Y)),
+                "PauliZ" => Ok(Value::Pauli(Pauli::Z)),
+                _ => Err(format!("invalid Pauli `{v}`")),
+            },
+            Repr::Qubit(v) => Ok(Value::Qubit(Qubit(v))),
+            Repr::Range(Range { start, step, end }) => Ok(Value::Range(start, step, end)),
+            Repr::Result(v) => match v.as_str() {
+                "Zero" => Ok(Value::Result(false)),
+                "One" => Ok(Value::Result(true)),
+                _ => Err(format!("invalid Result `{v}`")),
+            },
+            Repr::String(v) => Ok(Value::String(v.into())),
+            Repr::Tuple(vals) => Ok(Value::Tuple(collect(vals)?)),
+        }
+    }
+}
+
+fn collect(vals: Vec<Repr>) -> Result<Arc<[Value]>, String> {
+    vals.into_iter().map(Value::try_from).collect()
+}
+
+fn pauli_name(pauli: Pauli) -> &'static str {
+    match pauli {
+        Pauli::I => "PauliI",
+        Pauli::X => "PauliX",
+        Pauli::Y => "PauliY",
+        Pauli::Z => "PauliZ",
+    }
+}
+
+/// A global callable, or a closure if it has captures. The IDs identify the callable within the
+/// package store that evaluated it.
+#[derive(Deserialize, Serialize)]
+struct Callable<T> {
+    package: usize,
+    item: usize,
+    adjoint: bool,
+    controlled: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    captures: Option<T>,
+}
+
+impl<T> Callable<T> {
+    fn id(&self) -> (GlobalId, FunctorApp) {
+        let id = GlobalId {
+            package: self.package.into(),
+            item: self.item.into(),
+        };
+        let functor = FunctorApp {
+            adjoint: self.adjoint,
+            controlled: self.controlled,
+        };
+        (id, functor)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct Range {
+    start: Option<i64>,
+    step: i64,
+    end: Option<i64>,
+}
+
+/// A double is a JSON number when it is finite. JSON has no numbers for the other doubles, so they
+/// are written as the strings `NaN`, `Infinity` and `-Infinity` instead.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Double {
+    Finite(f64),
+    NonFinite(String),
+}
+
+impl From<f64> for Double {
+    fn from(v: f64) -> Self {
+        if v.is_finite() {
+            Double::Finite(v)
+        } else if v.is_nan() {
+            Double::NonFinite("NaN".to_string())
+        } else if v > 0.0 {
+            Double::NonFinite("Infinity".to_string())
+        } else {
+            Double::NonFinite("-Infinity".to_string())
+        }
+    }
+}
+
+impl TryFrom<Double> for f64 {
+    type Error = String;
+
+    fn try_from(double: Double) -> Result<Self, Self::Error> {
+        match double {
+            Double::Finite(v) => Ok(v),
+            Double::NonFinite(v) => match v.as_str() {
+                "NaN" => Ok(f64::NAN),
+                "Infinity" => Ok(f64::INFINITY),
+                "-Infinity" => Ok(f64::NEG_INFINITY),
+                _ => Err(format!("invalid Double `{v}`")),
+            },
+        }
+    }
+}
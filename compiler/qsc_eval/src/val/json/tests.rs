@@ -0,0 +1,151 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::val::{FunctorApp, GlobalId, Qubit, Value};
+use expect_test::{expect, Expect};
+use num_bigint::BigInt;
+use qsc_hir::hir::Pauli;
+
+fn check(value: &Value, expect: &Expect) {
+    let json = serde_json::to_string(value).expect("value should serialize");
+    expect.assert_eq(&json);
+    let parsed: Value = serde_json::from_str(&json).expect("value should deserialize");
+    match (value, &parsed) {
+        (Value::Double(x), Value::Double(y)) if x.is_nan() => assert!(y.is_nan()),
+        _ => assert_eq!(value, &parsed),
+    }
+}
+
+fn check_error(json: &str, expect: &Expect) {
+    let error = serde_json::from_str::<Value>(json).expect_err("value should not deserialize");
+    expect.assert_eq(&error.to_string());
+}
+
+#[test]
+fn prim_values() {
+    check(&Value::Int(-3), &expect![[r#"{"type":"Int","value":-3}"#]]);
+    check(
+        &Value::Double(1.0),
+        &expect![[r#"{"type":"Double","value":1.0}"#]],
+    );
+    check(
+        &Value::Bool(true),
+        &expect![[r#"{"type":"Bool","value":true}"#]],
+    );
+    check(
+        &Value::String("say \"hi\"".into()),
+        &expect![[r#"{"type":"String","value":"say \"hi\""}"#]],
+    );
+    check(
+        &Value::Result(true),
+        &expect![[r#"{"type":"Result","value":"One"}"#]],
+    );
+    check(
+        &Value::Pauli(Pauli::Y),
+        &expect![[r#"{"type":"Pauli","value":"PauliY"}"#]],
+    );
+    check(
+        &Value::Qubit(Qubit(2)),
+        &expect![[r#"{"type":"Qubit","value":2}"#]],
+    );
+}
+
+#[test]
+fn big_int_is_a_string() {
+    let big: BigInt = "-123456789012345678901234567890"
+        .parse()
+        .expect("string should be a valid BigInt");
+    check(
+        &Value::BigInt(big),
+        &expect![[r#"{"type":"BigInt","value":"-123456789012345678901234567890"}"#]],
+    );
+}
+
+#[test]
+fn non_finite_doubles_are_strings() {
+    check(
+        &Value::Double(f64::NAN),
+        &expect![[r#"{"type":"Double","value":"NaN"}"#]],
+    );
+    check(
+        &Value::Double(f64::INFINITY),
+        &expect![[r#"{"type":"Double","value":"Infinity"}"#]],
+    );
+    check(
+        &Value::Double(f64::NEG_INFINITY),
+        &expect![[r#"{"type":"Double","value":"-Infinity"}"#]],
+    );
+}
+
+#[test]
+fn ranges_with_open_ends() {
+    check(
+        &Value::Range(Some(1), 2, Some(9)),
+        &expect![[r#"{"type":"Range","value":{"start":1,"step":2,"end":9}}"#]],
+    );
+    check(
+        &Value::Range(None, -1, None),
+        &expect![[r#"{"type":"Range","value":{"start":null,"step":-1,"end":null}}"#]],
+    );
+}
+
+#[test]
+fn arrays_and_tuples_are_distinct() {
+    check(
+        &Value::Array(vec![Value::Int(1)].into()),
+        &expect![[r#"{"type":"Array","value":[{"type":"Int","value":1}]}"#]],
+    );
+    check(
+        &Value::Tuple(vec![Value::Int(1)].into()),
+        &expect![[r#"{"type":"Tuple","value":[{"type":"Int","value":1}]}"#]],
+    );
+    check(&Value::unit(), &expect![[r#"{"type":"Tuple","value":[]}"#]]);
+}
+
+#[test]
+fn callables_are_described_by_id() {
+    let id = GlobalId {
+        package: 1.into(),
+        item: 4.into(),
+    };
+    let functor = FunctorApp {
+        adjoint: true,
+        controlled: 2,
+    };
+    check(
+        &Value::Global(id, functor),
+        &expect![[
+            r#"{"type":"Global","value":{"package":1,"item":4,"adjoint":true,"controlled":2}}"#
+        ]],
+    );
+    check(
+        &Value::Closure(vec![Value::Bool(false)].into(), id, FunctorApp::default()),
+        &expect![[
+            r#"{"type":"Closure","value":{"package":1,"item":4,"adjoint":false,"controlled":0,"captures":[{"type":"Bool","value":false}]}}"#
+        ]],
+    );
+}
+
+#[test]
+fn invalid_values_are_errors() {
+    check_error(
+        r#"{"type":"Int","value":1.5}"#,
+        &expect!["invalid type: floating point `1.5`, expected i64 at line 1 column 25"],
+    );
+    check_error(
+        r#"{"type":"Result","value":"Two"}"#,
+        &expect!["invalid Result `Two`"],
+    );
+    check_error(
+        r#"{"type":"BigInt","value":"12a"}"#,
+        &expect!["invalid BigInt `12a`"],
+    );
+    check_error(
+        r#"{"type":"Array","value":[{"type":"Pauli","value":"X"}]}"#,
+        &expect!["invalid Pauli `X`"],
+    );
+    check_error(
+        r#"{"type":"Qubits","value":1}"#,
+        &expect!["unknown variant `Qubits`, expected one of `Array`, `BigInt`, `Bool`, `Closure`, `Double`, `Global`, `Int`, `Pauli`, `Qubit`, `Range`, `Result`, `String`, `Tuple` at line 1 column 16"],
+    );
+}
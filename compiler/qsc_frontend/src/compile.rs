@@ -27,7 +27,7 @@ use qsc_hir::{
     validate::Validator as HirValidator,
     visit::Visitor as _,
 };
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, iter, slice, sync::Arc};
 use thiserror::Error;
 
 #[allow(clippy::module_name_repetitions)]
@@ -42,13 +42,32 @@ pub struct CompileUnit {
 #[derive(Debug, Default)]
 pub struct SourceMap {
     sources: Vec<Source>,
+    imports: Vec<Import>,
     entry: Option<Source>,
 }
 
+/// A source that was translated into Q# by another front end, instead of being parsed.
+#[derive(Debug)]
+struct Import {
+    source: Source,
+    namespace: ast::Namespace,
+}
+
 impl SourceMap {
     pub fn new(
         sources: impl IntoIterator<Item = (SourceName, SourceContents)>,
         entry: Option<Arc<str>>,
+    ) -> Self {
+        Self::with_imports(sources, iter::empty(), entry)
+    }
+
+    /// Creates a source map that also contains imported sources, each with the namespace that it
+    /// was translated into. The spans in an imported namespace are relative to the start of its
+    /// source.
+    pub fn with_imports(
+        sources: impl IntoIterator<Item = (SourceName, SourceContents)>,
+        imports: impl IntoIterator<Item = (SourceName, SourceContents, ast::Namespace)>,
+        entry: Option<Arc<str>>,
     ) -> Self {
         let mut offset_sources = Vec::new();
         for (name, contents) in sources {
@@ -59,14 +78,27 @@ impl SourceMap {
             });
         }
 
+        let mut offset = next_offset(&offset_sources);
+        let mut offset_imports = Vec::new();
+        for (name, contents, namespace) in imports {
+            let source = Source {
+                name,
+                contents,
+                offset,
+            };
+            offset = next_offset(slice::from_ref(&source));
+            offset_imports.push(Import { source, namespace });
+        }
+
         let entry_source = entry.map(|contents| Source {
             name: "<entry>".into(),
             contents,
-            offset: next_offset(&offset_sources),
+            offset,
         });
 
         Self {
             sources: offset_sources,
+            imports: offset_imports,
             entry: entry_source,
         }
     }
@@ -75,6 +107,7 @@ impl SourceMap {
     pub fn find_by_offset(&self, offset: u32) -> Option<&Source> {
         self.sources
             .iter()
+            .chain(self.imports.iter().map(|import| &import.source))
             .chain(&self.entry)
             .rev()
             .find(|source| offset >= source.offset)
@@ -97,7 +130,10 @@ impl SourceMap {
 
     #[must_use]
     pub fn find_by_name(&self, name: &str) -> Option<&Source> {
-        self.sources.iter().find(|s| s.name.as_ref() == name)
+        self.sources
+            .iter()
+            .chain(self.imports.iter().map(|import| &import.source))
+            .find(|s| s.name.as_ref() == name)
     }
 }
 
@@ -363,6 +399,12 @@ fn parse_all(sources: &SourceMap) -> (ast::Package, Vec<qsc_parse::Error>) {
         append_parse_errors(&mut errors, source.offset, source_errors);
     }
 
+    for import in &sources.imports {
+        let mut namespace = import.namespace.clone();
+        Offsetter(import.source.offset).visit_namespace(&mut namespace);
+        namespaces.push(namespace);
+    }
+
     let entry = sources
         .entry
         .as_ref()
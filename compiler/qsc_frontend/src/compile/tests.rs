@@ -201,6 +201,42 @@ fn two_files_error() {
     assert_eq!(vec![("test2", Span { lo: 50, hi: 51 })], errors);
 }
 
+#[test]
+fn imported_namespace_error() {
+    let import = indoc! {"
+        namespace Bar {
+            function B() : Unit {
+                C();
+            }
+        }
+    "};
+    let (mut namespaces, parse_errors) = qsc_parse::namespaces(import);
+    assert!(parse_errors.is_empty(), "{parse_errors:#?}");
+    let sources = SourceMap::with_imports(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Foo {
+                    function A() : Unit {}
+                }
+            "}
+            .into(),
+        )],
+        [("import".into(), import.into(), namespaces.remove(0))],
+        Some("Bar.B()".into()),
+    );
+
+    let unit = compile(&PackageStore::new(super::core()), &[], sources);
+    let errors: Vec<_> = unit
+        .errors
+        .iter()
+        .map(|error| source_span(&unit.sources, error))
+        .collect();
+
+    assert_eq!(vec![("import", Span { lo: 50, hi: 51 })], errors);
+    assert!(unit.package.entry.is_some());
+}
+
 #[test]
 fn entry_call_operation() {
     let sources = SourceMap::new(
@@ -220,8 +256,12 @@ fn entry_call_operation() {
     assert!(unit.errors.is_empty(), "{:#?}", unit.errors);
 
     let entry = &unit.package.entry.expect("package should have entry");
-    let ExprKind::Call(callee, _) = &entry.kind else { panic!("entry should be a call") };
-    let ExprKind::Var(res, _) = &callee.kind else { panic!("callee should be a variable") };
+    let ExprKind::Call(callee, _) = &entry.kind else {
+        panic!("entry should be a call")
+    };
+    let ExprKind::Var(res, _) = &callee.kind else {
+        panic!("callee should be a variable")
+    };
     assert_eq!(
         &Res::Item(ItemId {
             package: None,
@@ -292,8 +332,13 @@ fn replace_node() {
         .items
         .get(LocalItemId::from(1))
         .expect("package should have item")
-        .kind else { panic!("item should be a callable"); };
-    let SpecBody::Impl(_, block) = &callable.body.body else { panic!("callable body have a block") };
+        .kind
+    else {
+        panic!("item should be a callable");
+    };
+    let SpecBody::Impl(_, block) = &callable.body.body else {
+        panic!("callable body have a block")
+    };
     expect![[r#"
         Block 4 [39-56] [Type Int]:
             Stmt 5 [49-50]: Expr: Expr 8 [49-50] [Type Int]: Lit: Int(2)"#]]
@@ -428,11 +473,22 @@ fn package_dependency() {
         .items
         .get(foo_id)
         .expect("package should have item")
-        .kind else { panic!("item should be a callable"); };
-    let SpecBody::Impl(_, block) = &callable.body.body else { panic!("callable body have a block") };
-    let StmtKind::Expr(expr) = &block.stmts[0].kind else { panic!("statement should be an expression") };
-    let ExprKind::Call(callee, _) = &expr.kind else { panic!("expression should be a call") };
-    let ExprKind::Var(res, _) = &callee.kind else { panic!("callee should be a variable") };
+        .kind
+    else {
+        panic!("item should be a callable");
+    };
+    let SpecBody::Impl(_, block) = &callable.body.body else {
+        panic!("callable body have a block")
+    };
+    let StmtKind::Expr(expr) = &block.stmts[0].kind else {
+        panic!("statement should be an expression")
+    };
+    let ExprKind::Call(callee, _) = &expr.kind else {
+        panic!("expression should be a call")
+    };
+    let ExprKind::Var(res, _) = &callee.kind else {
+        panic!("callee should be a variable")
+    };
     assert_eq!(
         &Res::Item(ItemId {
             package: Some(package1),
@@ -483,14 +539,64 @@ fn package_dependency_internal() {
         .items
         .get(LocalItemId::from(1))
         .expect("package should have item")
-        .kind else { panic!("item should be a callable"); };
-    let SpecBody::Impl(_, block) = &callable.body.body else { panic!("callable body have a block") };
-    let StmtKind::Expr(expr) = &block.stmts[0].kind else { panic!("statement should be an expression") };
-    let ExprKind::Call(callee, _) = &expr.kind else { panic!("expression should be a call") };
-    let ExprKind::Var(res, _) = &callee.kind else { panic!("callee should be a variable") };
+        .kind
+    else {
+        panic!("item should be a callable");
+    };
+    let SpecBody::Impl(_, block) = &callable.body.body else {
+        panic!("callable body have a block")
+    };
+    let StmtKind::Expr(expr) = &block.stmts[0].kind else {
+        panic!("statement should be an expression")
+    };
+    let ExprKind::Call(callee, _) = &expr.kind else {
+        panic!("expression should be a call")
+    };
+    let ExprKind::Var(res, _) = &callee.kind else {
+        panic!("callee should be a variable")
+    };
     assert_eq!(&Res::Err, res);
 }
 
+#[test]
+fn package_dependency_udt() {
+    let mut store = PackageStore::new(super::core());
+
+    let sources1 = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Package1 {
+                    newtype Pair = (First : Int, Second : Int);
+                    function Swapped(pair : Pair) : Pair {
+                        Pair(pair::Second, pair::First)
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    let package1 = store.insert(compile(&store, &[], sources1));
+
+    let sources2 = SourceMap::new(
+        [(
+            "test".into(),
+            indoc! {"
+                namespace Package2 {
+                    function Bar() : Int {
+                        Package1.Swapped(Package1.Pair(1, 2))::First
+                    }
+                }
+            "}
+            .into(),
+        )],
+        None,
+    );
+    let unit2 = compile(&store, &[package1], sources2);
+    assert!(unit2.errors.is_empty(), "{:#?}", unit2.errors);
+}
+
 #[test]
 fn std_dependency() {
     let mut store = PackageStore::new(super::core());
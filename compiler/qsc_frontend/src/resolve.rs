@@ -18,7 +18,7 @@ use qsc_hir::{
 };
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
-    rc::Rc,
+    sync::Arc,
     vec,
 };
 use thiserror::Error;
@@ -74,11 +74,11 @@ pub(super) enum Error {
 
 struct Scope {
     kind: ScopeKind,
-    opens: HashMap<Rc<str>, Vec<Open>>,
-    tys: HashMap<Rc<str>, ItemId>,
-    terms: HashMap<Rc<str>, ItemId>,
-    vars: HashMap<Rc<str>, NodeId>,
-    ty_vars: HashMap<Rc<str>, ParamId>,
+    opens: HashMap<Arc<str>, Vec<Open>>,
+    tys: HashMap<Arc<str>, ItemId>,
+    terms: HashMap<Arc<str>, ItemId>,
+    vars: HashMap<Arc<str>, NodeId>,
+    ty_vars: HashMap<Arc<str>, ParamId>,
 }
 
 impl Scope {
@@ -103,9 +103,9 @@ impl Scope {
 }
 
 struct GlobalScope {
-    tys: HashMap<Rc<str>, HashMap<Rc<str>, Res>>,
-    terms: HashMap<Rc<str>, HashMap<Rc<str>, Res>>,
-    namespaces: HashSet<Rc<str>>,
+    tys: HashMap<Arc<str>, HashMap<Arc<str>, Res>>,
+    terms: HashMap<Arc<str>, HashMap<Arc<str>, Res>>,
+    namespaces: HashSet<Arc<str>>,
 }
 
 impl GlobalScope {
@@ -120,7 +120,7 @@ impl GlobalScope {
 
 #[derive(Eq, PartialEq)]
 enum ScopeKind {
-    Namespace(Rc<str>),
+    Namespace(Arc<str>),
     Callable,
     Block,
 }
@@ -132,7 +132,7 @@ enum NameKind {
 }
 
 struct Open {
-    namespace: Rc<str>,
+    namespace: Arc<str>,
     span: Span,
 }
 
@@ -204,7 +204,7 @@ impl Resolver {
             ast::PatKind::Bind(name, _) => {
                 let scope = self.scopes.last_mut().expect("binding should have scope");
                 self.names.insert(name.id, Res::Local(name.id));
-                scope.vars.insert(Rc::clone(&name.name), name.id);
+                scope.vars.insert(Arc::clone(&name.name), name.id);
             }
             ast::PatKind::Discard(_) | ast::PatKind::Elided => {}
             ast::PatKind::Paren(pat) => self.bind_pat(pat),
@@ -213,11 +213,11 @@ impl Resolver {
     }
 
     fn bind_open(&mut self, name: &ast::Ident, alias: &Option<Box<ast::Ident>>) {
-        let alias = alias.as_ref().map_or("".into(), |a| Rc::clone(&a.name));
+        let alias = alias.as_ref().map_or("".into(), |a| Arc::clone(&a.name));
         let scope = self.scopes.last_mut().expect("open item should have scope");
         if self.globals.namespaces.contains(&name.name) {
             scope.opens.entry(alias).or_default().push(Open {
-                namespace: Rc::clone(&name.name),
+                namespace: Arc::clone(&name.name),
                 span: name.span,
             });
         } else {
@@ -233,14 +233,14 @@ impl Resolver {
                 let id = intrapackage(assigner.next_item());
                 self.names.insert(decl.name.id, Res::Item(id));
                 let scope = self.scopes.last_mut().expect("binding should have scope");
-                scope.terms.insert(Rc::clone(&decl.name.name), id);
+                scope.terms.insert(Arc::clone(&decl.name.name), id);
             }
             ast::ItemKind::Ty(name, _) => {
                 let id = intrapackage(assigner.next_item());
                 self.names.insert(name.id, Res::Item(id));
                 let scope = self.scopes.last_mut().expect("binding should have scope");
-                scope.tys.insert(Rc::clone(&name.name), id);
-                scope.terms.insert(Rc::clone(&name.name), id);
+                scope.tys.insert(Arc::clone(&name.name), id);
+                scope.terms.insert(Arc::clone(&name.name), id);
             }
             ast::ItemKind::Err => {}
         }
@@ -252,7 +252,7 @@ impl Resolver {
                 .scopes
                 .last_mut()
                 .expect("type parameters should have scope");
-            scope.ty_vars.insert(Rc::clone(&ident.name), ix.into());
+            scope.ty_vars.insert(Arc::clone(&ident.name), ix.into());
             self.names.insert(ident.id, Res::Param(ix.into()));
         });
     }
@@ -292,7 +292,7 @@ impl AstVisitor<'_> for With<'_> {
             self.resolver
                 .globals
                 .namespaces
-                .insert(Rc::clone(&namespace.name.name));
+                .insert(Arc::clone(&namespace.name.name));
 
             for item in namespace.items.iter() {
                 match bind_global_item(
@@ -308,7 +308,7 @@ impl AstVisitor<'_> for With<'_> {
             }
         }
 
-        let kind = ScopeKind::Namespace(Rc::clone(&namespace.name.name));
+        let kind = ScopeKind::Namespace(Arc::clone(&namespace.name.name));
         self.with_scope(kind, |visitor| {
             for item in namespace.items.iter() {
                 if let ast::ItemKind::Open(name, alias) = &*item.kind {
@@ -463,7 +463,7 @@ impl GlobalTable {
             );
             self.scope
                 .namespaces
-                .insert(Rc::clone(&namespace.name.name));
+                .insert(Arc::clone(&namespace.name.name));
 
             for item in namespace.items.iter() {
                 match bind_global_item(
@@ -511,7 +511,7 @@ impl GlobalTable {
 /// Tries to extract a field name from an expression in cases where it is syntactically ambiguous
 /// whether the expression is a field name or a variable name. This applies to the index operand in
 /// a ternary update operator.
-pub(super) fn extract_field_name<'a>(names: &Names, expr: &'a ast::Expr) -> Option<&'a Rc<str>> {
+pub(super) fn extract_field_name<'a>(names: &Names, expr: &'a ast::Expr) -> Option<&'a Arc<str>> {
     // Follow the same reasoning as `is_field_update`.
     match &*expr.kind {
         ast::ExprKind::Path(path)
@@ -542,7 +542,7 @@ fn is_field_update(globals: &GlobalScope, scopes: &[Scope], index: &ast::Expr) -
 fn bind_global_item(
     names: &mut Names,
     scope: &mut GlobalScope,
-    namespace: &Rc<str>,
+    namespace: &Arc<str>,
     next_id: impl FnOnce() -> ItemId,
     item: &ast::Item,
 ) -> Result<(), Error> {
@@ -552,9 +552,9 @@ fn bind_global_item(
             names.insert(decl.name.id, res);
             match scope
                 .terms
-                .entry(Rc::clone(namespace))
+                .entry(Arc::clone(namespace))
                 .or_default()
-                .entry(Rc::clone(&decl.name.name))
+                .entry(Arc::clone(&decl.name.name))
             {
                 Entry::Occupied(_) => Err(Error::Duplicate(
                     decl.name.name.to_string(),
@@ -573,14 +573,14 @@ fn bind_global_item(
             match (
                 scope
                     .terms
-                    .entry(Rc::clone(namespace))
+                    .entry(Arc::clone(namespace))
                     .or_default()
-                    .entry(Rc::clone(&name.name)),
+                    .entry(Arc::clone(&name.name)),
                 scope
                     .tys
-                    .entry(Rc::clone(namespace))
+                    .entry(Arc::clone(namespace))
                     .or_default()
-                    .entry(Rc::clone(&name.name)),
+                    .entry(Arc::clone(&name.name)),
             ) {
                 (Entry::Occupied(_), _) | (_, Entry::Occupied(_)) => Err(Error::Duplicate(
                     name.name.to_string(),
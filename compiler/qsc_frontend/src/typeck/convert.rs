@@ -11,7 +11,7 @@ use qsc_hir::{
     hir,
     ty::{Arrow, FunctorSet, FunctorSetValue, GenericParam, ParamId, Scheme, Ty, UdtField},
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub(crate) struct MissingTyError(pub(super) Span);
 
@@ -116,7 +116,7 @@ pub(super) fn ast_ty_def_fields(def: &TyDef) -> Vec<UdtField> {
     match &*def.kind {
         TyDefKind::Field(Some(name), _) => {
             vec![UdtField {
-                name: Rc::clone(&name.name),
+                name: Arc::clone(&name.name),
                 path: hir::FieldPath::default(),
             }]
         }
@@ -6,11 +6,11 @@ use crate::{
     ty::Scheme,
 };
 use qsc_data_structures::index_map;
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::HashMap, sync::Arc};
 
 pub struct Global {
-    pub namespace: Rc<str>,
-    pub name: Rc<str>,
+    pub namespace: Arc<str>,
+    pub name: Arc<str>,
     pub visibility: Visibility,
     pub kind: Kind,
 }
@@ -32,8 +32,8 @@ pub struct Term {
 
 #[derive(Default)]
 pub struct Table {
-    tys: HashMap<Rc<str>, HashMap<Rc<str>, Ty>>,
-    terms: HashMap<Rc<str>, HashMap<Rc<str>, Term>>,
+    tys: HashMap<Arc<str>, HashMap<Arc<str>, Ty>>,
+    terms: HashMap<Arc<str>, HashMap<Arc<str>, Term>>,
 }
 
 impl Table {
@@ -97,8 +97,8 @@ impl PackageIter<'_> {
 
         match (&item.kind, &parent) {
             (ItemKind::Callable(decl), Some(ItemKind::Namespace(namespace, _))) => Some(Global {
-                namespace: Rc::clone(&namespace.name),
-                name: Rc::clone(&decl.name.name),
+                namespace: Arc::clone(&namespace.name),
+                name: Arc::clone(&decl.name.name),
                 visibility: item.visibility,
                 kind: Kind::Term(Term {
                     id,
@@ -107,8 +107,8 @@ impl PackageIter<'_> {
             }),
             (ItemKind::Ty(name, def), Some(ItemKind::Namespace(namespace, _))) => {
                 self.next = Some(Global {
-                    namespace: Rc::clone(&namespace.name),
-                    name: Rc::clone(&name.name),
+                    namespace: Arc::clone(&namespace.name),
+                    name: Arc::clone(&name.name),
                     visibility: item.visibility,
                     kind: Kind::Term(Term {
                         id,
@@ -117,15 +117,15 @@ impl PackageIter<'_> {
                 });
 
                 Some(Global {
-                    namespace: Rc::clone(&namespace.name),
-                    name: Rc::clone(&name.name),
+                    namespace: Arc::clone(&namespace.name),
+                    name: Arc::clone(&name.name),
                     visibility: item.visibility,
                     kind: Kind::Ty(Ty { id }),
                 })
             }
             (ItemKind::Namespace(ident, _), None) => Some(Global {
                 namespace: "".into(),
-                name: Rc::clone(&ident.name),
+                name: Arc::clone(&ident.name),
                 visibility: Visibility::Public,
                 kind: Kind::Namespace,
             }),
@@ -1,11 +1,11 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use crate::hir::{CallableKind, FieldPath, Functor, ItemId, Res};
+use crate::hir::{CallableKind, FieldPath, Functor, ItemId, PackageId, Res};
 use std::{
     collections::HashMap,
     fmt::{self, Debug, Display, Formatter, Write},
-    rc::Rc,
+    sync::Arc,
 };
 
 /// A type.
@@ -33,6 +33,30 @@ pub enum Ty {
 impl Ty {
     /// The unit type.
     pub const UNIT: Self = Self::Tuple(Vec::new());
+
+    /// Qualifies the user-defined types in this type that are local to their package with the given
+    /// package, so that the type refers to the same items when it is used from another package.
+    #[must_use]
+    pub fn with_package(&self, package: PackageId) -> Self {
+        match self {
+            Ty::Array(item) => Ty::Array(Box::new(item.with_package(package))),
+            Ty::Arrow(arrow) => Ty::Arrow(Box::new(arrow_with_package(arrow, package))),
+            Ty::Tuple(items) => Ty::Tuple(
+                items
+                    .iter()
+                    .map(|item| item.with_package(package))
+                    .collect(),
+            ),
+            Ty::Udt(Res::Item(ItemId {
+                package: None,
+                item,
+            })) => Ty::Udt(Res::Item(ItemId {
+                package: Some(package),
+                item: *item,
+            })),
+            Ty::Infer(_) | Ty::Param(_) | Ty::Prim(_) | Ty::Udt(_) | Ty::Err => self.clone(),
+        }
+    }
 }
 
 impl Display for Ty {
@@ -106,6 +130,16 @@ impl Scheme {
             Err(InstantiationError::Arity)
         }
     }
+
+    /// Qualifies the user-defined types in this scheme that are local to their package with the
+    /// given package. See [`Ty::with_package`].
+    #[must_use]
+    pub fn with_package(&self, package: PackageId) -> Self {
+        Self {
+            params: self.params.clone(),
+            ty: Box::new(arrow_with_package(&self.ty, package)),
+        }
+    }
 }
 
 /// A type scheme instantiation error.
@@ -163,6 +197,15 @@ fn instantiate_arrow_ty<'a>(
     })
 }
 
+fn arrow_with_package(arrow: &Arrow, package: PackageId) -> Arrow {
+    Arrow {
+        kind: arrow.kind,
+        input: Box::new(arrow.input.with_package(package)),
+        output: Box::new(arrow.output.with_package(package)),
+        functors: arrow.functors,
+    }
+}
+
 impl Display for GenericParam {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
@@ -414,6 +457,16 @@ impl Udt {
         }
     }
 
+    /// Qualifies the user-defined types in the base type that are local to their package with the
+    /// given package. See [`Ty::with_package`].
+    #[must_use]
+    pub fn with_package(&self, package: PackageId) -> Self {
+        Self {
+            base: self.base.with_package(package),
+            fields: self.fields.clone(),
+        }
+    }
+
     /// The path to the field with the given name. Returns [None] if this user-defined type does not
     /// have a field with the given name.
     #[must_use]
@@ -464,7 +517,7 @@ impl Display for Udt {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct UdtField {
     /// The field name.
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     /// The field path.
     pub path: FieldPath,
 }
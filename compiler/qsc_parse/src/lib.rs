@@ -117,3 +117,18 @@ pub fn expr(input: &str) -> (Box<Expr>, Vec<Error>) {
         }
     }
 }
+
+/// Returns true if the input is a Q# identifier that is not a keyword, so that it can be used as
+/// the name of an item.
+#[must_use]
+pub fn is_ident(input: &str) -> bool {
+    let mut tokens = lex::Lexer::new(input);
+    match (tokens.next(), tokens.next()) {
+        (Some(Ok(token)), None) => {
+            token.kind == TokenKind::Ident
+                && token.span.lo == 0
+                && token.span.hi as usize == input.len()
+        }
+        _ => false,
+    }
+}
@@ -10,12 +10,12 @@ use qsc_hir::{
     },
     ty::{GenericArg, Prim, Ty},
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub(crate) struct IdentTemplate {
     pub id: NodeId,
     pub span: Span,
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub ty: Ty,
 }
 
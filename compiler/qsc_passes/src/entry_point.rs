@@ -8,9 +8,14 @@ use super::Error as PassErr;
 use miette::Diagnostic;
 use qsc_data_structures::span::Span;
 use qsc_hir::{
-    hir::{Attr, CallableDecl, Expr, ExprKind, Item, ItemKind, NodeId, Package, PatKind},
+    hir::{
+        Attr, Block, CallableDecl, Expr, ExprKind, Item, ItemKind, Mutability, NodeId, Package,
+        PatKind, SpecBody, Stmt, StmtKind,
+    },
+    ty::{Prim, Ty},
     visit::Visitor,
 };
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Clone, Debug, Diagnostic, Error)]
@@ -20,9 +25,16 @@ pub enum Error {
     #[diagnostic(code("Qsc.EntryPoint.Duplicate"))]
     Duplicate(String, #[label] Span),
 
-    #[error("entry point cannot have parameters")]
+    #[error("expected {0} entry point arguments, found {1}")]
     #[diagnostic(code("Qsc.EntryPoint.Args"))]
-    Args(#[label] Span),
+    Args(usize, usize, #[label] Span),
+
+    #[error("entry point parameter cannot have type `{0}`")]
+    #[diagnostic(help(
+        "entry point parameters must be of type Int, Double, Bool, String, Pauli, Result or BigInt, or arrays or tuples of them"
+    ))]
+    #[diagnostic(code("Qsc.EntryPoint.ParamTy"))]
+    ParamTy(String, #[label] Span),
 
     #[error("entry point must have body implementation only")]
     #[diagnostic(code("Qsc.EntryPoint.BodyMissing"))]
@@ -34,10 +46,28 @@ pub enum Error {
     NotFound,
 }
 
+/// A parameter of an entry point callable, which an argument must be given for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    /// The name of the parameter, or `_` if it is discarded.
+    pub name: Arc<str>,
+    /// The type of the parameter.
+    pub ty: Ty,
+    /// The span of the parameter.
+    pub span: Span,
+}
+
 /// Extracts a single entry point callable declaration, if found.
 /// # Errors
 /// Returns an error if a single entry point with no parameters cannot be found.
 pub fn extract_entry(package: &Package) -> Result<Expr, Vec<super::Error>> {
+    bind_entry(find_entry(package)?, Vec::new())
+}
+
+/// Finds the single entry point callable declaration in the package.
+/// # Errors
+/// Returns an error if there is no entry point callable or more than one.
+pub fn find_entry(package: &Package) -> Result<&CallableDecl, Vec<super::Error>> {
     let mut finder = EntryPointFinder {
         callables: Vec::new(),
     };
@@ -45,31 +75,7 @@ pub fn extract_entry(package: &Package) -> Result<Expr, Vec<super::Error>> {
     let entry_points = finder.callables;
 
     if entry_points.len() == 1 {
-        let ep = entry_points[0];
-        let arg_count = if let PatKind::Tuple(args) = &ep.input.kind {
-            args.len()
-        } else {
-            1
-        };
-        if arg_count == 0 {
-            if ep.adj.is_some() || ep.ctl.is_some() || ep.ctl_adj.is_some() {
-                Err(vec![PassErr::EntryPoint(Error::BodyMissing(ep.span))])
-            } else {
-                match &ep.body.body {
-                    qsc_hir::hir::SpecBody::Gen(_) => {
-                        Err(vec![PassErr::EntryPoint(Error::BodyMissing(ep.span))])
-                    }
-                    qsc_hir::hir::SpecBody::Impl(_, block) => Ok(Expr {
-                        id: NodeId::default(),
-                        span: Span::default(),
-                        ty: ep.output.clone(),
-                        kind: ExprKind::Block(block.clone()),
-                    }),
-                }
-            }
-        } else {
-            Err(vec![PassErr::EntryPoint(Error::Args(ep.input.span))])
-        }
+        Ok(entry_points[0])
     } else if entry_points.is_empty() {
         Err(vec![PassErr::EntryPoint(Error::NotFound)])
     } else {
@@ -80,6 +86,129 @@ pub fn extract_entry(package: &Package) -> Result<Expr, Vec<super::Error>> {
     }
 }
 
+/// The parameters of the entry point callable, in order.
+/// # Errors
+/// Returns an error for each parameter whose type cannot be given as an argument.
+pub fn params(ep: &CallableDecl) -> Result<Vec<Param>, Vec<super::Error>> {
+    let pats = match &ep.input.kind {
+        PatKind::Tuple(pats) => pats.iter().collect(),
+        PatKind::Bind(_) | PatKind::Discard => vec![&ep.input],
+    };
+
+    let mut params = Vec::new();
+    let mut errors = Vec::new();
+    for pat in pats {
+        if is_arg_ty(&pat.ty) {
+            let name = match &pat.kind {
+                PatKind::Bind(name) => Arc::clone(&name.name),
+                PatKind::Discard | PatKind::Tuple(_) => "_".into(),
+            };
+            params.push(Param {
+                name,
+                ty: pat.ty.clone(),
+                span: pat.span,
+            });
+        } else {
+            errors.push(PassErr::EntryPoint(Error::ParamTy(
+                pat.ty.to_string(),
+                pat.span,
+            )));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(params)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Creates the expression that evaluates the body of the entry point callable with its parameters
+/// bound to the arguments, which must be given in the order of the parameters.
+/// # Errors
+/// Returns an error if the entry point has specializations other than its body, if a parameter
+/// has a type that cannot be given as an argument, or if the number of arguments is wrong.
+pub fn bind_entry(ep: &CallableDecl, mut args: Vec<Expr>) -> Result<Expr, Vec<super::Error>> {
+    if ep.adj.is_some() || ep.ctl.is_some() || ep.ctl_adj.is_some() {
+        return Err(vec![PassErr::EntryPoint(Error::BodyMissing(ep.span))]);
+    }
+    let SpecBody::Impl(_, block) = &ep.body.body else {
+        return Err(vec![PassErr::EntryPoint(Error::BodyMissing(ep.span))]);
+    };
+
+    let params = params(ep)?;
+    if args.len() != params.len() {
+        return Err(vec![PassErr::EntryPoint(Error::Args(
+            params.len(),
+            args.len(),
+            ep.input.span,
+        ))]);
+    }
+
+    let body = Expr {
+        id: NodeId::default(),
+        span: Span::default(),
+        ty: ep.output.clone(),
+        kind: ExprKind::Block(block.clone()),
+    };
+    if params.is_empty() {
+        return Ok(body);
+    }
+
+    // The parameters are bound by a `let` statement in front of the body.
+    let input = if let PatKind::Tuple(_) = &ep.input.kind {
+        Expr {
+            id: NodeId::default(),
+            span: Span::default(),
+            ty: ep.input.ty.clone(),
+            kind: ExprKind::Tuple(args),
+        }
+    } else {
+        args.remove(0)
+    };
+    let stmts = vec![
+        Stmt {
+            id: NodeId::default(),
+            span: Span::default(),
+            kind: StmtKind::Local(Mutability::Immutable, ep.input.clone(), input),
+        },
+        Stmt {
+            id: NodeId::default(),
+            span: Span::default(),
+            kind: StmtKind::Expr(body),
+        },
+    ];
+    Ok(Expr {
+        id: NodeId::default(),
+        span: Span::default(),
+        ty: ep.output.clone(),
+        kind: ExprKind::Block(Block {
+            id: NodeId::default(),
+            span: Span::default(),
+            ty: ep.output.clone(),
+            stmts,
+        }),
+    })
+}
+
+/// Whether an argument can be given for a parameter of the type.
+fn is_arg_ty(ty: &Ty) -> bool {
+    match ty {
+        Ty::Prim(
+            Prim::BigInt
+            | Prim::Bool
+            | Prim::Double
+            | Prim::Int
+            | Prim::Pauli
+            | Prim::Result
+            | Prim::String,
+        ) => true,
+        Ty::Array(item) => is_arg_ty(item),
+        Ty::Tuple(items) => items.iter().all(is_arg_ty),
+        _ => false,
+    }
+}
+
 struct EntryPointFinder<'a> {
     callables: Vec<&'a CallableDecl>,
 }
@@ -3,9 +3,14 @@
 
 use expect_test::{expect, Expect};
 use indoc::indoc;
+use qsc_data_structures::span::Span;
 use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_hir::{
+    hir::{Expr, ExprKind, Lit, NodeId},
+    ty::{Prim, Ty},
+};
 
-use crate::entry_point::extract_entry;
+use crate::entry_point::{bind_entry, extract_entry, find_entry};
 
 fn check(file: &str, expr: &str, expect: &Expect) {
     let sources = SourceMap::new([("test".into(), file.into())], Some(expr.into()));
@@ -18,6 +23,26 @@ fn check(file: &str, expr: &str, expect: &Expect) {
     }
 }
 
+fn check_with_args(file: &str, args: Vec<Expr>, expect: &Expect) {
+    let sources = SourceMap::new([("test".into(), file.into())], None);
+    let unit = compile(&PackageStore::new(compile::core()), &[], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+
+    match find_entry(&unit.package).and_then(|ep| bind_entry(ep, args)) {
+        Ok(entry) => expect.assert_eq(&entry.to_string()),
+        Err(errors) => expect.assert_debug_eq(&errors),
+    }
+}
+
+fn lit(ty: Prim, lit: Lit) -> Expr {
+    Expr {
+        id: NodeId::default(),
+        span: Span::default(),
+        ty: Ty::Prim(ty),
+        kind: ExprKind::Lit(lit),
+    }
+}
+
 #[test]
 fn test_entry_point_attr_to_expr() {
     check(
@@ -89,3 +114,89 @@ fn test_entry_point_attr_multiple() {
         "#]],
     );
 }
+
+#[test]
+fn test_entry_point_params_bound_to_args() {
+    check_with_args(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main(n : Int, b : Bool) : Int { n }
+            }"},
+        vec![
+            lit(Prim::Int, Lit::Int(3)),
+            lit(Prim::Bool, Lit::Bool(true)),
+        ],
+        &expect![[r#"
+            Expr _id_ [0-0] [Type Int]: Expr Block: Block _id_ [0-0] [Type Int]:
+                Stmt _id_ [0-0]: Local (Immutable):
+                    Pat 2 [53-72] [Type (Int, Bool)]: Tuple:
+                        Pat 3 [54-61] [Type Int]: Bind: Ident 4 [54-55] "n"
+                        Pat 5 [63-71] [Type Bool]: Bind: Ident 6 [63-64] "b"
+                    Expr _id_ [0-0] [Type (Int, Bool)]: Tuple:
+                        Expr _id_ [0-0] [Type Int]: Lit: Int(3)
+                        Expr _id_ [0-0] [Type Bool]: Lit: Bool(true)
+                Stmt _id_ [0-0]: Expr: Expr _id_ [0-0] [Type Int]: Expr Block: Block 8 [79-84] [Type Int]:
+                    Stmt 9 [81-82]: Expr: Expr 10 [81-82] [Type Int]: Var: Local 4"#]],
+    );
+}
+
+#[test]
+fn test_entry_point_params_without_args() {
+    check(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main(n : Int) : Int { n }
+            }"},
+        "",
+        &expect![[r"
+            [
+                EntryPoint(
+                    Args(
+                        1,
+                        0,
+                        Span {
+                            lo: 54,
+                            hi: 61,
+                        },
+                    ),
+                ),
+            ]
+        "]],
+    );
+}
+
+#[test]
+fn test_entry_point_param_ty_unsupported() {
+    check_with_args(
+        indoc! {"
+            namespace Test {
+                @EntryPoint()
+                operation Main(q : Qubit, n : Int, f : (Int -> Int)) : Unit {}
+            }"},
+        vec![],
+        &expect![[r#"
+            [
+                EntryPoint(
+                    ParamTy(
+                        "Qubit",
+                        Span {
+                            lo: 54,
+                            hi: 63,
+                        },
+                    ),
+                ),
+                EntryPoint(
+                    ParamTy(
+                        "(Int -> Int)",
+                        Span {
+                            lo: 74,
+                            hi: 90,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
@@ -11,6 +11,7 @@ pub mod entry_point;
 mod invert_block;
 mod logic_sep;
 mod loop_unification;
+pub mod qubit_count;
 mod replace_qubit_allocation;
 mod spec_gen;
 
@@ -2,7 +2,7 @@
 // Licensed under the MIT License.
 
 use core::panic;
-use std::{mem::take, rc::Rc};
+use std::{mem::take, sync::Arc};
 
 use qsc_data_structures::span::Span;
 use qsc_hir::{
@@ -298,7 +298,7 @@ impl LoopUni<'_> {
             id,
             span,
             ty,
-            name: Rc::from(format!("{label}_{id}")),
+            name: Arc::from(format!("{label}_{id}")),
         }
     }
 }
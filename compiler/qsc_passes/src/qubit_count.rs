@@ -0,0 +1,569 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Static bounds on the number of qubits that a callable can have allocated at the same time,
+//! found without running it. Qubits are released at the end of the block that allocates them, so
+//! a loop needs only as many qubits as one of its iterations, and a branch as many as the larger
+//! of its two sides. The size of a qubit array is known when it is built from integer literals,
+//! immutable locals and `Int` parameters of the callable, and a bound on a callable with such
+//! parameters is written in terms of them, like `n + 1`. Callable arguments are followed into the
+//! callables they are passed to, so a call like `ApplyToEach(H, qs)` is bounded too.
+//!
+//! Allocations are recognized both as `use` and `borrow` statements and as the calls to
+//! `__quantum__rt__qubit_allocate` and `AllocateQubitArray` that the `ReplaceQubitAllocation` pass
+//! rewrites them into.
+
+#[cfg(test)]
+mod tests;
+
+use qsc_data_structures::span::Span;
+use qsc_frontend::compile::PackageStore;
+use qsc_hir::{
+    hir::{
+        BinOp, Block, CallableDecl, CallableKind, Expr, ExprKind, Functor, ItemKind, Lit,
+        LocalItemId, Mutability, NodeId, Package, PackageId, Pat, PatKind, QubitInit,
+        QubitInitKind, Res, SpecBody, StmtKind, StringComponent, UnOp,
+    },
+    ty::{Prim, Ty},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+/// An upper bound on the number of qubits allocated at the same time.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum QubitBound {
+    Bounded(Size),
+    /// There is no bound because of the expression at the span, like an array size that is not
+    /// known or a recursive call.
+    Unbounded(Span),
+}
+
+impl QubitBound {
+    fn zero() -> Self {
+        Self::Bounded(Size::default())
+    }
+
+    fn constant(n: u64) -> Self {
+        Self::Bounded(Size::constant(n))
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Bounded(a), Self::Bounded(b)) => Self::Bounded(a.add(b)),
+            (Self::Unbounded(span), _) | (_, Self::Unbounded(span)) => Self::Unbounded(*span),
+        }
+    }
+
+    fn max(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Bounded(a), Self::Bounded(b)) => Self::Bounded(a.max(b)),
+            (Self::Unbounded(span), _) | (_, Self::Unbounded(span)) => Self::Unbounded(*span),
+        }
+    }
+}
+
+impl Display for QubitBound {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Bounded(size) => Display::fmt(size, f),
+            Self::Unbounded(_) => f.write_str("unbounded"),
+        }
+    }
+}
+
+/// A number of qubits that is a constant plus a multiple of each of some `Int` parameters.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Size {
+    constant: u64,
+    params: BTreeMap<Arc<str>, u64>,
+}
+
+impl Size {
+    fn constant(n: u64) -> Self {
+        Self {
+            constant: n,
+            params: BTreeMap::new(),
+        }
+    }
+
+    fn param(name: Arc<str>) -> Self {
+        Self {
+            constant: 0,
+            params: [(name, 1)].into(),
+        }
+    }
+
+    /// The size if it does not depend on any parameter.
+    #[must_use]
+    pub fn as_constant(&self) -> Option<u64> {
+        self.params.is_empty().then_some(self.constant)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let mut params = self.params.clone();
+        for (name, &k) in &other.params {
+            let entry = params.entry(Arc::clone(name)).or_default();
+            *entry = entry.saturating_add(k);
+        }
+        Self {
+            constant: self.constant.saturating_add(other.constant),
+            params,
+        }
+    }
+
+    /// A size at least as large as both sizes for any non-negative parameters.
+    fn max(&self, other: &Self) -> Self {
+        let mut params = self.params.clone();
+        for (name, &k) in &other.params {
+            let entry = params.entry(Arc::clone(name)).or_default();
+            *entry = (*entry).max(k);
+        }
+        Self {
+            constant: self.constant.max(other.constant),
+            params,
+        }
+    }
+
+    fn scale(&self, k: u64) -> Self {
+        Self {
+            constant: self.constant.saturating_mul(k),
+            params: self
+                .params
+                .iter()
+                .filter(|_| k > 0)
+                .map(|(name, &j)| (Arc::clone(name), j.saturating_mul(k)))
+                .collect(),
+        }
+    }
+}
+
+impl Display for Size {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut terms: Vec<_> = self
+            .params
+            .iter()
+            .map(|(name, &k)| match k {
+                1 => name.to_string(),
+                _ => format!("{k} * {name}"),
+            })
+            .collect();
+        if self.constant > 0 || terms.is_empty() {
+            terms.push(self.constant.to_string());
+        }
+        f.write_str(&terms.join(" + "))
+    }
+}
+
+/// A callable item, identified by its package, where `None` is the package being analyzed.
+type ItemKey = (Option<PackageId>, LocalItemId);
+
+/// A callable, whether its controlled specializations are called, and the known parameters.
+type BoundKey = (ItemKey, bool, Vec<(NodeId, Binding)>);
+
+/// What is statically known about the value of a local variable.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Binding {
+    Size(Size),
+    Callable(Callee),
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Callee {
+    item: ItemKey,
+    /// The number of times the controlled functor is applied, each of which wraps the argument in
+    /// a tuple with the control qubits.
+    controls: usize,
+    /// True if the callee is a closure, whose input also has the captured variables.
+    closure: bool,
+}
+
+/// The qubits that an expression has allocated at most at any point while it is evaluated, and
+/// those that are still allocated after it is evaluated.
+struct Effect {
+    peak: QubitBound,
+    retained: QubitBound,
+}
+
+impl Effect {
+    fn none() -> Self {
+        Self {
+            peak: QubitBound::zero(),
+            retained: QubitBound::zero(),
+        }
+    }
+
+    fn transient(peak: QubitBound) -> Self {
+        Self {
+            peak,
+            retained: QubitBound::zero(),
+        }
+    }
+
+    /// The effect of evaluating this and then the other.
+    fn then(self, other: &Self) -> Self {
+        Self {
+            peak: self.peak.max(&self.retained.add(&other.peak)),
+            retained: self.retained.add(&other.retained),
+        }
+    }
+
+    /// The effect of evaluating either this or the other.
+    fn or(self, other: &Self) -> Self {
+        Self {
+            peak: self.peak.max(&other.peak),
+            retained: self.retained.max(&other.retained),
+        }
+    }
+}
+
+struct Scope {
+    package: Option<PackageId>,
+    locals: HashMap<NodeId, Binding>,
+}
+
+/// Finds qubit bounds for the callables of a package and the callables they call in the packages
+/// of the store. The package does not need to be in the store.
+pub struct QubitAnalysis<'a> {
+    store: &'a PackageStore,
+    package: &'a Package,
+    bounds: HashMap<BoundKey, QubitBound>,
+    active: Vec<ItemKey>,
+}
+
+impl<'a> QubitAnalysis<'a> {
+    #[must_use]
+    pub fn new(store: &'a PackageStore, package: &'a Package) -> Self {
+        Self {
+            store,
+            package,
+            bounds: HashMap::new(),
+            active: Vec::new(),
+        }
+    }
+
+    /// The bound on the qubits allocated by a call to the callable item in the package, in terms
+    /// of its `Int` parameters, or `None` if the item is not a callable. The controlled
+    /// specializations are only part of the bound on calls that apply the controlled functor.
+    pub fn callable_bound(&mut self, item: LocalItemId) -> Option<QubitBound> {
+        let decl = self.decl((None, item))?;
+        let mut params = Vec::new();
+        int_params(&decl.input, &mut params);
+        Some(self.call_bound((None, item), false, params))
+    }
+
+    fn decl(&self, (package, item): ItemKey) -> Option<&'a CallableDecl> {
+        let package = match package {
+            None => self.package,
+            Some(id) => &self.store.get(id)?.package,
+        };
+        match &package.items.get(item)?.kind {
+            ItemKind::Callable(decl) => Some(decl),
+            _ => None,
+        }
+    }
+
+    fn call_bound(
+        &mut self,
+        item: ItemKey,
+        controlled: bool,
+        mut params: Vec<(NodeId, Binding)>,
+    ) -> QubitBound {
+        let Some(decl) = self.decl(item) else {
+            return QubitBound::zero();
+        };
+        if decl.kind == CallableKind::Function {
+            return QubitBound::zero();
+        }
+        if self.active.contains(&item) {
+            return QubitBound::Unbounded(decl.name.span);
+        }
+        params.sort_by_key(|(id, _)| *id);
+        let key = (item, controlled, params);
+        if let Some(bound) = self.bounds.get(&key) {
+            return bound.clone();
+        }
+
+        self.active.push(item);
+        let mut scope = Scope {
+            package: item.0,
+            locals: key.2.iter().cloned().collect(),
+        };
+        let mut bound = QubitBound::zero();
+        let specs = if controlled {
+            [decl.ctl.as_ref(), decl.ctl_adj.as_ref()]
+        } else {
+            [Some(&decl.body), decl.adj.as_ref()]
+        };
+        for spec in specs.into_iter().flatten() {
+            if let SpecBody::Impl(_, block) = &spec.body {
+                bound = bound.max(&self.block(&mut scope, block).peak);
+            }
+        }
+        self.active.pop();
+        self.bounds.insert(key, bound.clone());
+        bound
+    }
+
+    fn block(&mut self, scope: &mut Scope, block: &Block) -> Effect {
+        let mut effect = Effect::none();
+        for stmt in &block.stmts {
+            match &stmt.kind {
+                StmtKind::Expr(expr) | StmtKind::Semi(expr) => {
+                    effect = effect.then(&self.expr(scope, expr));
+                }
+                StmtKind::Local(mutability, pat, expr) => {
+                    effect = effect.then(&self.expr(scope, expr));
+                    if let (Mutability::Immutable, PatKind::Bind(ident)) = (mutability, &pat.kind) {
+                        if let Some(binding) = binding(scope, expr) {
+                            scope.locals.insert(ident.id, binding);
+                        }
+                    }
+                }
+                StmtKind::Qubit(_, _, init, block) => {
+                    let qubits = init_size(scope, init);
+                    effect = effect.then(&match block {
+                        Some(block) => {
+                            Effect::transient(qubits.add(&self.block(scope, block).peak))
+                        }
+                        None => Effect {
+                            peak: qubits.clone(),
+                            retained: qubits,
+                        },
+                    });
+                }
+                StmtKind::Item(_) => {}
+            }
+        }
+        Effect::transient(effect.peak)
+    }
+
+    fn expr(&mut self, scope: &mut Scope, expr: &Expr) -> Effect {
+        match &expr.kind {
+            ExprKind::Block(block) => self.block(scope, block),
+            ExprKind::Call(callee, arg) => {
+                let effect = self.expr(scope, callee).then(&self.expr(scope, arg));
+                effect.then(&self.call(scope, callee, arg, expr.span))
+            }
+            ExprKind::Conjugate(within, apply) => {
+                let within = self.block(scope, within);
+                within.then(&self.block(scope, apply))
+            }
+            ExprKind::For(_, iter, block) => {
+                let iter = self.expr(scope, iter);
+                iter.then(&self.block(scope, block))
+            }
+            ExprKind::If(cond, then, otherwise) => {
+                let cond = self.expr(scope, cond);
+                let then = self.expr(scope, then);
+                let otherwise = match otherwise {
+                    Some(otherwise) => self.expr(scope, otherwise),
+                    None => Effect::none(),
+                };
+                cond.then(&then.or(&otherwise))
+            }
+            ExprKind::Repeat(body, cond, fixup) => {
+                let mut effect = self.block(scope, body).then(&self.expr(scope, cond));
+                if let Some(fixup) = fixup {
+                    effect = effect.then(&self.block(scope, fixup));
+                }
+                effect
+            }
+            ExprKind::While(cond, block) => {
+                let cond = self.expr(scope, cond);
+                cond.then(&self.block(scope, block))
+            }
+            ExprKind::Array(exprs) | ExprKind::Tuple(exprs) => self.exprs(scope, exprs),
+            ExprKind::ArrayRepeat(a, b)
+            | ExprKind::Assign(a, b)
+            | ExprKind::AssignOp(_, a, b)
+            | ExprKind::AssignField(a, _, b)
+            | ExprKind::BinOp(_, a, b)
+            | ExprKind::Index(a, b)
+            | ExprKind::UpdateField(a, _, b) => self.exprs(scope, [&**a, &**b]),
+            ExprKind::AssignIndex(a, b, c) | ExprKind::UpdateIndex(a, b, c) => {
+                self.exprs(scope, [&**a, &**b, &**c])
+            }
+            ExprKind::Fail(a)
+            | ExprKind::Field(a, _)
+            | ExprKind::Return(a)
+            | ExprKind::UnOp(_, a) => self.expr(scope, a),
+            ExprKind::Range(start, step, end) => self.exprs(
+                scope,
+                [start, step, end].into_iter().flatten().map(|e| &**e),
+            ),
+            ExprKind::String(components) => self.exprs(
+                scope,
+                components.iter().filter_map(|component| match component {
+                    StringComponent::Expr(expr) => Some(expr),
+                    StringComponent::Lit(_) => None,
+                }),
+            ),
+            ExprKind::Closure(..)
+            | ExprKind::Err
+            | ExprKind::Hole
+            | ExprKind::Lit(_)
+            | ExprKind::Var(..) => Effect::none(),
+        }
+    }
+
+    fn exprs<'b>(
+        &mut self,
+        scope: &mut Scope,
+        exprs: impl IntoIterator<Item = &'b Expr>,
+    ) -> Effect {
+        exprs.into_iter().fold(Effect::none(), |effect, expr| {
+            effect.then(&self.expr(scope, expr))
+        })
+    }
+
+    fn call(&mut self, scope: &Scope, callee: &Expr, arg: &Expr, span: Span) -> Effect {
+        let Some(target) = callee_of(scope, callee) else {
+            return match &callee.ty {
+                Ty::Arrow(arrow) if arrow.kind == CallableKind::Function => Effect::none(),
+                _ => Effect::transient(QubitBound::Unbounded(span)),
+            };
+        };
+        let Some(decl) = self.decl(target.item) else {
+            return Effect::none();
+        };
+
+        if target.item.0 == Some(PackageId::CORE) {
+            let allocated = match decl.name.name.as_ref() {
+                "__quantum__rt__qubit_allocate" => Some(QubitBound::constant(1)),
+                "AllocateQubitArray" => Some(size_bound(scope, arg)),
+                _ => None,
+            };
+            if let Some(allocated) = allocated {
+                return Effect {
+                    peak: allocated.clone(),
+                    retained: allocated,
+                };
+            }
+        }
+
+        let mut params = Vec::new();
+        if !target.closure {
+            if let Some(arg) = strip_controls(arg, target.controls) {
+                bind_params(scope, &decl.input, arg, &mut params);
+            }
+        }
+        match self.call_bound(target.item, target.controls > 0, params) {
+            QubitBound::Unbounded(_) => Effect::transient(QubitBound::Unbounded(span)),
+            bound @ QubitBound::Bounded(_) => Effect::transient(bound),
+        }
+    }
+}
+
+/// Binds each `Int` parameter in the pattern to a size in terms of that parameter.
+fn int_params(pat: &Pat, params: &mut Vec<(NodeId, Binding)>) {
+    match &pat.kind {
+        PatKind::Bind(ident) if pat.ty == Ty::Prim(Prim::Int) => params.push((
+            ident.id,
+            Binding::Size(Size::param(Arc::clone(&ident.name))),
+        )),
+        PatKind::Tuple(pats) => pats.iter().for_each(|pat| int_params(pat, params)),
+        PatKind::Bind(_) | PatKind::Discard => {}
+    }
+}
+
+/// Binds the parameters in the pattern to what is known about the parts of the argument.
+fn bind_params(scope: &Scope, pat: &Pat, arg: &Expr, params: &mut Vec<(NodeId, Binding)>) {
+    match (&pat.kind, &arg.kind) {
+        (PatKind::Bind(ident), _) => {
+            if let Some(binding) = binding(scope, arg) {
+                params.push((ident.id, binding));
+            }
+        }
+        (PatKind::Tuple(pats), ExprKind::Tuple(args)) if pats.len() == args.len() => {
+            for (pat, arg) in pats.iter().zip(args) {
+                bind_params(scope, pat, arg, params);
+            }
+        }
+        (PatKind::Tuple(pats), _) if pats.len() == 1 => bind_params(scope, &pats[0], arg, params),
+        _ => {}
+    }
+}
+
+/// The argument of the callable that the controlled functor was applied to.
+fn strip_controls(arg: &Expr, controls: usize) -> Option<&Expr> {
+    (0..controls).try_fold(arg, |arg, _| match &arg.kind {
+        ExprKind::Tuple(items) if items.len() == 2 => Some(&items[1]),
+        _ => None,
+    })
+}
+
+fn binding(scope: &Scope, expr: &Expr) -> Option<Binding> {
+    size(scope, expr)
+        .map(Binding::Size)
+        .or_else(|| callee_of(scope, expr).map(Binding::Callable))
+}
+
+fn callee_of(scope: &Scope, expr: &Expr) -> Option<Callee> {
+    match &expr.kind {
+        ExprKind::Var(Res::Item(id), _) => Some(Callee {
+            item: (id.package.or(scope.package), id.item),
+            controls: 0,
+            closure: false,
+        }),
+        ExprKind::Var(Res::Local(id), _) => match scope.locals.get(id) {
+            Some(Binding::Callable(callee)) => Some(*callee),
+            _ => None,
+        },
+        ExprKind::UnOp(UnOp::Functor(functor), inner) => {
+            let mut callee = callee_of(scope, inner)?;
+            if *functor == Functor::Ctl {
+                callee.controls += 1;
+            }
+            Some(callee)
+        }
+        ExprKind::Closure(_, item) => Some(Callee {
+            item: (scope.package, *item),
+            controls: 0,
+            closure: true,
+        }),
+        _ => None,
+    }
+}
+
+/// The value of an `Int` expression as a size, if it is known. Subtraction and division by a
+/// non-negative literal are bounded by the left side, since sizes are never negative.
+fn size(scope: &Scope, expr: &Expr) -> Option<Size> {
+    match &expr.kind {
+        ExprKind::Lit(Lit::Int(n)) => Some(Size::constant(u64::try_from(*n).unwrap_or(0))),
+        ExprKind::Var(Res::Local(id), _) => match scope.locals.get(id) {
+            Some(Binding::Size(size)) => Some(size.clone()),
+            _ => None,
+        },
+        ExprKind::BinOp(BinOp::Add, lhs, rhs) => Some(size(scope, lhs)?.add(&size(scope, rhs)?)),
+        ExprKind::BinOp(BinOp::Mul, lhs, rhs) => {
+            let (lhs, rhs) = (size(scope, lhs)?, size(scope, rhs)?);
+            match (lhs.as_constant(), rhs.as_constant()) {
+                (Some(k), _) => Some(rhs.scale(k)),
+                (_, Some(k)) => Some(lhs.scale(k)),
+                (None, None) => None,
+            }
+        }
+        ExprKind::BinOp(BinOp::Sub | BinOp::Div, lhs, rhs) => match rhs.kind {
+            ExprKind::Lit(Lit::Int(n)) if n >= 0 => size(scope, lhs),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn size_bound(scope: &Scope, expr: &Expr) -> QubitBound {
+    size(scope, expr).map_or(QubitBound::Unbounded(expr.span), QubitBound::Bounded)
+}
+
+fn init_size(scope: &Scope, init: &QubitInit) -> QubitBound {
+    match &init.kind {
+        QubitInitKind::Array(size) => size_bound(scope, size),
+        QubitInitKind::Single => QubitBound::constant(1),
+        QubitInitKind::Tuple(inits) => inits.iter().fold(QubitBound::zero(), |bound, init| {
+            bound.add(&init_size(scope, init))
+        }),
+    }
+}
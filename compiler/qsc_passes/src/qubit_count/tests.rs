@@ -0,0 +1,251 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{QubitAnalysis, QubitBound};
+use crate::run_default_passes;
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_hir::hir::ItemKind;
+use std::fmt::Write;
+
+fn check(file: &str, expect: &Expect) {
+    let store = PackageStore::new(compile::core());
+    let sources = SourceMap::new([("test".into(), file.into())], None);
+    let mut unit = compile(&store, &[], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    let errors = run_default_passes(store.core(), &mut unit);
+    assert!(errors.is_empty(), "{errors:?}");
+
+    let mut analysis = QubitAnalysis::new(&store, &unit.package);
+    let mut actual = String::new();
+    for (id, item) in unit.package.items.iter() {
+        if let ItemKind::Callable(decl) = &item.kind {
+            let bound = analysis
+                .callable_bound(id)
+                .expect("item should be a callable");
+            write!(actual, "{}: {bound}", decl.name.name).expect("string should be writable");
+            if let QubitBound::Unbounded(span) = bound {
+                let source = &file[span.lo as usize..span.hi as usize];
+                write!(actual, " at `{source}`").expect("string should be writable");
+            }
+            actual.push('\n');
+        }
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn single_qubits() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo() : Unit {
+                    use q = Qubit();
+                    use (a, b) = (Qubit(), Qubit());
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: 3
+        "]],
+    );
+}
+
+#[test]
+fn nested_blocks_release_qubits() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo() : Unit {
+                    use q = Qubit();
+                    {
+                        use qs = Qubit[4];
+                    }
+                    use r = Qubit[2] {
+                        use s = Qubit();
+                    }
+                    for i in 0..10 {
+                        use t = Qubit[3];
+                    }
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: 5
+        "]],
+    );
+}
+
+#[test]
+fn branches_take_larger_side() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo(b : Bool) : Unit {
+                    if b {
+                        use qs = Qubit[3];
+                    } else {
+                        use q = Qubit();
+                    }
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: 3
+        "]],
+    );
+}
+
+#[test]
+fn size_from_locals_and_params() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo(n : Int, m : Int) : Unit {
+                    let k = 2 * n + 1;
+                    use qs = Qubit[k];
+                    use rs = Qubit[m - 1];
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: m + 2 * n + 1
+        "]],
+    );
+}
+
+#[test]
+fn calls_substitute_arguments() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo(n : Int) : Unit {
+                    use qs = Qubit[n];
+                }
+                operation Bar() : Unit {
+                    use q = Qubit();
+                    Foo(4);
+                    Foo(2);
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: n
+            Bar: 5
+        "]],
+    );
+}
+
+#[test]
+fn callable_arguments_are_followed() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo(q : Qubit) : Unit {
+                    use aux = Qubit[2];
+                }
+                operation Apply(op : Qubit => Unit, q : Qubit) : Unit {
+                    op(q);
+                }
+                operation Bar() : Unit {
+                    use q = Qubit();
+                    Apply(Foo, q);
+                    let op = Foo;
+                    op(q);
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: 2
+            Apply: unbounded at `op(q)`
+            Bar: 3
+        "]],
+    );
+}
+
+#[test]
+fn controlled_calls_bind_inner_arguments() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo(n : Int) : Unit is Ctl {
+                    use qs = Qubit[n];
+                }
+                operation Bar() : Unit {
+                    use c = Qubit();
+                    Controlled Foo([c], 3);
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: n
+            Bar: 4
+        "]],
+    );
+}
+
+#[test]
+fn plain_calls_skip_controlled_specializations() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo(q : Qubit) : Unit is Ctl {
+                    body ... {}
+                    controlled (cs, ...) {
+                        use aux = Qubit[Length(cs)];
+                    }
+                }
+                operation Bar() : Unit {
+                    use q = Qubit();
+                    Foo(q);
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: 0
+            Bar: 1
+        "]],
+    );
+}
+
+#[test]
+fn unknown_size_is_unbounded() {
+    check(
+        indoc! {"
+            namespace Test {
+                function Size() : Int { 3 }
+                operation Foo() : Unit {
+                    use qs = Qubit[Size()];
+                }
+                operation Bar() : Unit {
+                    Foo();
+                }
+            }
+        "},
+        &expect![[r"
+            Size: 0
+            Foo: unbounded at `Size()`
+            Bar: unbounded at `Foo()`
+        "]],
+    );
+}
+
+#[test]
+fn recursion_is_unbounded() {
+    check(
+        indoc! {"
+            namespace Test {
+                operation Foo(n : Int) : Unit {
+                    if n > 0 {
+                        use q = Qubit();
+                        Foo(n - 1);
+                    }
+                }
+            }
+        "},
+        &expect![[r"
+            Foo: unbounded at `Foo(n - 1)`
+        "]],
+    );
+}
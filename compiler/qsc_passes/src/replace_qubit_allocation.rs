@@ -15,7 +15,7 @@ use qsc_hir::{
     mut_visit::{walk_expr, walk_stmt, MutVisitor},
     ty::{Prim, Ty},
 };
-use std::{mem::take, rc::Rc};
+use std::{mem::take, sync::Arc};
 
 use crate::common::{create_gen_core_ref, IdentTemplate};
 
@@ -186,7 +186,7 @@ impl<'a> ReplaceQubitAllocation<'a> {
         IdentTemplate {
             id,
             span,
-            name: Rc::from(format!("generated_ident_{id}")),
+            name: Arc::from(format!("generated_ident_{id}")),
             ty,
         }
     }
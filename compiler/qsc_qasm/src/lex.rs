@@ -0,0 +1,259 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! The lexer turns OpenQASM source into a flat list of tokens. Keywords are treated as
+//! identifiers, and whitespace and comments are discarded. Tokens never contain substrings from the
+//! original input, but are simply labels that refer back to regions in the input.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, ErrorKind};
+use qsc_data_structures::span::Span;
+use std::{
+    fmt::{self, Display, Formatter},
+    iter::Peekable,
+    str::CharIndices,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct Token {
+    pub(super) kind: TokenKind,
+    pub(super) span: Span,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum TokenKind {
+    /// `->`
+    Arrow,
+    /// `@`
+    At,
+    /// `^`
+    Caret,
+    /// A closing delimiter.
+    Close(Delim),
+    /// `:`
+    Colon,
+    /// `,`
+    Comma,
+    /// End of file.
+    Eof,
+    /// `=`
+    Eq,
+    /// `==`
+    EqEq,
+    /// A floating-point literal.
+    Float,
+    /// An identifier or keyword.
+    Ident,
+    /// An integer literal.
+    Int,
+    /// `-`
+    Minus,
+    /// An opening delimiter.
+    Open(Delim),
+    /// Any other operator, which is not used by the supported subset of OpenQASM.
+    Op,
+    /// `+`
+    Plus,
+    /// `;`
+    Semi,
+    /// `/`
+    Slash,
+    /// `*`
+    Star,
+    /// A string literal.
+    String,
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Arrow => f.write_str("`->`"),
+            TokenKind::At => f.write_str("`@`"),
+            TokenKind::Caret => f.write_str("`^`"),
+            TokenKind::Close(Delim::Brace) => f.write_str("`}`"),
+            TokenKind::Close(Delim::Bracket) => f.write_str("`]`"),
+            TokenKind::Close(Delim::Paren) => f.write_str("`)`"),
+            TokenKind::Colon => f.write_str("`:`"),
+            TokenKind::Comma => f.write_str("`,`"),
+            TokenKind::Eof => f.write_str("EOF"),
+            TokenKind::Eq => f.write_str("`=`"),
+            TokenKind::EqEq => f.write_str("`==`"),
+            TokenKind::Float => f.write_str("float"),
+            TokenKind::Ident => f.write_str("identifier"),
+            TokenKind::Int => f.write_str("integer"),
+            TokenKind::Minus => f.write_str("`-`"),
+            TokenKind::Open(Delim::Brace) => f.write_str("`{`"),
+            TokenKind::Open(Delim::Bracket) => f.write_str("`[`"),
+            TokenKind::Open(Delim::Paren) => f.write_str("`(`"),
+            TokenKind::Op => f.write_str("operator"),
+            TokenKind::Plus => f.write_str("`+`"),
+            TokenKind::Semi => f.write_str("`;`"),
+            TokenKind::Slash => f.write_str("`/`"),
+            TokenKind::Star => f.write_str("`*`"),
+            TokenKind::String => f.write_str("string"),
+        }
+    }
+}
+
+/// A delimiter token.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Delim {
+    /// `{` `}`
+    Brace,
+    /// `[` `]`
+    Bracket,
+    /// `(` `)`
+    Paren,
+}
+
+/// Splits the input into tokens, ending with an EOF token.
+pub(super) fn tokenize(input: &str) -> (Vec<Token>, Vec<Error>) {
+    let mut lexer = Lexer {
+        chars: input.char_indices().peekable(),
+        len: input.len(),
+        tokens: Vec::new(),
+        errors: Vec::new(),
+    };
+    lexer.run();
+    (lexer.tokens, lexer.errors)
+}
+
+struct Lexer<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    len: usize,
+    tokens: Vec<Token>,
+    errors: Vec<Error>,
+}
+
+impl Lexer<'_> {
+    fn run(&mut self) {
+        while let Some((lo, c)) = self.chars.next() {
+            let kind = match c {
+                _ if c.is_whitespace() => None,
+                '/' if self.next_if_eq('/') => {
+                    self.skip_line();
+                    None
+                }
+                '/' if self.next_if_eq('*') => {
+                    self.skip_block_comment();
+                    None
+                }
+                '"' => self.string(lo),
+                '0'..='9' => Some(self.number(false)),
+                '.' if matches!(self.peek(), Some(c) if c.is_ascii_digit()) => {
+                    Some(self.number(true))
+                }
+                '-' if self.next_if_eq('>') => Some(TokenKind::Arrow),
+                '=' if self.next_if_eq('=') => Some(TokenKind::EqEq),
+                '@' => Some(TokenKind::At),
+                '^' => Some(TokenKind::Caret),
+                ':' => Some(TokenKind::Colon),
+                ',' => Some(TokenKind::Comma),
+                '=' => Some(TokenKind::Eq),
+                '-' => Some(TokenKind::Minus),
+                '+' => Some(TokenKind::Plus),
+                ';' => Some(TokenKind::Semi),
+                '/' => Some(TokenKind::Slash),
+                '*' => Some(TokenKind::Star),
+                '{' => Some(TokenKind::Open(Delim::Brace)),
+                '[' => Some(TokenKind::Open(Delim::Bracket)),
+                '(' => Some(TokenKind::Open(Delim::Paren)),
+                '}' => Some(TokenKind::Close(Delim::Brace)),
+                ']' => Some(TokenKind::Close(Delim::Bracket)),
+                ')' => Some(TokenKind::Close(Delim::Paren)),
+                '!' | '<' | '>' | '&' | '|' | '%' | '~' | '.' | '$' => {
+                    while self.next_if(|c| matches!(c, '=' | '<' | '>' | '&' | '|')) {}
+                    Some(TokenKind::Op)
+                }
+                _ if c == '_' || c.is_alphabetic() => {
+                    while self.next_if(|c| c == '_' || c.is_alphanumeric()) {}
+                    Some(TokenKind::Ident)
+                }
+                _ => {
+                    let span = self.span(lo);
+                    self.errors.push(Error(ErrorKind::UnknownChar(c, span)));
+                    None
+                }
+            };
+
+            if let Some(kind) = kind {
+                let span = self.span(lo);
+                self.tokens.push(Token { kind, span });
+            }
+        }
+
+        let eof = self.span(self.len);
+        self.tokens.push(Token {
+            kind: TokenKind::Eof,
+            span: eof,
+        });
+    }
+
+    /// Lexes the rest of a number, where `fractional` is true if the decimal point has already
+    /// been consumed.
+    fn number(&mut self, fractional: bool) -> TokenKind {
+        let mut kind = if fractional {
+            TokenKind::Float
+        } else {
+            TokenKind::Int
+        };
+        while self.next_if(|c| c.is_ascii_digit() || c == '_') {}
+        if !fractional && self.next_if_eq('.') {
+            kind = TokenKind::Float;
+            while self.next_if(|c| c.is_ascii_digit() || c == '_') {}
+        }
+        if self.next_if(|c| c == 'e' || c == 'E') {
+            kind = TokenKind::Float;
+            self.next_if(|c| c == '+' || c == '-');
+            while self.next_if(|c| c.is_ascii_digit()) {}
+        }
+        kind
+    }
+
+    fn string(&mut self, lo: usize) -> Option<TokenKind> {
+        for (_, c) in self.chars.by_ref() {
+            if c == '"' {
+                return Some(TokenKind::String);
+            }
+        }
+
+        let span = self.span(lo);
+        self.errors.push(Error(ErrorKind::UnterminatedString(span)));
+        None
+    }
+
+    fn skip_line(&mut self) {
+        while self.next_if(|c| c != '\n') {}
+    }
+
+    fn skip_block_comment(&mut self) {
+        while let Some((_, c)) = self.chars.next() {
+            if c == '*' && self.next_if_eq('/') {
+                return;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn next_if(&mut self, f: impl FnOnce(char) -> bool) -> bool {
+        self.chars.next_if(|&(_, c)| f(c)).is_some()
+    }
+
+    fn next_if_eq(&mut self, c: char) -> bool {
+        self.next_if(|next| next == c)
+    }
+
+    /// The span from `lo` to the current position.
+    fn span(&mut self, lo: usize) -> Span {
+        let hi = self.chars.peek().map_or(self.len, |&(i, _)| i);
+        Span {
+            lo: lo.try_into().expect("offset should fit into u32"),
+            hi: hi.try_into().expect("offset should fit into u32"),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::tokenize;
+use expect_test::{expect, Expect};
+use std::fmt::Write;
+
+fn check(input: &str, expect: &Expect) {
+    let (tokens, errors) = tokenize(input);
+    let mut actual = String::new();
+    for token in tokens {
+        writeln!(
+            actual,
+            "{:?} {}: {:?}",
+            token.kind, token.span, &input[token.span]
+        )
+        .expect("writing to string should succeed");
+    }
+    for error in errors {
+        writeln!(actual, "{error:?}").expect("writing to string should succeed");
+    }
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn header() {
+    check(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";",
+        &expect![[r#"
+            Ident [0-8]: "OPENQASM"
+            Float [9-12]: "2.0"
+            Semi [12-13]: ";"
+            Ident [14-21]: "include"
+            String [22-34]: "\"qelib1.inc\""
+            Semi [34-35]: ";"
+            Eof [35-35]: ""
+        "#]],
+    );
+}
+
+#[test]
+fn numbers() {
+    check(
+        "1 2.5 .5 1e3 1.5E-2 1_000",
+        &expect![[r#"
+        Int [0-1]: "1"
+        Float [2-5]: "2.5"
+        Float [6-8]: ".5"
+        Float [9-12]: "1e3"
+        Float [13-19]: "1.5E-2"
+        Int [20-25]: "1_000"
+        Eof [25-25]: ""
+    "#]],
+    );
+}
+
+#[test]
+fn operators() {
+    check(
+        "-> == = - + * / ^ @ != <= ~",
+        &expect![[r#"
+        Arrow [0-2]: "->"
+        EqEq [3-5]: "=="
+        Eq [6-7]: "="
+        Minus [8-9]: "-"
+        Plus [10-11]: "+"
+        Star [12-13]: "*"
+        Slash [14-15]: "/"
+        Caret [16-17]: "^"
+        At [18-19]: "@"
+        Op [20-22]: "!="
+        Op [23-25]: "<="
+        Op [26-27]: "~"
+        Eof [27-27]: ""
+    "#]],
+    );
+}
+
+#[test]
+fn comments_are_skipped() {
+    check(
+        "h // comment\n/* block\ncomment */ q;",
+        &expect![[r#"
+        Ident [0-1]: "h"
+        Ident [33-34]: "q"
+        Semi [34-35]: ";"
+        Eof [35-35]: ""
+    "#]],
+    );
+}
+
+#[test]
+fn unicode_ident() {
+    check(
+        "rz(π) q;",
+        &expect![[r#"
+        Ident [0-2]: "rz"
+        Open(Paren) [2-3]: "("
+        Ident [3-5]: "π"
+        Close(Paren) [5-6]: ")"
+        Ident [7-8]: "q"
+        Semi [8-9]: ";"
+        Eof [9-9]: ""
+    "#]],
+    );
+}
+
+#[test]
+fn unknown_char() {
+    check(
+        "#pragma",
+        &expect![[r#"
+        Ident [1-7]: "pragma"
+        Eof [7-7]: ""
+        Error(UnknownChar('#', Span { lo: 0, hi: 1 }))
+    "#]],
+    );
+}
+
+#[test]
+fn unterminated_string() {
+    check(
+        "include \"qelib1.inc",
+        &expect![[r#"
+        Ident [0-7]: "include"
+        Eof [19-19]: ""
+        Error(UnterminatedString(Span { lo: 8, hi: 19 }))
+    "#]],
+    );
+}
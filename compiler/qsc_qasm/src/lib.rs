@@ -0,0 +1,181 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! The qsc QASM importer turns an OpenQASM 2 or 3 program into a Q# operation. The program is
+//! parsed into a small syntax tree, and then lowered into the Q# abstract syntax tree with gate
+//! definitions inlined and register broadcasts unrolled. Like the parser, the importer produces a
+//! tree with placeholder node identifiers that are expected to be replaced with unique identifiers
+//! by a later stage.
+
+mod lex;
+mod lower;
+mod parse;
+#[cfg(test)]
+mod tests;
+
+use lex::TokenKind;
+use miette::Diagnostic;
+use qsc_ast::ast::{self, CallableDecl, Ident, Item, ItemKind, NodeId};
+use qsc_data_structures::span::Span;
+use std::{result, sync::Arc};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Diagnostic, Eq, Error, PartialEq)]
+#[error(transparent)]
+#[diagnostic(transparent)]
+pub struct Error(ErrorKind);
+
+impl Error {
+    #[must_use]
+    pub fn with_offset(self, offset: u32) -> Self {
+        Self(self.0.with_offset(offset))
+    }
+}
+
+#[derive(Clone, Debug, Diagnostic, Eq, Error, PartialEq)]
+enum ErrorKind {
+    #[error("unrecognized character `{0}`")]
+    #[diagnostic(code("Qsc.Qasm.UnknownChar"))]
+    UnknownChar(char, #[label] Span),
+    #[error("unterminated string literal")]
+    #[diagnostic(code("Qsc.Qasm.UnterminatedString"))]
+    UnterminatedString(#[label] Span),
+    #[error("expected {0}, found {1}")]
+    #[diagnostic(code("Qsc.Qasm.Token"))]
+    Token(TokenKind, TokenKind, #[label] Span),
+    #[error("expected {0}, found {1}")]
+    #[diagnostic(code("Qsc.Qasm.Rule"))]
+    Rule(&'static str, TokenKind, #[label] Span),
+    #[error("invalid {0} literal")]
+    #[diagnostic(code("Qsc.Qasm.Literal"))]
+    Lit(&'static str, #[label] Span),
+    #[error("unsupported OpenQASM version")]
+    #[diagnostic(help("only OpenQASM 2 and 3 programs can be imported"))]
+    #[diagnostic(code("Qsc.Qasm.Version"))]
+    Version(#[label] Span),
+    #[error("cannot include `{0}`")]
+    #[diagnostic(help(
+        "only the standard gate libraries `qelib1.inc` and `stdgates.inc` can be included"
+    ))]
+    #[diagnostic(code("Qsc.Qasm.Include"))]
+    Include(String, #[label] Span),
+    #[error("unsupported OpenQASM feature: {0}")]
+    #[diagnostic(help(
+        "only gates, measurements, resets, and conditionals on bits can be imported"
+    ))]
+    #[diagnostic(code("Qsc.Qasm.Unsupported"))]
+    Unsupported(String, #[label] Span),
+    #[error("`{0}` is not declared")]
+    #[diagnostic(code("Qsc.Qasm.Undeclared"))]
+    Undeclared(String, #[label] Span),
+    #[error("`{0}` is already declared")]
+    #[diagnostic(code("Qsc.Qasm.Redeclared"))]
+    Redeclared(String, #[label] Span),
+    #[error("`{0}` is reserved")]
+    #[diagnostic(help("the imported operation uses this name for its qubit array"))]
+    #[diagnostic(code("Qsc.Qasm.Reserved"))]
+    Reserved(String, #[label] Span),
+    #[error("expected {0}, found `{1}`")]
+    #[diagnostic(code("Qsc.Qasm.Kind"))]
+    Kind(&'static str, String, #[label] Span),
+    #[error("gate `{0}` expects {1} {2}, found {3}")]
+    #[diagnostic(code("Qsc.Qasm.Arity"))]
+    Arity(String, usize, &'static str, usize, #[label] Span),
+    #[error("index out of range: {0}")]
+    #[diagnostic(code("Qsc.Qasm.IndexOutOfRange"))]
+    IndexOutOfRange(u64, #[label("out of range")] Span),
+    #[error("registers have different sizes")]
+    #[diagnostic(code("Qsc.Qasm.SizeMismatch"))]
+    SizeMismatch(#[label] Span),
+    #[error("value does not fit in the register: {0}")]
+    #[diagnostic(code("Qsc.Qasm.ValueTooLarge"))]
+    ValueTooLarge(u64, #[label] Span),
+    #[error("`{0}` is not a valid operation name")]
+    #[diagnostic(help(
+        "the operation is named after the file, so the file name must be a Q# identifier"
+    ))]
+    #[diagnostic(code("Qsc.Qasm.Name"))]
+    Name(String),
+}
+
+impl ErrorKind {
+    fn with_offset(self, offset: u32) -> Self {
+        match self {
+            Self::UnknownChar(c, span) => Self::UnknownChar(c, span + offset),
+            Self::UnterminatedString(span) => Self::UnterminatedString(span + offset),
+            Self::Token(expected, actual, span) => Self::Token(expected, actual, span + offset),
+            Self::Rule(name, token, span) => Self::Rule(name, token, span + offset),
+            Self::Lit(name, span) => Self::Lit(name, span + offset),
+            Self::Version(span) => Self::Version(span + offset),
+            Self::Include(name, span) => Self::Include(name, span + offset),
+            Self::Unsupported(name, span) => Self::Unsupported(name, span + offset),
+            Self::Undeclared(name, span) => Self::Undeclared(name, span + offset),
+            Self::Redeclared(name, span) => Self::Redeclared(name, span + offset),
+            Self::Reserved(name, span) => Self::Reserved(name, span + offset),
+            Self::Kind(expected, name, span) => Self::Kind(expected, name, span + offset),
+            Self::Arity(name, expected, kind, actual, span) => {
+                Self::Arity(name, expected, kind, actual, span + offset)
+            }
+            Self::IndexOutOfRange(index, span) => Self::IndexOutOfRange(index, span + offset),
+            Self::SizeMismatch(span) => Self::SizeMismatch(span + offset),
+            Self::ValueTooLarge(value, span) => Self::ValueTooLarge(value, span + offset),
+            Self::Name(name) => Self::Name(name),
+        }
+    }
+}
+
+type Result<T> = result::Result<T, Error>;
+
+/// Imports an OpenQASM program as a Q# operation with the given name.
+///
+/// The operation takes the qubits of the program as a `Qubit[]`, with the quantum registers laid
+/// out one after the other in declaration order, and returns the classical registers concatenated
+/// in the same way as a `Result[]`. If there are errors, the operation contains the parts of the
+/// program that could be imported. The name must be a Q# identifier that is not a keyword.
+#[must_use]
+pub fn callable(input: &str, name: &str) -> (CallableDecl, Vec<Error>) {
+    let (program, mut errors) = parse::program(input);
+    if !qsc_parse::is_ident(name) {
+        errors.insert(0, Error(ErrorKind::Name(name.to_string())));
+    }
+    let (decl, lower_errors) = lower::program(&program, name, input_span(input));
+    errors.extend(lower_errors);
+    (decl, errors)
+}
+
+/// Imports an OpenQASM program as a Q# operation with the given name, inside a namespace. The
+/// namespace can be compiled alongside Q# sources by adding it to a source map as an import.
+#[must_use]
+pub fn namespace(input: &str, namespace: &str, name: &str) -> (ast::Namespace, Vec<Error>) {
+    let (decl, errors) = callable(input, name);
+    let span = input_span(input);
+    let item = Item {
+        span,
+        kind: Box::new(ItemKind::Callable(Box::new(decl))),
+        ..Item::default()
+    };
+
+    let namespace = ast::Namespace {
+        id: NodeId::default(),
+        span,
+        doc: "".into(),
+        name: Box::new(Ident {
+            id: NodeId::default(),
+            span: Span::default(),
+            name: Arc::from(namespace),
+        }),
+        items: vec![Box::new(item)].into_boxed_slice(),
+    };
+
+    (namespace, errors)
+}
+
+fn input_span(input: &str) -> Span {
+    Span {
+        lo: 0,
+        hi: input
+            .len()
+            .try_into()
+            .expect("input length should fit into u32"),
+    }
+}
@@ -0,0 +1,716 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Lowers an OpenQASM syntax tree into a Q# operation. Qubit registers become slices of the
+//! operation's qubit array, classical registers become mutable `Result[]` variables, gate
+//! definitions are inlined at each application with their parameters evaluated, and operations on
+//! whole registers are unrolled into one call per qubit.
+
+use crate::{
+    parse::{Apply, BinOp, Cond, Expr, ExprKind, GateDef, Ident, Operand, Program, Stmt, StmtKind},
+    Error, ErrorKind, Result,
+};
+use qsc_ast::ast::{
+    self, Block, CallableBody, CallableDecl, CallableKind, Functor, Lit, Mutability, NodeId, Pat,
+    PatKind, Path, Ty, TyKind, UnOp,
+};
+use qsc_data_structures::span::Span;
+use std::{collections::HashMap, f64::consts, sync::Arc};
+
+/// The name of the operation's qubit array parameter.
+const QUBITS: &str = "qubits";
+
+const INTRINSIC: &str = "Microsoft.Quantum.Intrinsic";
+
+const CANON: &str = "Microsoft.Quantum.Canon";
+
+pub(super) fn program(program: &Program, name: &str, span: Span) -> (CallableDecl, Vec<Error>) {
+    let mut lowerer = Lowerer::default();
+    let mut stmts = Vec::new();
+    for stmt in &program.stmts {
+        lowerer.stmt(stmt, Context::Global, &mut stmts);
+    }
+
+    let output = lowerer
+        .bits
+        .iter()
+        .map(|reg| path_expr(None, &reg.name, reg.span))
+        .reduce(|lhs, rhs| {
+            expr(
+                Span::default(),
+                ast::ExprKind::BinOp(ast::BinOp::Add, lhs, rhs),
+            )
+        })
+        .unwrap_or_else(|| expr(Span::default(), ast::ExprKind::Array(Box::default())));
+    stmts.push(stmt_expr(output));
+
+    let qubits = ty(TyKind::Array(ty(TyKind::Path(path(
+        None,
+        "Qubit",
+        Span::default(),
+    )))));
+    let input = pat(PatKind::Paren(pat(PatKind::Bind(
+        ident(QUBITS, Span::default()),
+        Some(qubits),
+    ))));
+    let output = ty(TyKind::Array(ty(TyKind::Path(path(
+        None,
+        "Result",
+        Span::default(),
+    )))));
+
+    let decl = CallableDecl {
+        id: NodeId::default(),
+        span,
+        kind: CallableKind::Operation,
+        name: ident(name, Span::default()),
+        generics: Box::default(),
+        input,
+        output,
+        functors: None,
+        body: Box::new(CallableBody::Block(Box::new(Block {
+            id: NodeId::default(),
+            span,
+            stmts: stmts.into_iter().map(Box::new).collect(),
+        }))),
+    };
+
+    (decl, lowerer.errors)
+}
+
+enum Decl {
+    Qubits { offset: usize, size: usize },
+    Bits { size: usize },
+    Gate(Arc<GateDef>),
+}
+
+/// The parameter values and qubits of a gate definition whose body is being inlined.
+struct Scope {
+    params: HashMap<Arc<str>, f64>,
+    qubits: HashMap<Arc<str>, usize>,
+}
+
+#[derive(Clone, Copy)]
+enum Context<'a> {
+    /// The top level of the program.
+    Global,
+    /// The body of a conditional.
+    Block,
+    /// The body of a gate definition.
+    Gate(&'a Scope),
+}
+
+#[derive(Default)]
+struct Lowerer {
+    decls: HashMap<Arc<str>, Decl>,
+    num_qubits: usize,
+    /// The classical registers in declaration order, which make up the output of the operation.
+    bits: Vec<Ident>,
+    errors: Vec<Error>,
+}
+
+impl Lowerer {
+    fn stmt(&mut self, stmt: &Stmt, context: Context, stmts: &mut Vec<ast::Stmt>) {
+        if let Err(error) = self.try_stmt(stmt, context, stmts) {
+            self.errors.push(error);
+        }
+    }
+
+    fn try_stmt(
+        &mut self,
+        stmt: &Stmt,
+        context: Context,
+        stmts: &mut Vec<ast::Stmt>,
+    ) -> Result<()> {
+        match &stmt.kind {
+            StmtKind::QubitDecl(name, size) => {
+                global_only(context, "qubit declaration", stmt.span)?;
+                let size = to_usize(size.value, size.span)?;
+                self.declare(
+                    name,
+                    Decl::Qubits {
+                        offset: self.num_qubits,
+                        size,
+                    },
+                )?;
+                self.num_qubits += size;
+            }
+            StmtKind::BitDecl(name, size) => {
+                global_only(context, "bit declaration", stmt.span)?;
+                if name.name.as_ref() == QUBITS {
+                    return Err(Error(ErrorKind::Reserved(name.name.to_string(), name.span)));
+                }
+                let size = to_usize(size.value, size.span)?;
+                self.declare(name, Decl::Bits { size })?;
+                self.bits.push(name.clone());
+
+                // mutable c = [Zero, size = n];
+                let zero = expr(
+                    stmt.span,
+                    ast::ExprKind::Lit(Box::new(Lit::Result(ast::Result::Zero))),
+                );
+                let size = int_expr(size, stmt.span);
+                let pat = pat(PatKind::Bind(ident(&name.name, name.span), None));
+                let value = expr(stmt.span, ast::ExprKind::ArrayRepeat(zero, size));
+                stmts.push(stmt_local(Mutability::Mutable, pat, value));
+            }
+            StmtKind::Gate(def) => {
+                global_only(context, "gate definition", stmt.span)?;
+                self.declare(&def.name, Decl::Gate(def.clone()))?;
+            }
+            StmtKind::Apply(apply) => self.apply(apply, context, stmt.span, stmts)?,
+            StmtKind::Measure(qubit, bit) => {
+                not_in_gate(context, "measurement", stmt.span)?;
+                let qubits = self.qubits(qubit, context)?;
+                if let Some(bit) = bit {
+                    let bits = self.bits(bit)?;
+                    for args in broadcast(&[qubits, bits], stmt.span)? {
+                        // set c w/= i <- M(qubits[j]);
+                        let m = measure(args[0], qubit.span, stmt.span);
+                        let reg = path_expr(None, &bit.name.name, bit.name.span);
+                        let update =
+                            ast::ExprKind::AssignUpdate(reg, int_expr(args[1], bit.span), m);
+                        stmts.push(stmt_semi(expr(stmt.span, update)));
+                    }
+                } else {
+                    for q in qubits {
+                        // let _ = M(qubits[i]);
+                        let m = measure(q, qubit.span, stmt.span);
+                        let discard = pat(PatKind::Discard(None));
+                        stmts.push(stmt_local(Mutability::Immutable, discard, m));
+                    }
+                }
+            }
+            StmtKind::Reset(qubit) => {
+                not_in_gate(context, "reset", stmt.span)?;
+                for q in self.qubits(qubit, context)? {
+                    let args = vec![qubit_expr(q, qubit.span)];
+                    let reset = call(intrinsic("Reset", stmt.span), args, stmt.span);
+                    stmts.push(stmt_semi(reset));
+                }
+            }
+            StmtKind::Barrier | StmtKind::Err => {}
+            StmtKind::If(cond, then, otherwise) => {
+                not_in_gate(context, "conditional", stmt.span)?;
+                let cond = self.cond(cond)?;
+                let then = self.block(then, stmt.span);
+                let otherwise = (!otherwise.is_empty()).then(|| {
+                    let block = self.block(otherwise, stmt.span);
+                    expr(block.span, ast::ExprKind::Block(block))
+                });
+                let kind = ast::ExprKind::If(cond, then, otherwise);
+                stmts.push(stmt_expr(expr(stmt.span, kind)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn block(&mut self, stmts: &[Stmt], span: Span) -> Box<Block> {
+        let mut block = Vec::new();
+        for stmt in stmts {
+            self.stmt(stmt, Context::Block, &mut block);
+        }
+        Box::new(Block {
+            id: NodeId::default(),
+            span,
+            stmts: block.into_iter().map(Box::new).collect(),
+        })
+    }
+
+    fn apply(
+        &mut self,
+        apply: &Apply,
+        context: Context,
+        span: Span,
+        stmts: &mut Vec<ast::Stmt>,
+    ) -> Result<()> {
+        let name = &apply.name;
+        let scope_params = match context {
+            Context::Gate(scope) => Some(&scope.params),
+            Context::Global | Context::Block => None,
+        };
+        let params = apply
+            .params
+            .iter()
+            .map(|param| eval(param, scope_params))
+            .collect::<Result<Vec<_>>>()?;
+        let args = apply
+            .args
+            .iter()
+            .map(|arg| self.qubits(arg, context))
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(Decl::Gate(def)) = self.decls.get(&name.name) {
+            let def = def.clone();
+            check_arity(name, def.params.len(), "parameters", params.len())?;
+            check_arity(name, def.qubits.len(), "qubits", args.len())?;
+            for qubits in broadcast(&args, span)? {
+                let scope = Scope {
+                    params: def
+                        .params
+                        .iter()
+                        .map(|p| p.name.clone())
+                        .zip(params.iter().copied())
+                        .collect(),
+                    qubits: def
+                        .qubits
+                        .iter()
+                        .map(|q| q.name.clone())
+                        .zip(qubits)
+                        .collect(),
+                };
+                for stmt in &def.body {
+                    self.stmt(stmt, Context::Gate(&scope), stmts);
+                }
+            }
+            return Ok(());
+        }
+
+        let Some(builtin) = builtin(&name.name) else {
+            return Err(Error(if self.decls.contains_key(&name.name) {
+                ErrorKind::Kind("gate", name.name.to_string(), name.span)
+            } else {
+                ErrorKind::Undeclared(name.name.to_string(), name.span)
+            }));
+        };
+        check_arity(name, builtin.params, "parameters", params.len())?;
+        check_arity(name, builtin.qubits, "qubits", args.len())?;
+        let spans: Vec<_> = apply.args.iter().map(|arg| arg.span).collect();
+        for qubits in broadcast(&args, span)? {
+            let qubits: Vec<_> = qubits
+                .into_iter()
+                .zip(&spans)
+                .map(|(q, &span)| qubit_expr(q, span))
+                .collect();
+            match builtin.kind {
+                BuiltinKind::Call(namespace, callable, functor) => {
+                    let mut callee = path_expr(Some(namespace), callable, name.span);
+                    let mut args: Vec<_> = params
+                        .iter()
+                        .zip(&apply.params)
+                        .map(|(&value, param)| double_expr(value, param.span))
+                        .chain(qubits)
+                        .collect();
+                    if let Some(functor) = functor {
+                        callee = expr(
+                            name.span,
+                            ast::ExprKind::UnOp(UnOp::Functor(functor), callee),
+                        );
+                        if functor == Functor::Ctl {
+                            let control = args.remove(params.len());
+                            let controls = expr(
+                                control.span,
+                                ast::ExprKind::Array(vec![control].into_boxed_slice()),
+                            );
+                            let target = tuple(args, span);
+                            args = vec![controls, target];
+                        }
+                    }
+                    stmts.push(stmt_semi(call(callee, args, span)));
+                }
+                BuiltinKind::U => {
+                    // U(θ, φ, λ) = Rz(φ) Ry(θ) Rz(λ), up to a global phase.
+                    let (theta, phi, lambda) = match params[..] {
+                        [theta, phi, lambda] => (theta, phi, lambda),
+                        [phi, lambda] => (consts::FRAC_PI_2, phi, lambda),
+                        _ => unreachable!("U gates should have two or three parameters"),
+                    };
+                    let qubit = &qubits[0];
+                    for (gate, angle) in [("Rz", lambda), ("Ry", theta), ("Rz", phi)] {
+                        let args = vec![double_expr(angle, span), qubit.clone()];
+                        stmts.push(stmt_semi(call(intrinsic(gate, name.span), args, span)));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lowers the condition of an `if` statement into a comparison of the bits in a register
+    /// with the binary digits of the value, where the first bit is the least significant.
+    fn cond(&mut self, cond: &Cond) -> Result<Box<ast::Expr>> {
+        let bits = self.bits(&cond.lhs)?;
+        let width = u32::try_from(bits.len()).unwrap_or(u32::MAX);
+        if width < u64::BITS && cond.value >> width != 0 {
+            return Err(Error(ErrorKind::ValueTooLarge(cond.value, cond.span)));
+        }
+
+        let reg = &cond.lhs.name;
+        Ok(bits
+            .into_iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                let result = if cond.value >> i & 1 == 1 {
+                    ast::Result::One
+                } else {
+                    ast::Result::Zero
+                };
+                let bit = expr(
+                    cond.lhs.span,
+                    ast::ExprKind::Index(
+                        path_expr(None, &reg.name, reg.span),
+                        int_expr(bit, cond.lhs.span),
+                    ),
+                );
+                let result = expr(cond.span, ast::ExprKind::Lit(Box::new(Lit::Result(result))));
+                expr(cond.span, ast::ExprKind::BinOp(ast::BinOp::Eq, bit, result))
+            })
+            .reduce(|lhs, rhs| expr(cond.span, ast::ExprKind::BinOp(ast::BinOp::AndL, lhs, rhs)))
+            .unwrap_or_else(|| expr(cond.span, ast::ExprKind::Lit(Box::new(Lit::Bool(true))))))
+    }
+
+    fn declare(&mut self, name: &Ident, decl: Decl) -> Result<()> {
+        if self.decls.contains_key(&name.name) {
+            Err(Error(ErrorKind::Redeclared(
+                name.name.to_string(),
+                name.span,
+            )))
+        } else {
+            self.decls.insert(name.name.clone(), decl);
+            Ok(())
+        }
+    }
+
+    /// Resolves an operand to the indices of the qubits it refers to in the qubit array.
+    fn qubits(&self, operand: &Operand, context: Context) -> Result<Vec<usize>> {
+        let name = &operand.name;
+        if let Context::Gate(scope) = context {
+            return match (scope.qubits.get(&name.name), operand.index) {
+                (Some(&qubit), None) => Ok(vec![qubit]),
+                (Some(_), Some(_)) => Err(Error(ErrorKind::Unsupported(
+                    "indexing a gate argument".to_string(),
+                    operand.span,
+                ))),
+                (None, _) => Err(Error(ErrorKind::Undeclared(
+                    name.name.to_string(),
+                    name.span,
+                ))),
+            };
+        }
+
+        match self.decls.get(&name.name) {
+            Some(&Decl::Qubits { offset, size }) => {
+                Ok(indices(operand, size)?.map(|i| offset + i).collect())
+            }
+            Some(_) => Err(Error(ErrorKind::Kind(
+                "qubit register",
+                name.name.to_string(),
+                name.span,
+            ))),
+            None => Err(Error(ErrorKind::Undeclared(
+                name.name.to_string(),
+                name.span,
+            ))),
+        }
+    }
+
+    /// Resolves an operand to the indices of the bits it refers to in its register.
+    fn bits(&self, operand: &Operand) -> Result<Vec<usize>> {
+        let name = &operand.name;
+        match self.decls.get(&name.name) {
+            Some(&Decl::Bits { size }) => Ok(indices(operand, size)?.collect()),
+            Some(_) => Err(Error(ErrorKind::Kind(
+                "bit register",
+                name.name.to_string(),
+                name.span,
+            ))),
+            None => Err(Error(ErrorKind::Undeclared(
+                name.name.to_string(),
+                name.span,
+            ))),
+        }
+    }
+}
+
+struct Builtin {
+    params: usize,
+    qubits: usize,
+    kind: BuiltinKind,
+}
+
+enum BuiltinKind {
+    /// A call to a Q# operation, optionally with a functor applied. Controlled operations take the
+    /// first qubit as their control.
+    Call(&'static str, &'static str, Option<Functor>),
+    /// The general single-qubit rotation, which is decomposed into Euler rotations.
+    U,
+}
+
+/// Returns the standard gate with the given name, from either `qelib1.inc` or `stdgates.inc`.
+fn builtin(name: &str) -> Option<Builtin> {
+    let (params, qubits, kind) = match name {
+        "id" | "i" => (0, 1, BuiltinKind::Call(INTRINSIC, "I", None)),
+        "x" => (0, 1, BuiltinKind::Call(INTRINSIC, "X", None)),
+        "y" => (0, 1, BuiltinKind::Call(INTRINSIC, "Y", None)),
+        "z" => (0, 1, BuiltinKind::Call(INTRINSIC, "Z", None)),
+        "h" => (0, 1, BuiltinKind::Call(INTRINSIC, "H", None)),
+        "s" => (0, 1, BuiltinKind::Call(INTRINSIC, "S", None)),
+        "sdg" => (0, 1, BuiltinKind::Call(INTRINSIC, "S", Some(Functor::Adj))),
+        "t" => (0, 1, BuiltinKind::Call(INTRINSIC, "T", None)),
+        "tdg" => (0, 1, BuiltinKind::Call(INTRINSIC, "T", Some(Functor::Adj))),
+        "rx" => (1, 1, BuiltinKind::Call(INTRINSIC, "Rx", None)),
+        "ry" => (1, 1, BuiltinKind::Call(INTRINSIC, "Ry", None)),
+        "rz" => (1, 1, BuiltinKind::Call(INTRINSIC, "Rz", None)),
+        "p" | "phase" | "u1" => (1, 1, BuiltinKind::Call(INTRINSIC, "R1", None)),
+        "U" | "u" | "u3" => (3, 1, BuiltinKind::U),
+        "u2" => (2, 1, BuiltinKind::U),
+        "CX" | "cx" | "cnot" => (0, 2, BuiltinKind::Call(INTRINSIC, "CNOT", None)),
+        "cy" => (0, 2, BuiltinKind::Call(CANON, "CY", None)),
+        "cz" => (0, 2, BuiltinKind::Call(CANON, "CZ", None)),
+        "ch" => (0, 2, BuiltinKind::Call(INTRINSIC, "H", Some(Functor::Ctl))),
+        "crx" => (1, 2, BuiltinKind::Call(INTRINSIC, "Rx", Some(Functor::Ctl))),
+        "cry" => (1, 2, BuiltinKind::Call(INTRINSIC, "Ry", Some(Functor::Ctl))),
+        "crz" => (1, 2, BuiltinKind::Call(INTRINSIC, "Rz", Some(Functor::Ctl))),
+        "cp" | "cphase" | "cu1" => (1, 2, BuiltinKind::Call(INTRINSIC, "R1", Some(Functor::Ctl))),
+        "swap" => (0, 2, BuiltinKind::Call(INTRINSIC, "SWAP", None)),
+        "rxx" => (1, 2, BuiltinKind::Call(INTRINSIC, "Rxx", None)),
+        "ryy" => (1, 2, BuiltinKind::Call(INTRINSIC, "Ryy", None)),
+        "rzz" => (1, 2, BuiltinKind::Call(INTRINSIC, "Rzz", None)),
+        "ccx" => (0, 3, BuiltinKind::Call(INTRINSIC, "CCNOT", None)),
+        "cswap" => (
+            0,
+            3,
+            BuiltinKind::Call(INTRINSIC, "SWAP", Some(Functor::Ctl)),
+        ),
+        _ => return None,
+    };
+    Some(Builtin {
+        params,
+        qubits,
+        kind,
+    })
+}
+
+/// Evaluates a gate parameter, given the values of the parameters of the enclosing gate
+/// definition.
+fn eval(expr: &Expr, params: Option<&HashMap<Arc<str>, f64>>) -> Result<f64> {
+    match &expr.kind {
+        ExprKind::BinOp(op, lhs, rhs) => {
+            let (lhs, rhs) = (eval(lhs, params)?, eval(rhs, params)?);
+            Ok(match op {
+                BinOp::Add => lhs + rhs,
+                BinOp::Div => lhs / rhs,
+                BinOp::Exp => lhs.powf(rhs),
+                BinOp::Mul => lhs * rhs,
+                BinOp::Sub => lhs - rhs,
+            })
+        }
+        ExprKind::Call(name, arg) => {
+            let arg = eval(arg, params)?;
+            match name.name.as_ref() {
+                "arccos" => Ok(arg.acos()),
+                "arcsin" => Ok(arg.asin()),
+                "arctan" => Ok(arg.atan()),
+                "cos" => Ok(arg.cos()),
+                "exp" => Ok(arg.exp()),
+                "ln" => Ok(arg.ln()),
+                "sin" => Ok(arg.sin()),
+                "sqrt" => Ok(arg.sqrt()),
+                "tan" => Ok(arg.tan()),
+                _ => Err(Error(ErrorKind::Undeclared(
+                    name.name.to_string(),
+                    name.span,
+                ))),
+            }
+        }
+        &ExprKind::Lit(value) => Ok(value),
+        ExprKind::Name(name) => {
+            if let Some(&value) = params.and_then(|params| params.get(&name.name)) {
+                return Ok(value);
+            }
+            match name.name.as_ref() {
+                "pi" | "π" => Ok(consts::PI),
+                "tau" | "τ" => Ok(consts::TAU),
+                "euler" | "ℯ" => Ok(consts::E),
+                _ => Err(Error(ErrorKind::Undeclared(
+                    name.name.to_string(),
+                    name.span,
+                ))),
+            }
+        }
+        ExprKind::Neg(operand) => Ok(-eval(operand, params)?),
+    }
+}
+
+/// Expands operands that refer to whole registers into one set of operands per register
+/// element. Operands that refer to a single element are repeated.
+fn broadcast(args: &[Vec<usize>], span: Span) -> Result<Vec<Vec<usize>>> {
+    let len = args.iter().map(Vec::len).find(|&len| len != 1).unwrap_or(1);
+    if args.iter().any(|arg| arg.len() != 1 && arg.len() != len) {
+        return Err(Error(ErrorKind::SizeMismatch(span)));
+    }
+
+    Ok((0..len)
+        .map(|i| {
+            args.iter()
+                .map(|arg| if arg.len() == 1 { arg[0] } else { arg[i] })
+                .collect()
+        })
+        .collect())
+}
+
+/// The indices of the register elements that an operand refers to.
+fn indices(operand: &Operand, size: usize) -> Result<impl Iterator<Item = usize>> {
+    match operand.index {
+        None => Ok(0..size),
+        Some(index) => match usize::try_from(index) {
+            Ok(i) if i < size => Ok(i..i + 1),
+            _ => Err(Error(ErrorKind::IndexOutOfRange(index, operand.span))),
+        },
+    }
+}
+
+fn check_arity(name: &Ident, expected: usize, kind: &'static str, actual: usize) -> Result<()> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(Error(ErrorKind::Arity(
+            name.name.to_string(),
+            expected,
+            kind,
+            actual,
+            name.span,
+        )))
+    }
+}
+
+fn global_only(context: Context, feature: &str, span: Span) -> Result<()> {
+    match context {
+        Context::Global => Ok(()),
+        Context::Block | Context::Gate(_) => Err(Error(ErrorKind::Unsupported(
+            format!("{feature} inside a block"),
+            span,
+        ))),
+    }
+}
+
+fn not_in_gate(context: Context, feature: &str, span: Span) -> Result<()> {
+    match context {
+        Context::Global | Context::Block => Ok(()),
+        Context::Gate(_) => Err(Error(ErrorKind::Unsupported(
+            format!("{feature} inside a gate definition"),
+            span,
+        ))),
+    }
+}
+
+fn to_usize(value: u64, span: Span) -> Result<usize> {
+    usize::try_from(value).map_err(|_| Error(ErrorKind::Lit("integer", span)))
+}
+
+fn ident(name: &str, span: Span) -> Box<ast::Ident> {
+    Box::new(ast::Ident {
+        id: NodeId::default(),
+        span,
+        name: name.into(),
+    })
+}
+
+fn path(namespace: Option<&str>, name: &str, span: Span) -> Box<Path> {
+    Box::new(Path {
+        id: NodeId::default(),
+        span,
+        namespace: namespace.map(|namespace| ident(namespace, span)),
+        name: ident(name, span),
+    })
+}
+
+fn expr(span: Span, kind: ast::ExprKind) -> Box<ast::Expr> {
+    Box::new(ast::Expr {
+        id: NodeId::default(),
+        span,
+        kind: Box::new(kind),
+    })
+}
+
+fn path_expr(namespace: Option<&str>, name: &str, span: Span) -> Box<ast::Expr> {
+    expr(span, ast::ExprKind::Path(path(namespace, name, span)))
+}
+
+fn intrinsic(name: &str, span: Span) -> Box<ast::Expr> {
+    path_expr(Some(INTRINSIC), name, span)
+}
+
+fn int_expr(value: usize, span: Span) -> Box<ast::Expr> {
+    let value = i64::try_from(value).expect("value should fit into i64");
+    expr(span, ast::ExprKind::Lit(Box::new(Lit::Int(value))))
+}
+
+fn double_expr(value: f64, span: Span) -> Box<ast::Expr> {
+    expr(span, ast::ExprKind::Lit(Box::new(Lit::Double(value))))
+}
+
+/// A measurement of the qubit with the given index: `M(qubits[i])`.
+fn measure(qubit: usize, qubit_span: Span, span: Span) -> Box<ast::Expr> {
+    call(
+        intrinsic("M", span),
+        vec![qubit_expr(qubit, qubit_span)],
+        span,
+    )
+}
+
+/// The element of the qubit array with the given index: `qubits[i]`.
+fn qubit_expr(index: usize, span: Span) -> Box<ast::Expr> {
+    let qubits = path_expr(None, QUBITS, span);
+    expr(span, ast::ExprKind::Index(qubits, int_expr(index, span)))
+}
+
+fn tuple(items: impl IntoIterator<Item = Box<ast::Expr>>, span: Span) -> Box<ast::Expr> {
+    let mut items: Vec<_> = items.into_iter().collect();
+    if items.len() == 1 {
+        let item = items.remove(0);
+        expr(item.span, ast::ExprKind::Paren(item))
+    } else {
+        expr(span, ast::ExprKind::Tuple(items.into_boxed_slice()))
+    }
+}
+
+fn call(
+    callee: Box<ast::Expr>,
+    args: impl IntoIterator<Item = Box<ast::Expr>>,
+    span: Span,
+) -> Box<ast::Expr> {
+    expr(span, ast::ExprKind::Call(callee, tuple(args, span)))
+}
+
+fn stmt_expr(expr: Box<ast::Expr>) -> ast::Stmt {
+    ast::Stmt {
+        id: NodeId::default(),
+        span: expr.span,
+        kind: Box::new(ast::StmtKind::Expr(expr)),
+    }
+}
+
+fn stmt_semi(expr: Box<ast::Expr>) -> ast::Stmt {
+    ast::Stmt {
+        id: NodeId::default(),
+        span: expr.span,
+        kind: Box::new(ast::StmtKind::Semi(expr)),
+    }
+}
+
+fn stmt_local(mutability: Mutability, pat: Box<Pat>, value: Box<ast::Expr>) -> ast::Stmt {
+    ast::Stmt {
+        id: NodeId::default(),
+        span: value.span,
+        kind: Box::new(ast::StmtKind::Local(mutability, pat, value)),
+    }
+}
+
+fn pat(kind: PatKind) -> Box<Pat> {
+    Box::new(Pat {
+        id: NodeId::default(),
+        span: Span::default(),
+        kind: Box::new(kind),
+    })
+}
+
+fn ty(kind: TyKind) -> Box<Ty> {
+    Box::new(Ty {
+        id: NodeId::default(),
+        span: Span::default(),
+        kind: Box::new(kind),
+    })
+}
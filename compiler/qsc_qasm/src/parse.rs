@@ -0,0 +1,646 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! The parser uses recursive descent to turn a token stream into a syntax tree for the subset of
+//! OpenQASM that can be imported. Statements that use other features are reported as unsupported
+//! and skipped, so that the rest of the program can still be checked.
+
+use crate::{
+    lex::{self, Delim, Token, TokenKind},
+    Error, ErrorKind, Result,
+};
+use qsc_data_structures::span::Span;
+use std::sync::Arc;
+
+/// Keywords of OpenQASM features that cannot be imported.
+const UNSUPPORTED: &[&str] = &[
+    "angle",
+    "array",
+    "bool",
+    "box",
+    "break",
+    "cal",
+    "complex",
+    "const",
+    "continue",
+    "ctrl",
+    "def",
+    "defcal",
+    "defcalgrammar",
+    "delay",
+    "duration",
+    "end",
+    "extern",
+    "float",
+    "for",
+    "gphase",
+    "input",
+    "int",
+    "inv",
+    "let",
+    "negctrl",
+    "opaque",
+    "output",
+    "pow",
+    "return",
+    "stretch",
+    "switch",
+    "uint",
+    "while",
+];
+
+/// The gate libraries that are built in to the importer.
+const STANDARD_INCLUDES: &[&str] = &["qelib1.inc", "stdgates.inc"];
+
+pub(super) struct Program {
+    pub(super) stmts: Vec<Stmt>,
+}
+
+pub(super) struct Stmt {
+    pub(super) span: Span,
+    pub(super) kind: StmtKind,
+}
+
+pub(super) enum StmtKind {
+    /// A quantum register declaration: `qreg q[2];` or `qubit[2] q;`.
+    QubitDecl(Ident, Size),
+    /// A classical register declaration: `creg c[2];` or `bit[2] c;`.
+    BitDecl(Ident, Size),
+    /// A gate definition.
+    Gate(Arc<GateDef>),
+    /// A gate application.
+    Apply(Apply),
+    /// A measurement, optionally stored in a bit: `measure q -> c;` or `c = measure q;`.
+    Measure(Operand, Option<Operand>),
+    /// A reset.
+    Reset(Operand),
+    /// A barrier, which does not affect the result of the program.
+    Barrier,
+    /// A conditional on the value of a classical register.
+    If(Cond, Vec<Stmt>, Vec<Stmt>),
+    /// A statement that could not be parsed or is not supported.
+    Err,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct Ident {
+    pub(super) span: Span,
+    pub(super) name: Arc<str>,
+}
+
+/// The size of a register, along with the span of the size expression.
+pub(super) struct Size {
+    pub(super) value: u64,
+    pub(super) span: Span,
+}
+
+pub(super) struct GateDef {
+    pub(super) name: Ident,
+    pub(super) params: Vec<Ident>,
+    pub(super) qubits: Vec<Ident>,
+    pub(super) body: Vec<Stmt>,
+}
+
+pub(super) struct Apply {
+    pub(super) name: Ident,
+    pub(super) params: Vec<Expr>,
+    pub(super) args: Vec<Operand>,
+}
+
+/// A reference to a whole register or to one element of it.
+pub(super) struct Operand {
+    pub(super) span: Span,
+    pub(super) name: Ident,
+    pub(super) index: Option<u64>,
+}
+
+/// A comparison of a register or bit with an integer: `c == 1`.
+pub(super) struct Cond {
+    pub(super) span: Span,
+    pub(super) lhs: Operand,
+    pub(super) value: u64,
+}
+
+/// A classical expression for a gate parameter, which is evaluated when the program is imported.
+pub(super) struct Expr {
+    pub(super) span: Span,
+    pub(super) kind: ExprKind,
+}
+
+pub(super) enum ExprKind {
+    BinOp(BinOp, Box<Expr>, Box<Expr>),
+    Call(Ident, Box<Expr>),
+    Lit(f64),
+    Name(Ident),
+    Neg(Box<Expr>),
+}
+
+#[derive(Clone, Copy)]
+pub(super) enum BinOp {
+    Add,
+    Div,
+    Exp,
+    Mul,
+    Sub,
+}
+
+pub(super) fn program(input: &str) -> (Program, Vec<Error>) {
+    let (tokens, errors) = lex::tokenize(input);
+    let mut parser = Parser {
+        input,
+        tokens,
+        pos: 0,
+        errors,
+    };
+    let stmts = parser.program();
+    (Program { stmts }, parser.errors)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    errors: Vec<Error>,
+}
+
+impl Parser<'_> {
+    fn program(&mut self) -> Vec<Stmt> {
+        if self.keyword("OPENQASM") {
+            if let Err(error) = self.version() {
+                self.errors.push(error);
+                self.recover();
+            }
+        }
+
+        let mut stmts = Vec::new();
+        while self.peek().kind != TokenKind::Eof {
+            if let Some(stmt) = self.stmt_or_recover() {
+                stmts.push(stmt);
+            }
+        }
+        stmts
+    }
+
+    fn version(&mut self) -> Result<()> {
+        let token = self.peek();
+        if matches!(token.kind, TokenKind::Int | TokenKind::Float) {
+            self.advance();
+            let version = &self.input[token.span];
+            let major = version.split('.').next().unwrap_or_default();
+            if major != "2" && major != "3" {
+                return Err(Error(ErrorKind::Version(token.span)));
+            }
+            self.expect(TokenKind::Semi)
+        } else {
+            Err(Error(ErrorKind::Rule(
+                "version number",
+                token.kind,
+                token.span,
+            )))
+        }
+    }
+
+    /// Parses a statement. If the statement is invalid, reports the error and skips it.
+    fn stmt_or_recover(&mut self) -> Option<Stmt> {
+        let lo = self.peek().span.lo;
+        let start = self.pos;
+        match self.stmt() {
+            Ok(stmt) => stmt,
+            Err(error) => {
+                self.errors.push(error);
+                self.recover();
+                if self.pos == start {
+                    // Always make progress, even if the statement is a stray closing brace.
+                    self.advance();
+                }
+                Some(Stmt {
+                    span: self.span(lo),
+                    kind: StmtKind::Err,
+                })
+            }
+        }
+    }
+
+    /// Parses a statement, or returns `None` for statements that have no effect on the imported
+    /// program, like includes of the standard gate libraries.
+    fn stmt(&mut self) -> Result<Option<Stmt>> {
+        let lo = self.peek().span.lo;
+        let kind = if self.keyword("include") {
+            self.include()?;
+            return Ok(None);
+        } else if self.keyword("qreg") {
+            let name = self.ident()?;
+            let size = self.size()?;
+            StmtKind::QubitDecl(name, size)
+        } else if self.keyword("creg") {
+            let name = self.ident()?;
+            let size = self.size()?;
+            StmtKind::BitDecl(name, size)
+        } else if self.keyword("qubit") {
+            let size = self.opt_size()?;
+            let name = self.ident()?;
+            StmtKind::QubitDecl(name, size.unwrap_or(self.unit_size(lo)))
+        } else if self.keyword("bit") {
+            let size = self.opt_size()?;
+            let name = self.ident()?;
+            StmtKind::BitDecl(name, size.unwrap_or(self.unit_size(lo)))
+        } else if self.keyword("gate") {
+            return Ok(Some(Stmt {
+                kind: StmtKind::Gate(Arc::new(self.gate()?)),
+                span: self.span(lo),
+            }));
+        } else if self.keyword("measure") {
+            let qubit = self.operand()?;
+            let bit = if self.peek().kind == TokenKind::Arrow {
+                self.advance();
+                Some(self.operand()?)
+            } else {
+                None
+            };
+            StmtKind::Measure(qubit, bit)
+        } else if self.keyword("reset") {
+            StmtKind::Reset(self.operand()?)
+        } else if self.keyword("barrier") {
+            if self.peek().kind != TokenKind::Semi {
+                self.operands()?;
+            }
+            StmtKind::Barrier
+        } else if self.keyword("if") {
+            return Ok(Some(self.if_stmt(lo)?));
+        } else if let Some(error) = self.unsupported() {
+            return Err(error);
+        } else if self.peek().kind == TokenKind::Ident
+            && matches!(
+                self.lookahead(1).kind,
+                TokenKind::Eq | TokenKind::Open(Delim::Bracket)
+            )
+        {
+            let bit = self.operand()?;
+            self.expect(TokenKind::Eq)?;
+            if !self.keyword("measure") {
+                let span = self.span(lo);
+                return Err(Error(ErrorKind::Unsupported(
+                    "classical assignment".to_string(),
+                    span,
+                )));
+            }
+            StmtKind::Measure(self.operand()?, Some(bit))
+        } else {
+            StmtKind::Apply(self.apply()?)
+        };
+
+        self.expect(TokenKind::Semi)?;
+        Ok(Some(Stmt {
+            span: self.span(lo),
+            kind,
+        }))
+    }
+
+    fn include(&mut self) -> Result<()> {
+        let token = self.peek();
+        self.expect(TokenKind::String)?;
+        let path = self.input[token.span].trim_matches('"');
+        if !STANDARD_INCLUDES.contains(&path) {
+            return Err(Error(ErrorKind::Include(path.to_string(), token.span)));
+        }
+        self.expect(TokenKind::Semi)
+    }
+
+    fn gate(&mut self) -> Result<GateDef> {
+        let name = self.ident()?;
+        let params = if self.peek().kind == TokenKind::Open(Delim::Paren) {
+            self.advance();
+            let params = self.seq(Self::ident)?;
+            self.expect(TokenKind::Close(Delim::Paren))?;
+            params
+        } else {
+            Vec::new()
+        };
+        let qubits = self.seq(Self::ident)?;
+        let body = self.block()?;
+        Ok(GateDef {
+            name,
+            params,
+            qubits,
+            body,
+        })
+    }
+
+    fn apply(&mut self) -> Result<Apply> {
+        if self.peek().kind != TokenKind::Ident {
+            let token = self.peek();
+            return Err(Error(ErrorKind::Rule("statement", token.kind, token.span)));
+        }
+
+        let name = self.ident()?;
+
+        let params = if self.peek().kind == TokenKind::Open(Delim::Paren) {
+            self.advance();
+            let params = self.seq(Self::expr)?;
+            self.expect(TokenKind::Close(Delim::Paren))?;
+            params
+        } else {
+            Vec::new()
+        };
+        let args = self.operands()?;
+        Ok(Apply { name, params, args })
+    }
+
+    fn if_stmt(&mut self, lo: u32) -> Result<Stmt> {
+        self.expect(TokenKind::Open(Delim::Paren))?;
+        let cond_lo = self.peek().span.lo;
+        let lhs = self.operand()?;
+        if self.peek().kind != TokenKind::EqEq {
+            let span = self.span(cond_lo);
+            return Err(Error(ErrorKind::Unsupported(
+                "condition other than equality".to_string(),
+                span,
+            )));
+        }
+        self.advance();
+        let value = self.int()?.value;
+        let cond = Cond {
+            span: self.span(cond_lo),
+            lhs,
+            value,
+        };
+        self.expect(TokenKind::Close(Delim::Paren))?;
+        let then = self.body()?;
+        let otherwise = if self.keyword("else") {
+            self.body()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Stmt {
+            span: self.span(lo),
+            kind: StmtKind::If(cond, then, otherwise),
+        })
+    }
+
+    /// Parses the body of a conditional, which is either a block or a single statement.
+    fn body(&mut self) -> Result<Vec<Stmt>> {
+        if self.peek().kind == TokenKind::Open(Delim::Brace) {
+            self.block()
+        } else {
+            Ok(self.stmt()?.into_iter().collect())
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        self.expect(TokenKind::Open(Delim::Brace))?;
+        let mut stmts = Vec::new();
+        while !matches!(
+            self.peek().kind,
+            TokenKind::Close(Delim::Brace) | TokenKind::Eof
+        ) {
+            if let Some(stmt) = self.stmt_or_recover() {
+                stmts.push(stmt);
+            }
+        }
+        self.expect(TokenKind::Close(Delim::Brace))?;
+        Ok(stmts)
+    }
+
+    fn operands(&mut self) -> Result<Vec<Operand>> {
+        self.seq(Self::operand)
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        let name = self.ident()?;
+        let index = if self.peek().kind == TokenKind::Open(Delim::Bracket) {
+            self.advance();
+            let index = self.int()?.value;
+            self.expect(TokenKind::Close(Delim::Bracket))?;
+            Some(index)
+        } else {
+            None
+        };
+
+        Ok(Operand {
+            span: self.span(name.span.lo),
+            name,
+            index,
+        })
+    }
+
+    fn size(&mut self) -> Result<Size> {
+        self.expect(TokenKind::Open(Delim::Bracket))?;
+        let size = self.int()?;
+        self.expect(TokenKind::Close(Delim::Bracket))?;
+        Ok(size)
+    }
+
+    fn opt_size(&mut self) -> Result<Option<Size>> {
+        if self.peek().kind == TokenKind::Open(Delim::Bracket) {
+            self.size().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The size of a single qubit or bit declaration.
+    fn unit_size(&self, lo: u32) -> Size {
+        Size {
+            value: 1,
+            span: self.span(lo),
+        }
+    }
+
+    fn int(&mut self) -> Result<Size> {
+        let token = self.peek();
+        if token.kind != TokenKind::Int {
+            return Err(Error(ErrorKind::Rule("integer", token.kind, token.span)));
+        }
+        self.advance();
+        let value = self.input[token.span]
+            .replace('_', "")
+            .parse()
+            .map_err(|_| Error(ErrorKind::Lit("integer", token.span)))?;
+        Ok(Size {
+            value,
+            span: token.span,
+        })
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.expr_op(0)
+    }
+
+    /// Parses an expression whose binary operators all have at least the given precedence.
+    fn expr_op(&mut self, min_prec: u8) -> Result<Expr> {
+        let lo = self.peek().span.lo;
+        let mut lhs = if self.peek().kind == TokenKind::Minus {
+            self.advance();
+            // Negation binds tighter than multiplication, but looser than exponentiation.
+            let operand = self.expr_op(3)?;
+            Expr {
+                span: self.span(lo),
+                kind: ExprKind::Neg(Box::new(operand)),
+            }
+        } else {
+            self.expr_base()?
+        };
+
+        loop {
+            let (op, prec, right_assoc) = match self.peek().kind {
+                TokenKind::Plus => (BinOp::Add, 1, false),
+                TokenKind::Minus => (BinOp::Sub, 1, false),
+                TokenKind::Star => (BinOp::Mul, 2, false),
+                TokenKind::Slash => (BinOp::Div, 2, false),
+                TokenKind::Caret => (BinOp::Exp, 3, true),
+                _ => break,
+            };
+            if prec < min_prec {
+                break;
+            }
+            self.advance();
+            let rhs = self.expr_op(if right_assoc { prec } else { prec + 1 })?;
+            lhs = Expr {
+                span: self.span(lo),
+                kind: ExprKind::BinOp(op, Box::new(lhs), Box::new(rhs)),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn expr_base(&mut self) -> Result<Expr> {
+        let token = self.peek();
+        let kind = match token.kind {
+            TokenKind::Int | TokenKind::Float => {
+                self.advance();
+                let value = self.input[token.span]
+                    .replace('_', "")
+                    .parse()
+                    .map_err(|_| Error(ErrorKind::Lit("number", token.span)))?;
+                ExprKind::Lit(value)
+            }
+            TokenKind::Ident => {
+                let name = self.ident()?;
+                if self.peek().kind == TokenKind::Open(Delim::Paren) {
+                    self.advance();
+                    let arg = self.expr()?;
+                    self.expect(TokenKind::Close(Delim::Paren))?;
+                    ExprKind::Call(name, Box::new(arg))
+                } else {
+                    ExprKind::Name(name)
+                }
+            }
+            TokenKind::Open(Delim::Paren) => {
+                self.advance();
+                let expr = self.expr()?;
+                self.expect(TokenKind::Close(Delim::Paren))?;
+                expr.kind
+            }
+            _ => return Err(Error(ErrorKind::Rule("expression", token.kind, token.span))),
+        };
+
+        Ok(Expr {
+            span: self.span(token.span.lo),
+            kind,
+        })
+    }
+
+    /// Parses a comma-separated sequence of one or more items.
+    fn seq<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let mut items = vec![item(self)?];
+        while self.peek().kind == TokenKind::Comma {
+            self.advance();
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    fn ident(&mut self) -> Result<Ident> {
+        let token = self.peek();
+        if token.kind == TokenKind::Ident {
+            self.advance();
+            Ok(Ident {
+                span: token.span,
+                name: self.input[token.span].into(),
+            })
+        } else {
+            Err(Error(ErrorKind::Rule("identifier", token.kind, token.span)))
+        }
+    }
+
+    /// Consumes the next token if it is the given keyword.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let token = self.peek();
+        if token.kind == TokenKind::Ident && &self.input[token.span] == keyword {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns an error if the next token is the keyword of an unsupported feature.
+    fn unsupported(&self) -> Option<Error> {
+        let token = self.peek();
+        let text = &self.input[token.span];
+        (token.kind == TokenKind::Ident && UNSUPPORTED.contains(&text))
+            .then(|| Error(ErrorKind::Unsupported(format!("`{text}`"), token.span)))
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<()> {
+        let token = self.peek();
+        if token.kind == kind {
+            self.advance();
+            Ok(())
+        } else {
+            Err(Error(ErrorKind::Token(kind, token.kind, token.span)))
+        }
+    }
+
+    /// Skips tokens until the end of the current statement, which is either a semicolon or a
+    /// balanced block. Stops before a closing brace that ends an enclosing block.
+    fn recover(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            match self.peek().kind {
+                TokenKind::Eof => return,
+                TokenKind::Semi if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                TokenKind::Open(Delim::Brace) => depth += 1,
+                TokenKind::Close(Delim::Brace) if depth == 0 => return,
+                TokenKind::Close(Delim::Brace) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.advance();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    fn peek(&self) -> Token {
+        self.lookahead(0)
+    }
+
+    fn lookahead(&self, n: usize) -> Token {
+        let last = self.tokens.len() - 1;
+        self.tokens[(self.pos + n).min(last)]
+    }
+
+    fn advance(&mut self) {
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+    }
+
+    /// The span from `lo` to the end of the last consumed token.
+    fn span(&self, lo: u32) -> Span {
+        let hi = self
+            .pos
+            .checked_sub(1)
+            .map_or(lo, |prev| self.tokens[prev].span.hi);
+        Span { lo, hi: hi.max(lo) }
+    }
+}
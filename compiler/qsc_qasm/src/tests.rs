@@ -0,0 +1,741 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::namespace;
+use expect_test::{expect, Expect};
+use indoc::indoc;
+use qsc_codegen::qasm::generate_qasm;
+use qsc_frontend::compile::{self, compile, PackageStore, SourceMap};
+use qsc_passes::{run_core_passes, run_default_passes};
+
+/// Imports the program, compiles it along with an entry expression that calls it on the given
+/// number of qubits, and checks the QASM that is generated from the compiled program.
+fn check(input: &str, num_qubits: usize, expect: &Expect) {
+    let (namespace, errors) = namespace(input, "Test", "Circuit");
+    assert!(errors.is_empty(), "{errors:?}");
+
+    let mut core = compile::core();
+    run_core_passes(&mut core);
+    let mut store = PackageStore::new(core);
+    let mut std = compile::std(&store);
+    assert!(std.errors.is_empty());
+    assert!(run_default_passes(store.core(), &mut std).is_empty());
+    let std_id = store.insert(std);
+
+    let entry = format!("{{ use qs = Qubit[{num_qubits}]; Test.Circuit(qs) }}");
+    let sources = SourceMap::with_imports(
+        [],
+        [("test.qasm".into(), input.into(), namespace)],
+        Some(entry.into()),
+    );
+    let mut unit = compile(&store, &[std_id], sources);
+    assert!(unit.errors.is_empty(), "{:?}", unit.errors);
+    let pass_errors = run_default_passes(store.core(), &mut unit);
+    assert!(pass_errors.is_empty(), "{pass_errors:?}");
+    let id = store.insert(unit);
+
+    match generate_qasm(&store, id) {
+        Ok(qasm) => expect.assert_eq(&qasm),
+        Err(errors) => expect.assert_debug_eq(&errors),
+    }
+}
+
+fn check_errors(input: &str, expect: &Expect) {
+    let (_, errors) = namespace(input, "Test", "Circuit");
+    expect.assert_debug_eq(&errors);
+}
+
+#[test]
+fn bell_pair() {
+    check(
+        indoc! {r#"
+            OPENQASM 2.0;
+            include "qelib1.inc";
+            qreg q[2];
+            creg c[2];
+            h q[0];
+            cx q[0], q[1];
+            measure q -> c;
+        "#},
+        2,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[2] q;
+            bit[2] c;
+
+            h q[0];
+            cx q[0], q[1];
+            c[0] = measure q[0];
+            c[1] = measure q[1];
+        "#]],
+    );
+}
+
+#[test]
+fn qasm3_declarations_and_measurement() {
+    check(
+        indoc! {r#"
+            OPENQASM 3;
+            include "stdgates.inc";
+            qubit[2] q;
+            qubit r;
+            bit[2] c;
+            bit d;
+            x q[1];
+            y r;
+            c = measure q;
+            d[0] = measure r;
+        "#},
+        3,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[3] q;
+            bit[3] c;
+
+            x q[1];
+            y q[2];
+            c[0] = measure q[0];
+            c[1] = measure q[1];
+            c[2] = measure q[2];
+        "#]],
+    );
+}
+
+#[test]
+fn registers_are_broadcast() {
+    check(
+        indoc! {"
+            OPENQASM 2.0;
+            qreg a[2];
+            qreg b[2];
+            creg c[4];
+            h a;
+            cx a, b;
+            cx a[0], b;
+            measure a[0] -> c[0];
+            measure a[1] -> c[1];
+            measure b[0] -> c[2];
+            measure b[1] -> c[3];
+        "},
+        4,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[4] q;
+            bit[4] c;
+
+            h q[0];
+            h q[1];
+            cx q[0], q[2];
+            cx q[1], q[3];
+            cx q[0], q[2];
+            cx q[0], q[3];
+            c[0] = measure q[0];
+            c[1] = measure q[1];
+            c[2] = measure q[2];
+            c[3] = measure q[3];
+        "#]],
+    );
+}
+
+#[test]
+fn gate_parameters_are_evaluated() {
+    check(
+        indoc! {"
+            OPENQASM 2.0;
+            qreg q[2];
+            creg c[2];
+            rx(pi/2) q[0];
+            ry(-pi/4 + 1) q[0];
+            rz(2^3 * 0.5) q[0];
+            rzz(sin(pi/2)) q[0], q[1];
+            u1(1.5e-1) q[1];
+            sdg q[1];
+            tdg q[1];
+            measure q -> c;
+        "},
+        2,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            gate rzz(theta) a, b { cx a, b; rz(theta) b; cx a, b; }
+
+            qubit[2] q;
+            bit[2] c;
+
+            rx(1.5707963267948966) q[0];
+            ry(0.21460183660255172) q[0];
+            rz(4.0) q[0];
+            rzz(1.0) q[0], q[1];
+            rz(0.15) q[1];
+            sdg q[1];
+            tdg q[1];
+            c[0] = measure q[0];
+            c[1] = measure q[1];
+        "#]],
+    );
+}
+
+#[test]
+fn u_gate_is_decomposed() {
+    check(
+        indoc! {"
+            OPENQASM 2.0;
+            qreg q[1];
+            creg c[1];
+            U(pi, 0, pi) q[0];
+            u2(0, pi) q[0];
+            measure q -> c;
+        "},
+        1,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[1] q;
+            bit[1] c;
+
+            rz(3.141592653589793) q[0];
+            ry(3.141592653589793) q[0];
+            rz(0.0) q[0];
+            rz(3.141592653589793) q[0];
+            ry(1.5707963267948966) q[0];
+            rz(0.0) q[0];
+            c[0] = measure q[0];
+        "#]],
+    );
+}
+
+#[test]
+fn gate_definitions_are_inlined() {
+    check(
+        indoc! {"
+            OPENQASM 2.0;
+            gate rot(theta, phi) a { rx(theta) a; rz(phi / 2) a; }
+            gate bell a, b { h a; cx a, b; }
+            gate twice(theta) a, b { bell a, b; rot(theta, 2 * theta) b; barrier a, b; }
+            qreg q[2];
+            creg c[2];
+            twice(0.5) q[0], q[1];
+            measure q -> c;
+        "},
+        2,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[2] q;
+            bit[2] c;
+
+            h q[0];
+            cx q[0], q[1];
+            rx(0.5) q[1];
+            rz(0.5) q[1];
+            c[0] = measure q[0];
+            c[1] = measure q[1];
+        "#]],
+    );
+}
+
+#[test]
+fn teleport() {
+    check(
+        indoc! {"
+            OPENQASM 2.0;
+            qreg q[3];
+            creg m0[1];
+            creg m1[1];
+            creg out[1];
+            x q[0];
+            h q[1];
+            cx q[1], q[2];
+            cx q[0], q[1];
+            h q[0];
+            measure q[0] -> m0[0];
+            measure q[1] -> m1[0];
+            if (m1 == 1) x q[2];
+            if (m0 == 1) z q[2];
+            measure q[2] -> out[0];
+        "},
+        3,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[3] q;
+            bit[3] c;
+
+            x q[0];
+            h q[1];
+            cx q[1], q[2];
+            cx q[0], q[1];
+            h q[0];
+            c[0] = measure q[0];
+            c[1] = measure q[1];
+            if (c[1] == 1) {
+                x q[2];
+            }
+            if (c[0] == 1) {
+                z q[2];
+            }
+            c[2] = measure q[2];
+        "#]],
+    );
+}
+
+#[test]
+fn qasm3_conditional_blocks() {
+    check(
+        indoc! {"
+            OPENQASM 3.0;
+            qubit[2] q;
+            bit[2] c;
+            h q;
+            c = measure q;
+            if (c[0] == 1) {
+                x q[1];
+                z q[1];
+            } else {
+                h q[1];
+            }
+            if (c == 2) {
+                reset q[0];
+            }
+        "},
+        2,
+        &expect![[r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+
+            qubit[2] q;
+            bit[2] c;
+
+            h q[0];
+            h q[1];
+            c[0] = measure q[0];
+            c[1] = measure q[1];
+            if (c[0] == 1) {
+                x q[1];
+                z q[1];
+            } else {
+                h q[1];
+            }
+            bool var_4;
+            if (c[0] == 0) {
+                var_4 = (c[1] == 1);
+            } else {
+                var_4 = false;
+            }
+            if (var_4) {
+                reset q[0];
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn unsupported_statements_are_reported() {
+    check_errors(
+        indoc! {"
+            OPENQASM 3.0;
+            qubit[2] q;
+            for i in [0:1] { h q[i]; }
+            int x = 5;
+            ctrl @ x q[0], q[1];
+            opaque g a;
+            h q[0];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    Unsupported(
+                        "`for`",
+                        Span {
+                            lo: 26,
+                            hi: 29,
+                        },
+                    ),
+                ),
+                Error(
+                    Unsupported(
+                        "`int`",
+                        Span {
+                            lo: 53,
+                            hi: 56,
+                        },
+                    ),
+                ),
+                Error(
+                    Unsupported(
+                        "`ctrl`",
+                        Span {
+                            lo: 64,
+                            hi: 68,
+                        },
+                    ),
+                ),
+                Error(
+                    Unsupported(
+                        "`opaque`",
+                        Span {
+                            lo: 85,
+                            hi: 91,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn unknown_include() {
+    check_errors(
+        indoc! {r#"
+            OPENQASM 2.0;
+            include "mygates.inc";
+        "#},
+        &expect![[r#"
+            [
+                Error(
+                    Include(
+                        "mygates.inc",
+                        Span {
+                            lo: 22,
+                            hi: 35,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn unsupported_version() {
+    check_errors(
+        "OPENQASM 4.0;",
+        &expect![[r#"
+        [
+            Error(
+                Version(
+                    Span {
+                        lo: 9,
+                        hi: 12,
+                    },
+                ),
+            ),
+        ]
+    "#]],
+    );
+}
+
+#[test]
+fn undeclared_names() {
+    check_errors(
+        indoc! {"
+            qreg q[1];
+            foo q[0];
+            h r[0];
+            rx(theta) q[0];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    Undeclared(
+                        "foo",
+                        Span {
+                            lo: 11,
+                            hi: 14,
+                        },
+                    ),
+                ),
+                Error(
+                    Undeclared(
+                        "r",
+                        Span {
+                            lo: 23,
+                            hi: 24,
+                        },
+                    ),
+                ),
+                Error(
+                    Undeclared(
+                        "theta",
+                        Span {
+                            lo: 32,
+                            hi: 37,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn redeclared_register() {
+    check_errors(
+        indoc! {"
+            qreg q[1];
+            creg q[1];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    Redeclared(
+                        "q",
+                        Span {
+                            lo: 16,
+                            hi: 17,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn reserved_register_name() {
+    check_errors(
+        "creg qubits[1];",
+        &expect![[r#"
+        [
+            Error(
+                Reserved(
+                    "qubits",
+                    Span {
+                        lo: 5,
+                        hi: 11,
+                    },
+                ),
+            ),
+        ]
+    "#]],
+    );
+}
+
+#[test]
+fn wrong_arity() {
+    check_errors(
+        indoc! {"
+            qreg q[2];
+            cx q[0];
+            rx q[0];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    Arity(
+                        "cx",
+                        2,
+                        "qubits",
+                        1,
+                        Span {
+                            lo: 11,
+                            hi: 13,
+                        },
+                    ),
+                ),
+                Error(
+                    Arity(
+                        "rx",
+                        1,
+                        "parameters",
+                        0,
+                        Span {
+                            lo: 20,
+                            hi: 22,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn index_out_of_range() {
+    check_errors(
+        indoc! {"
+            qreg q[2];
+            h q[2];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    IndexOutOfRange(
+                        2,
+                        Span {
+                            lo: 13,
+                            hi: 17,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn broadcast_size_mismatch() {
+    check_errors(
+        indoc! {"
+            qreg a[2];
+            qreg b[3];
+            cx a, b;
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    SizeMismatch(
+                        Span {
+                            lo: 22,
+                            hi: 30,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn operand_kind_mismatch() {
+    check_errors(
+        indoc! {"
+            qreg q[1];
+            creg c[1];
+            h c[0];
+            measure q[0] -> q[0];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    Kind(
+                        "qubit register",
+                        "c",
+                        Span {
+                            lo: 24,
+                            hi: 25,
+                        },
+                    ),
+                ),
+                Error(
+                    Kind(
+                        "bit register",
+                        "q",
+                        Span {
+                            lo: 46,
+                            hi: 47,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn condition_value_too_large() {
+    check_errors(
+        indoc! {"
+            qreg q[1];
+            creg c[2];
+            if (c == 4) x q[0];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    ValueTooLarge(
+                        4,
+                        Span {
+                            lo: 26,
+                            hi: 32,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn parse_errors_recover() {
+    check_errors(
+        indoc! {"
+            qreg q[1]
+            h q[0];
+            x q[0] q[1];
+            }
+            z q[0];
+        "},
+        &expect![[r#"
+            [
+                Error(
+                    Token(
+                        Semi,
+                        Ident,
+                        Span {
+                            lo: 10,
+                            hi: 11,
+                        },
+                    ),
+                ),
+                Error(
+                    Token(
+                        Semi,
+                        Ident,
+                        Span {
+                            lo: 25,
+                            hi: 26,
+                        },
+                    ),
+                ),
+                Error(
+                    Rule(
+                        "statement",
+                        Close(
+                            Brace,
+                        ),
+                        Span {
+                            lo: 31,
+                            hi: 32,
+                        },
+                    ),
+                ),
+                Error(
+                    Undeclared(
+                        "q",
+                        Span {
+                            lo: 35,
+                            hi: 36,
+                        },
+                    ),
+                ),
+            ]
+        "#]],
+    );
+}
+
+#[test]
+fn invalid_operation_names() {
+    for name in ["bell-pair", "2qubits", "operation", "Zero", ""] {
+        let (_, errors) = namespace("qreg q[1];", "Test", name);
+        assert_eq!(
+            errors,
+            [super::Error(super::ErrorKind::Name(name.to_string()))],
+            "{name}"
+        );
+    }
+
+    let (_, errors) = namespace("qreg q[1];", "Test", "bell_pair2");
+    assert!(errors.is_empty(), "{errors:?}");
+}
@@ -5,7 +5,12 @@
 mod tests;
 
 use crate::qsc_utils::{map_offset, span_contains, Compilation};
-use qsc::hir::{ty::Ty, visit::Visitor, CallableDecl, CallableKind};
+use qsc::hir::{
+    ty::Ty,
+    visit::{self, Visitor},
+    CallableDecl, CallableKind, Item, LocalItemId,
+};
+use qsc::qubit_count::{QubitAnalysis, QubitBound};
 
 #[derive(Debug, PartialEq)]
 pub struct Hover {
@@ -30,13 +35,19 @@ pub(crate) fn get_hover(
 
     let mut callable_finder = CallableFinder {
         offset,
+        item: None,
         header: None,
+        operation: None,
         start: 0,
         end: 0,
     };
     callable_finder.visit_package(package);
 
-    callable_finder.header.map(|header| Hover {
+    let mut header = callable_finder.header?;
+    if let Some(item) = callable_finder.operation {
+        header.push_str(&qubits_line(compilation, item));
+    }
+    Some(Hover {
         contents: header,
         span: Span {
             start: callable_finder.start,
@@ -45,14 +56,47 @@ pub(crate) fn get_hover(
     })
 }
 
+/// A line describing the bound on the qubits allocated by the operation, and when there is none,
+/// the code that prevents it. Operations that allocate no qubits have no line.
+fn qubits_line(compilation: &Compilation, item: LocalItemId) -> String {
+    let mut analysis = QubitAnalysis::new(&compilation.package_store, &compilation.package);
+    match analysis.callable_bound(item) {
+        Some(QubitBound::Unbounded(span)) => {
+            let code = compilation
+                .source_map
+                .find_by_offset(span.lo)
+                .and_then(|source| {
+                    source
+                        .contents
+                        .get((span.lo - source.offset) as usize..(span.hi - source.offset) as usize)
+                });
+            match code {
+                Some(code) => format!("\nqubits: unbounded because of `{code}`\n"),
+                None => "\nqubits: unbounded\n".to_string(),
+            }
+        }
+        Some(QubitBound::Bounded(size)) if size.as_constant() != Some(0) => {
+            format!("\nqubits: {size}\n")
+        }
+        _ => String::new(),
+    }
+}
+
 struct CallableFinder {
     offset: u32,
+    item: Option<LocalItemId>,
     header: Option<String>,
+    operation: Option<LocalItemId>,
     start: u32,
     end: u32,
 }
 
 impl Visitor<'_> for CallableFinder {
+    fn visit_item(&mut self, item: &Item) {
+        self.item = Some(item.id);
+        visit::walk_item(self, item);
+    }
+
     fn visit_callable_decl(&mut self, decl: &CallableDecl) {
         if span_contains(decl.name.span, self.offset) {
             let kind = match decl.kind {
@@ -84,6 +128,9 @@ impl Visitor<'_> for CallableFinder {
             );
 
             self.header = Some(header);
+            if decl.kind == CallableKind::Operation {
+                self.operation = self.item;
+            }
             self.start = decl.name.span.lo;
             self.end = decl.name.span.hi;
         }
@@ -24,6 +24,49 @@ fn hover_callable() {
     );
 }
 
+#[test]
+fn hover_operation_qubit_count() {
+    assert_hover(
+        r"
+        namespace Test {
+            operation ◉F↘oo◉(n : Int) : Unit {
+                use q = Qubit();
+                use qs = Qubit[2 * n];
+            }
+        }
+    ",
+        Some(indoc!(
+            r"```qsharp
+            operation FooInt : Unit
+            ```
+
+            qubits: 2 * n + 1
+        "
+        )),
+    );
+}
+
+#[test]
+fn hover_operation_unbounded_qubit_count() {
+    assert_hover(
+        r"
+        namespace Test {
+            operation ◉F↘oo◉(qs : Qubit[]) : Unit {
+                use aux = Qubit[Length(qs)];
+            }
+        }
+    ",
+        Some(indoc!(
+            r"```qsharp
+            operation Foo(Qubit)[] : Unit
+            ```
+
+            qubits: unbounded because of `Length(qs)`
+        "
+        )),
+    );
+}
+
 /// Asserts that the hover text at the given cursor position matches the expected hover text.
 /// The cursor position is indicated by a `↘` marker in the source text.
 /// The expected hover span is indicated by two `◉` markers in the source text.
@@ -107,8 +107,16 @@ export function getLanguageServiceWorker(
   return proxy;
 }
 
-export { type Dump, type ShotResult } from "./compiler/common.js";
-export { type CompilerState } from "./compiler/compiler.js";
+export {
+  type Dump,
+  type QscValue,
+  type ShotResult,
+} from "./compiler/common.js";
+export {
+  type CompilerState,
+  type IRunOptions,
+  type IShotHistogram,
+} from "./compiler/compiler.js";
 export { QscEventTarget } from "./compiler/events.js";
 export {
   getAllKatas,
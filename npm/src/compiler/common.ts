@@ -9,8 +9,32 @@ export type Dump = {
   [index: string]: [number, number];
 };
 
+// The typed form of a Q# value, which tells apart values with the same display
+// form, such as 1 and 1.0. A BigInt is a string of digits, and a Double that is
+// not finite is one of the strings "NaN", "Infinity" or "-Infinity".
+export type QscValue =
+  | { type: "Array" | "Tuple"; value: QscValue[] }
+  | { type: "BigInt" | "Pauli" | "Result" | "String"; value: string }
+  | { type: "Bool"; value: boolean }
+  | { type: "Double"; value: number | "NaN" | "Infinity" | "-Infinity" }
+  | { type: "Int" | "Qubit"; value: number }
+  | {
+      type: "Range";
+      value: { start: number | null; step: number; end: number | null };
+    }
+  | {
+      type: "Global" | "Closure";
+      value: {
+        package: number;
+        item: number;
+        adjoint: boolean;
+        controlled: number;
+        captures?: QscValue[];
+      };
+    };
+
 export type Result =
-  | { success: true; value: string }
+  | { success: true; value: string; typed: QscValue }
   | { success: false; value: VSDiagnostic };
 
 interface DumpMsg {
@@ -39,6 +63,7 @@ function outputAsResult(msg: string): ResultMsg | null {
         result: {
           success: obj.success,
           value: obj.result,
+          typed: obj.value,
         },
       };
     }
@@ -79,5 +104,6 @@ export function eventStringToMsg(msg: string): EventMsg | null {
 export type ShotResult = {
   success: boolean;
   result: string | VSDiagnostic;
+  typed?: QscValue;
   events: Array<MessageMsg | DumpMsg>;
 };
@@ -3,7 +3,7 @@
 
 import type { IDiagnostic, ICompletionList } from "../../lib/node/qsc_wasm.cjs";
 import { log } from "../log.js";
-import { eventStringToMsg } from "./common.js";
+import { eventStringToMsg, QscValue } from "./common.js";
 import { mapDiagnostics, VSDiagnostic } from "../vsdiagnostic.js";
 import { IQscEventTarget, QscEvents, makeEvent } from "./events.js";
 import { IServiceProxy, ServiceState } from "../worker-proxy.js";
@@ -12,18 +12,60 @@ import { IServiceProxy, ServiceState } from "../worker-proxy.js";
 // so use those as the set used by the shared compiler
 type Wasm = typeof import("../../lib/node/qsc_wasm.cjs");
 
+/**
+ * The options for running a program, where every option may be left out. A run in a worker is
+ * cancelled by terminating the worker, as a cancellation check cannot be posted to it.
+ */
+export interface IRunOptions {
+  /** The number of shots to run, which is one by default. */
+  shots?: number;
+  /** The error probabilities of the noise applied while the program runs. */
+  noise?: {
+    depolarizing?: number;
+    bitFlip?: number;
+    phaseFlip?: number;
+    idle?: number;
+    readout?: number;
+  };
+  /** Makes the measurements and random numbers of the run reproducible. */
+  seed?: number;
+  /** Fails a shot that takes more steps than the limit. */
+  stepLimit?: number;
+  /** Fails a shot that allocates more qubits at the same time than the limit. */
+  qubitLimit?: number;
+  /** Fails a shot that builds an array longer than the limit. */
+  arrayLimit?: number;
+}
+
+/**
+ * The values of the shots of a run, with the number of shots that gave each value. Each bucket
+ * has the display form of its value as the result and the typed form as the value.
+ */
+export interface IShotHistogram {
+  shots: number;
+  buckets: { result: string; value: QscValue; count: number }[];
+  failures: number;
+}
+
 // These need to be async/promise results for when communicating across a WebWorker, however
 // for running the compiler in the same thread the result will be synchronous (a resolved promise).
 export interface ICompiler {
   checkCode(code: string): Promise<VSDiagnostic[]>;
   getHir(code: string): Promise<string>;
+  getCircuit(code: string, expr: string): Promise<string>;
   getCompletions(): Promise<ICompletionList>;
   run(
     code: string,
     expr: string,
-    shots: number,
+    options: IRunOptions,
     eventHandler: IQscEventTarget
-  ): Promise<void>;
+  ): Promise<IShotHistogram>;
+  runEntry(
+    code: string,
+    args: Record<string, unknown>,
+    options: IRunOptions,
+    eventHandler: IQscEventTarget
+  ): Promise<IShotHistogram>;
   runKata(
     user_code: string,
     verify_code: string,
@@ -80,6 +122,10 @@ export class Compiler implements ICompiler {
     return this.wasm.get_hir(code);
   }
 
+  async getCircuit(code: string, expr: string): Promise<string> {
+    return this.wasm.get_circuit(code, expr);
+  }
+
   async getCompletions(): Promise<ICompletionList> {
     // Temporary implementation until we have the language
     // service properly wired up to the editor.
@@ -92,17 +138,33 @@ export class Compiler implements ICompiler {
   async run(
     code: string,
     expr: string,
-    shots: number,
+    options: IRunOptions,
     eventHandler: IQscEventTarget
-  ): Promise<void> {
+  ): Promise<IShotHistogram> {
     // All results are communicated as events, but if there is a compiler error (e.g. an invalid
     // entry expression or similar), it may throw on run. The caller should expect this promise
     // may reject without all shots running or events firing.
-    this.wasm.run(
+    return this.wasm.run(
       code,
       expr,
       (msg: string) => onCompilerEvent(msg, eventHandler),
-      shots
+      options
+    );
+  }
+
+  async runEntry(
+    code: string,
+    args: Record<string, unknown>,
+    options: IRunOptions,
+    eventHandler: IQscEventTarget
+  ): Promise<IShotHistogram> {
+    // The arguments are given to the parameters of the entry point by name, and are checked
+    // against their types before any shots run.
+    return this.wasm.run(
+      code,
+      "",
+      (msg: string) => onCompilerEvent(msg, eventHandler),
+      { ...options, args }
     );
   }
 
@@ -113,6 +113,7 @@ export class QscEventTarget implements IQscEventTarget {
 
     this.results[shotIdx].success = result.success;
     this.results[shotIdx].result = result.value;
+    if (result.success) this.results[shotIdx].typed = result.typed;
     this.shotActive = false;
 
     this.queueUiRefresh();
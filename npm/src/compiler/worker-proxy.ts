@@ -15,8 +15,10 @@ import { QscEventData } from "./events.js";
 const requests: MethodMap<ICompiler> = {
   checkCode: "request",
   getHir: "request",
+  getCircuit: "request",
   getCompletions: "request",
   run: "requestWithProgress",
+  runEntry: "requestWithProgress",
   runKata: "requestWithProgress",
 };
 
@@ -31,7 +31,7 @@ export function runSingleShot(code, expr, useWorker) {
     const compiler = useWorker ? getCompilerWorker() : getCompiler();
 
     compiler
-      .run(code, expr, 1, resultsHandler)
+      .run(code, expr, { shots: 1 }, resultsHandler)
       .then(() => resolve(resultsHandler.getResults()[0]))
       .catch((err) => reject(err))
       /* @ts-expect-error: ICompiler does not include 'terminate' */
@@ -52,6 +52,26 @@ test("basic eval", async () => {
   assert.equal(result.result, "42");
 });
 
+test("result has typed value", async () => {
+  const code = `namespace Test {
+        function Values() : (Int, Double, Result[]) {
+            (1, 1.0, [One])
+        }
+    }`;
+
+  const result = await runSingleShot(code, "Test.Values()", false);
+  assert(result.success);
+  assert.equal(result.result, "(1, 1.0, [One])");
+  assert.deepStrictEqual(result.typed, {
+    type: "Tuple",
+    value: [
+      { type: "Int", value: 1 },
+      { type: "Double", value: 1.0 },
+      { type: "Array", value: [{ type: "Result", value: "One" }] },
+    ],
+  });
+});
+
 test("EntryPoint only", async () => {
   const code = `
 namespace Test {
@@ -66,6 +86,29 @@ namespace Test {
   assert(result.result === "Zero");
 });
 
+test("circuit of traced operations", async () => {
+  const code = `namespace Test {
+    operation Bell() : Result[] {
+        use qs = Qubit[2];
+        H(qs[0]);
+        CNOT(qs[0], qs[1]);
+        Microsoft.Quantum.Measurement.MResetEachZ(qs)
+    }
+}`;
+  const compiler = getCompiler();
+  const circuit = JSON.parse(await compiler.getCircuit(code, "Test.Bell()"));
+  assert.equal(circuit.qubits, 2);
+  const gates = circuit.operations.filter((op) => op.kind === "Gate");
+  assert.deepEqual(
+    gates.map((op) => [op.gate, op.controls, op.targets]),
+    [
+      ["H", [], [0]],
+      ["Cx", [0], [1]],
+    ]
+  );
+  assert.equal(code.slice(gates[1].span.lo, gates[1].span.hi), "CNOT");
+});
+
 test("one syntax error", async () => {
   const compiler = getCompiler();
 
@@ -242,7 +285,7 @@ test("worker 100 shots", async () => {
 
   const resultsHandler = new QscEventTarget(true);
   const compiler = getCompilerWorker();
-  await compiler.run(code, expr, 100, resultsHandler);
+  await compiler.run(code, expr, { shots: 100 }, resultsHandler);
   compiler.terminate();
 
   const results = resultsHandler.getResults();
@@ -255,12 +298,65 @@ test("worker 100 shots", async () => {
   });
 });
 
+test("worker run returns histogram", async () => {
+  const code = `namespace Test {
+        @EntryPoint()
+        operation Main() : Result {
+            use q = Qubit();
+            X(q);
+            M(q)
+        }
+    }`;
+
+  const resultsHandler = new QscEventTarget(true);
+  const compiler = getCompilerWorker();
+  const histogram = await compiler.run(
+    code,
+    "",
+    { shots: 10, seed: 42, noise: { readout: 1 } },
+    resultsHandler
+  );
+  compiler.terminate();
+
+  assert.deepEqual(histogram, {
+    shots: 10,
+    buckets: [
+      { result: "Zero", value: { type: "Result", value: "Zero" }, count: 10 },
+    ],
+    failures: 0,
+  });
+});
+
+test("worker entry point with args", async () => {
+  const code = `namespace Test {
+        @EntryPoint()
+        function Scaled(factor : Int, values : Int[]) : Int[] {
+            Microsoft.Quantum.Arrays.Mapped(x -> factor * x, values)
+        }
+    }`;
+
+  const resultsHandler = new QscEventTarget(true);
+  const compiler = getCompilerWorker();
+  await compiler.runEntry(
+    code,
+    { factor: 3, values: [1, 2] },
+    { shots: 1 },
+    resultsHandler
+  );
+  compiler.terminate();
+
+  const results = resultsHandler.getResults();
+  assert.equal(results.length, 1);
+  assert(results[0].success);
+  assert.equal(results[0].result, "[3, 6]");
+});
+
 test("Run samples", async () => {
   const compiler = getCompilerWorker();
   const resultsHandler = new QscEventTarget(true);
 
   for await (const sample of samples) {
-    await compiler.run(sample.code, "", 1, resultsHandler);
+    await compiler.run(sample.code, "", { shots: 1 }, resultsHandler);
   }
 
   compiler.terminate();
@@ -285,7 +381,7 @@ test("state change", async () => {
         return M(q1);
     }
   }`;
-  await compiler.run(code, "", 10, resultsHandler);
+  await compiler.run(code, "", { shots: 10 }, resultsHandler);
   compiler.terminate();
   // There SHOULDN'T be a race condition here between the 'run' promise completing and the
   // statechange events firing, as the run promise should 'resolve' in the next microtask,
@@ -313,7 +409,7 @@ test("cancel worker", () => {
     const resultsHandler = new QscEventTarget(false);
 
     // Queue some tasks that will never complete
-    compiler.run(code, "", 10, resultsHandler).catch((err) => {
+    compiler.run(code, "", { shots: 10 }, resultsHandler).catch((err) => {
       cancelledArray.push(err);
     });
     compiler.checkCode(code).catch((err) => {
@@ -409,7 +505,7 @@ async function testCompilerError(useWorker) {
   let promiseResult = undefined;
   let lastState = undefined;
   await compiler
-    .run("invalid code", "", 1, events)
+    .run("invalid code", "", { shots: 1 }, events)
     .then(() => {
       promiseResult = "success";
     })
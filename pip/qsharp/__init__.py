@@ -1,7 +1,7 @@
 # Copyright (c) Microsoft Corporation.
 # Licensed under the MIT License.
 
-from ._qsharp import interpret, interpret_file
+from ._qsharp import interpret, interpret_file, run_entry, circuit, estimate, estimate_physical
 
 from ._native import Result, Pauli, QSharpError
 
@@ -16,4 +16,14 @@ except NameError:
     pass
 
 
-__all__ = ["interpret", "interpret_file", "Result", "Pauli", "QSharpError"]
+__all__ = [
+    "interpret",
+    "interpret_file",
+    "run_entry",
+    "circuit",
+    "estimate",
+    "estimate_physical",
+    "Result",
+    "Pauli",
+    "QSharpError",
+]
@@ -2,13 +2,42 @@
 # Licensed under the MIT License.
 
 from enum import Enum
-from typing import Any, Callable
+from typing import Any, Callable, Dict, List, Optional, Tuple
 
 class Interpreter:
     """A Q# interpreter."""
 
-    def __init__(self) -> None:
-        """Initializes a new Q# interpreter."""
+    def __init__(
+        self,
+        *,
+        depolarizing: float = 0.0,
+        bit_flip: float = 0.0,
+        phase_flip: float = 0.0,
+        idle: float = 0.0,
+        readout: float = 0.0,
+        seed: Optional[int] = None,
+        step_limit: Optional[int] = None,
+        qubit_limit: Optional[int] = None,
+        array_limit: Optional[int] = None,
+    ) -> None:
+        """
+        Initializes a new Q# interpreter.
+
+        :param depolarizing: The probability of a random Pauli error on each qubit a gate acts on.
+        :param bit_flip: The probability of an X error on each qubit a gate acts on.
+        :param phase_flip: The probability of a Z error on each qubit a gate acts on.
+        :param idle: The probability of a random Pauli error on each active qubit a gate does not act on.
+        :param readout: The probability that a measurement reports the wrong result.
+        :param seed: A seed that makes measurements and random numbers reproducible.
+        :param step_limit: The number of steps after which a statement stops with an error, so that
+            code that never finishes cannot run forever.
+        :param qubit_limit: The number of qubits that may be allocated at the same time. A statement
+            that would allocate more stops with an error.
+        :param array_limit: The length that arrays built by repetition or concatenation may have. A
+            statement that would build a longer array stops with an error.
+
+        :raises ValueError: If a probability is not between 0 and 1.
+        """
         ...
     def interpret(self, input: str, output_fn: Callable[[Output], None]) -> Any:
         """
@@ -20,6 +49,109 @@ class Interpreter:
         :returns value: The value returned by the last statement in the input.
 
         :raises QSharpError: If there is an error interpreting the input.
+        :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+        """
+        ...
+    def run(
+        self, input: str, shots: int, output_fn: Callable[[Output], None]
+    ) -> Tuple[List[Tuple[Any, int]], List[Tuple[int, str]]]:
+        """
+        Interprets Q# source code once per shot, each time on a new simulator.
+
+        :param input: The Q# source code to interpret.
+        :param shots: The number of times to interpret the input.
+        :param output_fn: A callback function that will be called with each output.
+
+        :returns (histogram, errors): Each distinct value returned by a shot with the number of
+            shots that returned it, and the index and error message of each shot that failed.
+
+        :raises QSharpError: If the input cannot be compiled.
+        :raises KeyboardInterrupt: If the interpreter is interrupted while running the shots.
+        """
+        ...
+    def circuit(self, input: str, output_fn: Callable[[Output], None]) -> Output:
+        """
+        Interprets Q# source code on a new simulator while tracing the quantum operations it
+        applies.
+
+        :param input: The Q# source code to interpret.
+        :param output_fn: A callback function that will be called with each output.
+
+        :returns circuit: The circuit of the operations that were applied, as an output that is
+            displayed as a diagram.
+
+        :raises QSharpError: If there is an error interpreting the input.
+        :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+        """
+        ...
+    def estimate(
+        self, input: str, measurements: str, output_fn: Callable[[Output], None]
+    ) -> Dict[str, int]:
+        """
+        Interprets Q# source code without simulating it and counts the logical resources that its
+        quantum operations use.
+
+        :param input: The Q# source code to interpret.
+        :param measurements: How measurements are resolved: `zero`, `random`, or the results in
+            order as a string of `0` and `1` like `0110`, after which they are `Zero`.
+        :param output_fn: A callback function that will be called with each output.
+
+        :returns resources: A dictionary of the resources, keyed by name.
+
+        :raises ValueError: If the measurement policy is not valid.
+        :raises QSharpError: If there is an error interpreting the input, including input that
+            needs the quantum state, like a call to `DumpMachine`.
+        :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+        """
+        ...
+    def estimate_physical(
+        self,
+        input: str,
+        measurements: str,
+        qubit_model: str,
+        qec_scheme: Optional[str],
+        error_budget: float,
+        output_fn: Callable[[Output], None],
+    ) -> str:
+        """
+        Interprets Q# source code without simulating it and estimates the physical resources needed
+        to run it on a qubit technology with surface code error correction.
+
+        :param input: The Q# source code to interpret.
+        :param measurements: How measurements are resolved: `zero`, `random`, or the results in
+            order as a string of `0` and `1` like `0110`, after which they are `Zero`.
+        :param qubit_model: The name of the qubit technology: `gate_ns_e3`, `gate_ns_e4`,
+            `gate_us_e3` or `gate_us_e4`, or a custom model as a JSON object with the gate times in
+            nanoseconds and the error rates.
+        :param qec_scheme: The surface code error correction scheme as a JSON object with its
+            `threshold` and `crossing_prefactor`, or `None` for the default scheme.
+        :param error_budget: The largest probability of an error that the estimate allows.
+        :param output_fn: A callback function that will be called with each output.
+
+        :returns report: The estimate as a JSON report.
+
+        :raises ValueError: If the measurement policy, the qubit model or the error correction
+            scheme is not valid.
+        :raises QSharpError: If there is an error interpreting the input, or if the resources
+            cannot be estimated for the qubit model and error budget.
+        :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+        """
+        ...
+    def run_entry(
+        self, args: Dict[str, Any], output_fn: Callable[[Output], None]
+    ) -> Any:
+        """
+        Runs the most recently defined entry point, binding its parameters to the given arguments.
+
+        :param args: The argument for each parameter of the entry point, by parameter name.
+        :param output_fn: A callback function that will be called with each output.
+
+        :returns value: The value returned by the entry point.
+
+        :raises TypeError: If an argument is not a value that Q# can represent.
+        :raises QSharpError: If there is no entry point, the arguments do not match its parameters,
+            or there is an error running it.
+        :raises KeyboardInterrupt: If the interpreter is interrupted while running the entry point.
         """
         ...
 
@@ -44,7 +176,7 @@ class Pauli(Enum):
 class Output:
     """
     An output returned from the Q# interpreter.
-    Outputs can be a state dumps or messages. These are normally printed to the console.
+    Outputs can be a state dumps, circuits or messages. These are normally printed to the console.
     """
 
     def __repr__(self) -> str: ...
@@ -1,6 +1,8 @@
 # Copyright (c) Microsoft Corporation.
 # Licensed under the MIT License.
 
+import json
+
 from ._native import Interpreter
 
 # Create a Q# interpreter singleton.
@@ -24,6 +26,102 @@ def interpret(input):
     return _interpreter.interpret(input, callback)
 
 
+def circuit(input):
+    """
+    Interprets Q# source code while tracing the quantum operations it applies. The quantum state
+    from previous calls is discarded first, but their variables and callables remain available.
+
+    Output is printed to console.
+
+    :param input: The Q# source code to interpret.
+    :returns circuit: The circuit of the operations that were applied, which is displayed as a
+        diagram.
+    :raises QSharpError: If there is an error interpreting the input.
+    """
+
+    def callback(output):
+        print(output)
+
+    return _interpreter.circuit(input, callback)
+
+
+def estimate(input, measurements="zero"):
+    """
+    Interprets Q# source code without simulating it and counts the logical resources that its
+    quantum operations use: the peak and total number of qubits allocated, the number of gates of
+    each kind, and the depth and T-depth of the circuit.
+
+    :param input: The Q# source code to interpret.
+    :param measurements: How measurements are resolved: "zero", "random", or the results in order
+        as a string of 0s and 1s like "0110", after which they are Zero.
+    :returns resources: A dictionary of the resources, keyed by name.
+    :raises QSharpError: If there is an error interpreting the input.
+    """
+
+    def callback(output):
+        print(output)
+
+    return _interpreter.estimate(input, measurements, callback)
+
+
+def estimate_physical(
+    input,
+    qubit_model="gate_ns_e3",
+    error_budget=1e-3,
+    measurements="zero",
+    qec_scheme=None,
+):
+    """
+    Interprets Q# source code without simulating it and estimates the physical qubits, code
+    distance and runtime needed to run it on a qubit technology with surface code error correction.
+
+    :param input: The Q# source code to interpret.
+    :param qubit_model: The name of the qubit technology: "gate_ns_e3", "gate_ns_e4", "gate_us_e3"
+        or "gate_us_e4", or a custom model as a dictionary with "one_qubit_gate_time",
+        "two_qubit_gate_time" and "measurement_time" in nanoseconds, "one_qubit_gate_error",
+        "two_qubit_gate_error", "measurement_error" and "t_gate_error", and an optional "name".
+    :param error_budget: The largest probability of an error that the estimate allows.
+    :param measurements: How measurements are resolved: "zero", "random", or the results in order
+        as a string of 0s and 1s like "0110", after which they are Zero.
+    :param qec_scheme: The surface code error correction scheme as a dictionary with its
+        "threshold" and "crossing_prefactor", where a key that is left out has its default value,
+        or None for the default scheme.
+    :returns report: A dictionary with the logical resources, the qubit model, the error
+        correction scheme and the physical estimate. Times are in nanoseconds.
+    :raises QSharpError: If there is an error interpreting the input or estimating its resources.
+    """
+
+    def callback(output):
+        print(output)
+
+    if isinstance(qubit_model, dict):
+        qubit_model = json.dumps(qubit_model)
+    if qec_scheme is not None:
+        qec_scheme = json.dumps(qec_scheme)
+    report = _interpreter.estimate_physical(
+        input, measurements, qubit_model, qec_scheme, error_budget, callback
+    )
+    return json.loads(report)
+
+
+def run_entry(**kwargs):
+    """
+    Runs the most recently defined entry point, passing each keyword argument to the parameter
+    of the same name.
+
+    Output is printed to console.
+
+    :returns value: The value returned by the entry point.
+    :raises QSharpError: If there is no entry point, the arguments do not match its parameters,
+        or there is an error running it.
+    """
+
+    def callback(output):
+        print(output)
+
+    return _interpreter.run_entry(kwargs, callback)
+
+
 def interpret_file(path) -> None:
     """
     Reads Q# source code from a file and interprets it.
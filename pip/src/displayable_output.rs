@@ -6,6 +6,8 @@ mod tests;
 
 use num_bigint::BigUint;
 use num_complex::{Complex, Complex64, ComplexFloat};
+use qsc::interpret::{Cell, Circuit};
+use std::fmt::Write;
 
 pub struct DisplayableState(pub Vec<(BigUint, Complex64)>, pub usize);
 
@@ -69,7 +71,140 @@ impl DisplayableState {
     }
 }
 
+/// The probability of measuring each basis state of a register that is entangled with other
+/// qubits, and the purity of the register's state.
+pub struct DisplayableProbabilities(pub Vec<(BigUint, f64)>, pub usize, pub f64);
+
+impl DisplayableProbabilities {
+    pub fn to_plain(&self) -> String {
+        let mut plain = format!(
+            "REGISTER: entangled with other qubits (purity {:.4})",
+            self.2
+        );
+        for (id, probability) in &self.0 {
+            write!(
+                plain,
+                "\n|{}⟩: {:.4}%",
+                DisplayableState::fmt_basis_state_label(id, self.1),
+                probability * 100.0
+            )
+            .expect("writing to string should succeed");
+        }
+        plain
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut rows = String::new();
+        for (id, probability) in &self.0 {
+            let percent = probability * 100.0;
+            write!(
+                rows,
+                include_str!("probabilities_row_template.html"),
+                DisplayableState::fmt_basis_state_label(id, self.1),
+                percent,
+                percent
+            )
+            .expect("writing to string should succeed");
+        }
+        format!(
+            include_str!("probabilities_header_template.html"),
+            self.2, rows
+        )
+    }
+}
+
+/// The circuit of quantum operations traced from Q# code.
+pub struct DisplayableCircuit(pub Circuit);
+
+impl DisplayableCircuit {
+    const WIRE_SPACING: usize = 50;
+    const LABEL_WIDTH: usize = 40;
+    const BOX_HEIGHT: usize = 30;
+
+    pub fn to_plain(&self) -> String {
+        format!("CIRCUIT:\n{}", self.0.diagram())
+    }
+
+    /// Draws the circuit as an SVG image, with a wire for each qubit and the operations in the
+    /// same columns as the plain diagram.
+    pub fn to_html(&self) -> String {
+        let qubit_count = self.0.qubit_count();
+        let wire_y = |q: usize| Self::WIRE_SPACING * q + Self::WIRE_SPACING / 2;
+        let mut body = String::new();
+        let mut x = Self::LABEL_WIDTH;
+        for column in self.0.columns() {
+            let chars = column
+                .iter()
+                .flat_map(|operation| Circuit::cells(operation))
+                .map(|(_, cell)| match cell {
+                    Cell::Box(label) => label.chars().count(),
+                    Cell::Control | Cell::Swap => 1,
+                })
+                .max()
+                .unwrap_or(1);
+            let width = 20 + 10 * chars;
+            let center = x + width / 2;
+            for operation in column {
+                let lo = operation.qubits.iter().copied().min().unwrap_or_default();
+                let hi = operation.qubits.iter().copied().max().unwrap_or_default();
+                if lo < hi {
+                    write!(
+                        body,
+                        r#"<line x1="{center}" y1="{}" x2="{center}" y2="{}" stroke="black" />"#,
+                        wire_y(lo),
+                        wire_y(hi)
+                    )
+                    .expect("writing to string should succeed");
+                }
+                for (q, cell) in Circuit::cells(operation) {
+                    Self::write_cell(&mut body, center, wire_y(q), width - 10, &cell);
+                }
+            }
+            x += width;
+        }
+
+        let mut wires = String::new();
+        for q in 0..qubit_count {
+            let y = wire_y(q);
+            write!(
+                wires,
+                r#"<text x="5" y="{y}" dominant-baseline="middle">q{q}</text><line x1="{}" y1="{y}" x2="{}" y2="{y}" stroke="black" />"#,
+                Self::LABEL_WIDTH - 10,
+                x + 10
+            )
+            .expect("writing to string should succeed");
+        }
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="14">{wires}{body}</svg>"#,
+            x + 20,
+            Self::WIRE_SPACING * qubit_count
+        )
+    }
+
+    fn write_cell(svg: &mut String, x: usize, y: usize, width: usize, cell: &Cell) {
+        match cell {
+            Cell::Control => write!(svg, r#"<circle cx="{x}" cy="{y}" r="5" fill="black" />"#),
+            Cell::Swap => write!(
+                svg,
+                r#"<path d="M {} {} l 12 12 m 0 -12 l -12 12" stroke="black" />"#,
+                x - 6,
+                y - 6
+            ),
+            Cell::Box(label) => write!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{width}" height="{}" fill="white" stroke="black" /><text x="{x}" y="{y}" text-anchor="middle" dominant-baseline="middle">{label}</text>"#,
+                x - width / 2,
+                y - Self::BOX_HEIGHT / 2,
+                Self::BOX_HEIGHT
+            ),
+        }
+        .expect("writing to string should succeed");
+    }
+}
+
 pub enum DisplayableOutput {
     State(DisplayableState),
+    Probabilities(DisplayableProbabilities),
+    Circuit(DisplayableCircuit),
     Message(String),
 }
@@ -3,8 +3,9 @@
 
 use num_bigint::BigUint;
 use num_complex::Complex;
+use qsc::interpret::{Circuit, Gate, Operation, OperationKind};
 
-use crate::displayable_output::DisplayableState;
+use crate::displayable_output::{DisplayableCircuit, DisplayableProbabilities, DisplayableState};
 
 #[test]
 fn display_neg_zero() {
@@ -22,3 +23,36 @@ fn display_rounds_to_neg_zero() {
     // -0.00001 should be displayed as 0.0000 without a minus sign
     assert_eq!("STATE:\n|0⟩: 0.0000+0.0000𝑖", s.to_plain());
 }
+
+#[test]
+fn display_entangled_register_probabilities() {
+    let s = DisplayableProbabilities(
+        vec![(BigUint::default(), 0.5), (BigUint::from(3_u8), 0.5)],
+        2,
+        0.5,
+    );
+    assert_eq!(
+        "REGISTER: entangled with other qubits (purity 0.5000)\n|00⟩: 50.0000%\n|11⟩: 50.0000%",
+        s.to_plain()
+    );
+}
+
+#[test]
+fn display_circuit() {
+    let s = DisplayableCircuit(Circuit::new(vec![
+        Operation {
+            kind: OperationKind::Gate(Gate::H),
+            qubits: vec![0],
+            span: None,
+        },
+        Operation {
+            kind: OperationKind::Gate(Gate::Cx),
+            qubits: vec![0, 1],
+            span: None,
+        },
+    ]));
+    assert_eq!("CIRCUIT:\nq0: -H--@-\n        |\nq1: ----X-\n", s.to_plain());
+    let html = s.to_html();
+    assert!(html.starts_with("<svg"));
+    assert!(html.contains(r#"<circle cx="85" cy="25" r="5" fill="black" />"#));
+}
@@ -1,21 +1,30 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use crate::displayable_output::{DisplayableOutput, DisplayableState};
+use crate::displayable_output::{
+    DisplayableCircuit, DisplayableOutput, DisplayableProbabilities, DisplayableState,
+};
 use miette::Report;
 use num_bigint::BigUint;
 use num_complex::Complex64;
-use pyo3::{create_exception, exceptions::PyException, prelude::*, types::PyList, types::PyTuple};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyKeyboardInterrupt, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+};
 use qsc::{
+    estimate::{self, QecScheme, QubitModel},
     hir,
     interpret::{
-        output::{Error, Receiver},
+        args::Arg,
+        output::{Error, Receiver, RegisterState},
         stateful::{self, LineError},
-        Value,
+        CancellationToken, MeasurementPolicy, NoiseModel, Value,
     },
     SourceMap,
 };
-use std::{fmt::Write, sync::Arc};
+use std::fmt::Write;
 
 #[pymodule]
 fn _native(py: Python, m: &PyModule) -> PyResult<()> {
@@ -31,16 +40,83 @@ fn _native(py: Python, m: &PyModule) -> PyResult<()> {
 #[pyclass(unsendable)]
 pub(crate) struct Interpreter {
     pub(crate) interpreter: stateful::Interpreter,
+    /// Cancelled when a signal handler raises an exception, such as after Ctrl+C.
+    cancellation: CancellationToken,
 }
 
 #[pymethods]
 /// A Q# interpreter.
 impl Interpreter {
     #[new]
+    #[pyo3(signature = (*, depolarizing=0.0, bit_flip=0.0, phase_flip=0.0, idle=0.0, readout=0.0, seed=None, step_limit=None, qubit_limit=None, array_limit=None))]
     /// Initializes a new Q# interpreter.
-    pub(crate) fn new(_py: Python) -> PyResult<Self> {
+    ///
+    /// :param depolarizing: The probability of a random Pauli error on each qubit a gate acts on.
+    /// :param bit_flip: The probability of an X error on each qubit a gate acts on.
+    /// :param phase_flip: The probability of a Z error on each qubit a gate acts on.
+    /// :param idle: The probability of a random Pauli error on each active qubit a gate does not act on.
+    /// :param readout: The probability that a measurement reports the wrong result.
+    /// :param seed: A seed that makes measurements and random numbers reproducible.
+    /// :param step_limit: The number of steps after which a statement stops with an error, so that
+    ///     code that never finishes cannot run forever.
+    /// :param qubit_limit: The number of qubits that may be allocated at the same time. A statement
+    ///     that would allocate more stops with an error.
+    /// :param array_limit: The length that arrays built by repetition or concatenation may have. A
+    ///     statement that would build a longer array stops with an error.
+    ///
+    /// :raises ValueError: If a probability is not between 0 and 1.
+    #[allow(clippy::doc_markdown, clippy::too_many_arguments)]
+    pub(crate) fn new(
+        _py: Python,
+        depolarizing: f64,
+        bit_flip: f64,
+        phase_flip: f64,
+        idle: f64,
+        readout: f64,
+        seed: Option<u64>,
+        step_limit: Option<u64>,
+        qubit_limit: Option<usize>,
+        array_limit: Option<usize>,
+    ) -> PyResult<Self> {
+        let noise = NoiseModel {
+            depolarizing,
+            bit_flip,
+            phase_flip,
+            idle,
+            readout,
+        };
+        if !noise.is_valid() {
+            return Err(PyValueError::new_err(
+                "noise probabilities must be between 0 and 1",
+            ));
+        }
+
+        // Signal handlers only run when asked to, so a long evaluation checks for them while it
+        // polls for cancellation.
+        let cancellation =
+            CancellationToken::with_poll(|| Python::with_gil(|py| py.check_signals().is_err()));
         match stateful::Interpreter::new(true, SourceMap::default()) {
-            Ok(interpreter) => Ok(Self { interpreter }),
+            Ok(interpreter) => {
+                let mut interpreter = interpreter
+                    .with_noise(noise)
+                    .with_cancellation(cancellation.clone());
+                if let Some(seed) = seed {
+                    interpreter = interpreter.with_seed(seed);
+                }
+                if let Some(steps) = step_limit {
+                    interpreter = interpreter.with_step_limit(steps);
+                }
+                if let Some(qubits) = qubit_limit {
+                    interpreter = interpreter.with_qubit_limit(qubits);
+                }
+                if let Some(len) = array_limit {
+                    interpreter = interpreter.with_array_limit(len);
+                }
+                Ok(Self {
+                    interpreter,
+                    cancellation,
+                })
+            }
             Err(errors) => {
                 let mut message = String::new();
                 for error in errors {
@@ -59,6 +135,8 @@ impl Interpreter {
     /// :returns value: The value returned by the last statement in the input.
     ///
     /// :raises QSharpError: If there is an error interpreting the input.
+    /// :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+    #[allow(clippy::doc_markdown)]
     fn interpret(
         &mut self,
         py: Python,
@@ -66,9 +144,220 @@ impl Interpreter {
         callback: Option<PyObject>,
     ) -> PyResult<PyObject> {
         let mut receiver = OptionalCallbackReceiver { callback, py };
+        self.cancellation.reset();
         match self.interpreter.interpret_line(&mut receiver, input) {
             Ok(value) => Ok(ValueWrapper(value).into_py(py)),
-            Err(errors) => Err(QSharpError::new_err(format_errors(input, errors))),
+            Err(_) if self.cancellation.is_cancelled() => Err(interrupted()),
+            Err(errors) => Err(QSharpError::new_err(format_errors(errors))),
+        }
+    }
+
+    /// Interprets Q# source code once per shot, each time on a new simulator.
+    ///
+    /// :param input: The Q# source code to interpret.
+    /// :param shots: The number of times to interpret the input.
+    /// :param output_fn: A callback function that will be called with each output.
+    ///
+    /// :returns (histogram, errors): Each distinct value returned by a shot with the number of
+    ///     shots that returned it, and the index and error message of each shot that failed.
+    ///
+    /// :raises QSharpError: If the input cannot be compiled.
+    /// :raises KeyboardInterrupt: If the interpreter is interrupted while running the shots.
+    #[allow(clippy::doc_markdown)]
+    fn run(
+        &mut self,
+        py: Python,
+        input: &str,
+        shots: usize,
+        callback: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let mut receiver = OptionalCallbackReceiver { callback, py };
+        self.cancellation.reset();
+        match self.interpreter.run_shots(&mut receiver, input, shots) {
+            Ok(results) if !results.errors.is_empty() && self.cancellation.is_cancelled() => {
+                Err(interrupted())
+            }
+            Ok(results) => {
+                let histogram = PyList::new(
+                    py,
+                    results
+                        .histogram
+                        .into_iter()
+                        .map(|(value, count)| -> PyObject {
+                            (ValueWrapper(value), count).into_py(py)
+                        }),
+                );
+                let errors = PyList::new(
+                    py,
+                    results
+                        .errors
+                        .into_iter()
+                        .map(|(shot, errors)| -> PyObject {
+                            (shot, format_errors(errors)).into_py(py)
+                        }),
+                );
+                Ok((histogram, errors).into_py(py))
+            }
+            Err(errors) => Err(QSharpError::new_err(format_errors(errors))),
+        }
+    }
+
+    /// Interprets Q# source code on a new simulator while tracing the quantum operations it
+    /// applies.
+    ///
+    /// :param input: The Q# source code to interpret.
+    /// :param output_fn: A callback function that will be called with each output.
+    ///
+    /// :returns circuit: The circuit of the operations that were applied, as an output that is
+    ///     displayed as a diagram.
+    ///
+    /// :raises QSharpError: If there is an error interpreting the input.
+    /// :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+    #[allow(clippy::doc_markdown)]
+    fn circuit(
+        &mut self,
+        py: Python,
+        input: &str,
+        callback: Option<PyObject>,
+    ) -> PyResult<Py<Output>> {
+        let mut receiver = OptionalCallbackReceiver { callback, py };
+        self.cancellation.reset();
+        match self.interpreter.circuit(&mut receiver, input) {
+            Ok(circuit) => Py::new(
+                py,
+                Output(DisplayableOutput::Circuit(DisplayableCircuit(circuit))),
+            ),
+            Err(_) if self.cancellation.is_cancelled() => Err(interrupted()),
+            Err(errors) => Err(QSharpError::new_err(format_errors(errors))),
+        }
+    }
+
+    /// Interprets Q# source code without simulating it and counts the logical resources that its
+    /// quantum operations use.
+    ///
+    /// :param input: The Q# source code to interpret.
+    /// :param measurements: How measurements are resolved: `zero`, `random`, or the results in
+    ///     order as a string of `0` and `1` like `0110`, after which they are `Zero`.
+    /// :param output_fn: A callback function that will be called with each output.
+    ///
+    /// :returns resources: A dictionary of the resources, keyed by name.
+    ///
+    /// :raises ValueError: If the measurement policy is not valid.
+    /// :raises QSharpError: If there is an error interpreting the input, including input that
+    ///     needs the quantum state, like a call to `DumpMachine`.
+    /// :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+    #[allow(clippy::doc_markdown)]
+    fn estimate(
+        &mut self,
+        py: Python,
+        input: &str,
+        measurements: &str,
+        callback: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let policy: MeasurementPolicy = measurements.parse().map_err(PyValueError::new_err)?;
+        let mut receiver = OptionalCallbackReceiver { callback, py };
+        self.cancellation.reset();
+        match self.interpreter.estimate(&mut receiver, input, policy) {
+            Ok(resources) => {
+                let dict = PyDict::new(py);
+                dict.set_item("peak_qubits", resources.peak_qubits)?;
+                dict.set_item("total_qubits", resources.total_qubits)?;
+                dict.set_item("t_gates", resources.t_gates)?;
+                dict.set_item("rotations", resources.rotations)?;
+                dict.set_item("cnot_gates", resources.cnot_gates)?;
+                dict.set_item("ccx_gates", resources.ccx_gates)?;
+                dict.set_item("measurements", resources.measurements)?;
+                dict.set_item("other_gates", resources.other_gates)?;
+                dict.set_item("depth", resources.depth)?;
+                dict.set_item("t_depth", resources.t_depth)?;
+                Ok(dict.into_py(py))
+            }
+            Err(_) if self.cancellation.is_cancelled() => Err(interrupted()),
+            Err(errors) => Err(QSharpError::new_err(format_errors(errors))),
+        }
+    }
+
+    /// Interprets Q# source code without simulating it and estimates the physical resources needed
+    /// to run it on a qubit technology with surface code error correction.
+    ///
+    /// :param input: The Q# source code to interpret.
+    /// :param measurements: How measurements are resolved: `zero`, `random`, or the results in
+    ///     order as a string of `0` and `1` like `0110`, after which they are `Zero`.
+    /// :param qubit_model: The name of the qubit technology: `gate_ns_e3`, `gate_ns_e4`,
+    ///     `gate_us_e3` or `gate_us_e4`, or a custom model as a JSON object with the gate times in
+    ///     nanoseconds and the error rates.
+    /// :param qec_scheme: The surface code error correction scheme as a JSON object with its
+    ///     `threshold` and `crossing_prefactor`, or `None` for the default scheme.
+    /// :param error_budget: The largest probability of an error that the estimate allows.
+    /// :param output_fn: A callback function that will be called with each output.
+    ///
+    /// :returns report: The estimate as a JSON report.
+    ///
+    /// :raises ValueError: If the measurement policy, the qubit model or the error correction
+    ///     scheme is not valid.
+    /// :raises QSharpError: If there is an error interpreting the input, or if the resources
+    ///     cannot be estimated for the qubit model and error budget.
+    /// :raises KeyboardInterrupt: If the interpreter is interrupted while evaluating the input.
+    #[pyo3(signature = (input, measurements, qubit_model, qec_scheme, error_budget, callback))]
+    #[allow(clippy::doc_markdown, clippy::too_many_arguments)]
+    fn estimate_physical(
+        &mut self,
+        py: Python,
+        input: &str,
+        measurements: &str,
+        qubit_model: &str,
+        qec_scheme: Option<&str>,
+        error_budget: f64,
+        callback: Option<PyObject>,
+    ) -> PyResult<String> {
+        let policy: MeasurementPolicy = measurements.parse().map_err(PyValueError::new_err)?;
+        let model: QubitModel = qubit_model.parse().map_err(PyValueError::new_err)?;
+        let qec: QecScheme = qec_scheme
+            .map(str::parse)
+            .transpose()
+            .map_err(PyValueError::new_err)?
+            .unwrap_or_default();
+        let mut receiver = OptionalCallbackReceiver { callback, py };
+        self.cancellation.reset();
+        match self.interpreter.estimate(&mut receiver, input, policy) {
+            Ok(resources) => {
+                estimate::estimate(&resources, &model, &qec, error_budget)
+                    .map(|physical| physical.json())
+                    .map_err(|error| QSharpError::new_err(format!("{:?}", Report::new(error))))
+            }
+            Err(_) if self.cancellation.is_cancelled() => Err(interrupted()),
+            Err(errors) => Err(QSharpError::new_err(format_errors(errors))),
+        }
+    }
+
+    /// Runs the most recently defined entry point, binding its parameters to the given arguments.
+    ///
+    /// :param args: The argument for each parameter of the entry point, by parameter name.
+    /// :param output_fn: A callback function that will be called with each output.
+    ///
+    /// :returns value: The value returned by the entry point.
+    ///
+    /// :raises TypeError: If an argument is not a value that Q# can represent.
+    /// :raises QSharpError: If there is no entry point, the arguments do not match its parameters,
+    ///     or there is an error running it.
+    /// :raises KeyboardInterrupt: If the interpreter is interrupted while running the entry point.
+    #[allow(clippy::doc_markdown)]
+    fn run_entry(
+        &mut self,
+        py: Python,
+        args: &PyDict,
+        callback: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let args = args
+            .iter()
+            .map(|(name, arg)| Ok((name.extract()?, Arg::Value(to_value(arg)?))))
+            .collect::<PyResult<Vec<(String, Arg)>>>()?;
+        let mut receiver = OptionalCallbackReceiver { callback, py };
+        self.cancellation.reset();
+        match self.interpreter.run_entry(&mut receiver, args) {
+            Ok(value) => Ok(ValueWrapper(value).into_py(py)),
+            Err(_) if self.cancellation.is_cancelled() => Err(interrupted()),
+            Err(errors) => Err(QSharpError::new_err(format_errors(errors))),
         }
     }
 }
@@ -80,7 +369,11 @@ create_exception!(
     "An error returned from the Q# interpreter."
 );
 
-fn format_errors(expr: &str, errors: Vec<LineError>) -> String {
+fn interrupted() -> PyErr {
+    PyKeyboardInterrupt::new_err("Q# evaluation was interrupted")
+}
+
+fn format_errors(errors: Vec<LineError>) -> String {
     errors
         .into_iter()
         .map(|e| {
@@ -88,7 +381,7 @@ fn format_errors(expr: &str, errors: Vec<LineError>) -> String {
             if let Some(stack_trace) = e.stack_trace() {
                 write!(message, "{stack_trace}").unwrap();
             }
-            let report = Report::new(e).with_source_code(Arc::new(expr.to_owned()));
+            let report = Report::new(e);
             write!(message, "{report:?}").unwrap();
             message
         })
@@ -100,11 +393,13 @@ pub(crate) struct Output(DisplayableOutput);
 
 #[pymethods]
 /// An output returned from the Q# interpreter.
-/// Outputs can be a state dumps or messages. These are normally printed to the console.
+/// Outputs can be a state dumps, circuits or messages. These are normally printed to the console.
 impl Output {
     fn __repr__(&self) -> String {
         match &self.0 {
             DisplayableOutput::State(state) => state.to_plain(),
+            DisplayableOutput::Probabilities(probabilities) => probabilities.to_plain(),
+            DisplayableOutput::Circuit(circuit) => circuit.to_plain(),
             DisplayableOutput::Message(msg) => msg.clone(),
         }
     }
@@ -116,6 +411,8 @@ impl Output {
     fn _repr_html_(&self) -> String {
         match &self.0 {
             DisplayableOutput::State(state) => state.to_html(),
+            DisplayableOutput::Probabilities(probabilities) => probabilities.to_html(),
+            DisplayableOutput::Circuit(circuit) => circuit.to_html(),
             DisplayableOutput::Message(msg) => format!("<p>{msg}</p>"),
         }
     }
@@ -171,19 +468,60 @@ impl IntoPy<PyObject> for ValueWrapper {
     }
 }
 
+// Mapping of Python value types to Q# value types, the reverse of `ValueWrapper`.
+fn to_value(obj: &PyAny) -> PyResult<Value> {
+    // `bool` is a subclass of `int`, so it must be checked first.
+    if let Ok(val) = obj.downcast::<PyBool>() {
+        Ok(Value::Bool(val.is_true()))
+    } else if let Ok(val) = obj.downcast::<PyInt>() {
+        match val.extract() {
+            Ok(val) => Ok(Value::Int(val)),
+            Err(_) => Ok(Value::BigInt(
+                val.str()?
+                    .to_str()?
+                    .parse()
+                    .expect("Python int should be a valid BigInt"),
+            )),
+        }
+    } else if let Ok(val) = obj.downcast::<PyFloat>() {
+        Ok(Value::Double(val.value()))
+    } else if let Ok(val) = obj.downcast::<PyString>() {
+        Ok(Value::String(val.to_str()?.into()))
+    } else if let Ok(val) = obj.extract::<PyRef<Result>>() {
+        Ok(Value::Result(matches!(*val, Result::One)))
+    } else if let Ok(val) = obj.extract::<PyRef<Pauli>>() {
+        Ok(Value::Pauli(match *val {
+            Pauli::I => hir::Pauli::I,
+            Pauli::X => hir::Pauli::X,
+            Pauli::Y => hir::Pauli::Y,
+            Pauli::Z => hir::Pauli::Z,
+        }))
+    } else if let Ok(val) = obj.downcast::<PyList>() {
+        Ok(Value::Array(
+            val.iter().map(to_value).collect::<PyResult<_>>()?,
+        ))
+    } else if let Ok(val) = obj.downcast::<PyTuple>() {
+        Ok(Value::Tuple(
+            val.iter().map(to_value).collect::<PyResult<_>>()?,
+        ))
+    } else if obj.is_none() {
+        Ok(Value::unit())
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "cannot convert a value of type `{}` to Q#",
+            obj.get_type().name()?
+        )))
+    }
+}
+
 struct OptionalCallbackReceiver<'a> {
     callback: Option<PyObject>,
     py: Python<'a>,
 }
 
-impl Receiver for OptionalCallbackReceiver<'_> {
-    fn state(
-        &mut self,
-        state: Vec<(BigUint, Complex64)>,
-        qubit_count: usize,
-    ) -> core::result::Result<(), Error> {
+impl OptionalCallbackReceiver<'_> {
+    fn output(&self, out: DisplayableOutput) -> core::result::Result<(), Error> {
         if let Some(callback) = &self.callback {
-            let out = DisplayableOutput::State(DisplayableState(state, qubit_count));
             callback
                 .call1(
                     self.py,
@@ -196,20 +534,41 @@ impl Receiver for OptionalCallbackReceiver<'_> {
         }
         Ok(())
     }
+}
+
+impl Receiver for OptionalCallbackReceiver<'_> {
+    fn state(
+        &mut self,
+        state: Vec<(BigUint, Complex64)>,
+        qubit_count: usize,
+    ) -> core::result::Result<(), Error> {
+        self.output(DisplayableOutput::State(DisplayableState(
+            state,
+            qubit_count,
+        )))
+    }
+
+    fn register_state(
+        &mut self,
+        state: RegisterState,
+        qubit_count: usize,
+    ) -> core::result::Result<(), Error> {
+        self.output(match state {
+            RegisterState::Separable(state) => {
+                DisplayableOutput::State(DisplayableState(state, qubit_count))
+            }
+            RegisterState::Entangled {
+                probabilities,
+                purity,
+            } => DisplayableOutput::Probabilities(DisplayableProbabilities(
+                probabilities,
+                qubit_count,
+                purity,
+            )),
+        })
+    }
 
     fn message(&mut self, msg: &str) -> core::result::Result<(), Error> {
-        if let Some(callback) = &self.callback {
-            let out = DisplayableOutput::Message(msg.to_owned());
-            callback
-                .call1(
-                    self.py,
-                    PyTuple::new(
-                        self.py,
-                        &[Py::new(self.py, Output(out)).expect("should be able to create output")],
-                    ),
-                )
-                .map_err(|_| Error)?;
-        }
-        Ok(())
+        self.output(DisplayableOutput::Message(msg.to_owned()))
     }
 }
@@ -2,7 +2,10 @@
 # Licensed under the MIT License.
 
 from qsharp._native import Interpreter, Result, Pauli, QSharpError
+import _thread
+import json
 import pytest
+import threading
 
 
 # Tests for the native Q# interpreter class
@@ -43,6 +46,27 @@ def test_dump_output() -> None:
     assert called
 
 
+def test_dump_register_output() -> None:
+    e = Interpreter()
+    outputs = []
+    e.interpret(
+        """
+    use qs = Qubit[3];
+    H(qs[0]);
+    CNOT(qs[0], qs[1]);
+    X(qs[2]);
+    Microsoft.Quantum.Diagnostics.DumpRegister([qs[2]]);
+    Microsoft.Quantum.Diagnostics.DumpRegister([qs[0]]);
+    ResetAll(qs);
+    """,
+        lambda output: outputs.append(output.__repr__()),
+    )
+    assert outputs == [
+        "STATE:\n|1⟩: 1.0000+0.0000𝑖",
+        "REGISTER: entangled with other qubits (purity 0.5000)\n|0⟩: 50.0000%\n|1⟩: 50.0000%",
+    ]
+
+
 def test_error() -> None:
     e = Interpreter()
 
@@ -118,3 +142,212 @@ def test_value_array() -> None:
     e = Interpreter()
     value = e.interpret("[1, 2, 3]")
     assert value == [1, 2, 3]
+
+
+def test_readout_noise() -> None:
+    e = Interpreter(readout=1.0)
+    value = e.interpret("use q = Qubit(); M(q)")
+    assert value == Result.One
+
+
+def test_invalid_noise() -> None:
+    with pytest.raises(ValueError):
+        Interpreter(bit_flip=2.0)
+
+
+def test_seed() -> None:
+    program = """{
+        use qs = Qubit[8];
+        for q in qs { H(q); }
+        let results = Microsoft.Quantum.Measurement.MResetEachZ(qs);
+        (results, Microsoft.Quantum.Random.DrawRandomInt(0, 100))
+    }"""
+    first = Interpreter(seed=42).interpret(program)
+    second = Interpreter(seed=42).interpret(program)
+    assert first == second
+
+
+def test_run_shots() -> None:
+    e = Interpreter(seed=42)
+    histogram, errors = e.run(
+        """{
+        use q = Qubit();
+        H(q);
+        let result = Microsoft.Quantum.Measurement.MResetZ(q);
+        if result == One { fail "measured One"; }
+        result
+    }""",
+        20,
+        None,
+    )
+    assert errors
+    assert histogram == [(Result.Zero, 20 - len(errors))]
+
+
+def test_step_limit() -> None:
+    e = Interpreter(step_limit=1000)
+    with pytest.raises(QSharpError) as excinfo:
+        e.interpret("while true {}", lambda output: None)
+    assert "step limit" in str(excinfo.value)
+    assert e.interpret("1 + 1", lambda output: None) == 2
+
+
+def test_qubit_limit() -> None:
+    e = Interpreter(qubit_limit=2)
+    with pytest.raises(QSharpError) as excinfo:
+        e.interpret("{ use qs = Qubit[3]; }", lambda output: None)
+    assert "limit of 2" in str(excinfo.value)
+    assert e.interpret("{ use qs = Qubit[2]; }", lambda output: None) is None
+
+
+def test_array_limit() -> None:
+    e = Interpreter(array_limit=10)
+    with pytest.raises(QSharpError) as excinfo:
+        e.interpret("[0, size = 1000000000]", lambda output: None)
+    assert "limit of 10" in str(excinfo.value)
+    assert e.interpret("[0, size = 2] + [1]", lambda output: None) == [0, 0, 1]
+
+
+def test_keyboard_interrupt() -> None:
+    e = Interpreter()
+    timer = threading.Timer(0.5, _thread.interrupt_main)
+    timer.start()
+    with pytest.raises(KeyboardInterrupt):
+        e.interpret("while true {}", lambda output: None)
+    timer.join()
+    assert e.interpret("1 + 1", lambda output: None) == 2
+
+
+def test_run_entry_with_args() -> None:
+    e = Interpreter()
+    e.interpret(
+        """
+    @EntryPoint()
+    function Main(n : Int, x : Double, big : BigInt, r : Result, ps : Pauli[], t : (Bool, String)) : (Int, Double, BigInt, Result, Pauli[], (Bool, String)) {
+        (n, x, big, r, ps, t)
+    }
+    """,
+        lambda output: None,
+    )
+    value = e.run_entry(
+        {
+            "n": 3,
+            "x": 2,
+            "big": 2**70,
+            "r": Result.One,
+            "ps": [Pauli.X, Pauli.Z],
+            "t": (True, "text"),
+        },
+        lambda output: None,
+    )
+    assert value == (
+        3,
+        2.0,
+        "<BigInt> 1180591620717411303424",
+        Result.One,
+        [Pauli.X, Pauli.Z],
+        (True, "text"),
+    )
+
+
+def test_run_entry_args_are_checked() -> None:
+    e = Interpreter()
+    e.interpret(
+        "@EntryPoint() function Main(n : Int) : Int { n }", lambda output: None
+    )
+    with pytest.raises(QSharpError) as excinfo:
+        e.run_entry({"n": "three"}, lambda output: None)
+    assert "invalid argument for parameter `n`" in str(excinfo.value)
+    with pytest.raises(TypeError):
+        e.run_entry({"n": object()}, lambda output: None)
+    with pytest.raises(QSharpError) as excinfo:
+        e.run_entry({}, lambda output: None)
+    assert "missing argument for parameter `n`" in str(excinfo.value)
+
+
+def test_circuit() -> None:
+    e = Interpreter()
+    circuit = e.circuit(
+        "use qs = Qubit[2]; H(qs[0]); CNOT(qs[0], qs[1]); ResetAll(qs);",
+        lambda output: None,
+    )
+    assert str(circuit) == (
+        "CIRCUIT:\n"
+        "q0: -H--@--Reset-\n"
+        "        |\n"
+        "q1: ----X--Reset-\n"
+    )
+    assert circuit._repr_html_().startswith("<svg")
+
+
+def test_estimate() -> None:
+    e = Interpreter()
+    program = "use q = Qubit(); T(q); if M(q) == One { H(q); T(q); } Reset(q);"
+    resources = e.estimate(program, "zero", lambda output: None)
+    assert resources["peak_qubits"] == 1
+    assert resources["t_gates"] == 1
+    assert resources["measurements"] == 1
+    assert resources["t_depth"] == 1
+    resources = e.estimate(program, "1", lambda output: None)
+    assert resources["t_gates"] == 2
+    assert resources["depth"] == 5
+    with pytest.raises(ValueError):
+        e.estimate(program, "maybe", lambda output: None)
+
+
+def test_estimate_physical() -> None:
+    e = Interpreter()
+    program = "use q = Qubit(); T(q); Reset(q);"
+    report = json.loads(
+        e.estimate_physical(
+            program, "zero", "gate_ns_e3", None, 1e-3, lambda output: None
+        )
+    )
+    assert report["logical"]["t_gates"] == 1
+    assert report["qubit_model"]["name"] == "gate_ns_e3"
+    assert report["qec_scheme"]["threshold"] == 0.01
+    assert report["t_factories"] == 1
+    assert report["physical_qubits"] > 0
+    with pytest.raises(ValueError):
+        e.estimate_physical(
+            program, "zero", "gate_ps_e9", None, 1e-3, lambda output: None
+        )
+    with pytest.raises(QSharpError):
+        e.estimate_physical(
+            program, "zero", "gate_ns_e3", None, 2.0, lambda output: None
+        )
+
+
+def test_estimate_physical_with_custom_model_and_scheme() -> None:
+    e = Interpreter()
+    program = "use q = Qubit(); T(q); Reset(q);"
+    model = json.dumps(
+        {
+            "name": "fast",
+            "one_qubit_gate_time": 10,
+            "two_qubit_gate_time": 20,
+            "measurement_time": 30,
+            "one_qubit_gate_error": 1e-4,
+            "two_qubit_gate_error": 1e-4,
+            "measurement_error": 1e-4,
+            "t_gate_error": 1e-4,
+        }
+    )
+    report = json.loads(
+        e.estimate_physical(
+            program,
+            "zero",
+            model,
+            '{"threshold": 0.005}',
+            1e-3,
+            lambda output: None,
+        )
+    )
+    assert report["qubit_model"]["name"] == "fast"
+    assert report["qubit_model"]["two_qubit_gate_time"] == 20
+    assert report["qec_scheme"]["threshold"] == 0.005
+    assert report["qec_scheme"]["crossing_prefactor"] == 0.03
+    with pytest.raises(ValueError):
+        e.estimate_physical(
+            program, "zero", model, '{"distance": 3}', 1e-3, lambda output: None
+        )
@@ -30,3 +30,22 @@ def test_stdout_multiple_lines() -> None:
         )
 
     assert f.getvalue() == "STATE:\n|0⟩: 1.0000+0.0000𝑖\nHello!\n"
+
+
+def test_run_entry() -> None:
+    qsharp.interpret(
+        """
+    @EntryPoint()
+    function Greet(name : String, times : Int) : Unit {
+        for _ in 1..times {
+            Message($"Hello, {name}!");
+        }
+    }
+    """
+    )
+    f = io.StringIO()
+    with redirect_stdout(f):
+        result = qsharp.run_entry(name="world", times=2)
+
+    assert result is None
+    assert f.getvalue() == "Hello, world!\nHello, world!\n"
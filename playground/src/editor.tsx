@@ -110,7 +110,12 @@ export function Editor(props: {
         // This is for a kata. Provide the verification code.
         await props.compiler.runKata(code, props.kataVerify, props.evtTarget);
       } else {
-        await props.compiler.run(code, runExpr, shotCount, props.evtTarget);
+        await props.compiler.run(
+          code,
+          runExpr,
+          { shots: shotCount },
+          props.evtTarget
+        );
       }
     } catch (err) {
       // This could fail for several reasons, e.g. the run being cancelled.
@@ -9,13 +9,16 @@ use qsc::{
     compile,
     hir::PackageId,
     interpret::{
-        output::{self, Receiver},
-        stateless,
+        args::Arg,
+        output::{self, Receiver, RegisterState},
+        stateless, CancellationToken, Circuit, NoiseModel, Recorders, ShotResults, Tracer,
     },
     PackageStore, SourceMap,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::fmt::Write;
+use serde_wasm_bindgen::Serializer;
+use std::{cell::RefCell, fmt::Write};
 use wasm_bindgen::prelude::*;
 
 mod language_service;
@@ -101,6 +104,23 @@ where
         Ok(())
     }
 
+    fn register_state(
+        &mut self,
+        state: RegisterState,
+        qubit_count: usize,
+    ) -> Result<(), output::Error> {
+        match state {
+            // A separable register is dumped like the whole machine, so that it is shown the same way.
+            RegisterState::Separable(state) => self.state(state, qubit_count),
+            // An entangled register has no amplitudes to show, so it is described by a message.
+            state @ RegisterState::Entangled { .. } => {
+                let mut msg = Vec::new();
+                output::write_register_state(&mut msg, &state, qubit_count)?;
+                self.message(String::from_utf8_lossy(&msg).trim_end())
+            }
+        }
+    }
+
     fn message(&mut self, msg: &str) -> Result<(), output::Error> {
         let msg_json = json!({"type": "Message", "message": msg});
         (self.event_cb)(&msg_json.to_string());
@@ -108,13 +128,67 @@ where
     }
 }
 
-fn run_internal<F>(code: &str, expr: &str, event_cb: F, shots: u32) -> Result<(), stateless::Error>
+/// The error probabilities accepted by [`run`], with every probability defaulting to zero.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Noise {
+    depolarizing: f64,
+    bit_flip: f64,
+    phase_flip: f64,
+    idle: f64,
+    readout: f64,
+}
+
+impl From<Noise> for NoiseModel {
+    fn from(noise: Noise) -> Self {
+        NoiseModel {
+            depolarizing: noise.depolarizing,
+            bit_flip: noise.bit_flip,
+            phase_flip: noise.phase_flip,
+            idle: noise.idle,
+            readout: noise.readout,
+        }
+    }
+}
+
+/// The options accepted by [`run`]. Without an option, the program runs for one shot with no noise,
+/// seed, limits or arguments.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RunOptions {
+    shots: Option<u32>,
+    noise: Option<Noise>,
+    seed: Option<u64>,
+    step_limit: Option<u64>,
+    qubit_limit: Option<usize>,
+    array_limit: Option<usize>,
+    /// The arguments, with a property for each parameter of the entry point.
+    args: Option<serde_json::Map<String, serde_json::Value>>,
+    /// The token that polls the host's `isCancelled` check, which is a function and so is not
+    /// deserialized with the other options.
+    #[serde(skip)]
+    cancellation: Option<CancellationToken>,
+}
+
+fn run_internal<F>(
+    code: &str,
+    expr: &str,
+    event_cb: F,
+    options: RunOptions,
+) -> Result<serde_json::Value, stateless::Error>
 where
     F: Fn(&str),
 {
     let mut out = CallbackReceiver { event_cb };
     let sources = SourceMap::new([("code".into(), code.into())], Some(expr.into()));
-    let context = stateless::Context::new(true, sources);
+    // Without arguments, a missing entry point is reported by each shot like other runtime errors.
+    let context = stateless::Context::new(true, sources).and_then(|context| match options.args {
+        Some(args) if !args.is_empty() => context.with_args(
+            args.into_iter()
+                .map(|(name, value)| (name, Arg::Json(value))),
+        ),
+        _ => Ok(context),
+    });
     if let Err(err) = context {
         // TODO: handle multiple errors
         // https://github.com/microsoft/qsharp/issues/149
@@ -125,51 +199,168 @@ where
         (out.event_cb)(&msg.to_string());
         return Err(e);
     }
-    let context = context.expect("context should be valid");
-    for _ in 0..shots {
+    let mut context = context
+        .expect("context should be valid")
+        .with_noise(options.noise.unwrap_or_default().into());
+    if let Some(seed) = options.seed {
+        context = context.with_seed(seed);
+    }
+    if let Some(steps) = options.step_limit {
+        context = context.with_step_limit(steps);
+    }
+    if let Some(qubits) = options.qubit_limit {
+        context = context.with_qubit_limit(qubits);
+    }
+    if let Some(len) = options.array_limit {
+        context = context.with_array_limit(len);
+    }
+    if let Some(token) = &options.cancellation {
+        context = context.with_cancellation(token.clone());
+    }
+    let mut results = ShotResults::default();
+    for shot in 0..options.shots.unwrap_or(1) {
+        if options
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            break;
+        }
         let result = context.eval(&mut out);
-        let mut success = true;
-        let msg: serde_json::Value = match result {
-            Ok(value) => serde_json::Value::String(value.to_string()),
+        // A successful result is also given as a typed value, so that it can be told apart from
+        // other values with the same display form.
+        let msg = match &result {
+            Ok(value) => json!(
+                {"type": "Result", "success": true, "result": value.to_string(), "value": value}),
             Err(errors) => {
                 // TODO: handle multiple errors
                 // https://github.com/microsoft/qsharp/issues/149
-                success = false;
-                VSDiagnostic::from(&errors[0]).json()
+                let diag = VSDiagnostic::from(&errors[0]).json();
+                json!({"type": "Result", "success": false, "result": diag})
             }
         };
 
-        let msg_string = json!({"type": "Result", "success": success, "result": msg}).to_string();
-        (out.event_cb)(&msg_string);
+        (out.event_cb)(&msg.to_string());
+        results.record(shot as usize, result);
     }
-    Ok(())
+
+    let buckets: Vec<_> = results
+        .histogram
+        .iter()
+        .map(|(value, count)| json!({"result": value.to_string(), "value": value, "count": count}))
+        .collect();
+    Ok(json!({"shots": results.shots(), "buckets": buckets, "failures": results.errors.len()}))
+}
+
+thread_local! {
+    /// The host's cancellation check for the run in progress on this thread.
+    static IS_CANCELLED: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+/// Calls the host's cancellation check for the run in progress, if there is one.
+fn host_cancelled() -> bool {
+    IS_CANCELLED.with(|is_cancelled| {
+        is_cancelled.borrow().as_ref().is_some_and(|is_cancelled| {
+            is_cancelled
+                .call0(&JsValue::null())
+                .is_ok_and(|cancelled| cancelled.is_truthy())
+        })
+    })
 }
 
+/// Runs the program with the given options, reporting the output and result of each shot through
+/// the events callback, and returns a histogram of the shot results like
+/// `{shots: 2, buckets: [{result: "One", value: {type: "Result", value: "One"}, count: 2}],
+/// failures: 0}`, where each bucket has the display and typed forms of its value like the
+/// per-shot `Result` events. The options are an object like
+/// `{shots: 10, noise: {depolarizing: 0.01}, seed: 42, args: {n: 3}}`, where every property may be
+/// left out:
+/// - `shots`, the number of shots to run, which is one by default.
+/// - `noise`, the error probabilities of the `depolarizing`, `bitFlip`, `phaseFlip`, `idle` and
+///   `readout` noise.
+/// - `seed`, which makes the measurements and random numbers of the run reproducible.
+/// - `stepLimit`, `qubitLimit` and `arrayLimit`, which fail a shot that takes more steps, allocates
+///   more qubits at the same time, or builds a longer array than the limit instead of exhausting
+///   the host.
+/// - `args`, the arguments to the entry point, with a property for each of its parameters.
+/// - `isCancelled`, a function that the host can use to stop the run by returning true. It is
+///   called periodically while shots run, for example to read a flag shared with the thread that
+///   started the run. A cancelled run fails the shot in progress with an interrupted error and
+///   skips the remaining shots.
 #[wasm_bindgen]
 pub fn run(
     code: &str,
     expr: &str,
     event_cb: &js_sys::Function,
-    shots: u32,
+    options: JsValue,
 ) -> Result<JsValue, JsValue> {
     if !event_cb.is_function() {
         return Err(JsError::new("Events callback function must be provided").into());
     }
 
-    match run_internal(
+    let (mut options, is_cancelled) = if options.is_undefined() || options.is_null() {
+        (RunOptions::default(), None)
+    } else {
+        let is_cancelled = js_sys::Reflect::get(&options, &JsValue::from_str("isCancelled"))?;
+        (
+            serde_wasm_bindgen::from_value::<RunOptions>(options)?,
+            is_cancelled.dyn_into::<js_sys::Function>().ok(),
+        )
+    };
+    if !NoiseModel::from(options.noise.unwrap_or_default()).is_valid() {
+        return Err(JsError::new("Noise probabilities must be between 0 and 1").into());
+    }
+    options.cancellation = is_cancelled.map(|is_cancelled| {
+        IS_CANCELLED.with(|host| *host.borrow_mut() = Some(is_cancelled));
+        CancellationToken::with_poll(host_cancelled)
+    });
+
+    let result = run_internal(
         code,
         expr,
         |msg: &str| {
             // See example at https://rustwasm.github.io/wasm-bindgen/reference/receiving-js-closures-in-rust.html
             let _ = event_cb.call1(&JsValue::null(), &JsValue::from(msg));
         },
-        shots,
-    ) {
-        Ok(()) => Ok(JsValue::TRUE),
+        options,
+    );
+    IS_CANCELLED.with(|host| host.borrow_mut().take());
+    match result {
+        Ok(histogram) => Ok(histogram.serialize(&Serializer::json_compatible())?),
         Err(e) => Err(JsError::from(e).into()),
     }
 }
 
+fn get_circuit_internal(code: &str, expr: &str) -> Result<String, Vec<stateless::Error>> {
+    let sources = SourceMap::new([("code".into(), code.into())], Some(expr.into()));
+    let context = stateless::Context::new(true, sources)?;
+    let mut tracer = Tracer::new();
+    context.eval_with_recorders(
+        &mut CallbackReceiver {
+            event_cb: |_: &str| {},
+        },
+        Recorders {
+            tracer: Some(&mut tracer),
+            ..Recorders::default()
+        },
+    )?;
+    Ok(Circuit::new(tracer.into_operations()).json())
+}
+
+/// Runs the program once and returns the quantum operations it applies as a JSON circuit, like
+/// `{"qubits": 1, "operations": [{"kind": "Gate", "gate": "H", "controls": [], "targets": [0],
+/// "span": {"lo": 80, "hi": 81}}]}`. Each operation has the span of the call in the program that
+/// applied it, as offsets into the code. Output from the program is discarded.
+#[wasm_bindgen]
+pub fn get_circuit(code: &str, expr: &str) -> Result<JsValue, JsValue> {
+    match get_circuit_internal(code, expr) {
+        Ok(circuit) => Ok(JsValue::from_str(&circuit)),
+        // TODO: handle multiple errors
+        // https://github.com/microsoft/qsharp/issues/149
+        Err(e) => Err(JsError::from(&e[0]).into()),
+    }
+}
+
 fn run_kata_exercise_internal(
     verification_source: &str,
     exercise_implementation: &str,
@@ -232,16 +423,52 @@ mod test {
         let expr = "Test.Answer()";
         let count = std::cell::Cell::new(0);
 
-        let _result = crate::run_internal(
+        let result = crate::run_internal(
             code,
             expr,
             |_msg| {
                 assert!(_msg.contains("42"));
                 count.set(count.get() + 1);
             },
-            2,
+            crate::RunOptions {
+                shots: Some(2),
+                ..crate::RunOptions::default()
+            },
         );
         assert_eq!(count.get(), 2);
+        assert_eq!(
+            result.expect("run should succeed").to_string(),
+            r#"{"buckets":[{"count":2,"result":"42","value":{"type":"Int","value":42}}],"failures":0,"shots":2}"#
+        );
+    }
+
+    #[test]
+    fn test_get_circuit() {
+        let code = "namespace Test {
+            operation Main() : Result {
+                use q = Qubit();
+                H(q);
+                Microsoft.Quantum.Measurement.MResetZ(q)
+            }
+        }";
+        let circuit =
+            crate::get_circuit_internal(code, "Test.Main()").expect("circuit should be traced");
+        let circuit: serde_json::Value =
+            serde_json::from_str(&circuit).expect("circuit should be JSON");
+        assert_eq!(circuit["qubits"], 1);
+        let kinds: Vec<_> = circuit["operations"]
+            .as_array()
+            .expect("operations should be an array")
+            .iter()
+            .map(|operation| operation["kind"].as_str().expect("kind should be a string"))
+            .collect();
+        assert_eq!(kinds, ["Allocate", "Gate", "MResetZ", "Release"]);
+        let h = &circuit["operations"][1];
+        assert_eq!(h["gate"], "H");
+        assert_eq!(
+            &code[h["span"]["lo"].as_u64().unwrap() as usize..][..1],
+            "H"
+        );
     }
 
     #[test]
@@ -284,7 +511,7 @@ mod test {
             |_msg_| {
                 assert!(_msg_.contains("hi") || _msg_.contains("result"));
             },
-            1,
+            crate::RunOptions::default(),
         );
         assert!(result.is_ok());
     }
@@ -306,7 +533,7 @@ mod test {
             |_msg_| {
                 assert!(_msg_.contains(r#"\ta\n\t"#) || _msg_.contains("result"));
             },
-            1,
+            crate::RunOptions::default(),
         );
         assert!(result.is_ok());
     }
@@ -333,7 +560,7 @@ mod test {
                         || _msg_.contains("result")
                 );
             },
-            1,
+            crate::RunOptions::default(),
         );
         assert!(result.is_ok());
     }
@@ -353,7 +580,7 @@ mod test {
             |_msg_| {
                 assert!(_msg_.contains("hi") || _msg_.contains("result"));
             },
-            1,
+            crate::RunOptions::default(),
         );
         assert!(result.is_ok());
     }
@@ -376,7 +603,230 @@ mod test {
                 assert!(msg.contains(r#""message":"entry point not found"#));
                 assert!(msg.contains(r#""start_pos":0"#));
             },
-            1,
+            crate::RunOptions::default(),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_readout_noise() {
+        let code = "namespace Sample {
+            operation main() : Result {
+                use q = Qubit();
+                M(q)
+            }
+        }";
+        let expr = "Sample.main()";
+        let result = crate::run_internal(
+            code,
+            expr,
+            |msg| {
+                assert!(msg.contains(r#""result":"One""#));
+            },
+            crate::RunOptions {
+                noise: Some(crate::Noise {
+                    readout: 1.0,
+                    ..crate::Noise::default()
+                }),
+                ..crate::RunOptions::default()
+            },
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_seeded_shots_are_reproducible() {
+        let code = "namespace Sample {
+            operation main() : (Result[], Int) {
+                use qs = Qubit[8];
+                for q in qs {
+                    H(q);
+                }
+                let results = Microsoft.Quantum.Measurement.MResetEachZ(qs);
+                (results, Microsoft.Quantum.Random.DrawRandomInt(0, 100))
+            }
+        }";
+        let expr = "Sample.main()";
+        let run = || {
+            let events = std::cell::RefCell::new(Vec::new());
+            let result = crate::run_internal(
+                code,
+                expr,
+                |msg| events.borrow_mut().push(msg.to_string()),
+                crate::RunOptions {
+                    shots: Some(3),
+                    seed: Some(42),
+                    ..crate::RunOptions::default()
+                },
+            );
+            assert!(result.is_ok());
+            events.into_inner()
+        };
+        let events = run();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events, run());
+    }
+
+    #[test]
+    fn test_step_limit_fails_runaway_shots() {
+        let code = "namespace Sample {
+            operation main() : Int {
+                mutable i = 0;
+                while true {
+                    set i += 1;
+                }
+                i
+            }
+        }";
+        let expr = "Sample.main()";
+        let result = crate::run_internal(
+            code,
+            expr,
+            |msg| assert!(msg.contains("Qsc.Eval.StepLimitExceeded")),
+            crate::RunOptions {
+                shots: Some(2),
+                step_limit: Some(10_000),
+                ..crate::RunOptions::default()
+            },
+        );
+        assert_eq!(
+            result.expect("program should compile")["failures"],
+            serde_json::json!(2)
+        );
+    }
+    #[test]
+    fn test_resource_limits_fail_large_shots() {
+        let code = "namespace Sample {
+            operation main() : Int {
+                use qs = Qubit[40];
+                Length([0, size = 1000000000])
+            }
+        }";
+        let expr = "Sample.main()";
+        let run = |qubit_limit, array_limit, error: &'static str| {
+            crate::run_internal(
+                code,
+                expr,
+                |msg| assert!(msg.contains(error)),
+                crate::RunOptions {
+                    qubit_limit,
+                    array_limit,
+                    ..crate::RunOptions::default()
+                },
+            )
+            .expect("program should compile")["failures"]
+                .clone()
+        };
+        assert_eq!(
+            run(Some(20), None, "Qsc.Eval.QubitLimitExceeded"),
+            serde_json::json!(1)
+        );
+        assert_eq!(
+            run(Some(40), Some(1000), "Qsc.Eval.ArrayLimitExceeded"),
+            serde_json::json!(1)
+        );
+    }
+    #[test]
+    fn test_cancelled_run_stops_shots() {
+        let code = "namespace Sample {
+            operation main() : Int {
+                mutable i = 0;
+                while true {
+                    set i += 1;
+                }
+                i
+            }
+        }";
+        let expr = "Sample.main()";
+        let polls = std::sync::atomic::AtomicUsize::new(0);
+        let token = crate::CancellationToken::with_poll(move || {
+            polls.fetch_add(1, std::sync::atomic::Ordering::Relaxed) >= 3
+        });
+        let result = crate::run_internal(
+            code,
+            expr,
+            |msg| assert!(msg.contains("Qsc.Eval.Interrupted")),
+            crate::RunOptions {
+                shots: Some(3),
+                cancellation: Some(token),
+                ..crate::RunOptions::default()
+            },
+        );
+        assert_eq!(
+            result.expect("program should compile"),
+            serde_json::json!({"shots": 1, "buckets": [], "failures": 1})
+        );
+    }
+
+    #[test]
+    fn test_entry_point_args_from_json() {
+        let code = "namespace Sample {
+            @EntryPoint()
+            operation main(n : Int, basis : Pauli[]) : String {
+                $\"{n} {basis}\"
+            }
+        }";
+        let args = serde_json::json!({"n": 2, "basis": ["PauliX", "PauliZ"]});
+        let result = crate::run_internal(
+            code,
+            "",
+            |msg| {
+                assert!(msg.contains(r#""result":"2 [PauliX, PauliZ]""#));
+            },
+            crate::RunOptions {
+                args: args.as_object().cloned(),
+                ..crate::RunOptions::default()
+            },
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_entry_point_args_are_type_checked() {
+        let code = "namespace Sample {
+            @EntryPoint()
+            operation main(n : Int) : Int { n }
+        }";
+        let result = crate::run_internal(
+            code,
+            "",
+            |msg| {
+                assert!(msg.contains(r#""success":false"#));
+                assert!(msg.contains("invalid argument for parameter `n` of type `Int`"));
+            },
+            crate::RunOptions {
+                args: serde_json::json!({"n": "two"}).as_object().cloned(),
+                ..crate::RunOptions::default()
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_result_has_typed_value() {
+        let code = "namespace Sample {
+            operation main() : (Int, Double[], BigInt) {
+                (1, [1.0], 2L)
+            }
+        }";
+        let expr = "Sample.main()";
+        let result = crate::run_internal(
+            code,
+            expr,
+            |msg| {
+                let msg: serde_json::Value =
+                    serde_json::from_str(msg).expect("message should be JSON");
+                assert_eq!(msg["result"], "(1, [1.0], 2)");
+                assert_eq!(
+                    msg["value"],
+                    serde_json::json!({"type": "Tuple", "value": [
+                        {"type": "Int", "value": 1},
+                        {"type": "Array", "value": [{"type": "Double", "value": 1.0}]},
+                        {"type": "BigInt", "value": "2"},
+                    ]})
+                );
+            },
+            crate::RunOptions::default(),
         );
         assert!(result.is_ok());
     }